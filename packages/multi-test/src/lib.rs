@@ -32,7 +32,7 @@ pub use crate::app::{
 };
 #[cfg(not(target_arch = "wasm32"))]
 pub use crate::bank::{Bank, BankKeeper, BankSudo};
-pub use crate::contracts::{Contract, ContractWrapper};
+pub use crate::contracts::{Contract, ContractInstantiationInfo, ContractWrapper};
 pub use crate::executor::{AppResponse, Executor};
 #[cfg(not(target_arch = "wasm32"))]
 pub use crate::module::Module;
@@ -0,0 +1,42 @@
+//! Umbrella crate over the secret-plus-utils packages.
+//!
+//! Every package sits behind a feature so contracts only compile what they use:
+//! `storage` (on by default), `utils`, `testing` and `cli`. The last two spawn
+//! processes and simulate chains, so they are never compiled for `wasm32`.
+//!
+//! ```ignore
+//! use secret_plus_utils::prelude::*;
+//! ```
+
+#[cfg(feature = "storage")]
+pub use secret_storage_plus as storage;
+#[cfg(feature = "utils")]
+pub use secret_utils as utils;
+
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub use secret_multi_test as testing;
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+pub use secretcli as cli;
+
+pub mod prelude {
+    #[cfg(feature = "storage")]
+    pub use secret_storage_plus::{
+        AppendStore, Bincode2, DequeStore, GenericItemStorage, GenericMapStorage, Item,
+        ItemStorage, Json, Map, MapStorage, PrimaryKey, Serde,
+    };
+    #[cfg(all(feature = "storage", feature = "iterator"))]
+    pub use secret_storage_plus::{Bound, IndexList, IndexedMap, MultiIndex, UniqueIndex};
+
+    #[cfg(feature = "utils")]
+    pub use secret_utils::{Duration, Expiration, NativeBalance, Threshold};
+
+    #[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+    pub use secret_multi_test::{App, Contract, ContractWrapper, Executor};
+
+    #[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+    pub use secretcli::{
+        cli_types::NetContract,
+        network::{set_network_config, NetworkConfig},
+        secretcli::{handle, init, query},
+    };
+}
@@ -0,0 +1,226 @@
+use crate::{cli_types::NetContract, network::network_config, secretcli::CONTRACT_CACHE_DIR};
+use serde::{de::Error, Deserialize, Serialize};
+use serde_json::{Result, Value};
+use std::{
+    collections::BTreeMap,
+    fs::{create_dir_all, File},
+    io::{BufReader, ErrorKind},
+    path::PathBuf,
+};
+
+///
+/// Human readable aliases for the contracts and accounts of a network, so scripts can refer
+/// to `contracts("treasury")` instead of hardcoding addresses.
+///
+/// Each network has its own book, saved next to the cached contracts. Contracts cached
+/// with [crate::secretcli::save_contract] are added to the active network's book.
+///
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct AddressBook {
+    #[serde(default)]
+    pub contracts: BTreeMap<String, NetContract>,
+    #[serde(default)]
+    pub accounts: BTreeMap<String, String>,
+}
+
+impl AddressBook {
+    ///
+    /// Loads the book of the given network, or an empty one if there is none yet
+    ///
+    pub fn load(network: &str) -> Result<Self> {
+        match File::open(Self::path(network)) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(AddressBook::default()),
+            Err(err) => Err(serde_json::Error::io(err)),
+        }
+    }
+
+    ///
+    /// Loads the book of the network set with [crate::network::set_network_config]
+    ///
+    pub fn load_active() -> Result<Self> {
+        Self::load(&network_config().network_name())
+    }
+
+    pub fn save(&self, network: &str) -> Result<()> {
+        create_dir_all(CONTRACT_CACHE_DIR).map_err(serde_json::Error::io)?;
+        let file = File::create(Self::path(network)).map_err(serde_json::Error::io)?;
+        serde_json::to_writer_pretty(file, self)
+    }
+
+    fn path(network: &str) -> PathBuf {
+        PathBuf::from(CONTRACT_CACHE_DIR).join(format!("{}.address_book", network))
+    }
+
+    pub fn add_contract(&mut self, alias: impl Into<String>, contract: NetContract) -> &mut Self {
+        self.contracts.insert(alias.into(), contract);
+        self
+    }
+
+    pub fn add_account(
+        &mut self,
+        alias: impl Into<String>,
+        address: impl Into<String>,
+    ) -> &mut Self {
+        self.accounts.insert(alias.into(), address.into());
+        self
+    }
+
+    pub fn contract(&self, alias: &str) -> Option<&NetContract> {
+        self.contracts.get(alias)
+    }
+
+    pub fn account(&self, alias: &str) -> Option<&str> {
+        self.accounts.get(alias).map(String::as_str)
+    }
+
+    ///
+    /// Address of an account or contract alias, anything else is assumed to already be an
+    /// address or key name and returned as is
+    ///
+    pub fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        self.account(name)
+            .or_else(|| {
+                self.contract(name)
+                    .map(|contract| contract.address.as_str())
+            })
+            .unwrap_or(name)
+    }
+
+    ///
+    /// Replaces placeholder strings anywhere in the message with what they refer to:
+    /// `"$account:alice"` and `"$contract:factory"` become addresses, `"$code_hash:factory"`
+    /// the contract's code hash. Fails on aliases missing from the book
+    ///
+    pub fn substitute(&self, msg: &mut Value) -> Result<()> {
+        match msg {
+            Value::String(string) => {
+                if let Some(resolved) = self.placeholder(string)? {
+                    *string = resolved;
+                }
+            }
+            Value::Array(values) => {
+                for value in values {
+                    self.substitute(value)?;
+                }
+            }
+            Value::Object(map) => {
+                for value in map.values_mut() {
+                    self.substitute(value)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// What a placeholder refers to, `None` if the string isn't one
+    fn placeholder(&self, string: &str) -> Result<Option<String>> {
+        let (kind, alias) = match string.strip_prefix('$').and_then(|s| s.split_once(':')) {
+            Some(placeholder) => placeholder,
+            None => return Ok(None),
+        };
+        let resolved = match kind {
+            "account" => self.account(alias).map(str::to_string),
+            "contract" => self
+                .contract(alias)
+                .map(|contract| contract.address.clone()),
+            "code_hash" => self
+                .contract(alias)
+                .map(|contract| contract.code_hash.clone()),
+            _ => return Ok(None),
+        };
+        resolved.map(Some).ok_or_else(|| {
+            Error::custom(format!("No {} named {} in the address book", kind, alias))
+        })
+    }
+}
+
+///
+/// Contract with the given alias in the active network's book
+///
+pub fn contracts(alias: &str) -> Result<NetContract> {
+    AddressBook::load_active()?
+        .contract(alias)
+        .cloned()
+        .ok_or_else(|| Error::custom(format!("No contract named {} in the address book", alias)))
+}
+
+///
+/// Address of the account with the given alias in the active network's book
+///
+pub fn accounts(alias: &str) -> Result<String> {
+    AddressBook::load_active()?
+        .account(alias)
+        .map(str::to_string)
+        .ok_or_else(|| Error::custom(format!("No account named {} in the address book", alias)))
+}
+
+///
+/// Serializes the message with its placeholders replaced from the active network's book,
+/// see [AddressBook::substitute]
+///
+pub fn resolve_msg<Message: Serialize>(msg: &Message) -> Result<Value> {
+    let mut msg = serde_json::to_value(msg)?;
+    AddressBook::load_active()?.substitute(&mut msg)?;
+    Ok(msg)
+}
+
+///
+/// Adds the contract to the active network's book
+///
+pub fn record_contract(alias: &str, contract: &NetContract) -> Result<()> {
+    let network = network_config().network_name();
+    let mut book = AddressBook::load(&network)?;
+    book.add_contract(alias, contract.clone());
+    book.save(&network)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_address_book() {
+        let treasury = NetContract::new("treasury", "1", "secret1treasury", "hash");
+        let mut book = AddressBook::default();
+        book.add_contract("treasury", treasury.clone())
+            .add_account("alice", "secret1alice");
+
+        assert_eq!(book.contract("treasury"), Some(&treasury));
+        assert_eq!(book.contract("alice"), None);
+        assert_eq!(book.account("alice"), Some("secret1alice"));
+        assert_eq!(book.resolve("alice"), "secret1alice");
+        assert_eq!(book.resolve("treasury"), "secret1treasury");
+        assert_eq!(book.resolve("secret1bob"), "secret1bob");
+
+        let mut msg = serde_json::json!({
+            "init": {
+                "admin": "$account:alice",
+                "treasury": { "address": "$contract:treasury", "hash": "$code_hash:treasury" },
+                "others": ["$account:alice", "$unrelated", "plain"]
+            }
+        });
+        book.substitute(&mut msg).unwrap();
+        assert_eq!(
+            msg,
+            serde_json::json!({
+                "init": {
+                    "admin": "secret1alice",
+                    "treasury": { "address": "secret1treasury", "hash": "hash" },
+                    "others": ["secret1alice", "$unrelated", "plain"]
+                }
+            })
+        );
+        assert!(book
+            .substitute(&mut serde_json::json!("$account:bob"))
+            .is_err());
+
+        let json = serde_json::to_string(&book).unwrap();
+        assert_eq!(serde_json::from_str::<AddressBook>(&json).unwrap(), book);
+        assert_eq!(
+            serde_json::from_str::<AddressBook>("{}").unwrap(),
+            AddressBook::default()
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use crate::cli_types::{coins_arg, TxQuery};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Result;
+use std::{collections::VecDeque, fs, path::Path, sync::Mutex, time::Duration};
+
+static TXS: Mutex<VecDeque<TxRecord>> = Mutex::new(VecDeque::new());
+
+/// Most txs kept, the oldest are dropped first so long-running bots don't grow without bound
+pub const MAX_RECORDED_TXS: usize = 10_000;
+
+const CSV_HEADER: &str =
+    "txhash,msg_type,contract,height,gas_wanted,gas_used,fee,latency_ms,success,timestamp";
+
+///
+/// One tx confirmed during the run, flattened for spreadsheets
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxRecord {
+    pub txhash: String,
+    /// Last part of the message's type url, e.g. `MsgExecuteContract`
+    pub msg_type: String,
+    pub contract: Option<String>,
+    pub height: u64,
+    pub gas_wanted: u64,
+    pub gas_used: u64,
+    /// Fee in the `--fees` format, e.g. `2500uscrt`
+    pub fee: String,
+    /// Time until the tx was found on chain, which starts right after it was broadcast
+    pub latency_ms: u64,
+    pub success: bool,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl TxRecord {
+    pub fn new(tx: &TxQuery, latency: Duration) -> Self {
+        let events = tx.logs.iter().flat_map(|log| &log.events);
+        let action = events
+            .clone()
+            .filter(|event| event.msg_type == "message")
+            .flat_map(|event| &event.attributes)
+            .find(|attribute| attribute.msg_key == "action")
+            .map(|attribute| attribute.value.as_str())
+            .unwrap_or_default();
+        TxRecord {
+            txhash: tx.txhash.clone(),
+            msg_type: action.rsplit('.').next().unwrap_or_default().to_string(),
+            contract: events
+                .flat_map(|event| &event.attributes)
+                .find(|attribute| attribute.msg_key == "contract_address")
+                .map(|attribute| attribute.value.clone()),
+            height: tx.height,
+            gas_wanted: tx.gas_wanted,
+            gas_used: tx.gas_used,
+            fee: tx
+                .fee()
+                .map(|fee| coins_arg(&fee.amount))
+                .unwrap_or_default(),
+            latency_ms: latency.as_millis() as u64,
+            // Failed txs have no logs
+            success: !tx.logs.is_empty(),
+            timestamp: tx.timestamp,
+        }
+    }
+
+    fn csv_row(&self) -> String {
+        [
+            self.txhash.clone(),
+            self.msg_type.clone(),
+            self.contract.clone().unwrap_or_default(),
+            self.height.to_string(),
+            self.gas_wanted.to_string(),
+            self.gas_used.to_string(),
+            self.fee.clone(),
+            self.latency_ms.to_string(),
+            self.success.to_string(),
+            self.timestamp.to_rfc3339(),
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+/// Quotes fields holding separators, doubling their quotes
+fn csv_field(field: &str) -> String {
+    if field.contains(|c| matches!(c, ',' | '"' | '\n')) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn txs() -> std::sync::MutexGuard<'static, VecDeque<TxRecord>> {
+    TXS.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn push_capped(txs: &mut VecDeque<TxRecord>, record: TxRecord, cap: usize) {
+    if txs.len() >= cap {
+        txs.pop_front();
+    }
+    txs.push_back(record);
+}
+
+/// Adds a tx broadcast during the run once it's confirmed, the latency is since its broadcast
+pub(crate) fn record_tx(tx: &TxQuery, latency: Duration) {
+    push_capped(&mut txs(), TxRecord::new(tx, latency), MAX_RECORDED_TXS);
+}
+
+///
+/// The last [MAX_RECORDED_TXS] txs confirmed since the start of the run or the last
+/// [clear_txs]
+///
+pub fn recorded_txs() -> Vec<TxRecord> {
+    txs().iter().cloned().collect()
+}
+
+///
+/// Same as [recorded_txs] but clears them, so a bot can export its txs periodically
+///
+pub fn take_txs() -> Vec<TxRecord> {
+    txs().drain(..).collect()
+}
+
+pub fn clear_txs() {
+    txs().clear();
+}
+
+pub fn to_csv(records: &[TxRecord]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for record in records {
+        csv.push_str(&record.csv_row());
+        csv.push('\n');
+    }
+    csv
+}
+
+///
+/// Writes the run's txs as CSV, one row per tx
+///
+pub fn write_csv(path: impl AsRef<Path>) -> Result<()> {
+    fs::write(path, to_csv(&recorded_txs())).map_err(serde_json::Error::io)
+}
+
+///
+/// Writes the run's txs as a JSON array
+///
+pub fn write_json(path: impl AsRef<Path>) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(&recorded_txs())?).map_err(serde_json::Error::io)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tx_record() {
+        let tx: TxQuery = serde_json::from_value(serde_json::json!({
+            "height": "42",
+            "txhash": "A1B2",
+            "gas_wanted": "200000",
+            "gas_used": "150000",
+            "timestamp": "2023-11-14T22:13:20Z",
+            "logs": [{ "msg_index": 0, "log": "", "events": [
+                { "type": "message", "attributes": [
+                    { "key": "action", "value": "/secret.compute.v1beta1.MsgExecuteContract" }
+                ] },
+                { "type": "wasm", "attributes": [
+                    { "key": "contract_address", "value": "secret1token" }
+                ] }
+            ] }],
+            "tx": { "body": { "memo": "" }, "auth_info": { "fee": {
+                "amount": [{ "denom": "uscrt", "amount": "2500" }, { "denom": "uatom", "amount": "1" }],
+                "gas_limit": "200000"
+            } } }
+        }))
+        .unwrap();
+
+        let record = TxRecord::new(&tx, Duration::from_millis(6200));
+        let mut txs = VecDeque::new();
+        for _ in 0..3 {
+            push_capped(&mut txs, record.clone(), 2);
+        }
+        assert_eq!(txs.len(), 2);
+
+        assert_eq!(record.msg_type, "MsgExecuteContract");
+        assert_eq!(record.contract.as_deref(), Some("secret1token"));
+        assert!(record.success);
+        assert_eq!(
+            to_csv(&[record]),
+            format!(
+                "{}\nA1B2,MsgExecuteContract,secret1token,42,200000,150000,\"2500uscrt,1uatom\",6200,true,2023-11-14T22:13:20+00:00\n",
+                CSV_HEADER
+            )
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use crate::utils::print_warning;
+use serde::{Deserialize, Serialize};
+use serde_json::Result;
+
+/// Set to `sandbox` to run test suites in memory, `chain` (the default) runs them on localsecret
+pub const BACKEND_VAR: &str = "SECRET_TEST_BACKEND";
+
+///
+/// Where a test suite's contracts run
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// A real node through secretd, e.g. localsecret
+    Chain,
+    /// The in-memory [crate::sandbox]
+    Sandbox,
+}
+
+///
+/// Features only some backends have, so suites can skip the tests that need them
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Encrypted inputs, outputs and padded responses
+    Encryption,
+    /// Code hashes derived from the uploaded wasm
+    CodeHashes,
+    GasMetering,
+    /// Indexing events from blocks and tx search
+    EventIndexing,
+    /// Keys from the secretd keyring, permits and signatures
+    Keyring,
+    Ibc,
+}
+
+impl Backend {
+    ///
+    /// Reads the backend from `SECRET_TEST_BACKEND`, defaulting to the chain
+    ///
+    pub fn from_env() -> Result<Self> {
+        match std::env::var(BACKEND_VAR) {
+            Ok(backend) => Self::parse(&backend),
+            Err(_) => Ok(Backend::Chain),
+        }
+    }
+
+    pub fn parse(backend: &str) -> Result<Self> {
+        match backend.trim().to_ascii_lowercase().as_str() {
+            "" | "chain" | "localsecret" => Ok(Backend::Chain),
+            "sandbox" => Ok(Backend::Sandbox),
+            other => Err(serde::de::Error::custom(format!(
+                "Unknown {} {}, expected chain or sandbox",
+                BACKEND_VAR, other
+            ))),
+        }
+    }
+
+    ///
+    /// The backend the test traits talk to on this thread
+    ///
+    pub fn current() -> Self {
+        #[cfg(feature = "sandbox")]
+        if crate::sandbox::is_active() {
+            return Backend::Sandbox;
+        }
+        Backend::Chain
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        match capability {
+            // The sandbox only mocks these
+            Capability::Encryption
+            | Capability::CodeHashes
+            | Capability::GasMetering
+            | Capability::EventIndexing
+            | Capability::Keyring
+            | Capability::Ibc => *self == Backend::Chain,
+        }
+    }
+}
+
+///
+/// Whether the current backend has the capability, warning that the test is skipped if not
+///
+/// # Arguments
+///
+/// * 'test' - Name of the test shown in the warning
+/// * 'capability' - What the test needs
+///
+pub fn requires(test: &str, capability: Capability) -> bool {
+    let backend = Backend::current();
+    let supported = backend.supports(capability);
+    if !supported {
+        print_warning(&format!(
+            "Skipping {}, {:?} is not supported by the {:?} backend",
+            test, capability, backend
+        ));
+    }
+    supported
+}
+
+///
+/// Runs a test suite against the backend `SECRET_TEST_BACKEND` selects. For the sandbox a fresh
+/// one is built and activated for the suite's duration, the chain is used as configured
+///
+/// # Arguments
+///
+/// * 'sandbox' - Builds the sandbox with the suite's native contracts registered
+/// * 'suite' - The tests, given the backend they run on
+///
+#[cfg(feature = "sandbox")]
+pub fn run_suite<T, S, F>(sandbox: S, suite: F) -> Result<T>
+where
+    S: FnOnce() -> crate::sandbox::Sandbox,
+    F: FnOnce(Backend) -> Result<T>,
+{
+    match Backend::from_env()? {
+        Backend::Chain => suite(Backend::Chain),
+        Backend::Sandbox => {
+            crate::sandbox::activate(sandbox());
+            let result = suite(Backend::Sandbox);
+            crate::sandbox::deactivate();
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_backend() {
+        assert_eq!(Backend::parse("sandbox").unwrap(), Backend::Sandbox);
+        assert_eq!(Backend::parse("LocalSecret").unwrap(), Backend::Chain);
+        assert_eq!(Backend::parse("").unwrap(), Backend::Chain);
+        assert!(Backend::parse("mainnet").is_err());
+    }
+
+    #[test]
+    fn test_capabilities() {
+        assert!(Backend::Chain.supports(Capability::Encryption));
+        assert!(!Backend::Sandbox.supports(Capability::Ibc));
+        assert_eq!(Backend::current(), Backend::Chain);
+    }
+}
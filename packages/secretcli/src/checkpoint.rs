@@ -0,0 +1,151 @@
+use crate::{cli_types::NetContract, secretcli::CONTRACT_CACHE_DIR};
+use serde::{Deserialize, Serialize};
+use serde_json::Result;
+use std::{
+    fs::{create_dir_all, remove_file, File},
+    io::{BufReader, ErrorKind},
+    path::PathBuf,
+};
+
+///
+/// Result of a scenario step that already ran
+///
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct CompletedStep {
+    pub name: String,
+    pub txhash: Option<String>,
+    pub contract: Option<NetContract>,
+}
+
+impl CompletedStep {
+    pub fn tx(txhash: impl Into<String>) -> Self {
+        CompletedStep {
+            txhash: Some(txhash.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn contract(contract: NetContract) -> Self {
+        CompletedStep {
+            contract: Some(contract),
+            ..Default::default()
+        }
+    }
+}
+
+///
+/// Records which steps of a test suite completed so an interrupted run can resume
+/// where it stopped instead of redeploying everything.
+///
+/// Checkpoints are saved next to the cached contracts after every completed step.
+///
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SuiteCheckpoint {
+    name: String,
+    steps: Vec<CompletedStep>,
+}
+
+impl SuiteCheckpoint {
+    ///
+    /// Loads the checkpoint for the given suite, or starts a new one if there is none
+    ///
+    pub fn load(name: &str) -> Result<Self> {
+        match File::open(Self::path(name)) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(SuiteCheckpoint {
+                name: name.to_string(),
+                steps: vec![],
+            }),
+            Err(err) => Err(serde_json::Error::io(err)),
+        }
+    }
+
+    fn path(name: &str) -> PathBuf {
+        PathBuf::from(CONTRACT_CACHE_DIR).join(format!("{}.checkpoint", name))
+    }
+
+    pub fn steps(&self) -> &[CompletedStep] {
+        &self.steps
+    }
+
+    pub fn get(&self, step: &str) -> Option<&CompletedStep> {
+        self.steps.iter().find(|completed| completed.name == step)
+    }
+
+    pub fn is_done(&self, step: &str) -> bool {
+        self.get(step).is_some()
+    }
+
+    ///
+    /// Marks a step as completed and persists the checkpoint
+    ///
+    pub fn record(&mut self, step: &str, mut result: CompletedStep) -> Result<()> {
+        result.name = step.to_string();
+        self.steps.retain(|completed| completed.name != step);
+        self.steps.push(result);
+        self.save()
+    }
+
+    ///
+    /// Runs the step unless a previous run already completed it, in which case the
+    /// recorded result is returned
+    ///
+    pub fn run<F>(&mut self, step: &str, action: F) -> Result<CompletedStep>
+    where
+        F: FnOnce() -> Result<CompletedStep>,
+    {
+        if let Some(completed) = self.get(step) {
+            return Ok(completed.clone());
+        }
+        self.record(step, action()?)?;
+        Ok(self.get(step).cloned().unwrap_or_default())
+    }
+
+    fn save(&self) -> Result<()> {
+        create_dir_all(CONTRACT_CACHE_DIR).map_err(serde_json::Error::io)?;
+        let file = File::create(Self::path(&self.name)).map_err(serde_json::Error::io)?;
+        serde_json::to_writer(file, self)
+    }
+
+    ///
+    /// Deletes the checkpoint so the next run starts from scratch
+    ///
+    pub fn clear(self) -> Result<()> {
+        match remove_file(Self::path(&self.name)) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(serde_json::Error::io(err)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::generate_label;
+
+    #[test]
+    fn test_checkpoint_round_trip() -> Result<()> {
+        let name = format!("secretcli-checkpoint-{}", generate_label(8));
+        let contract = NetContract {
+            label: "token".to_string(),
+            id: "1".to_string(),
+            address: "secret1token".to_string(),
+            code_hash: "abcd".to_string(),
+        };
+
+        let mut checkpoint = SuiteCheckpoint::load(&name)?;
+        assert!(checkpoint.steps().is_empty());
+        checkpoint.record("deploy", CompletedStep::contract(contract.clone()))?;
+        checkpoint.run("mint", || Ok(CompletedStep::tx("AB12")))?;
+
+        let mut restored = SuiteCheckpoint::load(&name)?;
+        assert_eq!(restored.steps(), checkpoint.steps());
+        assert_eq!(restored.get("deploy").unwrap().contract, Some(contract));
+        let mint = restored.run("mint", || panic!("completed steps don't run again"))?;
+        assert_eq!(mint.txhash.as_deref(), Some("AB12"));
+
+        restored.clear()?;
+        assert!(SuiteCheckpoint::load(&name)?.steps().is_empty());
+        Ok(())
+    }
+}
@@ -1,60 +1,565 @@
-use serde::{Deserialize, Serialize};
+use crate::constants::{SECRET_HD_PATH, SECRET_HRP};
+use crate::utils::{json_diff, JsonChange};
+use bech32::{FromBase32, ToBase32, Variant};
+use bip32::{DerivationPath, Language, Mnemonic, XPrv};
+use chrono::{DateTime, Utc};
+use ripemd::Ripemd160;
+use serde::{de::DeserializeOwned, de::Error, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    any::type_name,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+// The `{"status":"success","data":...}` envelope contracts built on secret-utils answer with
+pub use secret_utils::{HandleAnswer, ResponseStatus};
+
+/// secretd encodes most integers as JSON strings, this lets them be used as typed fields
+mod string_num {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use std::{fmt::Display, str::FromStr};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(u64),
+    }
+
+    pub fn serialize<T: Display, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        match StringOrNumber::deserialize(deserializer)? {
+            StringOrNumber::String(s) => s.parse().map_err(Error::custom),
+            StringOrNumber::Number(n) => n.to_string().parse().map_err(Error::custom),
+        }
+    }
+}
+
+static STRICT_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+///
+/// secretd output types are tolerant by default: missing fields get their default value and
+/// unknown ones are kept in `extra`. Strict mode makes [parse_output] fail on both instead,
+/// which shows exactly what changed in a new secretd release.
+///
+pub fn set_strict_output(strict: bool) {
+    STRICT_OUTPUT.store(strict, Ordering::Relaxed);
+}
+
+pub fn strict_output() -> bool {
+    STRICT_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// A type secretd prints, see [set_strict_output]
+pub trait CliOutput: Serialize + DeserializeOwned {
+    /// Top level fields that were kept in `extra`
+    fn unknown_fields(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+impl<T: CliOutput> CliOutput for Vec<T> {
+    fn unknown_fields(&self) -> Vec<String> {
+        self.iter()
+            .enumerate()
+            .flat_map(|(i, item)| {
+                item.unknown_fields()
+                    .into_iter()
+                    .map(move |field| format!("[{}].{}", i, field))
+            })
+            .collect()
+    }
+}
+
+macro_rules! impl_cli_output {
+    ($($output:ty),*) => {
+        $(impl CliOutput for $output {
+            fn unknown_fields(&self) -> Vec<String> {
+                self.extra.keys().cloned().collect()
+            }
+        })*
+    };
+}
+
+impl_cli_output!(
+    TxResponse,
+    TxCompute,
+    TxQuery,
+    TxSearchResponse,
+    ListCodeResponse,
+    ListContractCode,
+    InterchainAccount,
+    SlashingParams,
+    ValidatorSigningInfo,
+    Evidence,
+    DenomMetadata
+);
+impl CliOutput for ContractStateRaw {}
+impl CliOutput for SignedTx {}
+
+/// Deserializes secretd output, honoring [set_strict_output]
+pub fn parse_output<T: CliOutput>(value: Value) -> serde_json::Result<T> {
+    if strict_output() {
+        parse_output_strict(value)
+    } else {
+        serde_json::from_value(value)
+    }
+}
+
+/// Deserializes secretd output in strict mode whatever [set_strict_output] was set to
+pub fn parse_output_strict<T: CliOutput>(value: Value) -> serde_json::Result<T> {
+    let parsed: T = serde_json::from_value(value.clone())?;
+    let mut mismatches: Vec<String> = parsed
+        .unknown_fields()
+        .into_iter()
+        .map(|field| format!("unknown field {}", field))
+        .collect();
+    // Anything nested that didn't survive a round trip was either unknown or missing
+    for change in json_diff(&value, &serde_json::to_value(&parsed)?) {
+        match change {
+            JsonChange::Removed { path, .. } => mismatches.push(format!("unknown field {}", path)),
+            JsonChange::Added { path, .. } => mismatches.push(format!("missing field {}", path)),
+            JsonChange::Changed { .. } => {}
+        }
+    }
+    if mismatches.is_empty() {
+        Ok(parsed)
+    } else {
+        Err(Error::custom(format!(
+            "Unexpected {} output: {}",
+            type_name::<T>(),
+            mismatches.join(", ")
+        )))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct TxResponse {
-    pub height: String,
+    #[serde(with = "string_num")]
+    pub height: u64,
     pub txhash: String,
     pub codespace: String,
     pub code: Option<u128>,
     pub data: String,
     pub raw_log: String,
+    #[serde(with = "string_num", default)]
+    pub gas_wanted: u64,
+    #[serde(with = "string_num", default)]
+    pub gas_used: u64,
+    /// Warnings secretd printed while broadcasting, e.g. that the gas estimate was exceeded
+    #[serde(skip)]
+    pub stderr: String,
+    /// Fields this version doesn't know about yet
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct TxCompute {
     pub answers: Vec<TxAnswer>,
     pub output_logs: Vec<TxOutputLog>,
+    /// Fields this version doesn't know about yet
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl TxCompute {
+    /// Deserializes the decrypted data the contract returned for the first message
+    pub fn decode_answer<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        match self.answers.first() {
+            Some(answer) => answer.decode(),
+            None => Err(Error::custom("tx has no compute answers")),
+        }
+    }
+
+    /// Decodes the first message's [HandleAnswer] and returns its data if it succeeded
+    pub fn decode_handle_answer<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        self.decode_answer::<HandleAnswer<T>>()?
+            .into_data()
+            .map_err(Error::custom)
+    }
+}
+
+/// Params of the slashing module
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SlashingParams {
+    #[serde(with = "string_num")]
+    pub signed_blocks_window: u64,
+    /// Decimal, e.g. `0.500000000000000000`
+    pub min_signed_per_window: String,
+    /// Duration, e.g. `600s`
+    pub downtime_jail_duration: String,
+    pub slash_fraction_double_sign: String,
+    pub slash_fraction_downtime: String,
+    /// Fields this version doesn't know about yet
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Liveness of a validator as the slashing module tracks it
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ValidatorSigningInfo {
+    /// Consensus address, `secretvalcons1...`
+    pub address: String,
+    #[serde(with = "string_num")]
+    pub start_height: u64,
+    #[serde(with = "string_num")]
+    pub index_offset: u64,
+    pub jailed_until: DateTime<Utc>,
+    pub tombstoned: bool,
+    #[serde(with = "string_num")]
+    pub missed_blocks_counter: u64,
+    /// Fields this version doesn't know about yet
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ValidatorSigningInfo {
+    pub fn is_jailed(&self, now: DateTime<Utc>) -> bool {
+        self.tombstoned || self.jailed_until > now
+    }
+}
+
+/// Misbehaviour submitted to the evidence module, usually a double sign
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Evidence {
+    /// e.g. `/cosmos.evidence.v1beta1.Equivocation`
+    #[serde(rename = "@type")]
+    pub r#type: String,
+    #[serde(with = "string_num")]
+    pub height: u64,
+    pub time: DateTime<Utc>,
+    #[serde(with = "string_num")]
+    pub power: u64,
+    pub consensus_address: String,
+    /// Fields this version doesn't know about yet
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// One unit of a denom, e.g. `scrt` with exponent 6 for `uscrt`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct DenomUnit {
+    pub denom: String,
+    pub exponent: u32,
+    pub aliases: Vec<String>,
+}
+
+/// Bank module metadata of a native or IBC denom
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct DenomMetadata {
+    pub description: String,
+    pub denom_units: Vec<DenomUnit>,
+    /// Smallest unit, what balances are in
+    pub base: String,
+    /// Unit shown to users
+    pub display: String,
+    pub name: String,
+    pub symbol: String,
+    /// Fields this version doesn't know about yet
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl DenomMetadata {
+    /// Exponent of a unit by name or alias, relative to the base
+    pub fn exponent(&self, unit: &str) -> Option<u32> {
+        self.denom_units
+            .iter()
+            .find(|denom_unit| {
+                denom_unit.denom == unit || denom_unit.aliases.iter().any(|alias| alias == unit)
+            })
+            .map(|denom_unit| denom_unit.exponent)
+    }
+}
+
+/// Response of the ICA controller's interchain account query
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InterchainAccount {
+    pub address: String,
+    /// Fields this version doesn't know about yet
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// One answer of a secret-utils `BatchQuery`, `{"ok":"<base64>"}` or `{"err":"<message>"}`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchQueryResult {
+    Ok(String),
+    Err(String),
+}
+
+impl BatchQueryResult {
+    pub fn decode<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        match self {
+            BatchQueryResult::Ok(data) => {
+                let data = base64::decode(data).map_err(Error::custom)?;
+                serde_json::from_slice(&data)
+            }
+            BatchQueryResult::Err(err) => Err(Error::custom(err)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct TxAnswer {
     pub r#type: String,
     pub input: String,
+    #[serde(default)]
+    pub output_data: String,
+    #[serde(default)]
+    pub output_data_as_string: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl TxAnswer {
+    /// Deserializes the decrypted data the contract returned for this message
+    pub fn decode<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        if !self.output_data_as_string.is_empty() {
+            return serde_json::from_str(&self.output_data_as_string);
+        }
+        let data = base64::decode(&self.output_data).map_err(Error::custom)?;
+        serde_json::from_slice(&data)
+    }
+
+    /// The decrypted data exactly as the contract returned it, padding included
+    pub fn data(&self) -> serde_json::Result<Vec<u8>> {
+        if !self.output_data_as_string.is_empty() {
+            return Ok(self.output_data_as_string.as_bytes().to_vec());
+        }
+        base64::decode(&self.output_data).map_err(Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct TxOutputLog {
     pub r#type: String,
     pub attributes: Vec<TxAttribute>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "RawAttribute")]
 pub struct TxAttribute {
     pub key: String,
     pub value: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl From<RawAttribute> for TxAttribute {
+    fn from(raw: RawAttribute) -> Self {
+        let (key, value) = raw.normalize();
+        TxAttribute { key, value }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct TxQuery {
-    pub height: String,
+    #[serde(with = "string_num")]
+    pub height: u64,
     pub txhash: String,
     pub data: String,
     pub raw_log: String,
     pub logs: Vec<TxQueryLogs>,
-    pub gas_wanted: String,
-    pub gas_used: String,
-    //pub tx: String,
-    pub timestamp: String,
+    #[serde(with = "string_num")]
+    pub gas_wanted: u64,
+    #[serde(with = "string_num")]
+    pub gas_used: u64,
+    #[serde(default)]
+    pub tx: Option<TxQueryTx>,
+    pub timestamp: DateTime<Utc>,
+    /// Fields this version doesn't know about yet
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl TxQuery {
+    /// Fee paid for the tx, if the node returned the tx body
+    pub fn fee(&self) -> Option<&TxFee> {
+        self.tx.as_ref().map(|tx| &tx.auth_info.fee)
+    }
+
+    /// Memo of the tx, if the node returned the tx body
+    pub fn memo(&self) -> Option<&str> {
+        self.tx.as_ref().map(|tx| tx.body.memo.as_str())
+    }
+
+    /// Address of the first contract the tx instantiated
+    pub fn contract_address(&self) -> Option<String> {
+        self.logs
+            .iter()
+            .flat_map(|log| &log.events)
+            .flat_map(|event| &event.attributes)
+            .find(|attribute| attribute.msg_key == "contract_address")
+            .map(|attribute| attribute.value.clone())
+    }
+
+    /// Total fee paid in the given denom
+    pub fn fee_amount(&self, denom: &str) -> u128 {
+        self.fee()
+            .map(|fee| {
+                fee.amount
+                    .iter()
+                    .filter(|coin| coin.denom == denom)
+                    .map(|coin| coin.amount)
+                    .sum()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// A page of `query txs` results
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TxSearchResponse {
+    #[serde(with = "string_num")]
+    pub total_count: u64,
+    pub txs: Vec<TxQuery>,
+    /// Fields this version doesn't know about yet
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TxQueryTx {
+    pub body: TxBody,
+    pub auth_info: TxAuthInfo,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TxBody {
+    pub memo: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TxAuthInfo {
+    pub fee: TxFee,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TxFee {
+    pub amount: Vec<TxCoin>,
+    #[serde(with = "string_num")]
+    pub gas_limit: u64,
+    #[serde(default)]
+    pub payer: String,
+    #[serde(default)]
+    pub granter: String,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct TxCoin {
+    pub denom: String,
+    #[serde(with = "string_num")]
+    pub amount: u128,
+}
+
+impl TxCoin {
+    pub fn new(amount: u128, denom: impl Into<String>) -> Self {
+        TxCoin {
+            denom: denom.into(),
+            amount,
+        }
+    }
+}
+
+/// Formats as secretd's `--amount` expects, e.g. `100uscrt`
+impl fmt::Display for TxCoin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.amount, self.denom)
+    }
+}
+
+/// The `--amount` argument sending all the coins, e.g. `100uscrt,5ibc/27A6...`
+pub fn coins_arg(coins: &[TxCoin]) -> String {
+    coins
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Parses a coin list in the `--amount` format, e.g. `100uscrt,5ibc/27A6...`
+pub fn parse_coins(coins: &str) -> serde_json::Result<Vec<TxCoin>> {
+    coins
+        .split(',')
+        .map(str::trim)
+        .filter(|coin| !coin.is_empty())
+        .map(|coin| {
+            let split = coin
+                .find(|c: char| !c.is_ascii_digit())
+                .ok_or_else(|| Error::custom(format!("Coin {} has no denom", coin)))?;
+            let amount = coin[..split]
+                .parse()
+                .map_err(|_| Error::custom(format!("Coin {} has no amount", coin)))?;
+            Ok(TxCoin::new(amount, &coin[split..]))
+        })
+        .collect()
+}
+
+///
+/// Checks the coins can be sent together and sorts them by denom, as the chain only accepts
+/// sorted coins. Denoms follow the Cosmos SDK rules, amounts must not be zero and every denom
+/// may only appear once
+///
+pub fn validate_funds(coins: &[TxCoin]) -> serde_json::Result<Vec<TxCoin>> {
+    let mut coins = coins.to_vec();
+    coins.sort_by(|a, b| a.denom.cmp(&b.denom));
+    for (index, coin) in coins.iter().enumerate() {
+        let valid_denom = (3..=128).contains(&coin.denom.len())
+            && coin.denom.starts_with(|c: char| c.is_ascii_alphabetic())
+            && coin
+                .denom
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c));
+        if !valid_denom {
+            return Err(Error::custom(format!("Invalid denom {}", coin.denom)));
+        }
+        if coin.amount == 0 {
+            return Err(Error::custom(format!("Zero amount of {}", coin.denom)));
+        }
+        if index > 0 && coins[index - 1].denom == coin.denom {
+            return Err(Error::custom(format!("Duplicate denom {}", coin.denom)));
+        }
+    }
+    Ok(coins)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct TxQueryLogs {
     pub msg_index: i128,
     pub log: String,
     pub events: Vec<TxQueryEvents>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct TxQueryEvents {
     #[serde(rename = "type")]
     pub msg_type: String,
@@ -62,25 +567,201 @@ pub struct TxQueryEvents {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "RawAttribute")]
 pub struct TxQueryKeyValue {
     #[serde(rename = "key")]
     pub msg_key: String,
     pub value: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl From<RawAttribute> for TxQueryKeyValue {
+    fn from(raw: RawAttribute) -> Self {
+        let (msg_key, value) = raw.normalize();
+        TxQueryKeyValue { msg_key, value }
+    }
+}
+
+/// Event attribute as emitted by any known secretd version.
+///
+/// Older nodes name the key `msg_key` and base64 encode both key and value, newer ones use
+/// `key` with plaintext. Values may also be missing or null.
+#[derive(Deserialize)]
+struct RawAttribute {
+    #[serde(alias = "msg_key")]
+    key: String,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+impl RawAttribute {
+    /// Returns the plaintext key and value
+    fn normalize(self) -> (String, String) {
+        let value = self.value.unwrap_or_default();
+        // Attribute keys are always identifiers, so a key that decodes into one
+        // tells us the whole attribute was base64 encoded
+        match decode_base64(&self.key).filter(|key| is_identifier(key)) {
+            Some(key) => {
+                let value = decode_base64(&value).unwrap_or(value);
+                (key, value)
+            }
+            None => (self.key, value),
+        }
+    }
+}
+
+fn decode_base64(encoded: &str) -> Option<String> {
+    if encoded.is_empty() || encoded.len() % 4 != 0 {
+        return None;
+    }
+    base64::decode(encoded)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+}
+
+fn is_identifier(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-')
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct ListCodeResponse {
     pub code_id: u128,
     pub creator: String,
     pub code_hash: String,
+    #[serde(default)]
+    pub instantiate_permission: Option<AccessConfig>,
+    /// Fields this version doesn't know about yet
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl ListCodeResponse {
+    /// Whether the given address is allowed to instantiate this code
+    pub fn can_instantiate(&self, address: &str) -> bool {
+        self.instantiate_permission
+            .as_ref()
+            .map_or(true, |config| config.allows(address))
+    }
+}
+
+/// Instantiate permission as reported by the chain
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct AccessConfig {
+    pub permission: String,
+    #[serde(default)]
+    pub address: String,
+    #[serde(default)]
+    pub addresses: Vec<String>,
+}
+
+impl AccessConfig {
+    pub fn allows(&self, address: &str) -> bool {
+        match self.permission.as_str() {
+            "Everybody" => true,
+            "OnlyAddress" => self.address == address,
+            "AnyOfAddresses" => self.addresses.iter().any(|a| a == address),
+            _ => false,
+        }
+    }
+}
+
+/// Instantiate permission to set when storing code
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub enum InstantiatePermission {
+    Everybody,
+    OnlyAddress(String),
+    Nobody,
+}
+
+impl InstantiatePermission {
+    /// secretd flags that apply this permission
+    pub fn as_args(&self) -> Vec<&str> {
+        match self {
+            InstantiatePermission::Everybody => vec!["--instantiate-everybody", "true"],
+            InstantiatePermission::OnlyAddress(address) => {
+                vec!["--instantiate-only-address", address.as_str()]
+            }
+            InstantiatePermission::Nobody => vec!["--instantiate-nobody", "true"],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct ListContractCode {
     pub code_id: u128,
     pub creator: String,
     pub label: String,
     pub address: String,
+    /// Fields this version doesn't know about yet
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Client side filter for code and contract listings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListFilter {
+    pub creator: Option<String>,
+    pub label: Option<String>,
+}
+
+impl ListFilter {
+    /// Only entries created by the given address
+    pub fn creator(mut self, creator: impl Into<String>) -> Self {
+        self.creator = Some(creator.into());
+        self
+    }
+
+    /// Only contracts whose label contains the given text, ignored for code listings
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    fn matches_creator(&self, creator: &str) -> bool {
+        self.creator.as_ref().map_or(true, |c| c == creator)
+    }
+
+    pub fn matches_code(&self, code: &ListCodeResponse) -> bool {
+        self.matches_creator(&code.creator)
+    }
+
+    pub fn matches_contract(&self, contract: &ListContractCode) -> bool {
+        self.matches_creator(&contract.creator)
+            && self
+                .label
+                .as_ref()
+                .map_or(true, |label| contract.label.contains(label.as_str()))
+    }
+}
+
+/// Key of a raw contract storage entry, along with how it is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateKey<'a> {
+    Ascii(&'a str),
+    Hex(&'a str),
+    Base64(&'a str),
+}
+
+impl<'a> StateKey<'a> {
+    /// Key and the secretd flag matching its encoding
+    pub fn as_arg(&self) -> (&'a str, &'static str) {
+        match self {
+            StateKey::Ascii(key) => (*key, "--ascii"),
+            StateKey::Hex(key) => (*key, "--hex"),
+            StateKey::Base64(key) => (*key, "--b64"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ContractStateRaw {
+    #[serde(default)]
+    pub data: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
@@ -131,6 +812,97 @@ pub struct SignedTx {
     pub signature: String,
 }
 
+/// The signed part of a SNIP-24 query permit
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PermitParams {
+    pub permit_name: String,
+    pub allowed_tokens: Vec<String>,
+    pub chain_id: String,
+    pub permissions: Vec<String>,
+    /// Address the signer delegates read access to, e.g. a dapp backend, which then has to
+    /// prove it is that address when querying with the permit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delegate: Option<String>,
+}
+
+impl PermitParams {
+    ///
+    /// The amino sign doc wallets sign for this permit, with the fixed zero fee, account
+    /// number, sequence and empty memo contracts verify against
+    ///
+    pub fn sign_doc(&self) -> serde_json::Value {
+        let mut value = serde_json::json!({
+            "allowed_tokens": self.allowed_tokens,
+            "permissions": self.permissions,
+            "permit_name": self.permit_name,
+        });
+        if let Some(delegate) = &self.delegate {
+            value["delegate"] = serde_json::json!(delegate);
+        }
+        // serde_json maps are sorted, as amino JSON expects
+        serde_json::json!({
+            "account_number": "0",
+            "chain_id": self.chain_id,
+            "fee": {
+                "amount": [{ "amount": "0", "denom": "uscrt" }],
+                "gas": "1",
+            },
+            "memo": "",
+            "msgs": [{
+                "type": "query_permit",
+                "value": value,
+            }],
+            "sequence": "0",
+        })
+    }
+}
+
+///
+/// The ADR-36 amino sign doc for signing arbitrary `data` with an account key, as verified by
+/// secret-utils `verify_adr36`
+///
+pub fn adr36_sign_doc(signer: &str, data: &[u8]) -> serde_json::Value {
+    serde_json::json!({
+        "account_number": "0",
+        "chain_id": "",
+        "fee": { "amount": [], "gas": "0" },
+        "memo": "",
+        "msgs": [{
+            "type": "sign/MsgSignData",
+            "value": {
+                "data": base64::encode(data),
+                "signer": signer,
+            },
+        }],
+        "sequence": "0",
+    })
+}
+
+/// A signed query permit, serializes to what contracts expect in `WithPermit` queries
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Permit {
+    pub params: PermitParams,
+    pub signature: SignedTx,
+}
+
+impl Permit {
+    /// What the delegate of the permit signs to prove it holds it, the same bytes as
+    /// secret-utils `Permit::relayer_proof_data`
+    pub fn relayer_proof_data(&self, expires: u64) -> serde_json::Result<Vec<u8>> {
+        let mut data = base64::decode(&self.signature.signature).map_err(Error::custom)?;
+        data.extend_from_slice(&expires.to_be_bytes());
+        Ok(data)
+    }
+}
+
+/// A delegate's proof that it presents a delegated permit itself, passed along with the permit
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RelayerProof {
+    /// Block time in seconds from which contracts reject the proof
+    pub expires: u64,
+    pub signature: SignedTx,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PubKey {
     #[serde(rename = "type")]
@@ -138,8 +910,620 @@ pub struct PubKey {
     pub value: String,
 }
 
+impl PubKey {
+    /// Address of this key, expects `value` to be the base64 encoded compressed key
+    pub fn address(&self) -> serde_json::Result<String> {
+        let bytes = base64::decode(&self.value).map_err(Error::custom)?;
+        address_from_pubkey(&bytes)
+    }
+}
+
+/// Derives the secret1 address of a compressed secp256k1 public key
+pub fn address_from_pubkey(pubkey: &[u8]) -> serde_json::Result<String> {
+    let hash = Ripemd160::digest(Sha256::digest(pubkey));
+    bech32::encode(SECRET_HRP, hash.to_base32(), Variant::Bech32).map_err(Error::custom)
+}
+
+/// Derives the compressed public key secretd would import for this mnemonic
+pub fn pubkey_from_mnemonic(mnemonic: &str) -> serde_json::Result<[u8; 33]> {
+    let mnemonic = Mnemonic::new(mnemonic.trim(), Language::English).map_err(Error::custom)?;
+    let seed = mnemonic.to_seed("");
+    let path: DerivationPath = SECRET_HD_PATH.parse().map_err(Error::custom)?;
+    let key = XPrv::derive_from_path(&seed, &path).map_err(Error::custom)?;
+    Ok(key.public_key().to_bytes())
+}
+
+/// Derives the address secretd would give this mnemonic, without touching the keyring
+pub fn address_from_mnemonic(mnemonic: &str) -> serde_json::Result<String> {
+    address_from_pubkey(&pubkey_from_mnemonic(mnemonic)?)
+}
+
+/// Decodes a hex string, such as a code hash
+pub fn decode_hex(hex: &str) -> serde_json::Result<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return Err(Error::custom(format!("{} is not hex", hex)));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(Error::custom))
+        .collect()
+}
+
+///
+/// The address `instantiate2` gives a contract, known before it is instantiated, following
+/// wasmd's `BuildContractAddressPredictable` without the init message
+///
+/// # Arguments
+///
+/// * 'module' - Name of the chain's wasm module, see [crate::constants::COMPUTE_MODULE]
+/// * 'code_hash' - Hex code hash of the contract's code
+/// * 'creator' - Address of the instantiating account
+/// * 'salt' - Salt chosen by the creator, between 1 and 64 bytes
+///
+pub fn predict_contract_address(
+    module: &str,
+    code_hash: &str,
+    creator: &str,
+    salt: &[u8],
+) -> serde_json::Result<String> {
+    if salt.is_empty() || salt.len() > 64 {
+        return Err(Error::custom("Salt must be between 1 and 64 bytes"));
+    }
+    let (_, creator, _) = bech32::decode(creator).map_err(Error::custom)?;
+    let creator = Vec::<u8>::from_base32(&creator).map_err(Error::custom)?;
+
+    let mut key = module.as_bytes().to_vec();
+    key.push(0);
+    let code_hash = decode_hex(code_hash)?;
+    let parts: [&[u8]; 4] = [&code_hash, &creator, salt, &[]];
+    for part in parts {
+        key.extend((part.len() as u64).to_be_bytes());
+        key.extend(part);
+    }
+    let hash = Sha256::new()
+        .chain_update(Sha256::digest(b"module"))
+        .chain_update(key)
+        .finalize();
+    bech32::encode(SECRET_HRP, hash.to_base32(), Variant::Bech32).map_err(Error::custom)
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StoredContract {
     pub id: String,
     pub code_hash: String,
 }
+
+/// Confirmed result of a code upload
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StoredCode {
+    pub code_id: u64,
+    pub code_hash: String,
+    pub txhash: String,
+}
+
+impl From<StoredCode> for StoredContract {
+    fn from(code: StoredCode) -> Self {
+        StoredContract {
+            id: code.code_id.to_string(),
+            code_hash: code.code_hash,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TX_QUERY: &str = r#"{
+        "height": "1234",
+        "txhash": "A1B2C3",
+        "codespace": "",
+        "code": 0,
+        "data": "",
+        "raw_log": "[]",
+        "logs": [],
+        "info": "",
+        "gas_wanted": "200000",
+        "gas_used": "154321",
+        "tx": {
+            "@type": "/cosmos.tx.v1beta1.Tx",
+            "body": { "messages": [], "memo": "" },
+            "auth_info": {
+                "signer_infos": [],
+                "fee": {
+                    "amount": [{ "denom": "uscrt", "amount": "50000" }],
+                    "gas_limit": "200000",
+                    "payer": "",
+                    "granter": ""
+                }
+            },
+            "signatures": []
+        },
+        "timestamp": "2022-10-05T12:30:45Z"
+    }"#;
+
+    #[test]
+    fn test_tx_query_typed_fields() {
+        let query: TxQuery = serde_json::from_str(TX_QUERY).unwrap();
+
+        assert_eq!(query.height, 1234);
+        assert_eq!(query.gas_wanted, 200000);
+        assert_eq!(query.gas_used, 154321);
+        assert_eq!(query.timestamp.timestamp(), 1664973045);
+        assert_eq!(query.fee().unwrap().gas_limit, 200000);
+        assert_eq!(query.fee_amount("uscrt"), 50000);
+        assert_eq!(query.fee_amount("uatom"), 0);
+    }
+
+    // secretd 1.2+ style, plaintext under `key`
+    const PLAINTEXT_EVENT: &str = r#"{
+        "type": "message",
+        "attributes": [
+            { "key": "action", "value": "/secret.compute.v1beta1.MsgStoreCode" },
+            { "key": "code_id", "value": "12" }
+        ]
+    }"#;
+
+    // older secretd style, base64 under `key` with indexing info
+    const BASE64_EVENT: &str = r#"{
+        "type": "message",
+        "attributes": [
+            { "key": "YWN0aW9u", "value": "L3NlY3JldC5jb21wdXRlLnYxYmV0YTEuTXNnU3RvcmVDb2Rl", "index": true },
+            { "key": "Y29kZV9pZA==", "value": "MTI=", "index": true }
+        ]
+    }"#;
+
+    // secretcli output that renamed the key field
+    const MSG_KEY_EVENT: &str = r#"{
+        "type": "message",
+        "attributes": [
+            { "msg_key": "action", "value": "/secret.compute.v1beta1.MsgStoreCode" },
+            { "msg_key": "code_id", "value": "12" }
+        ]
+    }"#;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon \
+                            abandon abandon abandon abandon abandon about";
+    const ADDRESS: &str = "secret1gkle2qetd47g4qlruxu8kx4m97875t66qsgr0p";
+
+    #[test]
+    fn test_address_from_mnemonic() {
+        assert_eq!(address_from_mnemonic(MNEMONIC).unwrap(), ADDRESS);
+        assert!(address_from_mnemonic("not a mnemonic").is_err());
+    }
+
+    #[test]
+    fn test_address_from_pubkey() {
+        let pubkey = PubKey {
+            msg_type: "tendermint/PubKeySecp256k1".to_string(),
+            value: "Ahf+rTtp75Rgo4Y180LZcUwuGDllpabyUN4g9PAXjbWH".to_string(),
+        };
+        assert_eq!(pubkey.address().unwrap(), ADDRESS);
+        assert_eq!(
+            base64::encode(pubkey_from_mnemonic(MNEMONIC).unwrap()),
+            pubkey.value
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct MintAnswer {
+        token_id: String,
+    }
+
+    #[test]
+    fn test_decode_answer() {
+        let compute: TxCompute = serde_json::from_str(
+            r#"{
+                "answers": [{
+                    "type": "execute",
+                    "input": "{\"mint\":{}}",
+                    "output_data": "eyJ0b2tlbl9pZCI6IjQyIn0=",
+                    "output_data_as_string": ""
+                }],
+                "output_logs": []
+            }"#,
+        )
+        .unwrap();
+        let expected = MintAnswer {
+            token_id: "42".to_string(),
+        };
+        assert_eq!(compute.decode_answer::<MintAnswer>().unwrap(), expected);
+
+        let mut answer = compute.answers[0].clone();
+        answer.output_data_as_string = r#"{"token_id":"42"}"#.to_string();
+        assert_eq!(answer.decode::<MintAnswer>().unwrap(), expected);
+
+        let empty = TxCompute::default();
+        assert!(empty.decode_answer::<MintAnswer>().is_err());
+    }
+
+    #[test]
+    fn test_list_code_permissions() {
+        let codes: Vec<ListCodeResponse> = serde_json::from_str(
+            r#"[
+                { "code_id": 1, "creator": "secret1a", "code_hash": "aa" },
+                {
+                    "code_id": 2,
+                    "creator": "secret1a",
+                    "code_hash": "bb",
+                    "instantiate_permission": { "permission": "OnlyAddress", "address": "secret1a" }
+                },
+                {
+                    "code_id": 3,
+                    "creator": "secret1a",
+                    "code_hash": "cc",
+                    "instantiate_permission": { "permission": "Nobody" }
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        assert!(codes[0].can_instantiate("secret1b"));
+        assert!(codes[1].can_instantiate("secret1a"));
+        assert!(!codes[1].can_instantiate("secret1b"));
+        assert!(!codes[2].can_instantiate("secret1a"));
+    }
+
+    #[test]
+    fn test_list_filter() {
+        let contract = ListContractCode {
+            code_id: 1,
+            creator: "secret1a".to_string(),
+            label: "treasury-v2".to_string(),
+            address: "secret1contract".to_string(),
+            ..Default::default()
+        };
+
+        assert!(ListFilter::default().matches_contract(&contract));
+        assert!(ListFilter::default()
+            .creator("secret1a")
+            .label("treasury")
+            .matches_contract(&contract));
+        assert!(!ListFilter::default()
+            .creator("secret1b")
+            .matches_contract(&contract));
+        assert!(!ListFilter::default()
+            .label("staking")
+            .matches_contract(&contract));
+    }
+
+    #[test]
+    fn test_attribute_normalization() {
+        for fixture in [PLAINTEXT_EVENT, BASE64_EVENT, MSG_KEY_EVENT] {
+            let event: TxQueryEvents = serde_json::from_str(fixture).unwrap();
+
+            assert_eq!(event.msg_type, "message");
+            assert_eq!(event.attributes[0].msg_key, "action");
+            assert_eq!(
+                event.attributes[0].value,
+                "/secret.compute.v1beta1.MsgStoreCode"
+            );
+            assert_eq!(event.attributes[1].msg_key, "code_id");
+            assert_eq!(event.attributes[1].value, "12");
+
+            let log: TxOutputLog = serde_json::from_str(fixture).unwrap();
+            assert_eq!(log.attributes[1].key, "code_id");
+            assert_eq!(log.attributes[1].value, "12");
+        }
+    }
+
+    #[test]
+    fn test_attribute_missing_value() {
+        let attribute: TxAttribute =
+            serde_json::from_str(r#"{ "key": "sender", "value": null }"#).unwrap();
+        assert_eq!(attribute.key, "sender");
+        assert_eq!(attribute.value, "");
+
+        // plaintext keys that happen to be valid base64 are left alone
+        let attribute: TxAttribute =
+            serde_json::from_str(r#"{ "key": "from", "value": "abcd" }"#).unwrap();
+        assert_eq!(attribute.key, "from");
+        assert_eq!(attribute.value, "abcd");
+    }
+
+    #[test]
+    fn test_predict_contract_address() {
+        // cosmwasm-std's instantiate2_address vector, as a secret1 address
+        let creator = bech32::encode(
+            SECRET_HRP,
+            decode_hex("9999999999aaaaaaaaaabbbbbbbbbbcccccccccc")
+                .unwrap()
+                .to_base32(),
+            Variant::Bech32,
+        )
+        .unwrap();
+        let code_hash = "13a1fc994cc6d1c81b746ee0c0ff6f90043875e0bf1d9be6b7d779fc978dc2a5";
+        let expected = bech32::encode(
+            SECRET_HRP,
+            decode_hex("5e865d3e45ad3e961f77fd77d46543417ced44d924dc3e079b5415ff6775f847")
+                .unwrap()
+                .to_base32(),
+            Variant::Bech32,
+        )
+        .unwrap();
+        assert_eq!(
+            predict_contract_address("wasm", code_hash, &creator, b"a").unwrap(),
+            expected
+        );
+        assert_ne!(
+            predict_contract_address("wasm", code_hash, &creator, b"b").unwrap(),
+            expected
+        );
+        assert!(predict_contract_address("wasm", code_hash, &creator, b"").is_err());
+        assert!(decode_hex("abc").is_err());
+    }
+
+    #[test]
+    fn test_permit_sign_doc() {
+        let params = PermitParams {
+            permit_name: "test".to_string(),
+            allowed_tokens: vec!["secret1token".to_string()],
+            chain_id: "secret-4".to_string(),
+            permissions: vec!["balance".to_string()],
+            delegate: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&params.sign_doc()).unwrap(),
+            concat!(
+                r#"{"account_number":"0","chain_id":"secret-4","#,
+                r#""fee":{"amount":[{"amount":"0","denom":"uscrt"}],"gas":"1"},"memo":"","#,
+                r#""msgs":[{"type":"query_permit","value":{"allowed_tokens":["secret1token"],"#,
+                r#""permissions":["balance"],"permit_name":"test"}}],"sequence":"0"}"#
+            )
+        );
+
+        let delegated = PermitParams {
+            delegate: Some("secret1dapp".to_string()),
+            ..params
+        };
+        assert!(serde_json::to_string(&delegated.sign_doc())
+            .unwrap()
+            .contains(r#""allowed_tokens":["secret1token"],"delegate":"secret1dapp","#));
+    }
+
+    /// Permits signed here have to verify in contracts using secret-utils
+    #[test]
+    fn test_permit_matches_secret_utils() {
+        use cosmwasm_std::testing::MockApi;
+        use cosmwasm_std::{BlockInfo, Timestamp};
+
+        let pub_key = |value: &str| PubKey {
+            msg_type: "tendermint/PubKeySecp256k1".to_string(),
+            value: value.to_string(),
+        };
+        let permit = Permit {
+            params: PermitParams {
+                permit_name: "dapp".to_string(),
+                allowed_tokens: vec!["secret1token".to_string()],
+                chain_id: "secret-4".to_string(),
+                permissions: vec!["balance".to_string()],
+                delegate: Some("secret18kvyc4nelyueadktgx574st0dxkl9xexk9435e".to_string()),
+            },
+            signature: SignedTx {
+                pub_key: pub_key("AnWSqrXUNhjdoT+6ceOZPNdRenEtPaSWZMBu4b09H3Cv"),
+                signature: "gIB0DnCQQdG6NnCKDG6ZXkjELk89xhIBc+KFjD7cxEkzEV/qZDmW8dt/Z003nZ4aZBZzY1ml4dIFILcfoUwOUA==".to_string(),
+            },
+        };
+        let proof = RelayerProof {
+            expires: 1_700_000_000,
+            signature: SignedTx {
+                pub_key: pub_key("AuV0DmO60oCB7Xz2VN1sGQKcoDOC/AWrX13agfLFW4Rb"),
+                signature: "G2AwjMgQ2UNkVyDcDIZ3ivf46CiFTOTH/f+hFKrTn+0kys0m4bljtRPmhVnP3os5047MSeQhSfaq6DaVYVSuqw==".to_string(),
+            },
+        };
+        // What contracts receive
+        let utils_permit: secret_utils::Permit =
+            serde_json::from_value(serde_json::to_value(&permit).unwrap()).unwrap();
+        let utils_proof: secret_utils::RelayerProof =
+            serde_json::from_value(serde_json::to_value(&proof).unwrap()).unwrap();
+
+        assert_eq!(
+            serde_json::to_vec(&permit.params.sign_doc()).unwrap(),
+            utils_permit.sign_bytes().unwrap()
+        );
+        let mut undelegated = utils_permit.clone();
+        undelegated.params.delegate = None;
+        assert_eq!(
+            serde_json::to_vec(
+                &PermitParams {
+                    delegate: None,
+                    ..permit.params.clone()
+                }
+                .sign_doc()
+            )
+            .unwrap(),
+            undelegated.sign_bytes().unwrap()
+        );
+        assert_eq!(
+            permit.relayer_proof_data(proof.expires).unwrap(),
+            utils_permit.relayer_proof_data(proof.expires)
+        );
+
+        let block = BlockInfo {
+            height: 1,
+            time: Timestamp::from_seconds(proof.expires - 60),
+            chain_id: "secret-4".to_string(),
+        };
+        assert_eq!(
+            utils_permit
+                .verify_delegated(
+                    &MockApi::default(),
+                    &block,
+                    "secret1token",
+                    "secret",
+                    "secret18kvyc4nelyueadktgx574st0dxkl9xexk9435e",
+                    &utils_proof,
+                )
+                .unwrap(),
+            "secret1jfw5q2ygp0gvn450h3lu0hlwjanfsc5ufe5wu3"
+        );
+    }
+
+    #[test]
+    fn test_adr36_sign_doc() {
+        assert_eq!(
+            serde_json::to_string(&adr36_sign_doc("secret1signer", b"login")).unwrap(),
+            concat!(
+                r#"{"account_number":"0","chain_id":"","fee":{"amount":[],"gas":"0"},"memo":"","#,
+                r#""msgs":[{"type":"sign/MsgSignData","value":{"data":"bG9naW4=","#,
+                r#""signer":"secret1signer"}}],"sequence":"0"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_funds() {
+        let ibc = "ibc/27A6394C3F9FF9C9DCF5DFFADF9BB5FE9A37C7E92B006199894CF1824DF9AC7C";
+        let coins = parse_coins(&format!("100uscrt, 5{}", ibc)).unwrap();
+        assert_eq!(coins, vec![TxCoin::new(100, "uscrt"), TxCoin::new(5, ibc)]);
+        assert_eq!(
+            validate_funds(&coins).unwrap(),
+            vec![TxCoin::new(5, ibc), TxCoin::new(100, "uscrt")]
+        );
+        assert!(parse_coins("").unwrap().is_empty());
+        assert!(parse_coins("uscrt").is_err());
+        assert!(parse_coins("100").is_err());
+
+        assert!(validate_funds(&[TxCoin::new(0, "uscrt")]).is_err());
+        assert!(validate_funds(&[TxCoin::new(1, "1scrt")]).is_err());
+        assert!(validate_funds(&[TxCoin::new(1, "uscrt"), TxCoin::new(2, "uscrt")]).is_err());
+    }
+
+    #[test]
+    fn test_coins_arg() {
+        assert_eq!(coins_arg(&[]), "");
+        assert_eq!(
+            coins_arg(&[
+                TxCoin::new(100, "uscrt"),
+                TxCoin::new(
+                    5,
+                    "ibc/27A6394C3F9FF9C9DCF5DFFADF9BB5FE9A37C7E92B006199894CF1824DF9AC7C"
+                )
+            ]),
+            "100uscrt,5ibc/27A6394C3F9FF9C9DCF5DFFADF9BB5FE9A37C7E92B006199894CF1824DF9AC7C"
+        );
+    }
+
+    #[test]
+    fn test_output_tolerance() {
+        let output = serde_json::json!({
+            "code_id": 1,
+            "creator": "secret1a",
+            "code_hash": "aa",
+            "source": "https://example.com"
+        });
+
+        let code: ListCodeResponse = parse_output(output.clone()).unwrap();
+        assert_eq!(code.extra["source"], "https://example.com");
+        let code: ListCodeResponse = parse_output(serde_json::json!({ "code_id": 1 })).unwrap();
+        assert_eq!(code.code_hash, "");
+
+        // strict per call, the global flag would leak into tests running in parallel
+        let unknown = parse_output_strict::<ListCodeResponse>(output).unwrap_err();
+        let missing = parse_output_strict::<ListCodeResponse>(serde_json::json!({
+            "code_id": 1,
+            "creator": "secret1a",
+            "code_hash": "aa"
+        }))
+        .unwrap_err();
+
+        assert!(unknown.to_string().contains("unknown field source"));
+        assert!(missing
+            .to_string()
+            .contains("missing field instantiate_permission"));
+    }
+
+    #[test]
+    fn test_handle_answer() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Minted {
+            token_id: String,
+        }
+
+        let answer = |data: &str| TxCompute {
+            answers: vec![TxAnswer {
+                output_data_as_string: data.to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let success = answer(r#"{"status":"success","data":{"token_id":"42"}}"#);
+        assert_eq!(
+            success.decode_handle_answer::<Minted>().unwrap(),
+            Minted {
+                token_id: "42".to_string()
+            }
+        );
+        let failure = answer(r#"{"status":"failure"}"#);
+        assert!(failure.decode_handle_answer::<Minted>().is_err());
+    }
+
+    #[test]
+    fn test_denom_metadata() {
+        let metadata: DenomMetadata = serde_json::from_str(
+            r#"{
+                "description": "The native token of Secret Network",
+                "denom_units": [
+                    { "denom": "uscrt", "exponent": 0, "aliases": ["microscrt"] },
+                    { "denom": "scrt", "exponent": 6, "aliases": [] }
+                ],
+                "base": "uscrt",
+                "display": "scrt",
+                "name": "Secret",
+                "symbol": "SCRT"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(metadata.exponent(&metadata.display), Some(6));
+        assert_eq!(metadata.exponent("microscrt"), Some(0));
+        assert_eq!(metadata.exponent("mscrt"), None);
+        assert!(metadata.unknown_fields().is_empty());
+    }
+
+    #[test]
+    fn test_slashing_types() {
+        let info: ValidatorSigningInfo = serde_json::from_str(
+            r#"{
+                "address": "secretvalcons1abc",
+                "start_height": "0",
+                "index_offset": "120",
+                "jailed_until": "2023-11-14T22:13:20Z",
+                "tombstoned": false,
+                "missed_blocks_counter": "7"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(info.missed_blocks_counter, 7);
+        let before: DateTime<Utc> = "2023-11-14T22:00:00Z".parse().unwrap();
+        assert!(info.is_jailed(before));
+        assert!(!info.is_jailed(before + chrono::Duration::hours(1)));
+
+        let evidence: Evidence = serde_json::from_str(
+            r#"{
+                "@type": "/cosmos.evidence.v1beta1.Equivocation",
+                "height": "42",
+                "time": "2023-11-14T22:13:20Z",
+                "power": "100",
+                "consensus_address": "secretvalcons1abc"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(evidence.height, 42);
+        assert_eq!(evidence.consensus_address, info.address);
+    }
+
+    #[test]
+    fn test_batch_query_result() {
+        let results: Vec<BatchQueryResult> =
+            serde_json::from_str(r#"[{"ok":"eyJjb3VudCI6M30="},{"err":"bob not found"}]"#).unwrap();
+        assert_eq!(
+            results[0].decode::<Value>().unwrap(),
+            serde_json::json!({"count": 3})
+        );
+        assert_eq!(
+            results[1].decode::<Value>().unwrap_err().to_string(),
+            "bob not found"
+        );
+    }
+}
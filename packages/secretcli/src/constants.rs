@@ -2,3 +2,9 @@ pub const SECRETCLI: &str = "secretd";
 
 pub const STORE_GAS: &str = "10000000";
 pub const GAS: &str = "800000";
+
+pub const SECRET_HRP: &str = "secret";
+pub const SECRET_HD_PATH: &str = "m/44'/529'/0'/0/0";
+
+/// Name of the wasm module, part of the preimage of `instantiate2` addresses
+pub const COMPUTE_MODULE: &str = "compute";
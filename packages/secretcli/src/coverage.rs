@@ -0,0 +1,226 @@
+use crate::{cli_types::NetContract, schema::ContractSchemas};
+use serde::{Deserialize, Serialize};
+use serde_json::{Result, Value};
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex};
+
+static COVERAGE: Mutex<CoverageMatrix> = Mutex::new(CoverageMatrix {
+    contracts: BTreeMap::new(),
+});
+
+///
+/// Kind of message a contract was sent
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryPoint {
+    Execute,
+    Query,
+}
+
+impl EntryPoint {
+    /// Schema files the entry point's message may be in, newest name first
+    fn schema_names(&self) -> &'static [&'static str] {
+        match self {
+            EntryPoint::Execute => &["execute_msg", "handle_msg"],
+            EntryPoint::Query => &["query_msg"],
+        }
+    }
+}
+
+///
+/// How often each message variant of a contract was sent
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContractCoverage {
+    pub label: String,
+    pub execute: BTreeMap<String, u64>,
+    pub query: BTreeMap<String, u64>,
+}
+
+impl ContractCoverage {
+    pub fn calls(&self, entry: EntryPoint) -> &BTreeMap<String, u64> {
+        match entry {
+            EntryPoint::Execute => &self.execute,
+            EntryPoint::Query => &self.query,
+        }
+    }
+
+    fn calls_mut(&mut self, entry: EntryPoint) -> &mut BTreeMap<String, u64> {
+        match entry {
+            EntryPoint::Execute => &mut self.execute,
+            EntryPoint::Query => &mut self.query,
+        }
+    }
+
+    ///
+    /// Variants of the contract's schema nothing sent during the run
+    ///
+    pub fn untested(&self, entry: EntryPoint, schemas: &ContractSchemas) -> Result<Vec<String>> {
+        let calls = self.calls(entry);
+        Ok(schema_variants(entry, schemas)?
+            .into_iter()
+            .filter(|variant| !calls.contains_key(variant))
+            .collect())
+    }
+}
+
+///
+/// Message variants exercised per contract address, recorded for every execute and query
+/// sent through this crate
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoverageMatrix {
+    pub contracts: BTreeMap<String, ContractCoverage>,
+}
+
+impl CoverageMatrix {
+    fn record(&mut self, contract: &NetContract, entry: EntryPoint, variant: String) {
+        let coverage = self.contracts.entry(contract.address.clone()).or_default();
+        coverage.label = contract.label.clone();
+        *coverage.calls_mut(entry).entry(variant).or_default() += 1;
+    }
+
+    pub fn get(&self, address: &str) -> Option<&ContractCoverage> {
+        self.contracts.get(address)
+    }
+
+    ///
+    /// A table of every variant of each contract and how often it was sent, untested ones
+    /// are flagged
+    ///
+    /// # Arguments
+    ///
+    /// * 'schemas' - The contracts to report on and their schemas, listing the variants
+    ///
+    pub fn render(&self, schemas: &[(&NetContract, &ContractSchemas)]) -> Result<String> {
+        let mut out = String::new();
+        for (contract, schemas) in schemas {
+            let coverage = self.get(&contract.address).cloned().unwrap_or_default();
+            let _ = writeln!(out, "{} ({})", contract.label, contract.address);
+            for entry in [EntryPoint::Execute, EntryPoint::Query] {
+                let calls = coverage.calls(entry);
+                let mut variants = schema_variants(entry, schemas)?;
+                // Variants the schema doesn't know about were still sent
+                let unknown: Vec<String> = calls
+                    .keys()
+                    .filter(|variant| !variants.contains(variant))
+                    .cloned()
+                    .collect();
+                variants.extend(unknown);
+                for variant in variants {
+                    let count = calls.get(&variant).copied().unwrap_or_default();
+                    let _ = writeln!(
+                        out,
+                        "  {:<8} {:<32} {:>6}{}",
+                        format!("{:?}", entry).to_lowercase(),
+                        variant,
+                        count,
+                        if count == 0 { "  UNTESTED" } else { "" }
+                    );
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Variants of the entry point's message, none if the contract has no schema for it
+fn schema_variants(entry: EntryPoint, schemas: &ContractSchemas) -> Result<Vec<String>> {
+    match entry
+        .schema_names()
+        .iter()
+        .find(|name| schemas.schemas.contains_key(**name))
+    {
+        Some(name) => schemas.variants(name),
+        None => Ok(vec![]),
+    }
+}
+
+/// The variant of a serialized enum message, `{"transfer":{..}}` and `"transfer"` alike
+pub fn variant_name(msg: &Value) -> Option<String> {
+    match msg {
+        Value::String(variant) => Some(variant.clone()),
+        Value::Object(map) if map.len() == 1 => map.keys().next().cloned(),
+        _ => None,
+    }
+}
+
+/// Counts the message towards the contract's coverage
+pub(crate) fn record<Message: Serialize + ?Sized>(
+    contract: &NetContract,
+    entry: EntryPoint,
+    msg: &Message,
+) {
+    if let Some(variant) = serde_json::to_value(msg)
+        .ok()
+        .as_ref()
+        .and_then(variant_name)
+    {
+        COVERAGE
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .record(contract, entry, variant);
+    }
+}
+
+///
+/// Everything recorded so far
+///
+pub fn coverage() -> CoverageMatrix {
+    COVERAGE
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone()
+}
+
+pub fn reset_coverage() {
+    COVERAGE
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .contracts
+        .clear();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_variant_name() {
+        assert_eq!(
+            variant_name(&json!({ "transfer": { "amount": "1" } })),
+            Some("transfer".to_string())
+        );
+        assert_eq!(variant_name(&json!("pause")), Some("pause".to_string()));
+        assert_eq!(variant_name(&json!({ "a": 1, "b": 2 })), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let contract = NetContract::new("token", "1", "secret1token", "hash");
+        let mut matrix = CoverageMatrix::default();
+        matrix.record(&contract, EntryPoint::Execute, "transfer".to_string());
+        matrix.record(&contract, EntryPoint::Execute, "transfer".to_string());
+
+        let mut schemas = ContractSchemas::default();
+        schemas.schemas.insert(
+            "execute_msg".to_string(),
+            json!({ "oneOf": [
+                { "type": "object", "required": ["transfer"], "properties": { "transfer": {} } },
+                { "type": "object", "required": ["burn"], "properties": { "burn": {} } }
+            ] }),
+        );
+        let out = matrix.render(&[(&contract, &schemas)]).unwrap();
+        assert!(out.contains("transfer"));
+        assert!(out
+            .lines()
+            .any(|line| line.contains("burn") && line.ends_with("UNTESTED")));
+        assert_eq!(
+            matrix.contracts["secret1token"]
+                .untested(EntryPoint::Execute, &schemas)
+                .unwrap(),
+            vec!["burn"]
+        );
+    }
+}
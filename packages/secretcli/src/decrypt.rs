@@ -0,0 +1,188 @@
+use crate::{
+    cli_types::{TxCompute, TxQuery},
+    secretcli::{compute_hash, key_address, query_hash},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Result, Value};
+use std::{
+    fs::{self, create_dir_all},
+    path::{Path, PathBuf},
+};
+
+///
+/// A contract message as it was sent and answered, decrypted
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DecryptedMsg {
+    /// `execute` or `instantiate`
+    pub r#type: String,
+    /// The message, parsed when it was JSON
+    pub input: Value,
+    /// What the contract returned, parsed when it was JSON
+    pub output: Value,
+}
+
+///
+/// A contract event with its attributes decrypted
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DecryptedLog {
+    pub r#type: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+///
+/// Everything an auditor needs to see what a tx actually sent, in plaintext
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DecryptedTx {
+    pub txhash: String,
+    pub height: u64,
+    pub signer: String,
+    pub msgs: Vec<DecryptedMsg>,
+    pub logs: Vec<DecryptedLog>,
+}
+
+/// JSON as it is, anything else as a string
+fn parse_payload(payload: &str) -> Value {
+    serde_json::from_str(payload).unwrap_or_else(|_| Value::String(payload.to_string()))
+}
+
+/// Address that sent the tx's messages
+fn tx_sender(tx: &TxQuery) -> Option<&str> {
+    tx.logs
+        .iter()
+        .flat_map(|log| &log.events)
+        .filter(|event| event.msg_type == "message")
+        .flat_map(|event| &event.attributes)
+        .find(|attribute| attribute.msg_key == "sender")
+        .map(|attribute| attribute.value.as_str())
+}
+
+impl DecryptedTx {
+    pub fn new(tx: &TxQuery, compute: &TxCompute, signer: &str) -> Result<Self> {
+        let msgs = compute
+            .answers
+            .iter()
+            .map(|answer| {
+                let output = answer.data()?;
+                Ok(DecryptedMsg {
+                    r#type: answer.r#type.clone(),
+                    input: parse_payload(&answer.input),
+                    output: parse_payload(&String::from_utf8_lossy(&output)),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(DecryptedTx {
+            txhash: tx.txhash.clone(),
+            height: tx.height,
+            signer: signer.to_string(),
+            msgs,
+            logs: compute
+                .output_logs
+                .iter()
+                .map(|log| DecryptedLog {
+                    r#type: log.r#type.clone(),
+                    attributes: log
+                        .attributes
+                        .iter()
+                        .map(|attr| (attr.key.clone(), attr.value.clone()))
+                        .collect(),
+                })
+                .collect(),
+        })
+    }
+}
+
+///
+/// Decrypts a tx with secretd, which can only decrypt txs signed from its own keyring
+///
+/// # Arguments
+///
+/// * 'txhash' - Hash of the tx
+/// * 'signer' - Name of the local key that signed it
+///
+pub fn decrypt_tx(txhash: &str, signer: &str) -> Result<DecryptedTx> {
+    let tx = query_hash(txhash.to_string())?;
+    let address = key_address(signer).map_err(serde::de::Error::custom)?;
+    if let Some(sender) = tx_sender(&tx) {
+        if sender != address {
+            return Err(serde::de::Error::custom(format!(
+                "{} was sent by {}, not by {} ({})",
+                txhash, sender, signer, address
+            )));
+        }
+    }
+    DecryptedTx::new(&tx, &compute_hash(txhash.to_string())?, &address)
+}
+
+///
+/// Decrypts the txs and writes each to `<dir>/<txhash>.json`, returning the files written
+///
+/// # Arguments
+///
+/// * 'txhashes' - Hashes of the txs to export
+/// * 'signer' - Name of the local key that signed them
+/// * 'dir' - Directory the files are written to, created if missing
+///
+pub fn export_decrypted_txs(
+    txhashes: &[&str],
+    signer: &str,
+    dir: impl AsRef<Path>,
+) -> Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    create_dir_all(dir).map_err(serde_json::Error::io)?;
+    txhashes
+        .iter()
+        .map(|txhash| {
+            let decrypted = decrypt_tx(txhash, signer)?;
+            let path = dir.join(format!("{}.json", decrypted.txhash));
+            fs::write(&path, serde_json::to_string_pretty(&decrypted)?)
+                .map_err(serde_json::Error::io)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_decrypted_tx() {
+        let tx: TxQuery = serde_json::from_value(json!({
+            "height": "42",
+            "txhash": "A1B2",
+            "logs": [{ "msg_index": 0, "log": "", "events": [
+                { "type": "message", "attributes": [{ "key": "sender", "value": "secret1alice" }] }
+            ] }]
+        }))
+        .unwrap();
+        let compute: TxCompute = serde_json::from_value(json!({
+            "answers": [{
+                "type": "execute",
+                "input": "{\"transfer\":{\"amount\":\"5\"}}",
+                "output_data": "",
+                "output_data_as_string": "{\"transfer\":{\"status\":\"success\"}}"
+            }],
+            "output_logs": [{ "type": "wasm", "attributes": [{ "key": "amount", "value": "5" }] }]
+        }))
+        .unwrap();
+
+        assert_eq!(tx_sender(&tx), Some("secret1alice"));
+        let decrypted = DecryptedTx::new(&tx, &compute, "secret1alice").unwrap();
+        assert_eq!(
+            decrypted.msgs[0].input,
+            json!({ "transfer": { "amount": "5" } })
+        );
+        assert_eq!(
+            decrypted.msgs[0].output,
+            json!({ "transfer": { "status": "success" } })
+        );
+        assert_eq!(
+            decrypted.logs[0].attributes,
+            vec![("amount".to_string(), "5".to_string())]
+        );
+    }
+}
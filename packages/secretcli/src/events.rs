@@ -0,0 +1,307 @@
+use crate::{
+    cli_types::{TxCompute, TxQuery},
+    secretcli::{compute_hash, latest_block_height, txs_at_height},
+};
+use serde_json::Result;
+
+///
+/// An event a contract emitted, with its attributes decrypted when possible
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractEvent {
+    pub height: u64,
+    pub txhash: String,
+    pub contract: String,
+    /// `wasm` for plain attributes, `wasm-<type>` for custom events
+    pub event_type: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl ContractEvent {
+    /// Value of the first attribute with the key
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn has_attribute(&self, key: &str, value: &str) -> bool {
+        self.attributes.iter().any(|(k, v)| k == key && v == value)
+    }
+}
+
+///
+/// Follows the chain from a starting height and indexes the wasm events of every tx, so tests
+/// can assert on events without querying each tx hash.
+/// Call [EventIndexer::sync] to catch up with the chain before asserting.
+///
+#[derive(Debug, Clone, Default)]
+pub struct EventIndexer {
+    /// Next height to index
+    next_height: u64,
+    events: Vec<ContractEvent>,
+}
+
+impl EventIndexer {
+    pub fn new(from_height: u64) -> Self {
+        EventIndexer {
+            next_height: from_height,
+            events: vec![],
+        }
+    }
+
+    ///
+    /// Indexes from the next block on, i.e. only what happens after this call
+    ///
+    pub fn from_latest() -> Result<Self> {
+        Ok(Self::new(latest_block_height()? + 1))
+    }
+
+    ///
+    /// Indexes every block up to the latest one, returns how many events were added
+    ///
+    pub fn sync(&mut self) -> Result<usize> {
+        let before = self.events.len();
+        let latest = latest_block_height()?;
+        while self.next_height <= latest {
+            for tx in txs_at_height(self.next_height)? {
+                // Compute queries decrypt the attributes if the tx was sent from a local key
+                let decrypted = if has_wasm_events(&tx) {
+                    compute_hash(tx.txhash.clone()).ok()
+                } else {
+                    None
+                };
+                self.index_tx(&tx, decrypted.as_ref());
+            }
+            self.next_height += 1;
+        }
+        Ok(self.events.len() - before)
+    }
+
+    ///
+    /// Indexes the wasm events of a tx, from its decrypted compute logs when given
+    ///
+    pub fn index_tx(&mut self, tx: &TxQuery, decrypted: Option<&TxCompute>) {
+        let events: Vec<(&str, Vec<(&str, &str)>)> = match decrypted {
+            Some(compute) if !compute.output_logs.is_empty() => compute
+                .output_logs
+                .iter()
+                .map(|log| {
+                    let attributes = log
+                        .attributes
+                        .iter()
+                        .map(|attr| (attr.key.as_str(), attr.value.as_str()))
+                        .collect();
+                    (log.r#type.as_str(), attributes)
+                })
+                .collect(),
+            _ => tx
+                .logs
+                .iter()
+                .flat_map(|log| &log.events)
+                .map(|event| {
+                    let attributes = event
+                        .attributes
+                        .iter()
+                        .map(|attr| (attr.msg_key.as_str(), attr.value.as_str()))
+                        .collect();
+                    (event.msg_type.as_str(), attributes)
+                })
+                .collect(),
+        };
+
+        for (event_type, attributes) in events {
+            if !is_wasm_event(event_type) {
+                continue;
+            }
+            // Events of the same type within a message are merged, each contract's
+            // attributes start with its address
+            for (key, value) in attributes {
+                if key == "contract_address" {
+                    self.events.push(ContractEvent {
+                        height: tx.height,
+                        txhash: tx.txhash.clone(),
+                        contract: value.to_string(),
+                        event_type: event_type.to_string(),
+                        attributes: vec![],
+                    });
+                } else if let Some(event) = self.events.last_mut() {
+                    if event.txhash == tx.txhash && event.event_type == event_type {
+                        event.attributes.push((key.to_string(), value.to_string()));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Height of the next block [EventIndexer::sync] indexes
+    pub fn next_height(&self) -> u64 {
+        self.next_height
+    }
+
+    pub fn events(&self) -> &[ContractEvent] {
+        &self.events
+    }
+
+    /// Events emitted by the contract, in chain order
+    pub fn events_for(&self, contract: &str) -> EventFilter<'_> {
+        EventFilter {
+            events: self
+                .events
+                .iter()
+                .filter(|event| event.contract == contract)
+                .collect(),
+        }
+    }
+}
+
+///
+/// A selection of indexed events that can be narrowed down further
+///
+#[derive(Debug, Clone)]
+pub struct EventFilter<'a> {
+    events: Vec<&'a ContractEvent>,
+}
+
+impl<'a> EventFilter<'a> {
+    pub fn filter<F: Fn(&ContractEvent) -> bool>(self, predicate: F) -> Self {
+        EventFilter {
+            events: self
+                .events
+                .into_iter()
+                .filter(|event| predicate(event))
+                .collect(),
+        }
+    }
+
+    /// Events of the given type, `wasm` or the `wasm-` prefixed custom type
+    pub fn of_type(self, event_type: &str) -> Self {
+        self.filter(|event| {
+            event.event_type == event_type
+                || event.event_type.strip_prefix("wasm-") == Some(event_type)
+        })
+    }
+
+    pub fn with_attribute(self, key: &str, value: &str) -> Self {
+        self.filter(|event| event.has_attribute(key, value))
+    }
+
+    pub fn in_tx(self, txhash: &str) -> Self {
+        self.filter(|event| event.txhash == txhash)
+    }
+
+    pub fn count(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn first(&self) -> Option<&'a ContractEvent> {
+        self.events.first().copied()
+    }
+
+    pub fn last(&self) -> Option<&'a ContractEvent> {
+        self.events.last().copied()
+    }
+
+    pub fn all(&self) -> &[&'a ContractEvent] {
+        &self.events
+    }
+}
+
+fn is_wasm_event(event_type: &str) -> bool {
+    event_type == "wasm" || event_type.starts_with("wasm-")
+}
+
+fn has_wasm_events(tx: &TxQuery) -> bool {
+    tx.logs
+        .iter()
+        .flat_map(|log| &log.events)
+        .any(|event| is_wasm_event(&event.msg_type))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TX: &str = r#"{
+        "height": "42",
+        "txhash": "A1B2C3",
+        "logs": [{
+            "msg_index": 0,
+            "log": "",
+            "events": [
+                {"type": "message", "attributes": [{"key": "action", "value": "execute"}]},
+                {"type": "wasm", "attributes": [
+                    {"key": "contract_address", "value": "secret1token"},
+                    {"key": "action", "value": "transfer"},
+                    {"key": "amount", "value": "100"},
+                    {"key": "contract_address", "value": "secret1market"},
+                    {"key": "action", "value": "receive"}
+                ]},
+                {"type": "wasm-sale", "attributes": [
+                    {"key": "contract_address", "value": "secret1market"},
+                    {"key": "price", "value": "100"}
+                ]}
+            ]
+        }],
+        "timestamp": "2022-01-01T00:00:00Z"
+    }"#;
+
+    #[test]
+    fn test_index_tx() {
+        let tx: TxQuery = serde_json::from_str(TX).unwrap();
+        let mut indexer = EventIndexer::new(42);
+        indexer.index_tx(&tx, None);
+        assert_eq!(indexer.events().len(), 3);
+
+        let token = indexer.events_for("secret1token");
+        assert_eq!(token.count(), 1);
+        let transfer = token.first().unwrap();
+        assert_eq!(transfer.height, 42);
+        assert_eq!(transfer.txhash, "A1B2C3");
+        assert_eq!(transfer.attribute("amount"), Some("100"));
+        assert_eq!(transfer.attribute("price"), None);
+
+        let market = indexer.events_for("secret1market");
+        assert_eq!(market.count(), 2);
+        assert_eq!(market.clone().of_type("wasm").count(), 1);
+        let sale = market.clone().of_type("sale");
+        assert_eq!(sale.count(), 1);
+        assert_eq!(sale.first().unwrap().attribute("price"), Some("100"));
+        assert_eq!(
+            market.clone().with_attribute("action", "receive").count(),
+            1
+        );
+        assert!(market.in_tx("D4E5F6").is_empty());
+        assert!(indexer.events_for("secret1other").is_empty());
+    }
+
+    #[test]
+    fn test_index_decrypted_tx() {
+        let tx: TxQuery = serde_json::from_str(TX).unwrap();
+        let compute: TxCompute = serde_json::from_str(
+            r#"{
+                "answers": [],
+                "output_logs": [{"type": "wasm", "attributes": [
+                    {"key": "contract_address", "value": "secret1token"},
+                    {"key": "memo", "value": "decrypted"}
+                ]}]
+            }"#,
+        )
+        .unwrap();
+        let mut indexer = EventIndexer::new(42);
+        indexer.index_tx(&tx, Some(&compute));
+        assert_eq!(indexer.events().len(), 1);
+        assert_eq!(
+            indexer
+                .events_for("secret1token")
+                .with_attribute("memo", "decrypted")
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,656 @@
+use crate::{
+    network::{network_config, node_is_healthy, set_network_config, NetworkConfig},
+    utils::generate_label,
+};
+use bech32::{FromBase32, ToBase32, Variant};
+use serde::de::Error;
+use serde_json::{json, Result, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    thread, time,
+};
+
+/// Image the fork runs by default, override it with one sharing the source chain's seed
+pub const LOCALSECRET_IMAGE: &str = "ghcr.io/scrtlabs/localsecret:latest";
+
+/// Where localsecret keeps its genesis inside the container
+const CONTAINER_GENESIS: &str = "/root/.secretd/config/genesis.json";
+/// Where localsecret keeps its consensus key inside the container
+const CONTAINER_VALIDATOR_KEY: &str = "/root/.secretd/config/priv_validator_key.json";
+
+/// Chain id of forks by default, so txs signed for the source chain can't be replayed on it
+pub const FORK_CHAIN_ID: &str = "secret-fork";
+
+/// Tokens per unit of consensus power, the SDK's default power reduction
+const POWER_REDUCTION: u128 = 1_000_000;
+
+/// Timestamp the SDK exports for unset times
+const ZERO_TIME: &str = "1970-01-01T00:00:00Z";
+
+///
+/// The single validator a fork runs with in place of the source chain's validator set
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForkValidator {
+    /// Account self-delegating to the validator, its operator address is derived from it
+    pub account: String,
+    /// Base64 ed25519 consensus public key of the fork's node
+    pub consensus_pubkey: String,
+    /// Tokens bonded to the validator, in the staking denom
+    pub tokens: u128,
+}
+
+impl ForkValidator {
+    ///
+    /// The validator of a node's `priv_validator_key.json`
+    ///
+    /// # Arguments
+    ///
+    /// * 'key_file' - Consensus key of the fork's node
+    /// * 'account' - Account self-delegating to the validator
+    /// * 'tokens' - Tokens bonded to the validator
+    ///
+    pub fn from_key_file(key_file: &Path, account: &str, tokens: u128) -> Result<Self> {
+        let key: Value =
+            serde_json::from_slice(&fs::read(key_file).map_err(serde_json::Error::io)?)?;
+        let consensus_pubkey = key
+            .pointer("/pub_key/value")
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::custom("Validator key file has no pub_key"))?;
+        Ok(ForkValidator {
+            account: account.to_string(),
+            consensus_pubkey: consensus_pubkey.to_string(),
+            tokens,
+        })
+    }
+}
+
+///
+/// Contracts and codes kept in a forked genesis
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForkedState {
+    pub contracts: Vec<String>,
+    pub code_ids: Vec<String>,
+}
+
+///
+/// Turns an exported genesis into one a single local node can run: the compute state is
+/// trimmed down to the given contracts and the codes they run, so the fork boots quickly with
+/// only the state under test, the source chain's validators are replaced with the local one
+/// and the chain id is changed
+///
+/// # Arguments
+///
+/// * 'genesis' - Genesis from `secretd export` on a node synced with the source chain
+/// * 'contracts' - Addresses of the contracts to keep
+/// * 'validator' - The fork's only validator
+/// * 'chain_id' - Chain id of the fork, see [FORK_CHAIN_ID]
+///
+pub fn fork_genesis(
+    genesis: &mut Value,
+    contracts: &[&str],
+    validator: &ForkValidator,
+    chain_id: &str,
+) -> Result<ForkedState> {
+    let state = trim_compute(genesis, contracts)?;
+    replace_validators(genesis, validator)?;
+    genesis["chain_id"] = json!(chain_id);
+    Ok(state)
+}
+
+fn trim_compute(genesis: &mut Value, contracts: &[&str]) -> Result<ForkedState> {
+    let compute = genesis
+        .pointer_mut("/app_state/compute")
+        .ok_or_else(|| Error::custom("Genesis has no compute state"))?;
+
+    let mut kept = vec![];
+    let mut code_ids = BTreeSet::new();
+    if let Some(exported) = compute.get_mut("contracts").and_then(Value::as_array_mut) {
+        exported.retain(|contract| {
+            let address = contract["contract_address"].as_str().unwrap_or_default();
+            if !contracts.contains(&address) {
+                return false;
+            }
+            kept.push(address.to_string());
+            if let Some(code_id) = code_id(&contract["contract_info"]["code_id"]) {
+                code_ids.insert(code_id);
+            }
+            true
+        });
+    }
+    if let Some(missing) = contracts
+        .iter()
+        .find(|address| !kept.iter().any(|kept| kept == *address))
+    {
+        return Err(Error::custom(format!(
+            "Contract {} is not in the exported genesis",
+            missing
+        )));
+    }
+    if let Some(codes) = compute.get_mut("codes").and_then(Value::as_array_mut) {
+        codes.retain(|code| code_id(&code["code_id"]).map_or(false, |id| code_ids.contains(&id)));
+    }
+
+    Ok(ForkedState {
+        contracts: kept,
+        code_ids: code_ids.into_iter().collect(),
+    })
+}
+
+///
+/// Swaps the validator set for the local validator, bonded with its account's self-delegation.
+/// Every other bond, unbonding and reward record goes, and the module accounts are funded to
+/// match so the fork passes genesis validation
+///
+fn replace_validators(genesis: &mut Value, validator: &ForkValidator) -> Result<()> {
+    let pubkey = base64::decode(&validator.consensus_pubkey).map_err(Error::custom)?;
+    if pubkey.len() != 32 {
+        return Err(Error::custom("Consensus key is not an ed25519 public key"));
+    }
+    let consensus_address = &Sha256::digest(&pubkey)[..20];
+    let (hrp, account, _) = bech32::decode(&validator.account).map_err(Error::custom)?;
+    let account = Vec::<u8>::from_base32(&account).map_err(Error::custom)?;
+    let address = |suffix: &str, data: &[u8]| -> Result<String> {
+        bech32::encode(
+            &format!("{}{}", hrp, suffix),
+            data.to_base32(),
+            Variant::Bech32,
+        )
+        .map_err(Error::custom)
+    };
+    let operator = address("valoper", &account)?;
+    let consensus = address("valcons", consensus_address)?;
+    let power = (validator.tokens / POWER_REDUCTION).to_string();
+    let tokens = validator.tokens.to_string();
+    let shares = format!("{}.000000000000000000", tokens);
+
+    let tendermint_validators = json!([{
+        "address": consensus_address.iter().map(|b| format!("{:02X}", b)).collect::<String>(),
+        "pub_key": { "type": "tendermint/PubKeyEd25519", "value": validator.consensus_pubkey },
+        "power": power,
+        "name": "fork",
+    }]);
+    // Newer exports keep the validators with the consensus params
+    match genesis
+        .get_mut("consensus")
+        .filter(|consensus| consensus.is_object())
+    {
+        Some(consensus) => consensus["validators"] = tendermint_validators,
+        None => genesis["validators"] = tendermint_validators,
+    }
+
+    let app_state = genesis
+        .get_mut("app_state")
+        .ok_or_else(|| Error::custom("Genesis has no app state"))?;
+    let bond_denom = app_state
+        .pointer("/staking/params/bond_denom")
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::custom("Genesis has no bond denom"))?
+        .to_string();
+
+    let staking = &mut app_state["staking"];
+    staking["validators"] = json!([{
+        "operator_address": operator,
+        "consensus_pubkey": {
+            "@type": "/cosmos.crypto.ed25519.PubKey",
+            "key": validator.consensus_pubkey,
+        },
+        "jailed": false,
+        "status": "BOND_STATUS_BONDED",
+        "tokens": tokens,
+        "delegator_shares": shares,
+        "description": {
+            "moniker": "fork",
+            "identity": "",
+            "website": "",
+            "security_contact": "",
+            "details": "",
+        },
+        "unbonding_height": "0",
+        "unbonding_time": ZERO_TIME,
+        "commission": {
+            "commission_rates": {
+                "rate": "0.100000000000000000",
+                "max_rate": "0.200000000000000000",
+                "max_change_rate": "0.010000000000000000",
+            },
+            "update_time": ZERO_TIME,
+        },
+        "min_self_delegation": "1",
+    }]);
+    staking["delegations"] = json!([{
+        "delegator_address": validator.account,
+        "validator_address": operator,
+        "shares": shares,
+    }]);
+    staking["last_validator_powers"] = json!([{ "address": operator, "power": power }]);
+    staking["last_total_power"] = json!(power);
+    staking["unbonding_delegations"] = json!([]);
+    staking["redelegations"] = json!([]);
+
+    let distribution = &mut app_state["distribution"];
+    // Only the community pool is left in the distribution module, in whole tokens
+    let community_pool: Vec<Value> = distribution
+        .pointer("/fee_pool/community_pool")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|coin| {
+            let amount = coin["amount"].as_str()?.split('.').next()?;
+            (amount.trim_start_matches('0') != "")
+                .then(|| json!({ "denom": coin["denom"], "amount": amount }))
+        })
+        .collect();
+    distribution["previous_proposer"] = json!("");
+    distribution["outstanding_rewards"] =
+        json!([{ "validator_address": operator, "outstanding_rewards": [] }]);
+    distribution["validator_accumulated_commissions"] =
+        json!([{ "validator_address": operator, "accumulated": { "commission": [] } }]);
+    distribution["validator_historical_rewards"] = json!([{
+        "validator_address": operator,
+        "period": "0",
+        "rewards": { "cumulative_reward_ratio": [], "reference_count": 2 },
+    }]);
+    distribution["validator_current_rewards"] = json!([{
+        "validator_address": operator,
+        "rewards": { "rewards": [], "period": "1" },
+    }]);
+    distribution["delegator_starting_infos"] = json!([{
+        "delegator_address": validator.account,
+        "validator_address": operator,
+        "starting_info": { "previous_period": "0", "stake": shares, "height": "0" },
+    }]);
+    distribution["validator_slash_events"] = json!([]);
+
+    app_state["slashing"]["signing_infos"] = json!([{
+        "address": consensus,
+        "validator_signing_info": {
+            "address": consensus,
+            "start_height": "0",
+            "index_offset": "0",
+            "jailed_until": ZERO_TIME,
+            "tombstoned": false,
+            "missed_blocks_counter": "0",
+        },
+    }]);
+    app_state["slashing"]["missed_blocks"] = json!([{ "address": consensus, "missed_blocks": [] }]);
+
+    let bonded_pool = module_address(app_state, "bonded_tokens_pool")?;
+    let not_bonded_pool = module_address(app_state, "not_bonded_tokens_pool")?;
+    let distribution_module = module_address(app_state, "distribution")?;
+    let bank = &mut app_state["bank"];
+    let balances = bank["balances"]
+        .as_array_mut()
+        .ok_or_else(|| Error::custom("Genesis has no balances"))?;
+    let pools = [bonded_pool.as_str(), &not_bonded_pool, &distribution_module];
+    balances.retain(|balance| !pools.contains(&balance["address"].as_str().unwrap_or_default()));
+    balances.push(json!({
+        "address": bonded_pool,
+        "coins": [{ "denom": bond_denom, "amount": tokens }],
+    }));
+    if !community_pool.is_empty() {
+        balances.push(json!({ "address": distribution_module, "coins": community_pool }));
+    }
+    // An empty supply is summed up from the balances at genesis
+    bank["supply"] = json!([]);
+    Ok(())
+}
+
+/// Address of the module account with the given name
+fn module_address(app_state: &Value, name: &str) -> Result<String> {
+    app_state
+        .pointer("/auth/accounts")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .find(|account| account["name"] == name)
+        .and_then(|account| account.pointer("/base_account/address"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| Error::custom(format!("Genesis has no {} module account", name)))
+}
+
+/// Code ids are exported as strings or numbers depending on the version
+fn code_id(value: &Value) -> Option<String> {
+    match value {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+///
+/// Settings of a [LocalFork]
+///
+#[derive(Debug, Clone)]
+pub struct ForkConfig {
+    /// Genesis from `secretd export` on a node synced with the source chain
+    pub export: PathBuf,
+    pub contracts: Vec<String>,
+    /// `priv_validator_key.json` the fork's node signs blocks with
+    pub validator_key: PathBuf,
+    /// Account bonded to the fork's validator
+    pub validator_account: String,
+    pub validator_tokens: u128,
+    pub chain_id: String,
+    pub image: String,
+    pub rpc_port: u16,
+    /// Seconds to wait for the fork to produce blocks
+    pub startup_timeout: u64,
+}
+
+impl ForkConfig {
+    ///
+    /// # Arguments
+    ///
+    /// * 'export' - Genesis from `secretd export` on a node synced with the source chain
+    /// * 'contracts' - Addresses of the contracts to keep
+    /// * 'validator_key' - `priv_validator_key.json` the fork's node signs blocks with
+    /// * 'validator_account' - Account bonded to the fork's validator
+    ///
+    pub fn new(
+        export: impl Into<PathBuf>,
+        contracts: &[&str],
+        validator_key: impl Into<PathBuf>,
+        validator_account: &str,
+    ) -> Self {
+        ForkConfig {
+            export: export.into(),
+            contracts: contracts.iter().map(|c| c.to_string()).collect(),
+            validator_key: validator_key.into(),
+            validator_account: validator_account.to_string(),
+            validator_tokens: 1_000_000 * POWER_REDUCTION,
+            chain_id: FORK_CHAIN_ID.to_string(),
+            image: LOCALSECRET_IMAGE.to_string(),
+            rpc_port: 26657,
+            startup_timeout: 120,
+        }
+    }
+
+    pub fn validator_tokens(mut self, tokens: u128) -> Self {
+        self.validator_tokens = tokens;
+        self
+    }
+
+    pub fn chain_id(mut self, chain_id: impl Into<String>) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+
+    pub fn image(mut self, image: impl Into<String>) -> Self {
+        self.image = image.into();
+        self
+    }
+
+    pub fn rpc_port(mut self, port: u16) -> Self {
+        self.rpc_port = port;
+        self
+    }
+
+    pub fn startup_timeout(mut self, seconds: u64) -> Self {
+        self.startup_timeout = seconds;
+        self
+    }
+}
+
+///
+/// A localsecret container booted from production contract state, so migrations and
+/// integrations can be tested against real data through the usual secretcli functions.
+///
+/// While it is alive every command goes to the fork's node. Dropping it points secretd back
+/// at the previous nodes and removes the container.
+///
+/// The fork runs under its own chain id with the configured key as its only validator, so it
+/// produces blocks on its own and txs signed for it can't be replayed on the source chain.
+///
+/// Secret contract state is encrypted with the chain's consensus seed: the image has to share
+/// the source chain's seed for the contracts to read their state, and has to start from the
+/// mounted genesis and validator key rather than generate its own.
+///
+#[derive(Debug)]
+pub struct LocalFork {
+    container: String,
+    dir: PathBuf,
+    pub state: ForkedState,
+    previous: NetworkConfig,
+}
+
+impl LocalFork {
+    pub fn start(config: &ForkConfig) -> Result<Self> {
+        let contents = fs::read(&config.export).map_err(serde_json::Error::io)?;
+        let mut genesis: Value = serde_json::from_slice(&contents)?;
+        let contracts: Vec<&str> = config.contracts.iter().map(String::as_str).collect();
+        let validator = ForkValidator::from_key_file(
+            &config.validator_key,
+            &config.validator_account,
+            config.validator_tokens,
+        )?;
+        let state = fork_genesis(&mut genesis, &contracts, &validator, &config.chain_id)?;
+
+        let dir = std::env::temp_dir().join(format!("secretcli-fork-{}", generate_label(8)));
+        fs::create_dir_all(&dir).map_err(serde_json::Error::io)?;
+        let genesis_file = dir.join("genesis.json");
+        fs::write(&genesis_file, serde_json::to_vec(&genesis)?).map_err(serde_json::Error::io)?;
+
+        let container = format!("secretcli-fork-{}", generate_label(8).to_lowercase());
+        let output = Command::new("docker")
+            .args(["run", "-d", "--name", &container])
+            .args(["-p", &format!("{}:26657", config.rpc_port)])
+            .args(["-v", &mount(&genesis_file, CONTAINER_GENESIS)])
+            .args(["-v", &mount(&config.validator_key, CONTAINER_VALIDATOR_KEY)])
+            .arg(&config.image)
+            .output()
+            .map_err(serde_json::Error::io)?;
+        if !output.status.success() {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(Error::custom(format!(
+                "Could not start the fork: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let previous = network_config();
+        let node = format!("http://localhost:{}", config.rpc_port);
+        set_network_config(
+            previous
+                .clone()
+                .nodes(vec![node.clone()])
+                .name("fork")
+                .chain_id(&config.chain_id),
+        );
+        let fork = LocalFork {
+            container,
+            dir,
+            state,
+            previous,
+        };
+        for _ in 0..config.startup_timeout {
+            if node_is_healthy(&node) {
+                return Ok(fork);
+            }
+            thread::sleep(time::Duration::from_secs(1));
+        }
+        Err(Error::custom(format!(
+            "Fork node {} was not up after {} seconds",
+            node, config.startup_timeout
+        )))
+    }
+
+    pub fn container(&self) -> &str {
+        &self.container
+    }
+}
+
+fn mount(file: &Path, container_path: &str) -> String {
+    format!("{}:{}", file.display(), container_path)
+}
+
+impl Drop for LocalFork {
+    fn drop(&mut self) {
+        set_network_config(self.previous.clone());
+        let _ = Command::new("docker")
+            .args(["rm", "-f", &self.container])
+            .output();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    const ACCOUNT: &str = "secret1jfw5q2ygp0gvn450h3lu0hlwjanfsc5ufe5wu3";
+
+    fn validator() -> ForkValidator {
+        ForkValidator {
+            account: ACCOUNT.to_string(),
+            consensus_pubkey: base64::encode([7; 32]),
+            tokens: 5_000_000,
+        }
+    }
+
+    fn module(name: &str, address: &str) -> Value {
+        json!({
+            "@type": "/cosmos.auth.v1beta1.ModuleAccount",
+            "base_account": { "address": address },
+            "name": name,
+        })
+    }
+
+    fn exported() -> Value {
+        json!({
+            "chain_id": "secret-4",
+            "validators": [{ "address": "AA", "power": "100", "name": "mainnet" }],
+            "app_state": {
+                "auth": { "accounts": [
+                    module("bonded_tokens_pool", "secret1bonded"),
+                    module("not_bonded_tokens_pool", "secret1notbonded"),
+                    module("distribution", "secret1distribution"),
+                ] },
+                "bank": {
+                    "balances": [
+                        { "address": "secret1bonded", "coins": [{ "denom": "uscrt", "amount": "900" }] },
+                        { "address": "secret1notbonded", "coins": [{ "denom": "uscrt", "amount": "7" }] },
+                        { "address": "secret1distribution", "coins": [{ "denom": "uscrt", "amount": "50" }] },
+                        { "address": "secret1user", "coins": [{ "denom": "uscrt", "amount": "3" }] },
+                    ],
+                    "supply": [{ "denom": "uscrt", "amount": "960" }],
+                },
+                "distribution": {
+                    "fee_pool": { "community_pool": [
+                        { "denom": "uscrt", "amount": "12.500000000000000000" },
+                        { "denom": "uatom", "amount": "0.300000000000000000" },
+                    ] },
+                    "outstanding_rewards": [{ "validator_address": "secretvaloper1mainnet" }],
+                },
+                "slashing": { "signing_infos": [{ "address": "secretvalcons1mainnet" }] },
+                "staking": {
+                    "params": { "bond_denom": "uscrt" },
+                    "validators": [{ "operator_address": "secretvaloper1mainnet" }],
+                    "delegations": [{ "validator_address": "secretvaloper1mainnet" }],
+                    "last_total_power": "100",
+                },
+                "compute": {
+                    "codes": [
+                        { "code_id": "1", "code_bytes": "AA==" },
+                        { "code_id": "2", "code_bytes": "AQ==" },
+                        { "code_id": 3, "code_bytes": "Ag==" },
+                    ],
+                    "contracts": [
+                        { "contract_address": "secret1token", "contract_info": { "code_id": "1" } },
+                        { "contract_address": "secret1pool", "contract_info": { "code_id": 3 } },
+                        { "contract_address": "secret1other", "contract_info": { "code_id": "2" } },
+                    ],
+                    "sequences": [],
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_fork_genesis() {
+        let mut genesis = exported();
+        let state = fork_genesis(
+            &mut genesis,
+            &["secret1pool", "secret1token"],
+            &validator(),
+            FORK_CHAIN_ID,
+        )
+        .unwrap();
+        assert_eq!(state.contracts, vec!["secret1token", "secret1pool"]);
+        assert_eq!(state.code_ids, vec!["1", "3"]);
+
+        let compute = &genesis["app_state"]["compute"];
+        assert_eq!(compute["contracts"].as_array().unwrap().len(), 2);
+        assert_eq!(compute["codes"].as_array().unwrap().len(), 2);
+        assert_eq!(genesis["chain_id"], FORK_CHAIN_ID);
+    }
+
+    #[test]
+    fn test_local_validator() {
+        let mut genesis = exported();
+        fork_genesis(&mut genesis, &[], &validator(), "secret-local").unwrap();
+
+        let validators = genesis["validators"].as_array().unwrap();
+        assert_eq!(validators.len(), 1);
+        assert_eq!(validators[0]["power"], "5");
+        assert_eq!(validators[0]["pub_key"]["value"], base64::encode([7; 32]));
+
+        let staking = &genesis["app_state"]["staking"];
+        let operator = staking["validators"][0]["operator_address"]
+            .as_str()
+            .unwrap();
+        assert!(operator.starts_with("secretvaloper1"));
+        assert_eq!(staking["validators"][0]["status"], "BOND_STATUS_BONDED");
+        assert_eq!(staking["delegations"][0]["delegator_address"], ACCOUNT);
+        assert_eq!(staking["delegations"][0]["validator_address"], operator);
+        assert_eq!(staking["last_total_power"], "5");
+
+        let app_state = &genesis["app_state"];
+        assert_eq!(
+            app_state["distribution"]["outstanding_rewards"][0]["validator_address"],
+            operator
+        );
+        assert!(app_state["slashing"]["signing_infos"][0]["address"]
+            .as_str()
+            .unwrap()
+            .starts_with("secretvalcons1"));
+
+        // the pools hold exactly the new bond and the whole community pool tokens
+        let bank = &app_state["bank"];
+        assert_eq!(
+            bank["balances"],
+            json!([
+                { "address": "secret1user", "coins": [{ "denom": "uscrt", "amount": "3" }] },
+                { "address": "secret1bonded", "coins": [{ "denom": "uscrt", "amount": "5000000" }] },
+                { "address": "secret1distribution", "coins": [{ "denom": "uscrt", "amount": "12" }] },
+            ])
+        );
+        assert_eq!(bank["supply"], json!([]));
+    }
+
+    #[test]
+    fn test_missing_contract() {
+        let mut genesis = exported();
+        assert!(fork_genesis(
+            &mut genesis,
+            &["secret1missing"],
+            &validator(),
+            FORK_CHAIN_ID
+        )
+        .is_err());
+        assert!(fork_genesis(
+            &mut json!({ "app_state": {} }),
+            &[],
+            &validator(),
+            FORK_CHAIN_ID
+        )
+        .is_err());
+    }
+}
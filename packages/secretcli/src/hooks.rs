@@ -0,0 +1,150 @@
+use crate::cli_types::{NetContract, TxCoin, TxQuery};
+use serde_json::{Result, Value};
+use std::{cell::Cell, sync::RwLock};
+
+type BeforeHook = Box<dyn Fn(&TxContext) -> Result<()> + Send + Sync>;
+type AfterHook = Box<dyn Fn(&TxContext, &TxQuery) -> Result<()> + Send + Sync>;
+
+static BEFORE: RwLock<Vec<BeforeHook>> = RwLock::new(Vec::new());
+static AFTER: RwLock<Vec<AfterHook>> = RwLock::new(Vec::new());
+
+thread_local! {
+    /// Set while hooks run, so the txs they send don't run the hooks again
+    static IN_HOOK: Cell<bool> = Cell::new(false);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxKind {
+    Instantiate,
+    Execute,
+}
+
+///
+/// The contract tx a hook runs around
+///
+#[derive(Debug, Clone, Copy)]
+pub struct TxContext<'a> {
+    pub kind: TxKind,
+    /// For instantiations only the code id and code hash are set
+    pub contract: &'a NetContract,
+    pub msg: &'a Value,
+    pub sender: &'a str,
+    pub funds: &'a [TxCoin],
+}
+
+///
+/// Runs `hook` before every contract instantiation and execution, e.g. to snapshot balances
+/// or approve an allowance just in time. An error cancels the tx.
+///
+/// Txs the hook sends itself don't run any hooks.
+///
+pub fn before_tx<F>(hook: F)
+where
+    F: Fn(&TxContext) -> Result<()> + Send + Sync + 'static,
+{
+    BEFORE.write().unwrap().push(Box::new(hook));
+}
+
+///
+/// Runs `hook` once every contract instantiation and execution is confirmed, e.g. to assert
+/// balance deltas. An error is returned in place of the tx's result.
+///
+/// Txs the hook sends itself don't run any hooks.
+///
+pub fn after_tx<F>(hook: F)
+where
+    F: Fn(&TxContext, &TxQuery) -> Result<()> + Send + Sync + 'static,
+{
+    AFTER.write().unwrap().push(Box::new(hook));
+}
+
+pub fn clear_hooks() {
+    BEFORE.write().unwrap().clear();
+    AFTER.write().unwrap().clear();
+}
+
+/// Clears [IN_HOOK] when dropped, so a panicking hook doesn't disable hooks for good
+struct HookGuard;
+
+impl Drop for HookGuard {
+    fn drop(&mut self) {
+        IN_HOOK.with(|in_hook| in_hook.set(false));
+    }
+}
+
+/// Runs `f` unless hooks are already running on this thread
+fn guarded<F: FnOnce() -> Result<()>>(f: F) -> Result<()> {
+    if IN_HOOK.with(Cell::get) {
+        return Ok(());
+    }
+    IN_HOOK.with(|in_hook| in_hook.set(true));
+    let _guard = HookGuard;
+    f()
+}
+
+pub(crate) fn run_before(context: &TxContext) -> Result<()> {
+    guarded(|| {
+        BEFORE
+            .read()
+            .unwrap()
+            .iter()
+            .try_for_each(|hook| hook(context))
+    })
+}
+
+pub(crate) fn run_after(context: &TxContext, tx: &TxQuery) -> Result<()> {
+    guarded(|| {
+        AFTER
+            .read()
+            .unwrap()
+            .iter()
+            .try_for_each(|hook| hook(context, tx))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_hooks() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        before_tx(|context| {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            // A tx sent from a hook doesn't run it again
+            run_before(context)?;
+            if context.funds.is_empty() {
+                Ok(())
+            } else {
+                Err(serde::de::Error::custom("no funds allowed"))
+            }
+        });
+
+        let contract = NetContract::new("token", "1", "secret1token", "hash");
+        let msg = serde_json::json!({ "transfer": {} });
+        let mut context = TxContext {
+            kind: TxKind::Execute,
+            contract: &contract,
+            msg: &msg,
+            sender: "alice",
+            funds: &[],
+        };
+        assert!(run_before(&context).is_ok());
+        let funds = [TxCoin::new(1, "uscrt")];
+        context.funds = &funds;
+        assert!(run_before(&context).is_err());
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+
+        clear_hooks();
+        assert!(run_before(&context).is_ok());
+    }
+
+    #[test]
+    fn test_panicking_hook() {
+        let panicked = std::panic::catch_unwind(|| guarded(|| panic!("hook panicked")));
+        assert!(panicked.is_err());
+        // hooks still run afterwards
+        assert!(!IN_HOOK.with(Cell::get));
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{
+    cli_types::{NetContract, TxQuery},
+    network::{network_config, set_network_config},
+    secretcli::{search_txs_newest_first, test_init},
+};
+use serde::Serialize;
+use serde_json::Result;
+
+/// Longest memo the chain accepts
+pub const MAX_MEMO_LENGTH: usize = 256;
+
+///
+/// The deterministic memo of a deployment operation, the same on every run of the script
+///
+/// # Arguments
+///
+/// * 'deployment' - Name of the deployment, change it to deploy everything again
+/// * 'operation' - Name of the operation, unique within the deployment
+///
+pub fn operation_memo(deployment: &str, operation: &str) -> Result<String> {
+    let memo = format!("deploy:{}:{}", deployment, operation);
+    if memo.len() > MAX_MEMO_LENGTH {
+        return Err(serde::de::Error::custom(format!(
+            "Memo {} is longer than {} bytes",
+            memo, MAX_MEMO_LENGTH
+        )));
+    }
+    Ok(memo)
+}
+
+/// Puts the memo the network config had back when dropped, also if the action panicked
+struct MemoGuard {
+    previous: Option<String>,
+}
+
+impl Drop for MemoGuard {
+    fn drop(&mut self) {
+        let mut config = network_config();
+        config.memo = self.previous.take();
+        set_network_config(config);
+    }
+}
+
+///
+/// Runs `action` with the memo added to every tx it broadcasts
+///
+pub fn with_memo<T, F>(memo: &str, action: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T>,
+{
+    let config = network_config();
+    let _guard = MemoGuard {
+        previous: config.memo.clone(),
+    };
+    set_network_config(config.memo(memo));
+    action()
+}
+
+///
+/// The latest successful tx the sender broadcast with the memo from the given height on.
+/// The sender's txs are searched newest first, stopping at the height.
+///
+/// # Arguments
+///
+/// * 'sender' - Address that signed the tx
+/// * 'memo' - The memo to look for
+/// * 'since_height' - Lowest block height to search, 0 for the whole history
+///
+pub fn find_tx_with_memo(sender: &str, memo: &str, since_height: u64) -> Result<Option<TxQuery>> {
+    let mut found = None;
+    search_txs_newest_first(&format!("message.sender={}", sender), |tx| {
+        if tx.height < since_height {
+            return false;
+        }
+        if succeeded_with_memo(&tx, memo) {
+            found = Some(tx);
+            return false;
+        }
+        true
+    })?;
+    Ok(found)
+}
+
+///
+/// Every tx the sender broadcast from the given height on, oldest first. Older txs aren't
+/// fetched.
+///
+/// # Arguments
+///
+/// * 'sender' - Address that signed the txs
+/// * 'since_height' - Lowest block height to include, 0 for the whole history
+///
+pub fn find_txs_by_sender(sender: &str, since_height: u64) -> Result<Vec<TxQuery>> {
+    // `--events` only matches equality, so the newest txs are walked down to the height
+    let mut txs = vec![];
+    search_txs_newest_first(&format!("message.sender={}", sender), |tx| {
+        let keep_going = tx.height >= since_height;
+        if keep_going {
+            txs.push(tx);
+        }
+        keep_going
+    })?;
+    txs.reverse();
+    Ok(txs)
+}
+
+///
+/// Every tx of the sender from the given height on whose memo starts with the tag, oldest
+/// first. Memos are not indexed by the chain, so the search goes through the sender's txs
+///
+/// # Arguments
+///
+/// * 'sender' - Address that signed the txs
+/// * 'tag' - Memo prefix, e.g. `deploy:v2:` for every operation of a deployment
+/// * 'since_height' - Lowest block height to search, 0 for the whole history
+///
+pub fn find_txs_by_memo(sender: &str, tag: &str, since_height: u64) -> Result<Vec<TxQuery>> {
+    Ok(find_txs_by_sender(sender, since_height)?
+        .into_iter()
+        .filter(|tx| has_memo_tag(tx, tag))
+        .collect())
+}
+
+fn has_memo_tag(tx: &TxQuery, tag: &str) -> bool {
+    tx.memo().map_or(false, |memo| memo.starts_with(tag))
+}
+
+/// Failed txs have no logs, they must not count as done
+fn succeeded_with_memo(tx: &TxQuery, memo: &str) -> bool {
+    tx.memo() == Some(memo) && !tx.logs.is_empty()
+}
+
+///
+/// Runs the operations of a deployment at most once each, so re-running a deployment script
+/// that stopped halfway picks up where it stopped.
+///
+/// Every operation's txs carry a memo derived from the deployment and operation names. Before
+/// running an operation the sender's txs are searched for its memo, and the tx found is
+/// returned instead of broadcasting again. Unlike [crate::checkpoint::SuiteCheckpoint] this
+/// needs no local state, the chain is the record. Only txs from the height the deployment
+/// started at are searched, so the search doesn't grow with the sender's history.
+///
+#[derive(Debug, Clone)]
+pub struct IdempotentDeployer {
+    deployment: String,
+    /// Address of the key every operation is signed with
+    sender: String,
+    /// Height the first run of the deployment started at
+    since_height: u64,
+}
+
+impl IdempotentDeployer {
+    ///
+    /// # Arguments
+    ///
+    /// * 'deployment' - Name of the deployment, change it to deploy everything again
+    /// * 'sender_address' - Address of the key every operation is signed with
+    /// * 'since_height' - A height at or before the first run of the deployment, e.g. the
+    ///   [crate::secretcli::latest_block_height] it recorded. Operations run before it run again.
+    ///
+    pub fn new(
+        deployment: impl Into<String>,
+        sender_address: impl Into<String>,
+        since_height: u64,
+    ) -> Self {
+        IdempotentDeployer {
+            deployment: deployment.into(),
+            sender: sender_address.into(),
+            since_height,
+        }
+    }
+
+    pub fn memo(&self, operation: &str) -> Result<String> {
+        operation_memo(&self.deployment, operation)
+    }
+
+    ///
+    /// Every tx previous runs of the deployment broadcast, oldest first, including failed ones
+    ///
+    pub fn history(&self) -> Result<Vec<TxQuery>> {
+        find_txs_by_memo(
+            &self.sender,
+            &format!("deploy:{}:", self.deployment),
+            self.since_height,
+        )
+    }
+
+    ///
+    /// Returns the tx of a previous run of the operation, or runs it now with its memo
+    ///
+    /// # Arguments
+    ///
+    /// * 'operation' - Name of the operation, unique within the deployment
+    /// * 'action' - Broadcasts a single tx from the deployer's sender and returns it
+    ///
+    pub fn run<F>(&self, operation: &str, action: F) -> Result<TxQuery>
+    where
+        F: FnOnce() -> Result<TxQuery>,
+    {
+        let memo = self.memo(operation)?;
+        if let Some(tx) = find_tx_with_memo(&self.sender, &memo, self.since_height)? {
+            return Ok(tx);
+        }
+        with_memo(&memo, action)
+    }
+
+    ///
+    /// Instantiates the contract unless a previous run already did, returning it with its
+    /// address either way
+    ///
+    /// # Arguments
+    ///
+    /// * 'operation' - Name of the operation, unique within the deployment
+    /// * `msg` - Contract's init message
+    /// * 'contract' - The stored contract, its code id and code hash are used
+    /// * 'label' - The contract label, must not change between runs
+    /// * 'sender' - Key of the deployer's sender
+    /// * 'backend' - Keyring backend defaults to none
+    ///
+    pub fn instantiate<Message: Serialize>(
+        &self,
+        operation: &str,
+        msg: Message,
+        contract: &NetContract,
+        label: &str,
+        sender: impl AsRef<str>,
+        backend: Option<&str>,
+    ) -> Result<NetContract> {
+        let sender = sender.as_ref();
+        let tx = self.run(operation, || {
+            test_init(msg, contract, label, sender, None, backend)
+        })?;
+        let address = tx.contract_address().ok_or_else(|| {
+            serde::de::Error::custom(format!(
+                "{} did not instantiate a contract: {}",
+                tx.txhash, tx.raw_log
+            ))
+        })?;
+        let mut contract = contract.clone();
+        contract.address = address;
+        contract.label = label.to_string();
+        Ok(contract)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_operation_memo() {
+        assert_eq!(
+            operation_memo("v2", "instantiate token").unwrap(),
+            "deploy:v2:instantiate token"
+        );
+        assert!(operation_memo("v2", &"a".repeat(MAX_MEMO_LENGTH)).is_err());
+    }
+
+    #[test]
+    fn test_memo_matching() {
+        let tx = |memo: &str, logs: serde_json::Value| -> TxQuery {
+            serde_json::from_value(json!({
+                "height": "10",
+                "txhash": "ABC",
+                "data": "",
+                "raw_log": "",
+                "logs": logs,
+                "gas_wanted": "1",
+                "gas_used": "1",
+                "tx": { "body": { "memo": memo }, "auth_info": {} },
+                "timestamp": "2023-01-01T00:00:00Z",
+            }))
+            .unwrap()
+        };
+        let log = json!([{ "msg_index": 0, "log": "", "events": [] }]);
+        assert!(succeeded_with_memo(
+            &tx("deploy:v2:a", log.clone()),
+            "deploy:v2:a"
+        ));
+        assert!(!succeeded_with_memo(
+            &tx("deploy:v2:a", json!([])),
+            "deploy:v2:a"
+        ));
+        assert!(!succeeded_with_memo(&tx("deploy:v2:b", log), "deploy:v2:a"));
+        assert!(has_memo_tag(&tx("deploy:v2:b", json!([])), "deploy:v2:"));
+        assert!(!has_memo_tag(&tx("deploy:v20:b", json!([])), "deploy:v2:"));
+    }
+}
@@ -0,0 +1,652 @@
+use crate::{
+    cli_types::{NetContract, TxQuery},
+    events::{ContractEvent, EventIndexer},
+    idempotent::{find_tx_with_memo, with_memo, MAX_MEMO_LENGTH},
+    secretcli::{
+        account_address, latest_block_height, query, test_contract_handle, CONTRACT_CACHE_DIR,
+    },
+    utils::print_warning,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Result, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{create_dir_all, File},
+    io::{BufReader, ErrorKind},
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+type MsgBuilder = Box<dyn Fn(&Value) -> Result<Value>>;
+type Condition = Box<dyn Fn(&Value) -> bool>;
+type EventMsgBuilder = Box<dyn Fn(&ContractEvent) -> Result<Option<Value>>>;
+
+///
+/// A contract execution the keeper runs on a schedule
+///
+pub struct Job {
+    name: String,
+    contract: NetContract,
+    interval: Duration,
+    /// Query whose answer decides if the job runs and is handed to the message builder
+    trigger: Option<(Value, Condition)>,
+    msg: MsgBuilder,
+}
+
+impl Job {
+    ///
+    /// A job executing the built message at most once per interval
+    ///
+    /// # Arguments
+    ///
+    /// * 'name' - Unique name of the job, its state is persisted under it
+    /// * 'contract' - The contract to execute
+    /// * 'interval' - Least time between two runs
+    /// * 'msg' - Builds the handle message, given the trigger query's answer or `null`
+    ///
+    pub fn new<F>(
+        name: impl Into<String>,
+        contract: NetContract,
+        interval: Duration,
+        msg: F,
+    ) -> Self
+    where
+        F: Fn(&Value) -> Result<Value> + 'static,
+    {
+        Job {
+            name: name.into(),
+            contract,
+            interval,
+            trigger: None,
+            msg: Box::new(msg),
+        }
+    }
+
+    ///
+    /// Only runs the job when the contract's answer to the query satisfies the condition
+    ///
+    pub fn when<F>(mut self, query: Value, condition: F) -> Self
+    where
+        F: Fn(&Value) -> bool + 'static,
+    {
+        self.trigger = Some((query, Box::new(condition)));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+///
+/// A contract execution the keeper runs for every matching event another contract emits,
+/// e.g. liquidating on a lending contract whenever an oracle reports a liquidation opportunity
+///
+pub struct EventJob {
+    name: String,
+    /// Contract whose events are watched
+    source: String,
+    event_type: String,
+    contract: NetContract,
+    msg: EventMsgBuilder,
+}
+
+impl EventJob {
+    ///
+    /// A job executing the built message once per matching event
+    ///
+    /// # Arguments
+    ///
+    /// * 'name' - Unique name of the job, its state is persisted under it
+    /// * 'source' - Address of the contract whose events are watched
+    /// * 'event_type' - Custom event type, with or without the `wasm-` prefix, or `wasm`
+    /// * 'contract' - The contract to execute
+    /// * 'msg' - Builds the handle message from the event, `None` skips the event
+    ///
+    pub fn new<F>(
+        name: impl Into<String>,
+        source: &str,
+        event_type: &str,
+        contract: NetContract,
+        msg: F,
+    ) -> Self
+    where
+        F: Fn(&ContractEvent) -> Result<Option<Value>> + 'static,
+    {
+        EventJob {
+            name: name.into(),
+            source: source.to_string(),
+            event_type: event_type.to_string(),
+            contract,
+            msg: Box::new(msg),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn matches(&self, event: &ContractEvent) -> bool {
+        event.contract == self.source
+            && (event.event_type == self.event_type
+                || event.event_type.strip_prefix("wasm-") == Some(self.event_type.as_str()))
+    }
+}
+
+///
+/// How many times a failed execution is tried again, waiting longer after each attempt
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    /// Wait after the first failure, doubled after every following one
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before the given retry, starting at 0
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.backoff * 2u32.saturating_pow(retry)
+    }
+}
+
+///
+/// An event an event job gave up on, it is skipped from then on
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetter {
+    /// Id of the event, see [event_ids]
+    pub event: String,
+    pub height: u64,
+    /// Why the last attempt failed
+    pub error: String,
+}
+
+///
+/// What the keeper remembers about a job between runs
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobState {
+    pub last_run: Option<DateTime<Utc>>,
+    pub last_txhash: Option<String>,
+    pub runs: u64,
+    pub failures: u64,
+    pub last_error: Option<String>,
+    /// Events handled since the keeper's event cursor last moved, see [event_ids]
+    #[serde(default)]
+    pub handled_events: BTreeSet<String>,
+    /// Failed runs per event not handled yet
+    #[serde(default)]
+    pub event_attempts: BTreeMap<String, u32>,
+    /// Events that failed on too many runs, see [Keeper::max_event_attempts]
+    #[serde(default)]
+    pub dead_letters: Vec<DeadLetter>,
+}
+
+impl JobState {
+    /// Whether the interval passed since the last run
+    pub fn is_due(&self, interval: Duration, now: DateTime<Utc>) -> bool {
+        match self.last_run {
+            None => true,
+            Some(last_run) => (now - last_run)
+                .to_std()
+                .map_or(false, |since| since >= interval),
+        }
+    }
+
+    fn record(&mut self, job: &str, result: Result<TxQuery>) -> Option<TxQuery> {
+        match result {
+            Ok(tx) => {
+                self.last_run = Some(Utc::now());
+                self.last_txhash = Some(tx.txhash.clone());
+                self.runs += 1;
+                self.last_error = None;
+                Some(tx)
+            }
+            Err(err) => {
+                print_warning(&format!("Keeper job {} failed: {}", job, err));
+                self.failures += 1;
+                self.last_error = Some(err.to_string());
+                None
+            }
+        }
+    }
+
+    /// Counts a failed run of the event, giving up on it once it failed `max_attempts` runs.
+    /// Returns whether it was moved to the dead letters
+    fn event_failed(
+        &mut self,
+        job: &str,
+        id: &str,
+        event: &ContractEvent,
+        max_attempts: u32,
+    ) -> bool {
+        let attempts = self.event_attempts.entry(id.to_string()).or_default();
+        *attempts += 1;
+        if *attempts < max_attempts {
+            return false;
+        }
+        self.event_attempts.remove(id);
+        print_warning(&format!(
+            "Keeper job {} gave up on event {} after {} runs",
+            job, id, max_attempts
+        ));
+        self.dead_letters.push(DeadLetter {
+            event: id.to_string(),
+            height: event.height,
+            error: self.last_error.clone().unwrap_or_default(),
+        });
+        self.handled_events.insert(id.to_string());
+        true
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+struct KeeperState {
+    jobs: BTreeMap<String, JobState>,
+    /// First block whose events aren't all handled yet
+    #[serde(default)]
+    next_height: Option<u64>,
+}
+
+///
+/// The memo of one execution of a job, shared by its retries
+///
+fn execution_memo(keeper: &str, job: &str, started: DateTime<Utc>) -> Result<String> {
+    let memo = format!("keeper:{}:{}:{}", keeper, job, started.timestamp_millis());
+    if memo.len() > MAX_MEMO_LENGTH {
+        return Err(serde::de::Error::custom(format!(
+            "Memo {} is longer than {} bytes, shorten the keeper or job name",
+            memo, MAX_MEMO_LENGTH
+        )));
+    }
+    Ok(memo)
+}
+
+///
+/// Pairs every event with an id unique on chain, its tx hash and its position in the tx
+///
+pub fn event_ids(events: &[ContractEvent]) -> Vec<(String, &ContractEvent)> {
+    let mut ids = Vec::with_capacity(events.len());
+    let mut position = 0;
+    for (index, event) in events.iter().enumerate() {
+        if index > 0 && events[index - 1].txhash == event.txhash {
+            position += 1;
+        } else {
+            position = 0;
+        }
+        ids.push((format!("{}/{}", event.txhash, position), event));
+    }
+    ids
+}
+
+///
+/// Runs scheduled contract executions from one sender, a basis for off-chain automation
+/// bots. Jobs run one after the other so their txs never race for the sender's sequence
+/// number.
+///
+/// The state of every job is saved next to the cached contracts after each run, so a
+/// restarted keeper doesn't run jobs early.
+///
+/// Event jobs are fed by an [EventIndexer] that polls new blocks on every run, there is no
+/// websocket subscription. They are at least once: the keeper only moves past a block once
+/// every matching event in it was executed, failed ones are retried on the next run and the
+/// ones already handled are skipped. An event failing on every run would hold the cursor
+/// forever, so after [Keeper::max_event_attempts] runs it is moved to the job's dead letters
+/// instead. An event may still run twice if the keeper stops between broadcasting the tx and
+/// saving its state.
+///
+/// Every attempt of an execution carries the same memo. Before retrying, the sender's txs
+/// are searched for it, so a tx whose confirmation was lost isn't broadcast a second time.
+///
+pub struct Keeper {
+    name: String,
+    sender: String,
+    backend: Option<String>,
+    retry: RetryPolicy,
+    max_event_attempts: u32,
+    jobs: Vec<Job>,
+    event_jobs: Vec<EventJob>,
+    state: KeeperState,
+}
+
+impl Keeper {
+    ///
+    /// Loads the keeper's saved job state, or starts without any
+    ///
+    /// # Arguments
+    ///
+    /// * 'name' - Name the job state is persisted under
+    /// * 'sender' - Key every job is executed with
+    /// * 'backend' - Keyring backend defaults to none
+    ///
+    pub fn new(name: &str, sender: impl AsRef<str>, backend: Option<&str>) -> Result<Self> {
+        let state = match File::open(Self::path(name)) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => KeeperState::default(),
+            Err(err) => return Err(serde_json::Error::io(err)),
+        };
+        Ok(Keeper {
+            name: name.to_string(),
+            sender: sender.as_ref().to_string(),
+            backend: backend.map(str::to_string),
+            retry: RetryPolicy::default(),
+            max_event_attempts: 5,
+            jobs: vec![],
+            event_jobs: vec![],
+            state,
+        })
+    }
+
+    fn path(name: &str) -> PathBuf {
+        PathBuf::from(CONTRACT_CACHE_DIR).join(format!("{}.keeper", name))
+    }
+
+    pub fn job(mut self, job: Job) -> Self {
+        self.jobs.push(job);
+        self
+    }
+
+    pub fn on_event(mut self, job: EventJob) -> Self {
+        self.event_jobs.push(job);
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    ///
+    /// How many runs an event job tries an event before giving up on it, 5 by default. Each
+    /// run already retries the execution following the [RetryPolicy]
+    ///
+    pub fn max_event_attempts(mut self, attempts: u32) -> Self {
+        self.max_event_attempts = attempts.max(1);
+        self
+    }
+
+    pub fn state(&self, job: &str) -> Option<&JobState> {
+        self.state.jobs.get(job)
+    }
+
+    ///
+    /// Runs every job that is due and whose trigger holds, then every event job for the
+    /// events emitted since the last run, returning the txs it broadcast.
+    /// A failing job is recorded in its state and doesn't stop the others
+    ///
+    pub fn run_once(&mut self) -> Result<Vec<TxQuery>> {
+        let mut txs = vec![];
+        for index in 0..self.jobs.len() {
+            let job = &self.jobs[index];
+            let state = self.state.jobs.get(&job.name).cloned().unwrap_or_default();
+            if !state.is_due(job.interval, Utc::now()) {
+                continue;
+            }
+            let result = match self.triggered(job) {
+                Ok(Some(answer)) => {
+                    (job.msg)(&answer).and_then(|msg| self.execute(&job.name, &job.contract, &msg))
+                }
+                Ok(None) => continue,
+                Err(err) => Err(err),
+            };
+
+            let state = self.state.jobs.entry(job.name.clone()).or_default();
+            txs.extend(state.record(&job.name, result));
+            self.save()?;
+        }
+        if !self.event_jobs.is_empty() {
+            txs.extend(self.run_event_jobs()?);
+        }
+        Ok(txs)
+    }
+
+    fn run_event_jobs(&mut self) -> Result<Vec<TxQuery>> {
+        let from = match self.state.next_height {
+            Some(height) => height,
+            // Only events emitted after the keeper first started
+            None => latest_block_height()? + 1,
+        };
+        let mut indexer = EventIndexer::new(from);
+        indexer.sync()?;
+
+        let mut txs = vec![];
+        let mut complete = true;
+        for job in &self.event_jobs {
+            for (id, event) in event_ids(indexer.events()) {
+                let handled = self
+                    .state
+                    .jobs
+                    .get(&job.name)
+                    .map_or(false, |state| state.handled_events.contains(&id));
+                if handled || !job.matches(event) {
+                    continue;
+                }
+                let result = match (job.msg)(event) {
+                    Ok(Some(msg)) => self.execute(&job.name, &job.contract, &msg),
+                    Ok(None) => continue,
+                    Err(err) => Err(err),
+                };
+
+                let state = self.state.jobs.entry(job.name.clone()).or_default();
+                match state.record(&job.name, result) {
+                    Some(tx) => {
+                        state.event_attempts.remove(&id);
+                        state.handled_events.insert(id);
+                        txs.push(tx);
+                    }
+                    None => {
+                        if !state.event_failed(&job.name, &id, event, self.max_event_attempts) {
+                            complete = false;
+                        }
+                    }
+                }
+                self.save()?;
+            }
+        }
+
+        // Unhandled events are indexed again on the next run, handled ones are kept to skip them
+        if complete {
+            self.state.next_height = Some(indexer.next_height());
+            for job in &self.event_jobs {
+                if let Some(state) = self.state.jobs.get_mut(&job.name) {
+                    state.handled_events.clear();
+                    state.event_attempts.clear();
+                }
+            }
+        } else {
+            self.state.next_height = Some(from);
+        }
+        self.save()?;
+        Ok(txs)
+    }
+
+    ///
+    /// Runs the due jobs every tick until `stop` returns true. A failing run, e.g. the node
+    /// being unreachable, is logged and tried again on the next tick
+    ///
+    pub fn run<F: FnMut() -> bool>(&mut self, tick: Duration, mut stop: F) -> Result<()> {
+        while !stop() {
+            if let Err(err) = self.run_once() {
+                print_warning(&format!("Keeper {} run failed: {}", self.name, err));
+            }
+            thread::sleep(tick);
+        }
+        Ok(())
+    }
+
+    /// The trigger query's answer if the job should run, `null` for jobs without a trigger
+    fn triggered(&self, job: &Job) -> Result<Option<Value>> {
+        match &job.trigger {
+            None => Ok(Some(Value::Null)),
+            Some((msg, condition)) => {
+                let answer: Value = query(&job.contract, msg, None)?;
+                Ok(if condition(&answer) {
+                    Some(answer)
+                } else {
+                    None
+                })
+            }
+        }
+    }
+
+    fn execute(&self, job: &str, contract: &NetContract, msg: &Value) -> Result<TxQuery> {
+        let memo = execution_memo(&self.name, job, Utc::now())?;
+        let sender = account_address(&self.sender)?;
+        let since_height = latest_block_height()?;
+        let mut retry = 0;
+        loop {
+            let result = with_memo(&memo, || {
+                test_contract_handle(
+                    msg,
+                    contract,
+                    &self.sender,
+                    None,
+                    self.backend.as_deref(),
+                    None,
+                )
+            })
+            .and_then(|(_, tx)| {
+                // Failed txs have no logs
+                if tx.logs.is_empty() {
+                    Err(serde::de::Error::custom(format!(
+                        "{} failed: {}",
+                        tx.txhash, tx.raw_log
+                    )))
+                } else {
+                    Ok(tx)
+                }
+            });
+            if result.is_ok() || retry + 1 >= self.retry.max_attempts {
+                return result;
+            }
+            thread::sleep(self.retry.delay(retry));
+            // The tx may have been included after all, e.g. if only its lookup timed out
+            if let Some(tx) = find_tx_with_memo(&sender, &memo, since_height)? {
+                return Ok(tx);
+            }
+            retry += 1;
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        create_dir_all(CONTRACT_CACHE_DIR).map_err(serde_json::Error::io)?;
+        let file = File::create(Self::path(&self.name)).map_err(serde_json::Error::io)?;
+        serde_json::to_writer(file, &self.state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_job_is_due() {
+        let now: DateTime<Utc> = "2023-11-14T22:13:20Z".parse().unwrap();
+        let hour = Duration::from_secs(3600);
+        assert!(JobState::default().is_due(hour, now));
+
+        let state = JobState {
+            last_run: Some(now - chrono::Duration::minutes(30)),
+            ..Default::default()
+        };
+        assert!(!state.is_due(hour, now));
+        assert!(state.is_due(hour, now + chrono::Duration::minutes(30)));
+    }
+
+    fn event(txhash: &str, contract: &str, event_type: &str) -> ContractEvent {
+        ContractEvent {
+            height: 1,
+            txhash: txhash.to_string(),
+            contract: contract.to_string(),
+            event_type: event_type.to_string(),
+            attributes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_event_ids() {
+        let events = [
+            event("A", "secret1oracle", "wasm"),
+            event("A", "secret1oracle", "wasm-liquidation_opportunity"),
+            event("B", "secret1oracle", "wasm-liquidation_opportunity"),
+        ];
+        let ids: Vec<String> = event_ids(&events).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec!["A/0", "A/1", "B/0"]);
+    }
+
+    #[test]
+    fn test_event_job_matches() {
+        let job = EventJob::new(
+            "liquidate",
+            "secret1oracle",
+            "liquidation_opportunity",
+            NetContract::new("lending", "1", "secret1lending", "hash"),
+            |_| Ok(None),
+        );
+        assert!(job.matches(&event("A", "secret1oracle", "wasm-liquidation_opportunity")));
+        assert!(!job.matches(&event("A", "secret1other", "wasm-liquidation_opportunity")));
+        assert!(!job.matches(&event("A", "secret1oracle", "wasm")));
+    }
+
+    #[test]
+    fn test_event_dead_letter() {
+        let mut state = JobState::default();
+        let event = event("A", "secret1oracle", "wasm-liquidation_opportunity");
+        for _ in 0..2 {
+            state.record("liquidate", Err(serde::de::Error::custom("out of gas")));
+            assert!(!state.event_failed("liquidate", "A/0", &event, 3));
+        }
+        state.record("liquidate", Err(serde::de::Error::custom("out of gas")));
+        assert!(state.event_failed("liquidate", "A/0", &event, 3));
+
+        // skipped from then on, so the cursor can move past it
+        assert!(state.handled_events.contains("A/0"));
+        assert!(state.event_attempts.is_empty());
+        assert_eq!(
+            state.dead_letters,
+            vec![DeadLetter {
+                event: "A/0".to_string(),
+                height: 1,
+                error: "out of gas".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_execution_memo() {
+        let started: DateTime<Utc> = "2023-11-14T22:13:20Z".parse().unwrap();
+        assert_eq!(
+            execution_memo("bot", "harvest", started).unwrap(),
+            "keeper:bot:harvest:1700000000000"
+        );
+        assert_ne!(
+            execution_memo("bot", "harvest", started).unwrap(),
+            execution_memo(
+                "bot",
+                "harvest",
+                started + chrono::Duration::milliseconds(1)
+            )
+            .unwrap()
+        );
+        assert!(execution_memo("bot", &"a".repeat(MAX_MEMO_LENGTH), started).is_err());
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(0), Duration::from_secs(5));
+        assert_eq!(policy.delay(2), Duration::from_secs(20));
+    }
+}
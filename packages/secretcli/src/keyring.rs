@@ -0,0 +1,245 @@
+use crate::{
+    network::{network_config, set_network_config, NetworkConfig},
+    utils::generate_label,
+};
+use serde::de::Error;
+use serde_json::{Result, Value};
+use std::{
+    fmt, fs,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::{Output, Stdio},
+    thread, time,
+};
+
+///
+/// Why a keys command failed
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyError {
+    /// There is no key with that name in the keyring
+    NotFound(String),
+    /// The keyring needs a passphrase or could not be decrypted
+    KeyringLocked(String),
+    /// secretd is not installed where it's run
+    BinaryMissing(String),
+    Other(String),
+}
+
+impl KeyError {
+    fn classify(name: &str, stderr: &str) -> Self {
+        let lower = stderr.to_lowercase();
+        let matches = |patterns: &[&str]| patterns.iter().any(|pattern| lower.contains(pattern));
+        if matches(&["command not found", "executable file not found"]) {
+            KeyError::BinaryMissing(stderr.to_string())
+        } else if matches(&[
+            "not a valid name or address",
+            "key not found",
+            "could not be found in the keyring",
+        ]) {
+            KeyError::NotFound(name.to_string())
+        } else if matches(&[
+            "passphrase",
+            "password",
+            "keyring is locked",
+            "failed to decrypt",
+            "integrity check failed",
+            "error: eof",
+        ]) {
+            KeyError::KeyringLocked(stderr.to_string())
+        } else {
+            KeyError::Other(stderr.to_string())
+        }
+    }
+
+    /// Whether trying again may succeed, only unexpected failures are
+    pub fn is_transient(&self) -> bool {
+        matches!(self, KeyError::Other(_))
+    }
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::NotFound(name) => write!(f, "Key {} is not in the keyring", name),
+            KeyError::KeyringLocked(err) => write!(f, "Keyring is locked: {}", err),
+            KeyError::BinaryMissing(err) => write!(f, "secretd is not installed: {}", err),
+            KeyError::Other(err) => write!(f, "Keys command failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+///
+/// Runs a keys command on the active keyring, retrying failures that may be transient
+///
+/// # Arguments
+///
+/// * 'name' - Name of the key the command is about
+/// * 'args' - The command, e.g. `["keys", "show", "-a", name]`
+/// * 'retry' - How many times to retry a transient failure
+///
+pub(crate) fn run_keys_command(
+    name: &str,
+    args: &[&str],
+    retry: u32,
+) -> std::result::Result<Output, KeyError> {
+    let config = network_config();
+    let mut command: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    command.extend(
+        config
+            .keyring_args()
+            .map_err(|err| KeyError::Other(err.to_string()))?,
+    );
+    let mut cli = config.command(&command);
+    let mut attempt = 0;
+    loop {
+        let output = cli.output().map_err(|err| match err.kind() {
+            ErrorKind::NotFound => KeyError::BinaryMissing(err.to_string()),
+            _ => KeyError::Other(err.to_string()),
+        })?;
+        if output.status.success() {
+            return Ok(output);
+        }
+        let err = KeyError::classify(name, String::from_utf8_lossy(&output.stderr).trim());
+        if !err.is_transient() || attempt == retry {
+            return Err(err);
+        }
+        attempt += 1;
+        thread::sleep(time::Duration::from_secs(1));
+    }
+}
+
+///
+/// A throwaway `test` backend keyring, so test runs never touch the developer's OS keyring.
+///
+/// While it is alive every key and tx command goes through it. Dropping it restores the
+/// network config that was active before, previous keyring included, and deletes the
+/// directory with every key imported into it.
+///
+#[derive(Debug)]
+pub struct TestKeyring {
+    dir: PathBuf,
+    previous: NetworkConfig,
+}
+
+impl TestKeyring {
+    ///
+    /// Creates an empty keyring in the temp directory and makes it the active one
+    ///
+    pub fn new() -> Result<Self> {
+        let dir = std::env::temp_dir().join(format!("secretcli-keyring-{}", generate_label(8)));
+        fs::create_dir_all(&dir).map_err(serde_json::Error::io)?;
+
+        let previous = network_config();
+        set_network_config(previous.clone().keyring_dir(dir.to_string_lossy()));
+        Ok(TestKeyring { dir, previous })
+    }
+
+    ///
+    /// Creates a keyring with the given accounts already imported
+    ///
+    /// # Arguments
+    ///
+    /// * 'accounts' - Key names and their mnemonics
+    ///
+    pub fn with_accounts(accounts: &[(&str, &str)]) -> Result<Self> {
+        let keyring = Self::new()?;
+        for (name, mnemonic) in accounts {
+            keyring.import(name, mnemonic)?;
+        }
+        Ok(keyring)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    ///
+    /// Recovers a key from its mnemonic and returns its address
+    ///
+    /// # Arguments
+    ///
+    /// * 'name' - Name of the key in the keyring
+    /// * 'mnemonic' - The key's mnemonic
+    ///
+    pub fn import(&self, name: &str, mnemonic: &str) -> Result<String> {
+        let dir = self.dir.to_string_lossy().to_string();
+        recover_key(
+            name,
+            mnemonic,
+            &["--keyring-dir", &dir, "--keyring-backend", "test"],
+        )
+    }
+}
+
+///
+/// Runs `keys add --recover` with the mnemonic on stdin and returns the key's address
+///
+pub(crate) fn recover_key(name: &str, mnemonic: &str, keyring_args: &[&str]) -> Result<String> {
+    let recover = ["keys", "add", name, "--recover", "--output", "json"];
+    let mut child = network_config()
+        .command(recover.iter().chain(keyring_args))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(serde_json::Error::io)?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(format!("{}\n", mnemonic).as_bytes())
+        .map_err(serde_json::Error::io)?;
+    let output = child.wait_with_output().map_err(serde_json::Error::io)?;
+    if !output.status.success() {
+        return Err(Error::custom(format!(
+            "Could not import {}: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    // Depending on the version the key info is printed to stdout or stderr
+    let key: Value = serde_json::from_slice(&output.stdout)
+        .or_else(|_| serde_json::from_slice(&output.stderr))?;
+    key.get("address")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| Error::custom(format!("No address for imported key {}", name)))
+}
+
+impl Drop for TestKeyring {
+    fn drop(&mut self) {
+        set_network_config(self.previous.clone());
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_classify_key_errors() {
+        assert_eq!(
+            KeyError::classify(
+                "alice",
+                "Error: alice is not a valid name or address: decoding bech32 failed"
+            ),
+            KeyError::NotFound("alice".to_string())
+        );
+        assert!(matches!(
+            KeyError::classify("alice", "Error: EOF"),
+            KeyError::KeyringLocked(_)
+        ));
+        assert!(matches!(
+            KeyError::classify("alice", "bash: secretd: command not found"),
+            KeyError::BinaryMissing(_)
+        ));
+        let other = KeyError::classify("alice", "Error: resource temporarily unavailable");
+        assert!(other.is_transient());
+        assert!(!KeyError::NotFound("alice".to_string()).is_transient());
+    }
+}
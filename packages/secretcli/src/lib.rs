@@ -1,6 +1,65 @@
+//! Wrapper around the secretd binary for off-chain test scripts.
+//! This shells out and reads/writes local cache files so nothing is compiled for wasm32.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod address_book;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod analytics;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod backend;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod checkpoint;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod cli_types;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod secretcli;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod utils;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod constants;
-#[cfg(feature = "helpers")]
+#[cfg(not(target_arch = "wasm32"))]
+pub mod coverage;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod decrypt;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod events;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod fork;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod hooks;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod idempotent;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod keeper;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod keyring;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod metrics;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod multichain;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod network;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod padding;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod provenance;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod query_cache;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rehearsal;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod reporter;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sandbox;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod schema;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod snip20;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod trace;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod wallet;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod wasm;
+#[cfg(all(feature = "helpers", not(target_arch = "wasm32")))]
 pub mod deployable;
@@ -0,0 +1,236 @@
+//! Counters and histograms of the chain operations this crate runs, exposed in the
+//! Prometheus text format for bots and daemons built on it. Recording is always on and
+//! costs a lock per operation, nothing leaves the process until [serve] or [push] is called.
+
+use serde_json::Result;
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::Mutex,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+const GAS_BUCKETS: &[f64] = &[
+    50_000.0,
+    100_000.0,
+    200_000.0,
+    500_000.0,
+    1_000_000.0,
+    2_000_000.0,
+    5_000_000.0,
+];
+const LATENCY_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry::new());
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Cumulative count per bucket, same order as the bucket bounds
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, buckets: &[f64], value: f64) {
+        if self.counts.is_empty() {
+            self.counts = vec![0; buckets.len()];
+        }
+        for (bound, count) in buckets.iter().zip(&mut self.counts) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, buckets: &[f64]) {
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (bound, count) in buckets.iter().zip(&self.counts) {
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, self.count);
+        let _ = writeln!(out, "{}_sum {}", name, self.sum);
+        let _ = writeln!(out, "{}_count {}", name, self.count);
+    }
+}
+
+#[derive(Debug)]
+struct Registry {
+    txs: u64,
+    failures: BTreeMap<String, u64>,
+    gas_used: Histogram,
+    query_latency: Histogram,
+}
+
+impl Registry {
+    const fn new() -> Self {
+        Registry {
+            txs: 0,
+            failures: BTreeMap::new(),
+            gas_used: Histogram {
+                counts: Vec::new(),
+                sum: 0.0,
+                count: 0,
+            },
+            query_latency: Histogram {
+                counts: Vec::new(),
+                sum: 0.0,
+                count: 0,
+            },
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# TYPE secretcli_txs_total counter\n");
+        let _ = writeln!(out, "secretcli_txs_total {}", self.txs);
+        out.push_str("# TYPE secretcli_failures_total counter\n");
+        for (reason, count) in &self.failures {
+            let _ = writeln!(
+                out,
+                "secretcli_failures_total{{reason=\"{}\"}} {}",
+                reason.replace('\\', "\\\\").replace('"', "\\\""),
+                count
+            );
+        }
+        self.gas_used
+            .render(&mut out, "secretcli_gas_used", GAS_BUCKETS);
+        self.query_latency.render(
+            &mut out,
+            "secretcli_query_duration_seconds",
+            LATENCY_BUCKETS,
+        );
+        out
+    }
+}
+
+fn registry() -> std::sync::MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Counts a confirmed tx and the gas it used
+pub fn record_tx(gas_used: u64) {
+    let mut registry = registry();
+    registry.txs += 1;
+    registry.gas_used.observe(GAS_BUCKETS, gas_used as f64);
+}
+
+/// Counts a failed command or tx, `reason` becomes the label of the failure counter
+pub fn record_failure(reason: &str) {
+    *registry().failures.entry(reason.to_string()).or_default() += 1;
+}
+
+pub fn observe_query(duration: Duration) {
+    registry()
+        .query_latency
+        .observe(LATENCY_BUCKETS, duration.as_secs_f64());
+}
+
+///
+/// Every metric in the Prometheus text format
+///
+pub fn render() -> String {
+    registry().render()
+}
+
+///
+/// Serves the metrics on `http://<addr>/metrics` from a background thread
+///
+/// # Arguments
+///
+/// * 'addr' - Address to listen on, e.g. `0.0.0.0:9100`
+///
+pub fn serve(addr: impl ToSocketAddrs) -> Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(addr).map_err(serde_json::Error::io)?;
+    Ok(thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = respond(stream);
+        }
+    }))
+}
+
+fn respond(mut stream: TcpStream) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let (status, body) = if request_line.starts_with("GET /metrics ") {
+        ("200 OK", render())
+    } else {
+        ("404 Not Found", String::new())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+///
+/// Pushes the metrics to a Prometheus pushgateway, replacing the job's previous ones
+///
+/// # Arguments
+///
+/// * 'gateway' - Address of the pushgateway, e.g. `http://pushgateway:9091`
+/// * 'job' - Job the metrics are grouped under
+///
+pub fn push(gateway: &str, job: &str) -> Result<()> {
+    let host = gateway
+        .strip_prefix("http://")
+        .ok_or_else(|| serde::de::Error::custom("Only http:// pushgateways are supported"))?
+        .trim_end_matches('/');
+    let body = render();
+    let mut stream = TcpStream::connect(host).map_err(serde_json::Error::io)?;
+    write!(
+        stream,
+        "PUT /metrics/job/{} HTTP/1.1\r\nHost: {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        job,
+        host,
+        body.len(),
+        body
+    )
+    .map_err(serde_json::Error::io)?;
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(serde_json::Error::io)?;
+    match response.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => Err(serde::de::Error::custom(format!(
+            "Pushgateway refused the metrics: {}",
+            response.lines().next().unwrap_or_default()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut registry = Registry::new();
+        registry.txs = 2;
+        registry.gas_used.observe(GAS_BUCKETS, 150_000.0);
+        registry.gas_used.observe(GAS_BUCKETS, 3_000_000.0);
+        registry.failures.insert("out of gas".to_string(), 1);
+        registry
+            .query_latency
+            .observe(LATENCY_BUCKETS, Duration::from_millis(300).as_secs_f64());
+
+        let out = registry.render();
+        assert!(out.contains("secretcli_txs_total 2\n"));
+        assert!(out.contains("secretcli_failures_total{reason=\"out of gas\"} 1\n"));
+        assert!(out.contains("secretcli_gas_used_bucket{le=\"100000\"} 0\n"));
+        assert!(out.contains("secretcli_gas_used_bucket{le=\"200000\"} 1\n"));
+        assert!(out.contains("secretcli_gas_used_bucket{le=\"+Inf\"} 2\n"));
+        assert!(out.contains("secretcli_gas_used_sum 3150000\n"));
+        assert!(out.contains("secretcli_query_duration_seconds_bucket{le=\"0.5\"} 1\n"));
+        assert!(out.contains("secretcli_query_duration_seconds_count 1\n"));
+    }
+}
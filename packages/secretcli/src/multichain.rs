@@ -0,0 +1,150 @@
+use crate::network::{chain_id, network_config, set_network_config, NetworkConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::Result;
+use std::collections::{BTreeMap, BTreeSet};
+
+///
+/// What a deployment did on one chain
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChainOutcome<T> {
+    /// Chain id the network reported, unset if its node couldn't be reached
+    pub chain_id: Option<String>,
+    pub result: Option<T>,
+    pub error: Option<String>,
+}
+
+///
+/// Combined report of a deployment run against several chains, keyed by network name, as
+/// chain ids are not unique across local forks
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MultiChainReport<T> {
+    pub chains: BTreeMap<String, ChainOutcome<T>>,
+}
+
+impl<T> MultiChainReport<T> {
+    pub fn is_ok(&self) -> bool {
+        self.chains.values().all(|outcome| outcome.error.is_none())
+    }
+
+    pub fn get(&self, network: &str) -> Option<&T> {
+        self.chains
+            .get(network)
+            .and_then(|outcome| outcome.result.as_ref())
+    }
+
+    /// Networks the deployment failed on, with their errors
+    pub fn failures(&self) -> Vec<(&str, &str)> {
+        self.chains
+            .iter()
+            .filter_map(|(network, outcome)| {
+                outcome
+                    .error
+                    .as_deref()
+                    .map(|error| (network.as_str(), error))
+            })
+            .collect()
+    }
+
+    fn record(&mut self, network: String, chain_id: Option<String>, result: Result<T>) {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        self.chains.insert(
+            network,
+            ChainOutcome {
+                chain_id,
+                result,
+                error,
+            },
+        );
+    }
+}
+
+///
+/// Runs the same deployment against every network, one after the other, with each network's
+/// config active while its deployment runs. A failure on one chain is recorded in the report
+/// and the next chain is still deployed.
+///
+/// # Arguments
+///
+/// * 'networks' - Configs of the chains to deploy to, e.g. pulsar-3 and a [crate::fork::LocalFork].
+///   Their names key the report and must be unique, nothing is deployed otherwise.
+/// * 'deploy' - The deployment, given the config it runs with
+///
+pub fn deploy_to_networks<T, F>(
+    networks: &[NetworkConfig],
+    mut deploy: F,
+) -> Result<MultiChainReport<T>>
+where
+    F: FnMut(&NetworkConfig) -> Result<T>,
+{
+    check_unique_names(networks)?;
+    let previous = network_config();
+    let mut report = MultiChainReport {
+        chains: BTreeMap::new(),
+    };
+    for network in networks {
+        set_network_config(network.clone());
+        // A chain whose node can't be reached is still reported
+        let (chain_id, result) = match chain_id(network) {
+            Ok(chain_id) => (Some(chain_id), deploy(network)),
+            Err(err) => (None, Err(err)),
+        };
+        report.record(network.network_name(), chain_id, result);
+    }
+    set_network_config(previous);
+    Ok(report)
+}
+
+fn check_unique_names(networks: &[NetworkConfig]) -> Result<()> {
+    let mut names = BTreeSet::new();
+    match networks
+        .iter()
+        .map(NetworkConfig::network_name)
+        .find(|name| !names.insert(name.clone()))
+    {
+        Some(name) => Err(serde::de::Error::custom(format!(
+            "Network name {} is used more than once, name every network",
+            name
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut report = MultiChainReport {
+            chains: BTreeMap::new(),
+        };
+        report.record("testnet".to_string(), Some("pulsar-3".to_string()), Ok(1));
+        assert!(report.is_ok());
+
+        // forks of the same chain are kept apart by their network names
+        report.record(
+            "fork".to_string(),
+            Some("pulsar-3".to_string()),
+            Err(serde::de::Error::custom("out of gas")),
+        );
+        assert!(!report.is_ok());
+        assert_eq!(report.get("testnet"), Some(&1));
+        assert_eq!(report.get("fork"), None);
+        assert_eq!(report.failures(), vec![("fork", "out of gas")]);
+    }
+
+    #[test]
+    fn test_duplicate_names() {
+        let testnet = NetworkConfig::default().name("testnet");
+        let fork = NetworkConfig::default().name("fork");
+        assert!(check_unique_names(&[testnet.clone(), fork.clone()]).is_ok());
+        assert!(check_unique_names(&[testnet, fork.clone(), fork]).is_err());
+        // unnamed configs are all called default
+        assert!(check_unique_names(&[NetworkConfig::default(), NetworkConfig::default()]).is_err());
+    }
+}
@@ -0,0 +1,557 @@
+use crate::{constants::SECRETCLI, utils::print_warning};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    process::Command,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+static NETWORK_CONFIG: RwLock<Option<NetworkConfig>> = RwLock::new(None);
+/// Index of the node in [NetworkConfig::nodes] currently in use
+static ACTIVE_NODE: AtomicUsize = AtomicUsize::new(0);
+/// When the last request went to a node, shared by all threads to respect the rate limit
+static LAST_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Longest wait between retries of a rate limited request
+const MAX_BACKOFF_SECS: u64 = 30;
+
+///
+/// Where secretd is run
+///
+/// Remote runners only get the command line, no files. Commands that pass secretd a local
+/// path are refused with them: storing contracts, downloading code and the isolated keyring
+/// of [NetworkConfig::keyring_dir]. Run those with [Runner::Local].
+///
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Runner {
+    Local,
+    /// On another host through `ssh`, e.g. `validator@10.0.0.2`
+    Ssh {
+        host: String,
+    },
+    /// Inside a running container through `docker exec`
+    Docker {
+        container: String,
+    },
+}
+
+impl Default for Runner {
+    fn default() -> Self {
+        Runner::Local
+    }
+}
+
+///
+/// Network wide settings applied to every secretd call made by this crate
+///
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct NetworkConfig {
+    /// Account paying the fees of every tx through a feegrant allowance
+    pub fee_granter: Option<String>,
+    /// Account paying the fees of every tx, must also sign it
+    pub fee_payer: Option<String>,
+    /// RPC endpoints to use, in order of preference. Uses secretd's configured node when empty
+    #[serde(default)]
+    pub nodes: Vec<String>,
+    /// Name of the network, selects its [crate::address_book::AddressBook]
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Echoes every secretd command with its stdout and stderr
+    #[serde(default)]
+    pub verbose: bool,
+    /// Gzips contracts before uploading them, lowering the store fee
+    #[serde(default)]
+    pub compress_wasm: bool,
+    /// Keyring directory used with the `test` backend instead of the OS keyring,
+    /// see [crate::keyring::TestKeyring]
+    #[serde(default)]
+    pub keyring_dir: Option<String>,
+    /// Memo added to every tx, see [crate::idempotent::with_memo]
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Most requests per second sent to the node, for public endpoints that ban heavy users
+    #[serde(default)]
+    pub max_requests_per_second: Option<u32>,
+    /// Name or path of the secretd binary, `secretd` when unset
+    #[serde(default)]
+    pub binary: Option<String>,
+    /// Runs secretd on another host or in a container, see [Runner] for what it can't do
+    #[serde(default)]
+    pub runner: Runner,
+    /// Chain id txs are signed for, secretd's configured one when unset
+    #[serde(default)]
+    pub chain_id: Option<String>,
+}
+
+impl NetworkConfig {
+    pub fn fee_granter(mut self, granter: impl Into<String>) -> Self {
+        self.fee_granter = Some(granter.into());
+        self
+    }
+
+    pub fn fee_payer(mut self, payer: impl Into<String>) -> Self {
+        self.fee_payer = Some(payer.into());
+        self
+    }
+
+    pub fn nodes(mut self, nodes: Vec<String>) -> Self {
+        self.nodes = nodes;
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn compress_wasm(mut self, compress: bool) -> Self {
+        self.compress_wasm = compress;
+        self
+    }
+
+    pub fn keyring_dir(mut self, dir: impl Into<String>) -> Self {
+        self.keyring_dir = Some(dir.into());
+        self
+    }
+
+    pub fn memo(mut self, memo: impl Into<String>) -> Self {
+        self.memo = Some(memo.into());
+        self
+    }
+
+    pub fn max_requests_per_second(mut self, max: u32) -> Self {
+        self.max_requests_per_second = Some(max);
+        self
+    }
+
+    pub fn binary(mut self, binary: impl Into<String>) -> Self {
+        self.binary = Some(binary.into());
+        self
+    }
+
+    pub fn runner(mut self, runner: Runner) -> Self {
+        self.runner = runner;
+        self
+    }
+
+    pub fn chain_id(mut self, chain_id: impl Into<String>) -> Self {
+        self.chain_id = Some(chain_id.into());
+        self
+    }
+
+    /// Name of the network, `default` if none was set
+    pub fn network_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| "default".to_string())
+    }
+
+    /// Node currently in use
+    pub fn active_node(&self) -> Option<&str> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let index = ACTIVE_NODE.load(Ordering::Relaxed) % self.nodes.len();
+        Some(&self.nodes[index])
+    }
+
+    /// Extra flags that apply to tx commands
+    pub fn tx_args(&self) -> Vec<String> {
+        let mut args = vec![];
+        if let Some(granter) = &self.fee_granter {
+            args.push("--fee-granter".to_string());
+            args.push(granter.clone());
+        }
+        if let Some(payer) = &self.fee_payer {
+            args.push("--fee-payer".to_string());
+            args.push(payer.clone());
+        }
+        if let Some(memo) = &self.memo {
+            args.push("--note".to_string());
+            args.push(memo.clone());
+        }
+        if let Some(chain_id) = &self.chain_id {
+            args.push("--chain-id".to_string());
+            args.push(chain_id.clone());
+        }
+        args
+    }
+
+    /// A secretd command with the given arguments, run where [NetworkConfig::runner] says
+    pub fn command<I, S>(&self, args: I) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let binary = self.binary.as_deref().unwrap_or(SECRETCLI);
+        match &self.runner {
+            Runner::Local => {
+                let mut command = Command::new(binary);
+                command.args(args.into_iter().map(|arg| arg.as_ref().to_string()));
+                command
+            }
+            Runner::Ssh { host } => {
+                // The remote shell splits the command again, every argument must be quoted
+                let mut command = Command::new("ssh");
+                command.args(["-T", host, "--", &shell_quote(binary)]);
+                command.args(args.into_iter().map(|arg| shell_quote(arg.as_ref())));
+                command
+            }
+            Runner::Docker { container } => {
+                let mut command = Command::new("docker");
+                command.args(["exec", "-i", container, binary]);
+                command.args(args.into_iter().map(|arg| arg.as_ref().to_string()));
+                command
+            }
+        }
+    }
+
+    /// Flags that point key and tx commands at the isolated keyring, if any
+    pub fn keyring_args(&self) -> serde_json::Result<Vec<String>> {
+        match &self.keyring_dir {
+            Some(dir) => {
+                self.require_local("The isolated keyring")?;
+                Ok(vec![
+                    "--keyring-dir".to_string(),
+                    dir.clone(),
+                    "--keyring-backend".to_string(),
+                    "test".to_string(),
+                ])
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Refuses features that hand secretd a local path when it runs elsewhere
+    pub(crate) fn require_local(&self, feature: &str) -> serde_json::Result<()> {
+        match &self.runner {
+            Runner::Local => Ok(()),
+            Runner::Ssh { host } => Err(serde::de::Error::custom(format!(
+                "{} needs local files, it can't be used with secretd on {}",
+                feature, host
+            ))),
+            Runner::Docker { container } => Err(serde::de::Error::custom(format!(
+                "{} needs local files, it can't be used with secretd in container {}",
+                feature, container
+            ))),
+        }
+    }
+}
+
+/// Quotes an argument for a POSIX shell
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+///
+/// Sets the config used by all following secretd calls
+///
+pub fn set_network_config(config: NetworkConfig) {
+    *NETWORK_CONFIG.write().unwrap() = Some(config);
+}
+
+///
+/// Current config, or the default one if none was set
+///
+pub fn network_config() -> NetworkConfig {
+    NETWORK_CONFIG.read().unwrap().clone().unwrap_or_default()
+}
+
+///
+/// Checks that the node answers and is not catching up with the chain
+///
+pub fn node_is_healthy(node: &str) -> bool {
+    let config = network_config();
+    throttle(&config);
+    let output = match config.command(["status", "--node", node]).output() {
+        Ok(output) => output,
+        Err(_) => return false,
+    };
+    // Some secretd versions print the status to stderr
+    let status: Value = match serde_json::from_slice(&output.stdout)
+        .or_else(|_| serde_json::from_slice(&output.stderr))
+    {
+        Ok(status) => status,
+        Err(_) => return false,
+    };
+    let sync_info = status.get("SyncInfo").or_else(|| status.get("sync_info"));
+    sync_info
+        .and_then(|info| info.get("catching_up"))
+        .and_then(Value::as_bool)
+        == Some(false)
+}
+
+///
+/// Chain id of the node the config points at, secretd's configured node if it lists none
+///
+pub fn chain_id(config: &NetworkConfig) -> serde_json::Result<String> {
+    throttle(config);
+    let mut args = vec!["status".to_string()];
+    args.extend(node_args(config));
+    let output = config
+        .command(&args)
+        .output()
+        .map_err(serde_json::Error::io)?;
+    // Some secretd versions print the status to stderr
+    let status: Value = serde_json::from_slice(&output.stdout)
+        .or_else(|_| serde_json::from_slice(&output.stderr))?;
+    status
+        .get("NodeInfo")
+        .or_else(|| status.get("node_info"))
+        .and_then(|info| info.get("network"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| serde::de::Error::custom("Node status has no chain id"))
+}
+
+pub(crate) fn node_args(config: &NetworkConfig) -> Vec<String> {
+    match config.active_node() {
+        Some(node) => vec!["--node".to_string(), node.to_string()],
+        None => vec![],
+    }
+}
+
+/// Whether secretd failed because it could not reach the node
+pub(crate) fn is_connection_error(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    [
+        "connection refused",
+        "connection reset",
+        "no such host",
+        "dial tcp",
+        "timeout",
+        "post failed",
+    ]
+    .iter()
+    .any(|pattern| stderr.contains(pattern))
+}
+
+/// Whether the node turned the request down for being over its rate limit or overloaded.
+/// Only HTTP status lines count, amounts and heights in other errors contain the same digits.
+pub(crate) fn is_rate_limited(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    [
+        "429 too many requests",
+        "503 service unavailable",
+        // the RPC client's `(Status: 429 Too Many Requests, ...)` with the text cut off
+        "status: 429",
+        "status: 503",
+        "status code 429",
+        "status code 503",
+    ]
+    .iter()
+    .any(|pattern| stderr.contains(pattern))
+}
+
+///
+/// Waits until sending another request keeps under [NetworkConfig::max_requests_per_second]
+///
+pub(crate) fn throttle(config: &NetworkConfig) {
+    let interval = match config.max_requests_per_second {
+        Some(max) if max > 0 => Duration::from_secs(1) / max,
+        _ => return,
+    };
+    let mut last = LAST_REQUEST.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(wait) =
+        last.and_then(|last| (last + interval).checked_duration_since(Instant::now()))
+    {
+        thread::sleep(wait);
+    }
+    *last = Some(Instant::now());
+}
+
+/// Exponential wait before the given retry of a rate limited request
+pub(crate) fn backoff(retry: u32) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(retry).min(MAX_BACKOFF_SECS))
+}
+
+///
+/// Switches to the next healthy node, or simply the next one if none are healthy
+///
+pub(crate) fn failover(config: &NetworkConfig) {
+    let len = config.nodes.len();
+    if len < 2 {
+        return;
+    }
+    let current = ACTIVE_NODE.load(Ordering::Relaxed) % len;
+    let next = (1..len)
+        .map(|offset| (current + offset) % len)
+        .find(|&index| node_is_healthy(&config.nodes[index]))
+        .unwrap_or((current + 1) % len);
+    print_warning(&format!(
+        "Node {} is unreachable, switching to {}",
+        config.nodes[current], config.nodes[next]
+    ));
+    ACTIVE_NODE.store(next, Ordering::Relaxed);
+}
+
+///
+/// Fails over before broadcasting if the active node is unhealthy
+///
+pub(crate) fn ensure_healthy_node(config: &NetworkConfig) {
+    if let Some(node) = config.active_node() {
+        if !node_is_healthy(node) {
+            failover(config);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tx_args() {
+        assert!(NetworkConfig::default().tx_args().is_empty());
+
+        let config = NetworkConfig::default()
+            .fee_granter("secret1granter")
+            .fee_payer("secret1payer");
+        assert_eq!(
+            config.tx_args(),
+            vec![
+                "--fee-granter",
+                "secret1granter",
+                "--fee-payer",
+                "secret1payer"
+            ]
+        );
+        assert_eq!(
+            NetworkConfig::default().memo("deploy:v2:token").tx_args(),
+            vec!["--note", "deploy:v2:token"]
+        );
+        assert_eq!(
+            NetworkConfig::default().chain_id("secret-fork").tx_args(),
+            vec!["--chain-id", "secret-fork"]
+        );
+    }
+
+    #[test]
+    fn test_keyring_args() {
+        assert!(NetworkConfig::default().keyring_args().unwrap().is_empty());
+        assert_eq!(
+            NetworkConfig::default()
+                .keyring_dir("/tmp/keyring")
+                .keyring_args()
+                .unwrap(),
+            vec!["--keyring-dir", "/tmp/keyring", "--keyring-backend", "test"]
+        );
+        let remote = NetworkConfig::default().runner(Runner::Docker {
+            container: "localsecret".to_string(),
+        });
+        assert!(remote.keyring_args().unwrap().is_empty());
+        assert!(remote
+            .keyring_dir("/tmp/keyring")
+            .keyring_args()
+            .unwrap_err()
+            .to_string()
+            .contains("container localsecret"));
+    }
+
+    #[test]
+    fn test_runners() {
+        let args = |command: Command| -> Vec<String> {
+            std::iter::once(command.get_program())
+                .chain(command.get_args())
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect()
+        };
+        let query = ["q", "compute", "query", "secret1a", r#"{"it's":{}}"#];
+
+        assert_eq!(
+            args(NetworkConfig::default().command(query)),
+            vec![
+                "secretd",
+                "q",
+                "compute",
+                "query",
+                "secret1a",
+                r#"{"it's":{}}"#
+            ]
+        );
+        assert_eq!(
+            args(
+                NetworkConfig::default()
+                    .binary("secretd-v1.12")
+                    .runner(Runner::Docker {
+                        container: "localsecret".to_string()
+                    })
+                    .command(["status"])
+            ),
+            vec![
+                "docker",
+                "exec",
+                "-i",
+                "localsecret",
+                "secretd-v1.12",
+                "status"
+            ]
+        );
+        assert_eq!(
+            args(
+                NetworkConfig::default()
+                    .runner(Runner::Ssh {
+                        host: "validator@10.0.0.2".to_string()
+                    })
+                    .command(query)
+            ),
+            vec![
+                "ssh",
+                "-T",
+                "validator@10.0.0.2",
+                "--",
+                "'secretd'",
+                "'q'",
+                "'compute'",
+                "'query'",
+                "'secret1a'",
+                r#"'{"it'\''s":{}}'"#
+            ]
+        );
+    }
+
+    #[test]
+    fn test_connection_errors() {
+        assert!(is_connection_error(
+            "Error: post failed: Post \"http://localhost:26657\": dial tcp 127.0.0.1:26657: connect: connection refused"
+        ));
+        assert!(!is_connection_error("Error: key not found"));
+    }
+
+    #[test]
+    fn test_rate_limiting() {
+        assert!(is_rate_limited(
+            "Error: error in json rpc client: 429 Too Many Requests"
+        ));
+        assert!(is_rate_limited("Error: 503 Service Unavailable"));
+        assert!(is_rate_limited(
+            "error in json rpc client, with http response metadata: (Status: 429, Protocol HTTP/1.1)"
+        ));
+        assert!(!is_rate_limited("Error: key not found"));
+        assert!(!is_rate_limited(
+            "Error: 4290uscrt is smaller than 5031uscrt: insufficient funds"
+        ));
+        assert!(!is_rate_limited(
+            "account sequence mismatch, expected 503, got 429: incorrect account sequence"
+        ));
+        assert_eq!(backoff(0), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(8));
+        assert_eq!(backoff(40), Duration::from_secs(MAX_BACKOFF_SECS));
+
+        let config = NetworkConfig::default().max_requests_per_second(20);
+        let start = Instant::now();
+        for _ in 0..3 {
+            throttle(&config);
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    cli_types::NetContract,
+    secretcli::{query_bytes, test_contract_handle},
+};
+use serde::Serialize;
+use serde_json::Result;
+
+/// Block size most Secret contracts pad their responses to, same as secret-utils'
+pub const BLOCK_SIZE: usize = 256;
+
+///
+/// Length of a contract response and the message that produced it
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseLength {
+    pub msg: String,
+    pub len: usize,
+}
+
+///
+/// Fails listing every response whose length is not a multiple of the block size. Responses
+/// are encrypted as they are, so an unpadded length leaks what the response holds
+///
+/// # Arguments
+///
+/// * 'lengths' - Responses to check
+/// * 'block_size' - Block size the contract pads to
+///
+pub fn check_padding(lengths: &[ResponseLength], block_size: usize) -> Result<()> {
+    let unpadded: Vec<String> = lengths
+        .iter()
+        .filter(|response| block_size == 0 || response.len % block_size != 0)
+        .map(|response| format!("{} ({} bytes)", response.msg, response.len))
+        .collect();
+    if unpadded.is_empty() {
+        return Ok(());
+    }
+    Err(serde::de::Error::custom(format!(
+        "Responses not padded to {} bytes: {}",
+        block_size,
+        unpadded.join(", ")
+    )))
+}
+
+///
+/// Executes every message and measures the data the contract returned for it. Messages that
+/// return no data are skipped, there is nothing to leak
+///
+/// # Arguments
+///
+/// * 'contract' - The contract to execute
+/// * 'msgs' - Handle messages, vary them to cover responses of different sizes
+/// * 'sender' - Msg sender
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn handle_lengths<Message: Serialize>(
+    contract: &NetContract,
+    msgs: &[Message],
+    sender: impl AsRef<str>,
+    backend: Option<&str>,
+) -> Result<Vec<ResponseLength>> {
+    let sender = sender.as_ref();
+    let mut lengths = vec![];
+    for msg in msgs {
+        let (compute, tx) = test_contract_handle(msg, contract, sender, None, backend, None)?;
+        if tx.logs.is_empty() {
+            return Err(serde::de::Error::custom(format!(
+                "{} failed: {}",
+                tx.txhash, tx.raw_log
+            )));
+        }
+        for answer in &compute.answers {
+            let len = answer.data()?.len();
+            if len > 0 {
+                lengths.push(ResponseLength {
+                    msg: serde_json::to_string(msg)?,
+                    len,
+                });
+            }
+        }
+    }
+    Ok(lengths)
+}
+
+///
+/// Runs every query and measures the contract's response
+///
+/// # Arguments
+///
+/// * 'contract' - The contract to query
+/// * 'msgs' - Queries, vary them to cover responses of different sizes
+///
+pub fn query_lengths<Query: Serialize>(
+    contract: &NetContract,
+    msgs: &[Query],
+) -> Result<Vec<ResponseLength>> {
+    msgs.iter()
+        .map(|msg| {
+            Ok(ResponseLength {
+                msg: serde_json::to_string(msg)?,
+                len: query_bytes(contract, msg, None)?.len(),
+            })
+        })
+        .collect()
+}
+
+///
+/// Executes every message and fails unless all responses are padded to the block size
+///
+pub fn assert_handles_padded<Message: Serialize>(
+    contract: &NetContract,
+    msgs: &[Message],
+    block_size: usize,
+    sender: impl AsRef<str>,
+    backend: Option<&str>,
+) -> Result<()> {
+    let sender = sender.as_ref();
+    check_padding(
+        &handle_lengths(contract, msgs, sender, backend)?,
+        block_size,
+    )
+}
+
+///
+/// Runs every query and fails unless all responses are padded to the block size
+///
+pub fn assert_queries_padded<Query: Serialize>(
+    contract: &NetContract,
+    msgs: &[Query],
+    block_size: usize,
+) -> Result<()> {
+    check_padding(&query_lengths(contract, msgs)?, block_size)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn response(msg: &str, len: usize) -> ResponseLength {
+        ResponseLength {
+            msg: msg.to_string(),
+            len,
+        }
+    }
+
+    #[test]
+    fn test_check_padding() {
+        let padded = [response("balance", 256), response("history", 1024)];
+        assert!(check_padding(&padded, BLOCK_SIZE).is_ok());
+        assert!(check_padding(&[], BLOCK_SIZE).is_ok());
+
+        let err = check_padding(&[padded[0].clone(), response("info", 37)], BLOCK_SIZE)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("info (37 bytes)"));
+        assert!(!err.contains("balance"));
+    }
+
+    #[cfg(feature = "sandbox")]
+    #[test]
+    fn test_query_lengths() -> Result<()> {
+        use crate::{
+            sandbox::{activate, deactivate, Sandbox},
+            secretcli::{store_and_confirm, test_init},
+        };
+        use cosmwasm_std::{
+            to_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+        };
+        use secret_multi_test::ContractWrapper;
+
+        fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+            Ok(Response::new())
+        }
+        fn execute(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+            Ok(Response::new())
+        }
+        // Answers with `size` bytes, padded with spaces like secret-utils does when `pad` is set
+        fn query(_: Deps, _: Env, (size, pad): (usize, bool)) -> StdResult<Binary> {
+            let mut response = to_binary(&"a".repeat(size))?.0;
+            if pad {
+                let padding = (BLOCK_SIZE - response.len() % BLOCK_SIZE) % BLOCK_SIZE;
+                response.extend(std::iter::repeat(b' ').take(padding));
+            }
+            Ok(Binary(response))
+        }
+
+        activate(Sandbox::new().register(
+            "sized.wasm",
+            Box::new(ContractWrapper::new_with_empty(execute, instantiate, query)),
+        ));
+        let code = store_and_confirm("sized.wasm", "alice", None, None, None)?;
+        let mut contract = NetContract::new("sized", code.code_id.to_string(), "", code.code_hash);
+        let init = test_init(&Empty {}, &contract, "sized", "alice", None, None)?;
+        contract.address = init.contract_address().unwrap();
+
+        let lengths = query_lengths(&contract, &[(10, false), (300, true)])?;
+        // the quotes around the string make up the rest
+        assert_eq!(lengths[0].len, 12);
+        assert_eq!(lengths[1].len, 2 * BLOCK_SIZE);
+        assert!(assert_queries_padded(&contract, &[(1, true), (600, true)], BLOCK_SIZE).is_ok());
+        assert!(assert_queries_padded(&contract, &[(1, true), (1, false)], BLOCK_SIZE).is_err());
+        deactivate();
+        Ok(())
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{
+    cli_types::{InstantiatePermission, StoredCode},
+    network::network_config,
+    secretcli::{list_code, store_and_confirm, CONTRACT_CACHE_DIR},
+    wasm::read_module,
+};
+use chrono::{DateTime, Utc};
+use serde::{de::Error, Deserialize, Serialize};
+use serde_json::Result;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs::{create_dir_all, File},
+    io::{BufReader, ErrorKind},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Optimizer image version the wasm was built with, e.g. `1.0.10`, when not passed explicitly
+pub const OPTIMIZER_VERSION_VAR: &str = "OPTIMIZER_VERSION";
+
+///
+/// Where stored code came from, enough to rebuild it and check the result is identical
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub contract_file: String,
+    pub code_id: u64,
+    pub code_hash: String,
+    /// sha256 of the uncompressed wasm, what Secret uses as the code hash
+    pub wasm_checksum: String,
+    pub git_commit: Option<String>,
+    /// Whether the work tree had uncommitted changes, which makes the commit meaningless
+    pub git_dirty: bool,
+    pub rustc_version: Option<String>,
+    pub optimizer_version: Option<String>,
+    pub stored_at: DateTime<Utc>,
+}
+
+///
+/// Provenance of every code stored on a network, saved next to the cached contracts so it
+/// can be committed along with the deployment
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeploymentLock {
+    /// Keyed by contract file
+    #[serde(default)]
+    pub codes: BTreeMap<String, Provenance>,
+}
+
+impl DeploymentLock {
+    ///
+    /// Loads the lockfile of the given network, or an empty one if there is none yet
+    ///
+    pub fn load(network: &str) -> Result<Self> {
+        match File::open(Self::path(network)) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(DeploymentLock::default()),
+            Err(err) => Err(serde_json::Error::io(err)),
+        }
+    }
+
+    pub fn save(&self, network: &str) -> Result<()> {
+        create_dir_all(CONTRACT_CACHE_DIR).map_err(serde_json::Error::io)?;
+        let file = File::create(Self::path(network)).map_err(serde_json::Error::io)?;
+        serde_json::to_writer_pretty(file, self)
+    }
+
+    fn path(network: &str) -> PathBuf {
+        PathBuf::from(CONTRACT_CACHE_DIR).join(format!("{}.lock", network))
+    }
+
+    pub fn get(&self, contract_file: &str) -> Result<&Provenance> {
+        self.codes
+            .get(contract_file)
+            .ok_or_else(|| Error::custom(format!("No provenance recorded for {}", contract_file)))
+    }
+}
+
+///
+/// sha256 of the wasm module in hex, gzipped files are hashed uncompressed
+///
+pub fn wasm_checksum(contract_file: impl AsRef<Path>) -> Result<String> {
+    Ok(format!("{:x}", Sha256::digest(read_module(contract_file)?)))
+}
+
+///
+/// How two wasm modules compare, both taken uncompressed
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WasmDiff {
+    pub local_checksum: String,
+    pub downloaded_checksum: String,
+    pub local_size: u64,
+    pub downloaded_size: u64,
+}
+
+impl WasmDiff {
+    pub fn is_identical(&self) -> bool {
+        self.local_checksum == self.downloaded_checksum
+    }
+}
+
+///
+/// Compares a local build with code downloaded from chain, e.g. with
+/// [download_code](crate::secretcli::download_code), to check the chain runs the code we
+/// think it does
+///
+/// # Arguments
+///
+/// * 'local' - The wasm built from the repository
+/// * 'downloaded' - The wasm downloaded from chain
+///
+pub fn diff_wasm(local: impl AsRef<Path>, downloaded: impl AsRef<Path>) -> Result<WasmDiff> {
+    let local = read_module(local)?;
+    let downloaded = read_module(downloaded)?;
+    Ok(WasmDiff {
+        local_checksum: format!("{:x}", Sha256::digest(&local)),
+        downloaded_checksum: format!("{:x}", Sha256::digest(&downloaded)),
+        local_size: local.len() as u64,
+        downloaded_size: downloaded.len() as u64,
+    })
+}
+
+/// Trimmed stdout of a command that succeeded
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+impl Provenance {
+    ///
+    /// Collects the provenance of stored code from the current git checkout and toolchain
+    ///
+    /// # Arguments
+    ///
+    /// * 'contract_file' - The wasm file that was stored
+    /// * 'code' - The stored code
+    /// * 'optimizer_version' - Optimizer the wasm was built with, defaults to `OPTIMIZER_VERSION`
+    ///
+    pub fn collect(
+        contract_file: &str,
+        code: &StoredCode,
+        optimizer_version: Option<&str>,
+    ) -> Result<Self> {
+        Ok(Provenance {
+            contract_file: contract_file.to_string(),
+            code_id: code.code_id,
+            code_hash: code.code_hash.clone(),
+            wasm_checksum: wasm_checksum(contract_file)?,
+            git_commit: command_output("git", &["rev-parse", "HEAD"]),
+            git_dirty: command_output("git", &["status", "--porcelain"])
+                .map_or(false, |status| !status.is_empty()),
+            rustc_version: command_output("rustc", &["--version"]),
+            optimizer_version: optimizer_version
+                .map(str::to_string)
+                .or_else(|| std::env::var(OPTIMIZER_VERSION_VAR).ok()),
+            stored_at: Utc::now(),
+        })
+    }
+
+    ///
+    /// Fails unless the wasm hashes to the recorded checksum and the chain holds that code
+    /// under the recorded id
+    ///
+    pub fn check(&self, wasm_checksum: &str, chain_code_hash: &str) -> Result<()> {
+        if wasm_checksum != self.wasm_checksum {
+            return Err(Error::custom(format!(
+                "{} hashes to {}, but {} was deployed",
+                self.contract_file, wasm_checksum, self.wasm_checksum
+            )));
+        }
+        if !chain_code_hash.eq_ignore_ascii_case(&self.wasm_checksum) {
+            return Err(Error::custom(format!(
+                "Code {} on chain has hash {}, not the deployed {}",
+                self.code_id, chain_code_hash, self.wasm_checksum
+            )));
+        }
+        Ok(())
+    }
+}
+
+///
+/// Stores the code like [store_and_confirm] and records its provenance in the active
+/// network's lockfile
+///
+/// # Arguments
+///
+/// * 'contract_file' - Contract file to store
+/// * 'sender' - Msg sender
+/// * 'permission' - Who may instantiate the code, defaults to the chain's default
+/// * 'store_gas' - Gas price to use when storing the contract, defaults to 10000000
+/// * 'backend' - Keyring backend defaults to none
+/// * 'optimizer_version' - Optimizer the wasm was built with, defaults to `OPTIMIZER_VERSION`
+///
+pub fn store_with_provenance(
+    contract_file: &str,
+    sender: impl AsRef<str>,
+    permission: Option<&InstantiatePermission>,
+    store_gas: Option<&str>,
+    backend: Option<&str>,
+    optimizer_version: Option<&str>,
+) -> Result<StoredCode> {
+    let sender = sender.as_ref();
+    let code = store_and_confirm(contract_file, sender, permission, store_gas, backend)?;
+    let network = network_config().network_name();
+    let mut lock = DeploymentLock::load(&network)?;
+    lock.codes.insert(
+        contract_file.to_string(),
+        Provenance::collect(contract_file, &code, optimizer_version)?,
+    );
+    lock.save(&network)?;
+    Ok(code)
+}
+
+///
+/// Checks a deployment is reproducible: the wasm, e.g. rebuilt from the recorded commit, must
+/// hash to what was deployed and the chain must still hold that code
+///
+/// # Arguments
+///
+/// * 'contract_file' - Contract file the code was stored from, as recorded in the lockfile
+/// * 'rebuilt' - The wasm to check, defaults to the contract file itself
+///
+pub fn verify_provenance(contract_file: &str, rebuilt: Option<&str>) -> Result<Provenance> {
+    let lock = DeploymentLock::load(&network_config().network_name())?;
+    let provenance = lock.get(contract_file)?.clone();
+    let checksum = wasm_checksum(rebuilt.unwrap_or(contract_file))?;
+    let chain_code_hash = list_code()?
+        .into_iter()
+        .find(|code| code.code_id == provenance.code_id as u128)
+        .map(|code| code.code_hash)
+        .ok_or_else(|| Error::custom(format!("Code {} not found on chain", provenance.code_id)))?;
+    provenance.check(&checksum, &chain_code_hash)?;
+    Ok(provenance)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{utils::generate_label, wasm::gzip};
+    use std::fs;
+
+    #[test]
+    fn test_wasm_checksum() {
+        let wasm = b"\0asm\x01\0\0\0".to_vec();
+        let dir = std::env::temp_dir();
+        let name = format!("secretcli-provenance-{}", generate_label(8));
+        let plain = dir.join(format!("{}.wasm", name));
+        let compressed = dir.join(format!("{}.wasm.gz", name));
+        fs::write(&plain, &wasm).unwrap();
+        fs::write(&compressed, gzip(&wasm).unwrap()).unwrap();
+
+        let checksum = wasm_checksum(&plain).unwrap();
+        assert_eq!(checksum, format!("{:x}", Sha256::digest(&wasm)));
+        assert_eq!(wasm_checksum(&compressed).unwrap(), checksum);
+        let _ = fs::remove_file(plain);
+        let _ = fs::remove_file(compressed);
+    }
+
+    #[test]
+    fn test_diff_wasm() {
+        let wasm = b"\0asm\x01\0\0\0".to_vec();
+        let dir = std::env::temp_dir();
+        let name = format!("secretcli-diff-{}", generate_label(8));
+        let local = dir.join(format!("{}-local.wasm", name));
+        let downloaded = dir.join(format!("{}-downloaded.wasm.gz", name));
+        fs::write(&local, &wasm).unwrap();
+        fs::write(&downloaded, gzip(&wasm).unwrap()).unwrap();
+
+        let diff = diff_wasm(&local, &downloaded).unwrap();
+        assert!(diff.is_identical());
+        assert_eq!(diff.local_size, wasm.len() as u64);
+        fs::write(&downloaded, b"\0asm\x01\0\0\0\x01\x01\0").unwrap();
+        let diff = diff_wasm(&local, &downloaded).unwrap();
+        assert!(!diff.is_identical());
+        assert_eq!(diff.downloaded_size, diff.local_size + 3);
+        let _ = fs::remove_file(local);
+        let _ = fs::remove_file(downloaded);
+    }
+
+    #[test]
+    fn test_check() {
+        let provenance = Provenance {
+            contract_file: "token.wasm".to_string(),
+            code_id: 1,
+            code_hash: "abc".to_string(),
+            wasm_checksum: "abc".to_string(),
+            git_commit: None,
+            git_dirty: false,
+            rustc_version: None,
+            optimizer_version: None,
+            stored_at: Utc::now(),
+        };
+        assert!(provenance.check("abc", "ABC").is_ok());
+        assert!(provenance.check("def", "abc").is_err());
+        assert!(provenance.check("abc", "def").is_err());
+    }
+}
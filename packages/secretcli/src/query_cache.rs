@@ -0,0 +1,156 @@
+use crate::{
+    cli_types::NetContract,
+    network::network_config,
+    secretcli::{contract_info, list_code, list_contracts_by_code, query},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Result, Value};
+use std::{
+    collections::BTreeMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Cached results with when they were stored
+static CACHE: Mutex<BTreeMap<String, (Instant, Value)>> = Mutex::new(BTreeMap::new());
+
+///
+/// Returns the result cached under the key if it's younger than the TTL, otherwise runs
+/// `fetch` and caches what it returns. Failures are not cached
+///
+/// # Arguments
+///
+/// * 'key' - Identifies the result, the network name is added to it
+/// * 'ttl' - How long a cached result stays valid
+/// * 'fetch' - Produces the result
+///
+pub fn cached<T, F>(key: &str, ttl: Duration, fetch: F) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Result<T>,
+{
+    let key = format!("{}/{}", network_config().network_name(), key);
+    let hit = CACHE
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .get(&key)
+        .filter(|(stored, _)| stored.elapsed() < ttl)
+        .map(|(_, value)| value.clone());
+    if let Some(value) = hit {
+        return serde_json::from_value(value);
+    }
+    let result = fetch()?;
+    CACHE
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .insert(key, (Instant::now(), serde_json::to_value(&result)?));
+    Ok(result)
+}
+
+///
+/// Queries the contract unless the same query was answered within the TTL. Only meant for
+/// answers that don't change, such as token info
+///
+pub fn cached_query<Query: Serialize, Response: Serialize + DeserializeOwned>(
+    contract: &NetContract,
+    msg: Query,
+    ttl: Duration,
+) -> Result<Response> {
+    let key = format!(
+        "query/{}/{}",
+        contract.address,
+        serde_json::to_string(&msg)?
+    );
+    cached(&key, ttl, || query(contract, msg, None))
+}
+
+///
+/// Code hash of the uploaded code, looked up once per TTL
+///
+pub fn cached_code_hash(code_id: u64, ttl: Duration) -> Result<String> {
+    cached(&format!("code_hash/{}", code_id), ttl, || {
+        list_code()?
+            .into_iter()
+            .find(|code| code.code_id == code_id as u128)
+            .map(|code| code.code_hash)
+            .ok_or_else(|| serde::de::Error::custom(format!("Code {} was not found", code_id)))
+    })
+}
+
+///
+/// Contract info of the address, looked up once per TTL
+///
+pub fn cached_contract_info(address: &str, ttl: Duration) -> Result<Value> {
+    cached(&format!("contract_info/{}", address), ttl, || {
+        contract_info(address)
+    })
+}
+
+///
+/// Finds a deployed contract by its label, searching the contracts of every code, so scripts
+/// can reconnect to an instance without its cache file. Labels are unique on chain, so a
+/// found contract stays cached for the TTL
+///
+/// # Arguments
+///
+/// * 'label' - Label the contract was instantiated with
+/// * 'ttl' - How long a found contract stays cached
+///
+pub fn find_contract_by_label(label: &str, ttl: Duration) -> Result<NetContract> {
+    cached(&format!("label/{}", label), ttl, || {
+        // Newer codes first, that's where redeployed instances are
+        for code in list_code()?.into_iter().rev() {
+            let found = list_contracts_by_code(code.code_id.to_string())?
+                .into_iter()
+                .find(|contract| contract.label == label);
+            if let Some(contract) = found {
+                return Ok(NetContract::new(
+                    label,
+                    code.code_id.to_string(),
+                    contract.address,
+                    code.code_hash,
+                ));
+            }
+        }
+        Err(serde::de::Error::custom(format!(
+            "No contract labeled {} was found",
+            label
+        )))
+    })
+}
+
+///
+/// Forgets every cached result
+///
+pub fn clear_query_cache() {
+    CACHE.lock().unwrap_or_else(|err| err.into_inner()).clear();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_cached() {
+        let fetches = Cell::new(0);
+        let fetch = || -> Result<String> {
+            fetches.set(fetches.get() + 1);
+            Ok("hash".to_string())
+        };
+        let ttl = Duration::from_secs(60);
+
+        assert_eq!(cached("test_cached", ttl, fetch).unwrap(), "hash");
+        assert_eq!(cached("test_cached", ttl, fetch).unwrap(), "hash");
+        assert_eq!(fetches.get(), 1);
+
+        // Expired results are fetched again
+        cached("test_cached", Duration::ZERO, fetch).unwrap();
+        assert_eq!(fetches.get(), 2);
+
+        let failing = || -> Result<String> { Err(serde::de::Error::custom("unreachable")) };
+        assert!(cached("test_cached_failure", ttl, failing).is_err());
+        assert_eq!(cached("test_cached_failure", ttl, fetch).unwrap(), "hash");
+        assert_eq!(fetches.get(), 3);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{
+    cli_types::{NetContract, StoredCode, TxQuery},
+    secretcli::{migrate_contract, query, store_and_confirm},
+    utils::{json_diff, print_header, print_warning},
+};
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::{Result, Value};
+use std::fmt;
+
+type Check = Box<dyn Fn(&Value, &Value) -> std::result::Result<(), String>>;
+
+///
+/// A query whose result must keep holding a property across the migration
+///
+pub struct Invariant {
+    pub name: String,
+    pub query: Value,
+    check: Check,
+}
+
+impl Invariant {
+    ///
+    /// Checks the results before and after the migration with `check`, which returns the
+    /// reason when the invariant broke
+    ///
+    pub fn new<F>(name: impl Into<String>, query: Value, check: F) -> Self
+    where
+        F: Fn(&Value, &Value) -> std::result::Result<(), String> + 'static,
+    {
+        Invariant {
+            name: name.into(),
+            query,
+            check: Box::new(check),
+        }
+    }
+
+    /// The query must return exactly the same result after the migration
+    pub fn unchanged(name: impl Into<String>, query: Value) -> Self {
+        Invariant::new(name, query, |before, after| {
+            let changes = json_diff(before, after);
+            if changes.is_empty() {
+                Ok(())
+            } else {
+                let changes: Vec<String> = changes.iter().map(|c| c.to_string()).collect();
+                Err(changes.join(", "))
+            }
+        })
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct InvariantViolation {
+    pub invariant: String,
+    pub reason: String,
+}
+
+///
+/// Outcome of [upgrade_rehearsal]
+///
+#[derive(Serialize, Debug, Clone)]
+pub struct RehearsalReport {
+    pub code: StoredCode,
+    pub migrate_txhash: String,
+    pub gas_used: u64,
+    pub violations: Vec<InvariantViolation>,
+}
+
+impl RehearsalReport {
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl fmt::Display for RehearsalReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Migrated to code {} in {} ({} gas)",
+            self.code.code_id, self.migrate_txhash, self.gas_used
+        )?;
+        if self.is_ok() {
+            return write!(f, "{}", "All invariants hold".green());
+        }
+        for violation in &self.violations {
+            writeln!(
+                f,
+                "{}",
+                format!("{}: {}", violation.invariant, violation.reason).red()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// Rehearses a migration: snapshots the invariant queries, stores and migrates to the new
+/// code, then re-runs the queries and reports every broken invariant.
+/// Point the network config at a localsecret or mainnet fork first, the migration is real.
+///
+/// # Arguments
+///
+/// * 'old_contract' - The contract to migrate, the sender must be its admin
+/// * 'new_wasm' - Path to the new code
+/// * 'migrate_msg' - The migrate msg to serialize
+/// * 'invariants' - Queries to compare before and after
+/// * 'sender' - Msg sender
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn upgrade_rehearsal<Migrate: Serialize>(
+    old_contract: &NetContract,
+    new_wasm: &str,
+    migrate_msg: &Migrate,
+    invariants: &[Invariant],
+    sender: impl AsRef<str>,
+    backend: Option<&str>,
+) -> Result<RehearsalReport> {
+    let sender = sender.as_ref();
+    print_header("Snapshotting invariant queries");
+    let before = invariants
+        .iter()
+        .map(|invariant| query(old_contract, &invariant.query, None))
+        .collect::<Result<Vec<Value>>>()?;
+
+    print_header("Storing and migrating");
+    let code = store_and_confirm(new_wasm, sender, None, None, backend)?;
+    let migrate_tx = migrate_contract(
+        old_contract,
+        code.code_id,
+        migrate_msg,
+        sender,
+        None,
+        backend,
+    )?;
+    if migrate_tx.raw_log.contains("failed to execute message") {
+        return Err(serde::de::Error::custom(format!(
+            "Migration failed: {}",
+            migrate_tx.raw_log
+        )));
+    }
+
+    print_header("Checking invariants");
+    let new_contract = migrated(old_contract, &code);
+    let violations = check_invariants(invariants, &before, &new_contract, |contract, msg| {
+        query(contract, msg, None)
+    });
+    for violation in &violations {
+        print_warning(&format!(
+            "{} broke: {}",
+            violation.invariant, violation.reason
+        ));
+    }
+
+    Ok(report(code, &migrate_tx, violations))
+}
+
+///
+/// The contract as it is after migrating to `code`, queries must be encrypted for the new
+/// code hash
+///
+fn migrated(contract: &NetContract, code: &StoredCode) -> NetContract {
+    NetContract {
+        id: code.code_id.to_string(),
+        code_hash: code.code_hash.clone(),
+        ..contract.clone()
+    }
+}
+
+fn check_invariants<F>(
+    invariants: &[Invariant],
+    before: &[Value],
+    contract: &NetContract,
+    query_after: F,
+) -> Vec<InvariantViolation>
+where
+    F: Fn(&NetContract, &Value) -> Result<Value>,
+{
+    invariants
+        .iter()
+        .zip(before)
+        .filter_map(|(invariant, before)| {
+            let reason = match query_after(contract, &invariant.query) {
+                Ok(after) => (invariant.check)(before, &after).err()?,
+                Err(e) => format!("query failed after migration: {}", e),
+            };
+            Some(InvariantViolation {
+                invariant: invariant.name.clone(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+fn report(
+    code: StoredCode,
+    migrate_tx: &TxQuery,
+    violations: Vec<InvariantViolation>,
+) -> RehearsalReport {
+    RehearsalReport {
+        code,
+        migrate_txhash: migrate_tx.txhash.clone(),
+        gas_used: migrate_tx.gas_used,
+        violations,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_check_invariants() {
+        let invariants = vec![
+            Invariant::unchanged("token info", json!({ "token_info": {} })),
+            Invariant::new(
+                "supply never shrinks",
+                json!({ "supply": {} }),
+                |before, after| {
+                    if after["amount"].as_u64() >= before["amount"].as_u64() {
+                        Ok(())
+                    } else {
+                        Err(format!("{} -> {}", before["amount"], after["amount"]))
+                    }
+                },
+            ),
+            Invariant::unchanged("config", json!({ "config": {} })),
+        ];
+        let before = vec![
+            json!({ "name": "Token" }),
+            json!({ "amount": 10 }),
+            json!({ "admin": "a" }),
+        ];
+
+        let contract = NetContract::new("token", "1", "secret1token", "old");
+        let violations = check_invariants(&invariants, &before, &contract, |_, query| {
+            if query.get("token_info").is_some() {
+                Ok(json!({ "name": "Token" }))
+            } else if query.get("supply").is_some() {
+                Ok(json!({ "amount": 5 }))
+            } else {
+                Err(serde::de::Error::custom("unknown query"))
+            }
+        });
+
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].invariant, "supply never shrinks");
+        assert_eq!(violations[0].reason, "10 -> 5");
+        assert_eq!(violations[1].invariant, "config");
+        assert!(violations[1]
+            .reason
+            .starts_with("query failed after migration"));
+    }
+
+    #[test]
+    fn test_queries_new_code_hash() {
+        let old = NetContract::new("token", "1", "secret1token", "old");
+        let code = StoredCode {
+            code_id: 2,
+            code_hash: "new".to_string(),
+            txhash: "AB12".to_string(),
+        };
+        let invariants = vec![Invariant::unchanged("config", json!({ "config": {} }))];
+        let before = vec![json!({ "admin": "a" })];
+
+        let violations = check_invariants(
+            &invariants,
+            &before,
+            &migrated(&old, &code),
+            |contract, _| {
+                if contract.code_hash == "new" && contract.id == "2" {
+                    Ok(json!({ "admin": "a" }))
+                } else {
+                    Err(serde::de::Error::custom("encrypted for the old code hash"))
+                }
+            },
+        );
+
+        assert!(violations.is_empty());
+        assert_eq!(migrated(&old, &code).address, "secret1token");
+    }
+}
@@ -0,0 +1,167 @@
+use crate::cli_types::NetContract;
+use colored::Colorize;
+use serde_json::{json, Value};
+use std::sync::{Arc, RwLock};
+
+/// Set to `json` to print JSON lines instead of human readable output
+pub const OUTPUT_VAR: &str = "SECRETCLI_OUTPUT";
+
+static REPORTER: RwLock<Option<Arc<dyn Reporter>>> = RwLock::new(None);
+
+///
+/// Receives everything this crate prints, so tools built on it can choose how it's shown
+///
+pub trait Reporter: Send + Sync {
+    /// Start of a new phase
+    fn header(&self, header: &str);
+    fn info(&self, message: &str);
+    fn warning(&self, warning: &str);
+    /// A contract was deployed or loaded, `event` says which
+    fn contract(&self, event: &str, contract: &NetContract);
+    /// Any other result worth parsing
+    fn value(&self, event: &str, value: &Value);
+}
+
+///
+/// Colored output for people reading the terminal, the default
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HumanReporter;
+
+impl Reporter for HumanReporter {
+    fn header(&self, header: &str) {
+        println!("{}", header.on_blue());
+    }
+
+    fn info(&self, message: &str) {
+        println!("{}", message);
+    }
+
+    fn warning(&self, warning: &str) {
+        println!("{}", warning.on_yellow());
+    }
+
+    fn contract(&self, event: &str, contract: &NetContract) {
+        println!("{}", event.on_green());
+        println!(
+            "\tLabel: {}\n\tID: {}\n\tAddress: {}\n\tHash: {}",
+            contract.label, contract.id, contract.address, contract.code_hash
+        );
+    }
+
+    fn value(&self, event: &str, value: &Value) {
+        println!(
+            "{}: {}",
+            event,
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        );
+    }
+}
+
+///
+/// One JSON object per line, for CI pipelines parsing deployment results
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonReporter;
+
+impl JsonReporter {
+    fn line(kind: &str, mut fields: Value) -> String {
+        fields["kind"] = json!(kind);
+        fields.to_string()
+    }
+}
+
+impl Reporter for JsonReporter {
+    fn header(&self, header: &str) {
+        println!("{}", Self::line("header", json!({ "message": header })));
+    }
+
+    fn info(&self, message: &str) {
+        println!("{}", Self::line("info", json!({ "message": message })));
+    }
+
+    fn warning(&self, warning: &str) {
+        println!("{}", Self::line("warning", json!({ "message": warning })));
+    }
+
+    fn contract(&self, event: &str, contract: &NetContract) {
+        println!(
+            "{}",
+            Self::line("contract", json!({ "event": event, "contract": contract }))
+        );
+    }
+
+    fn value(&self, event: &str, value: &Value) {
+        println!(
+            "{}",
+            Self::line("value", json!({ "event": event, "value": value }))
+        );
+    }
+}
+
+///
+/// Sets the reporter all following output goes through
+///
+pub fn set_reporter(reporter: Box<dyn Reporter>) {
+    *REPORTER.write().unwrap_or_else(|err| err.into_inner()) = Some(Arc::from(reporter));
+}
+
+///
+/// Hands the current reporter to `f`. Until one is set it's a [JsonReporter] if
+/// `SECRETCLI_OUTPUT` is `json`, a [HumanReporter] otherwise
+///
+pub fn report<F: FnOnce(&dyn Reporter)>(f: F) {
+    // Not called under the lock, so reporters may report or set another reporter themselves
+    let reporter = REPORTER
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone();
+    if let Some(reporter) = reporter {
+        return f(reporter.as_ref());
+    }
+    match std::env::var(OUTPUT_VAR) {
+        Ok(mode) if mode.eq_ignore_ascii_case("json") => f(&JsonReporter),
+        _ => f(&HumanReporter),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_json_lines() {
+        assert_eq!(
+            JsonReporter::line("warning", json!({ "message": "low gas" })),
+            r#"{"kind":"warning","message":"low gas"}"#
+        );
+        let contract = NetContract::new("token", "1", "secret1token", "hash");
+        let line: Value = serde_json::from_str(&JsonReporter::line(
+            "contract",
+            json!({ "event": "Deployed contract", "contract": contract }),
+        ))
+        .unwrap();
+        assert_eq!(line["contract"]["address"], "secret1token");
+        assert_eq!(line["kind"], "contract");
+    }
+
+    struct SwitchingReporter;
+
+    impl Reporter for SwitchingReporter {
+        fn header(&self, _: &str) {
+            set_reporter(Box::new(HumanReporter));
+        }
+        fn info(&self, _: &str) {}
+        fn warning(&self, _: &str) {}
+        fn contract(&self, _: &str, _: &NetContract) {}
+        fn value(&self, _: &str, _: &Value) {}
+    }
+
+    #[test]
+    fn test_reporter_sets_reporter() {
+        set_reporter(Box::new(SwitchingReporter));
+        // would deadlock if the reporter was called under the lock
+        report(|reporter| reporter.header("switch"));
+        report(|reporter| reporter.info("reported by the human reporter"));
+    }
+}
@@ -0,0 +1,387 @@
+#![cfg(feature = "sandbox")]
+//! In-memory chain running natively compiled contracts on secret-multi-test, so test scripts
+//! written against [TestInit], [TestHandle] and [TestQuery] run in milliseconds locally and
+//! unchanged against a real chain.
+//!
+//! While a sandbox is active on the current thread every instantiate, execute, contract query
+//! and tx lookup goes to it instead of secretd, whether through the traits or the free functions.
+//! Secret-specific features are mocked: code hashes are generated from the code id, nothing is
+//! encrypted and key names are used as addresses.
+//!
+//! [TestInit]: crate::secretcli::TestInit
+//! [TestHandle]: crate::secretcli::TestHandle
+//! [TestQuery]: crate::secretcli::TestQuery
+
+use crate::cli_types::{
+    NetContract, StoredCode, TxAnswer, TxAttribute, TxCoin, TxCompute, TxOutputLog, TxQuery,
+    TxQueryEvents, TxQueryKeyValue, TxQueryLogs,
+};
+use chrono::Utc;
+use cosmwasm_std::{
+    Addr, Binary, Coin, ContractInfo, Empty, Event, Querier, QueryRequest, WasmQuery,
+};
+use secret_multi_test::{
+    App, AppResponse, BasicApp, Contract, ContractInstantiationInfo, Executor,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Result;
+use std::{cell::RefCell, collections::BTreeMap};
+
+thread_local! {
+    static SANDBOX: RefCell<Option<Sandbox>> = RefCell::new(None);
+}
+
+///
+/// An in-memory app with the native contracts test scripts can store, keyed by the contract
+/// file they would upload on a real chain
+///
+pub struct Sandbox {
+    app: BasicApp,
+    codes: BTreeMap<String, ContractInstantiationInfo>,
+    /// Fake tx hashes are numbered
+    txs: u64,
+    /// Every tx by hash, so it can be looked up like on chain
+    history: BTreeMap<String, (TxQuery, Option<TxCompute>)>,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sandbox {
+    pub fn new() -> Self {
+        Sandbox {
+            app: App::default(),
+            codes: BTreeMap::new(),
+            txs: 0,
+            history: BTreeMap::new(),
+        }
+    }
+
+    ///
+    /// Stores native code under the contract file scripts pass when deploying it
+    ///
+    /// # Arguments
+    ///
+    /// * 'contract_file' - The wasm file the code is compiled to, e.g. `contract.wasm.gz`
+    /// * 'code' - The contract's entry points, usually a `ContractWrapper`
+    ///
+    pub fn register(mut self, contract_file: &str, code: Box<dyn Contract<Empty>>) -> Self {
+        let info = self.app.store_code(code);
+        self.codes.insert(contract_file.to_string(), info);
+        self
+    }
+
+    /// The underlying app, to set balances, move blocks or query modules
+    pub fn app(&mut self) -> &mut BasicApp {
+        &mut self.app
+    }
+
+    fn next_txhash(&mut self) -> String {
+        self.txs += 1;
+        format!("{:064X}", self.txs)
+    }
+
+    fn tx(&mut self, response: &AppResponse, extra: Vec<Event>) -> TxQuery {
+        let events = extra
+            .iter()
+            .chain(&response.events)
+            .map(|event| TxQueryEvents {
+                msg_type: event.ty.clone(),
+                attributes: event
+                    .attributes
+                    .iter()
+                    .map(|attr| TxQueryKeyValue {
+                        msg_key: chain_key(&attr.key),
+                        value: attr.value.clone(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        let tx = TxQuery {
+            height: self.app.block_info().height,
+            txhash: self.next_txhash(),
+            logs: vec![TxQueryLogs {
+                msg_index: 0,
+                log: String::new(),
+                events,
+            }],
+            timestamp: Utc::now(),
+            ..Default::default()
+        };
+        self.history.insert(tx.txhash.clone(), (tx.clone(), None));
+        tx
+    }
+}
+
+/// multi-test prefixes the attributes it adds itself, secretd doesn't
+fn chain_key(key: &str) -> String {
+    match key {
+        "_contract_addr" | "_contract_address" => "contract_address".to_string(),
+        key => key.to_string(),
+    }
+}
+
+fn coins(funds: &[TxCoin]) -> Vec<Coin> {
+    funds
+        .iter()
+        .map(|coin| Coin::new(coin.amount, &coin.denom))
+        .collect()
+}
+
+fn contract_info(contract: &NetContract) -> ContractInfo {
+    ContractInfo {
+        address: Addr::unchecked(&contract.address),
+        code_hash: contract.code_hash.clone(),
+    }
+}
+
+///
+/// Makes the sandbox the chain of this thread until [deactivate] is called
+///
+pub fn activate(sandbox: Sandbox) {
+    SANDBOX.with(|active| *active.borrow_mut() = Some(sandbox));
+}
+
+///
+/// Goes back to secretd, returning the sandbox to inspect its final state
+///
+pub fn deactivate() -> Option<Sandbox> {
+    SANDBOX.with(|active| active.borrow_mut().take())
+}
+
+pub fn is_active() -> bool {
+    SANDBOX.with(|active| active.borrow().is_some())
+}
+
+///
+/// Hands the active sandbox to `f`, fails when none is active
+///
+pub fn with_sandbox<T, F: FnOnce(&mut Sandbox) -> Result<T>>(f: F) -> Result<T> {
+    SANDBOX.with(|active| match active.borrow_mut().as_mut() {
+        Some(sandbox) => f(sandbox),
+        None => Err(serde::de::Error::custom("No sandbox is active")),
+    })
+}
+
+/// Looks up the code registered for the contract file
+pub(crate) fn store(contract_file: &str) -> Result<StoredCode> {
+    with_sandbox(|sandbox| {
+        let info = sandbox.codes.get(contract_file).cloned().ok_or_else(|| {
+            serde::de::Error::custom(format!("No native code registered for {}", contract_file))
+        })?;
+        Ok(StoredCode {
+            code_id: info.code_id,
+            code_hash: info.code_hash,
+            txhash: sandbox.next_txhash(),
+        })
+    })
+}
+
+/// Instantiates the contract, the first event holds its address like on chain
+pub(crate) fn instantiate<Message: Serialize + ?Sized>(
+    contract: &NetContract,
+    msg: &Message,
+    label: &str,
+    sender: &str,
+    funds: &[TxCoin],
+) -> Result<TxQuery> {
+    with_sandbox(|sandbox| {
+        let code = ContractInstantiationInfo {
+            code_id: contract.id.parse().map_err(serde::de::Error::custom)?,
+            code_hash: contract.code_hash.clone(),
+        };
+        let info = sandbox
+            .app
+            .instantiate_contract(
+                code,
+                Addr::unchecked(sender),
+                &serde_json::to_value(msg)?,
+                &coins(funds),
+                label,
+                None,
+            )
+            .map_err(serde::de::Error::custom)?;
+        let message = Event::new("message")
+            .add_attribute("module", "compute")
+            .add_attribute("contract_address", info.address);
+        Ok(sandbox.tx(&AppResponse::default(), vec![message]))
+    })
+}
+
+/// Executes the contract, its data is recorded as the single compute answer of the tx
+pub(crate) fn execute<Message: Serialize + ?Sized>(
+    contract: &NetContract,
+    msg: &Message,
+    sender: &str,
+    funds: &[TxCoin],
+) -> Result<TxQuery> {
+    with_sandbox(|sandbox| {
+        let response = sandbox
+            .app
+            .execute_contract(
+                Addr::unchecked(sender),
+                &contract_info(contract),
+                &serde_json::to_value(msg)?,
+                &coins(funds),
+            )
+            .map_err(serde::de::Error::custom)?;
+        let compute = TxCompute {
+            answers: vec![TxAnswer {
+                r#type: "execute".to_string(),
+                input: serde_json::to_string(msg)?,
+                output_data: base64::encode(response.data.clone().unwrap_or_default().as_slice()),
+                output_data_as_string: String::new(),
+            }],
+            output_logs: response
+                .events
+                .iter()
+                .filter(|event| event.ty == "wasm" || event.ty.starts_with("wasm-"))
+                .map(|event| TxOutputLog {
+                    r#type: event.ty.clone(),
+                    attributes: event
+                        .attributes
+                        .iter()
+                        .map(|attr| TxAttribute {
+                            key: chain_key(&attr.key),
+                            value: attr.value.clone(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            extra: Default::default(),
+        };
+        let tx = sandbox.tx(&response);
+        sandbox
+            .history
+            .insert(tx.txhash.clone(), (tx.clone(), Some(compute)));
+        Ok(tx)
+    })
+}
+
+/// Looks up a tx of the sandbox by its hash
+pub(crate) fn tx_query(txhash: &str) -> Result<TxQuery> {
+    with_sandbox(|sandbox| {
+        sandbox
+            .history
+            .get(txhash)
+            .map(|(tx, _)| tx.clone())
+            .ok_or_else(|| serde::de::Error::custom(format!("No sandbox tx {}", txhash)))
+    })
+}
+
+/// The compute answers of an execute tx of the sandbox
+pub(crate) fn tx_compute(txhash: &str) -> Result<TxCompute> {
+    with_sandbox(|sandbox| {
+        sandbox
+            .history
+            .get(txhash)
+            .and_then(|(_, compute)| compute.clone())
+            .ok_or_else(|| serde::de::Error::custom(format!("No sandbox execute tx {}", txhash)))
+    })
+}
+
+pub(crate) fn query<Query: Serialize + ?Sized, Response: DeserializeOwned>(
+    contract: &NetContract,
+    msg: &Query,
+) -> Result<Response> {
+    with_sandbox(|sandbox| {
+        sandbox
+            .app
+            .wrap()
+            .query_wasm_smart(
+                contract.code_hash.clone(),
+                contract.address.clone(),
+                &serde_json::to_value(msg)?,
+            )
+            .map_err(serde::de::Error::custom)
+    })
+}
+
+/// The contract's query response as it returned it, padding included
+pub(crate) fn query_bytes<Query: Serialize + ?Sized>(
+    contract: &NetContract,
+    msg: &Query,
+) -> Result<Vec<u8>> {
+    let request: QueryRequest<Empty> = QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: contract.address.clone(),
+        code_hash: contract.code_hash.clone(),
+        msg: Binary(serde_json::to_vec(msg)?),
+    });
+    let request = serde_json::to_vec(&request)?;
+    with_sandbox(|sandbox| {
+        let response = sandbox
+            .app
+            .raw_query(&request)
+            .into_result()
+            .map_err(serde::de::Error::custom)?
+            .into_result()
+            .map_err(serde::de::Error::custom)?;
+        Ok(response.0)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::secretcli::{query, store_and_confirm, test_contract_handle, test_init};
+    use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+    use secret_multi_test::ContractWrapper;
+
+    fn count(deps: Deps) -> u64 {
+        let count = deps.storage.get(b"count").unwrap_or_default();
+        u64::from_be_bytes(count.try_into().unwrap_or_default())
+    }
+
+    fn counter() -> Box<dyn Contract<Empty>> {
+        fn instantiate(deps: DepsMut, _: Env, _: MessageInfo, start: u64) -> StdResult<Response> {
+            deps.storage.set(b"count", &start.to_be_bytes());
+            Ok(Response::new())
+        }
+        fn execute(deps: DepsMut, _: Env, _: MessageInfo, by: u64) -> StdResult<Response> {
+            let count = count(deps.as_ref()) + by;
+            deps.storage.set(b"count", &count.to_be_bytes());
+            Ok(Response::new().set_data(to_binary(&count)?))
+        }
+        fn query(deps: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+            to_binary(&count(deps))
+        }
+        Box::new(ContractWrapper::new_with_empty(execute, instantiate, query))
+    }
+
+    #[test]
+    fn test_chain_key() {
+        assert_eq!(chain_key("_contract_addr"), "contract_address");
+        assert_eq!(chain_key("amount"), "amount");
+    }
+
+    #[test]
+    fn test_activate() {
+        assert!(!is_active());
+        activate(Sandbox::new());
+        assert!(is_active());
+        assert!(store("missing.wasm").is_err());
+        assert!(deactivate().is_some());
+        assert!(!is_active());
+    }
+
+    #[test]
+    fn test_free_functions() -> Result<()> {
+        activate(Sandbox::new().register("counter.wasm", counter()));
+        let code = store_and_confirm("counter.wasm", "alice", None, None, None)?;
+        let mut contract =
+            NetContract::new("counter", code.code_id.to_string(), "", code.code_hash);
+        let init = test_init(&1u64, &contract, "counter", "alice", None, None)?;
+        contract.address = init.logs[0].events[0].attributes[1].value.clone();
+
+        let (compute, tx) = test_contract_handle(&2u64, &contract, "alice", None, None, None)?;
+        assert_eq!(compute.decode_answer::<u64>()?, 3);
+        assert_eq!(tx_query(&tx.txhash)?.txhash, tx.txhash);
+        let count: u64 = query(&contract, Empty {}, None)?;
+        assert_eq!(count, 3);
+        deactivate();
+        Ok(())
+    }
+}
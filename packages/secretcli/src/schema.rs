@@ -0,0 +1,383 @@
+use serde::Serialize;
+use serde_json::{Result, Value};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+/// Set to compare the schemas on disk instead of writing them, like passing `--check`
+pub const SCHEMA_CHECK_VAR: &str = "SCHEMA_CHECK";
+
+///
+/// Writes the JSON schema of each message type to `<dir>/<snake_case name>.json`, or in
+/// check mode fails if any of them differs from the file on disk. Meant for a contract's
+/// schema binary, so CI can run it with `--check` and catch schemas nobody regenerated.
+///
+/// The caller needs `schemars` as a dependency, which contracts already have.
+///
+/// ```ignore
+/// fn main() -> serde_json::Result<()> {
+///     write_schemas!("./schema", InstantiateMsg, ExecuteMsg, QueryMsg)
+/// }
+/// ```
+///
+#[macro_export]
+macro_rules! write_schemas {
+    ($dir:expr, $($msg:ty),+ $(,)?) => {
+        $crate::schema::write_schemas(
+            $dir,
+            &[$((stringify!($msg), $crate::schema::to_schema(&schemars::schema_for!($msg)))),+],
+            $crate::schema::check_mode(),
+        )
+    };
+}
+
+///
+/// Whether schemas should be checked rather than written, set with `--check` or
+/// `SCHEMA_CHECK`
+///
+pub fn check_mode() -> bool {
+    std::env::args().any(|arg| arg == "--check") || std::env::var_os(SCHEMA_CHECK_VAR).is_some()
+}
+
+pub fn to_schema<S: Serialize>(schema: &S) -> Result<Value> {
+    serde_json::to_value(schema)
+}
+
+///
+/// File stem of a message type's schema, `ExecuteMsg` becomes `execute_msg`
+///
+pub fn schema_name(type_name: &str) -> String {
+    let mut name = String::new();
+    for c in type_name.chars().filter(|c| c.is_ascii_alphanumeric()) {
+        if c.is_ascii_uppercase() {
+            if !name.is_empty() {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+///
+/// Writes or checks schemas, see [write_schemas!]
+///
+/// # Arguments
+///
+/// * 'dir' - Directory holding the schema files
+/// * 'schemas' - Type names and their schemas
+/// * 'check' - Compare against the files instead of writing them
+///
+pub fn write_schemas(
+    dir: impl AsRef<Path>,
+    schemas: &[(&str, Result<Value>)],
+    check: bool,
+) -> Result<()> {
+    let dir = dir.as_ref();
+    if !check {
+        fs::create_dir_all(dir).map_err(serde_json::Error::io)?;
+    }
+    let mut stale = vec![];
+    for (type_name, schema) in schemas {
+        let schema = match schema {
+            Ok(schema) => schema,
+            Err(err) => {
+                return Err(serde::de::Error::custom(format!(
+                    "Could not generate the {} schema: {}",
+                    type_name, err
+                )))
+            }
+        };
+        let path = dir.join(format!("{}.json", schema_name(type_name)));
+        if check {
+            if read_schema(&path)?.as_ref() != Some(schema) {
+                stale.push(path.display().to_string());
+            }
+        } else {
+            let mut contents = serde_json::to_string_pretty(schema)?;
+            contents.push('\n');
+            fs::write(&path, contents).map_err(serde_json::Error::io)?;
+        }
+    }
+    if !stale.is_empty() {
+        return Err(serde::de::Error::custom(format!(
+            "Schemas out of date, regenerate them without --check: {}",
+            stale.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+fn read_schema(path: &Path) -> Result<Option<Value>> {
+    match fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map(Some),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(serde_json::Error::io(err)),
+    }
+}
+
+///
+/// The schemas of a contract, loaded to check the messages a client sends still match them
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContractSchemas {
+    pub dir: PathBuf,
+    /// Schemas by file stem, e.g. `execute_msg`
+    pub schemas: BTreeMap<String, Value>,
+}
+
+impl ContractSchemas {
+    ///
+    /// Loads every `.json` schema in the directory
+    ///
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut schemas = BTreeMap::new();
+        for entry in fs::read_dir(dir).map_err(serde_json::Error::io)? {
+            let path = entry.map_err(serde_json::Error::io)?.path();
+            if path.extension().map_or(false, |ext| ext == "json") {
+                if let (Some(stem), Some(schema)) = (path.file_stem(), read_schema(&path)?) {
+                    schemas.insert(stem.to_string_lossy().to_string(), schema);
+                }
+            }
+        }
+        Ok(ContractSchemas {
+            dir: dir.to_path_buf(),
+            schemas,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Value> {
+        self.schemas.get(name).ok_or_else(|| {
+            serde::de::Error::custom(format!("No {} schema in {}", name, self.dir.display()))
+        })
+    }
+
+    ///
+    /// Variant names of an enum message such as `execute_msg`
+    ///
+    pub fn variants(&self, name: &str) -> Result<Vec<String>> {
+        Ok(self
+            .variant_schemas(name)?
+            .into_iter()
+            .map(|(variant, _)| variant)
+            .collect())
+    }
+
+    ///
+    /// Checks a message a client builds against the schema: the variant must exist, required
+    /// fields must be set and no unknown fields may be sent. Nested values aren't checked.
+    ///
+    /// # Arguments
+    ///
+    /// * 'name' - Schema the message belongs to, e.g. `execute_msg`
+    /// * 'msg' - The JSON message
+    ///
+    pub fn check_msg(&self, name: &str, msg: &Value) -> Result<()> {
+        let (variant, fields) = match msg {
+            Value::String(variant) => (variant.as_str(), None),
+            Value::Object(map) if map.len() == 1 => {
+                let (variant, fields) = map.iter().next().unwrap();
+                (variant.as_str(), Some(fields))
+            }
+            _ => {
+                return Err(serde::de::Error::custom(format!(
+                    "{} is not a {} variant",
+                    msg, name
+                )))
+            }
+        };
+        let variants = self.variant_schemas(name)?;
+        let body = variants
+            .iter()
+            .find(|(known, _)| known == variant)
+            .map(|(_, body)| *body)
+            .ok_or_else(|| {
+                serde::de::Error::custom(format!("{} has no variant {}", name, variant))
+            })?;
+
+        let (body, fields) = match (body, fields) {
+            (None, None) => return Ok(()),
+            (Some(body), Some(Value::Object(fields))) => (self.resolve(name, body)?, fields),
+            _ => {
+                return Err(serde::de::Error::custom(format!(
+                    "{}.{} doesn't match the shape in the schema",
+                    name, variant
+                )))
+            }
+        };
+        let empty = serde_json::Map::new();
+        let properties = body["properties"].as_object().unwrap_or(&empty);
+        for required in body["required"].as_array().into_iter().flatten() {
+            let required = required.as_str().unwrap_or_default();
+            if fields.get(required).map_or(true, Value::is_null) {
+                return Err(serde::de::Error::custom(format!(
+                    "{}.{} is missing required field {}",
+                    name, variant, required
+                )));
+            }
+        }
+        if body["additionalProperties"] == Value::Bool(false) {
+            if let Some((unknown, _)) = fields
+                .iter()
+                .find(|(field, value)| !value.is_null() && !properties.contains_key(*field))
+            {
+                return Err(serde::de::Error::custom(format!(
+                    "{}.{} has no field {}",
+                    name, variant, unknown
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Variants with the schema of their fields, `None` for unit variants
+    fn variant_schemas(&self, name: &str) -> Result<Vec<(String, Option<&Value>)>> {
+        let schema = self.get(name)?;
+        let mut variants = vec![];
+        let options = schema["oneOf"]
+            .as_array()
+            .or_else(|| schema["anyOf"].as_array())
+            .ok_or_else(|| serde::de::Error::custom(format!("{} is not an enum", name)))?;
+        for option in options {
+            for unit in option["enum"].as_array().into_iter().flatten() {
+                if let Some(unit) = unit.as_str() {
+                    variants.push((unit.to_string(), None));
+                }
+            }
+            if let Some(properties) = option["properties"].as_object() {
+                for (variant, body) in properties {
+                    variants.push((variant.clone(), Some(body)));
+                }
+            }
+        }
+        Ok(variants)
+    }
+
+    /// Follows a `#/definitions/..` reference within the same schema
+    fn resolve<'a>(&'a self, name: &str, body: &'a Value) -> Result<&'a Value> {
+        match body["$ref"].as_str() {
+            Some(reference) => {
+                let definition = reference.trim_start_matches("#/definitions/");
+                let resolved = &self.get(name)?["definitions"][definition];
+                if resolved.is_null() {
+                    return Err(serde::de::Error::custom(format!(
+                        "{} references missing {}",
+                        name, reference
+                    )));
+                }
+                Ok(resolved)
+            }
+            None => Ok(body),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::generate_label;
+    use serde_json::json;
+
+    fn execute_schema() -> Value {
+        json!({
+            "title": "ExecuteMsg",
+            "oneOf": [
+                { "type": "string", "enum": ["pause"] },
+                {
+                    "type": "object",
+                    "required": ["transfer"],
+                    "properties": {
+                        "transfer": {
+                            "type": "object",
+                            "required": ["amount", "recipient"],
+                            "properties": {
+                                "amount": { "type": "string" },
+                                "recipient": { "type": "string" },
+                                "memo": { "type": ["string", "null"] }
+                            },
+                            "additionalProperties": false
+                        }
+                    },
+                    "additionalProperties": false
+                },
+                {
+                    "type": "object",
+                    "required": ["receive"],
+                    "properties": { "receive": { "$ref": "#/definitions/ReceiveMsg" } },
+                    "additionalProperties": false
+                }
+            ],
+            "definitions": {
+                "ReceiveMsg": {
+                    "type": "object",
+                    "required": ["sender"],
+                    "properties": { "sender": { "type": "string" } }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_schema_name() {
+        assert_eq!(schema_name("ExecuteMsg"), "execute_msg");
+        assert_eq!(schema_name("QueryAnswer"), "query_answer");
+        assert_eq!(schema_name("msg::InitMsg"), "init_msg");
+    }
+
+    #[test]
+    fn test_write_and_check() {
+        let dir = std::env::temp_dir().join(format!("secretcli-schema-{}", generate_label(8)));
+        let schemas = [("ExecuteMsg", Ok(execute_schema()))];
+        write_schemas(&dir, &schemas, false).unwrap();
+        assert!(dir.join("execute_msg.json").exists());
+        write_schemas(&dir, &schemas, true).unwrap();
+
+        let drifted = [
+            ("ExecuteMsg", Ok(json!({ "title": "ExecuteMsg" }))),
+            ("QueryMsg", Ok(json!({}))),
+        ];
+        let err = write_schemas(&dir, &drifted, true).unwrap_err();
+        assert!(err.to_string().contains("execute_msg.json"));
+        assert!(err.to_string().contains("query_msg.json"));
+
+        let loaded = ContractSchemas::load(&dir).unwrap();
+        assert_eq!(loaded.get("execute_msg").unwrap(), &execute_schema());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_check_msg() {
+        let schemas = ContractSchemas {
+            dir: PathBuf::from("schema"),
+            schemas: BTreeMap::from([("execute_msg".to_string(), execute_schema())]),
+        };
+        assert_eq!(
+            schemas.variants("execute_msg").unwrap(),
+            vec!["pause", "transfer", "receive"]
+        );
+
+        let check = |msg: Value| schemas.check_msg("execute_msg", &msg);
+        assert!(check(json!("pause")).is_ok());
+        assert!(check(json!({ "transfer": { "amount": "1", "recipient": "a" } })).is_ok());
+        assert!(
+            check(json!({ "transfer": { "amount": "1", "recipient": "a", "memo": null } })).is_ok()
+        );
+        assert!(check(json!({ "receive": { "sender": "a", "extra": 1 } })).is_ok());
+
+        assert!(check(json!("unpause")).is_err());
+        assert!(check(json!({ "pause": {} })).is_err());
+        assert!(check(json!({ "transfer": { "amount": "1" } })).is_err());
+        assert!(
+            check(json!({ "transfer": { "amount": "1", "recipient": "a", "padding": "" } }))
+                .is_err()
+        );
+        assert!(check(json!({ "receive": {} })).is_err());
+        assert!(schemas.check_msg("query_msg", &json!("pause")).is_err());
+    }
+}
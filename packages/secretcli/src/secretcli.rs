@@ -1,23 +1,42 @@
 #[cfg(feature = "helpers")]
 use crate::deployable::Deployable;
 use crate::{
+    address_book::record_contract,
+    analytics,
     cli_types::{
-        ListCodeResponse, ListContractCode, NetContract, SignedTx, StoredContract, TxCompute,
-        TxQuery, TxResponse,
+        address_from_mnemonic, adr36_sign_doc, coins_arg, parse_coins, parse_output,
+        predict_contract_address, validate_funds, BatchQueryResult, ContractStateRaw,
+        DenomMetadata, Evidence, InstantiatePermission, InterchainAccount, ListCodeResponse,
+        ListContractCode, ListFilter, NetContract, Permit, PermitParams, RelayerProof, SignedTx,
+        SlashingParams, StateKey, StoredCode, StoredContract, TxCoin, TxCompute, TxQuery,
+        TxResponse, TxSearchResponse, ValidatorSigningInfo,
     },
-    constants::{GAS, SECRETCLI, STORE_GAS},
-    utils::print_contract,
+    constants::{COMPUTE_MODULE, GAS, STORE_GAS},
+    coverage::{self, EntryPoint},
+    hooks::{self, TxContext, TxKind},
+    keyring::{run_keys_command, KeyError},
+    network::{
+        backoff, ensure_healthy_node, failover, is_connection_error, is_rate_limited,
+        network_config, node_args, throttle,
+    },
+    query_cache::cached,
+    reporter::report,
+    utils::print_warning,
+    wasm::prepare_upload,
 };
-use colored::Colorize;
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use secret_utils::WrappedTokenMsg;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{Result, Value};
 use std::{
-    fs::{create_dir, File},
+    collections::BTreeMap,
+    fs::{create_dir, create_dir_all, File},
     io::{self, BufReader, Error, ErrorKind, Write},
     path::Path,
-    process::Command,
+    sync::Mutex,
     thread, time,
 };
+use tracing::{field::Empty, instrument, Span};
 
 //secretcli tx sign-doc tx_to_sign --from sign-test
 
@@ -41,6 +60,16 @@ pub struct Report {
     pub gas_used: String,
 }
 
+///
+/// Output of a secretd call that succeeded, raw or parsed
+///
+struct CommandOutput<T> {
+    value: T,
+    /// secretd prints warnings here even when the command succeeds, e.g. when the gas
+    /// estimate was exceeded
+    stderr: String,
+}
+
 ///
 /// Will run any secretcli command and return its output
 ///
@@ -49,29 +78,112 @@ pub struct Report {
 /// * 'command' - a string array that contains the command to forward\
 ///
 fn secretcli_run(command: Vec<String>, max_retry: Option<i32>) -> Result<Value> {
+    Ok(secretcli_output(command, max_retry)?.value)
+}
+
+///
+/// Runs a secretcli command like [secretcli_run], keeping what it printed to stderr
+///
+fn secretcli_output(
+    mut command: Vec<String>,
+    max_retry: Option<i32>,
+) -> Result<CommandOutput<Value>> {
+    command.append(&mut vec_str_to_vec_string(vec!["--output", "json"]));
+    let CommandOutput { value: out, stderr } = secretcli_exec(command, max_retry)?;
+    if String::from_utf8_lossy(&out).contains("output_error") {
+        print_warning(&String::from_utf8_lossy(&out));
+    }
+    let value = serde_json::from_str(&String::from_utf8_lossy(&out)).map_err(|err| {
+        if stderr.is_empty() {
+            err
+        } else {
+            serde::de::Error::custom(format!("{}, secretd: {}", err, stderr))
+        }
+    })?;
+    Ok(CommandOutput { value, stderr })
+}
+
+///
+/// Runs a secretcli command as is and returns what it printed to stdout, retrying while it
+/// fails. Commands that still fail after the retries are errors carrying their stderr.
+///
+fn secretcli_exec(command: Vec<String>, max_retry: Option<i32>) -> Result<CommandOutput<Vec<u8>>> {
     let retry = max_retry.unwrap_or(30);
+    let config = network_config();
     let mut commands = command;
-    commands.append(&mut vec_str_to_vec_string(vec!["--output", "json"]));
-    let mut cli = Command::new(SECRETCLI);
-    if !commands.is_empty() {
-        cli.args(commands);
+    let is_tx = commands.first().map(String::as_str) == Some("tx");
+    let uses_node = is_tx || matches!(commands.first().map(String::as_str), Some("q" | "query"));
+    if is_tx || commands.first().map(String::as_str) == Some("keys") {
+        // After any backend the caller passed, so the isolated keyring wins
+        commands.append(&mut config.keyring_args()?);
+    }
+    if is_tx {
+        commands.append(&mut config.tx_args());
+        // Broadcasting to a node that is still syncing can get the tx lost
+        ensure_healthy_node(&config);
     }
 
-    let mut result = cli.output().expect("Unexpected error");
+    let run = |commands: &Vec<String>| {
+        let mut args = commands.clone();
+        if uses_node {
+            throttle(&config);
+            args.extend(node_args(&config));
+        }
+        let mut cli = config.command(&args);
+        if config.verbose {
+            report(|reporter| reporter.info(&format!("Running {:?}", cli)));
+        }
+        let output = cli.output().expect("Unexpected error");
+        if config.verbose {
+            report(|reporter| {
+                reporter.info(String::from_utf8_lossy(&output.stdout).trim_end());
+                if !output.stderr.is_empty() {
+                    reporter.warning(String::from_utf8_lossy(&output.stderr).trim_end());
+                }
+            });
+        }
+        output
+    };
+
+    let mut result = run(&commands);
+    let mut rate_limited = 0;
     // We wait cause sometimes the query/action takes a while
     for _ in 0..retry {
-        if !result.stderr.is_empty() {
-            thread::sleep(time::Duration::from_secs(1));
+        // Warnings also go to stderr, only a failed command is worth retrying
+        if !result.status.success() {
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            if uses_node && is_rate_limited(&stderr) {
+                thread::sleep(backoff(rate_limited));
+                rate_limited += 1;
+            } else {
+                if uses_node && is_connection_error(&stderr) {
+                    failover(&config);
+                }
+                thread::sleep(time::Duration::from_secs(1));
+            }
         } else {
             break;
         }
-        result = cli.output().expect("Unexpected error");
+        result = run(&commands);
     }
-    let out = result.stdout;
-    if String::from_utf8_lossy(&out).contains("output_error") {
-        println!("{:?}", &String::from_utf8_lossy(&out));
+    let stderr = String::from_utf8_lossy(&result.stderr).trim().to_string();
+    if !result.status.success() {
+        crate::metrics::record_failure(if is_rate_limited(&stderr) {
+            "rate_limited"
+        } else if is_connection_error(&stderr) {
+            "connection"
+        } else {
+            "command"
+        });
+        return Err(serde::de::Error::custom(format!(
+            "secretd failed with {}: {}",
+            result.status, stderr
+        )));
     }
-    serde_json::from_str(&String::from_utf8_lossy(&out))
+    Ok(CommandOutput {
+        value: result.stdout,
+        stderr,
+    })
 }
 
 ///
@@ -79,22 +191,29 @@ fn secretcli_run(command: Vec<String>, max_retry: Option<i32>) -> Result<Value>
 ///
 /// # Arguments
 ///
-/// * 'contract' - Contract to be stored
+/// * 'contract' - Contract to be stored, gzipped first if the network config asks for it
 /// * 'user' - User that will handle the tx, defaults to a
 /// * 'gas' - Gas to pay, defaults to 10000000
 /// * 'backend' - The backend keyring, defaults to test
+/// * 'permission' - Who may instantiate the code, defaults to the chain's default
 ///
 fn store_contract(
     contract: &str,
     user: Option<&str>,
     gas: Option<&str>,
     backend: Option<&str>,
+    permission: Option<&InstantiatePermission>,
 ) -> Result<TxResponse> {
+    let config = network_config();
+    config.require_local("Storing a contract")?;
+    // Deletes a compressed copy when it drops, after the broadcast below
+    let upload = prepare_upload(contract, config.compress_wasm)?;
+    let path = upload.path().to_string_lossy().to_string();
     let mut command_arr = vec![
         "tx",
         "compute",
         "store",
-        contract,
+        path.as_str(),
         "--from",
         user.unwrap_or("a"),
         "--gas",
@@ -107,43 +226,251 @@ fn store_contract(
         command_arr.push(backend);
     }
 
-    let command = vec_str_to_vec_string(command_arr);
-    let json = secretcli_run(command, None)?;
-    let out: Result<TxResponse> = serde_json::from_value(json);
-    out
+    if let Some(permission) = permission {
+        command_arr.extend(permission.as_args());
+    }
+
+    broadcast(command_arr, None)
+}
+
+/// Txs this process broadcast that weren't found on chain yet, with when they were broadcast
+static PENDING_TXS: Mutex<BTreeMap<String, time::Instant>> = Mutex::new(BTreeMap::new());
+
+///
+/// Broadcasts a tx command. The tx is recorded in the metrics and analytics once, when
+/// [query_hash] first finds it on chain, no matter how often it's looked up afterwards
+///
+fn broadcast(command: Vec<&str>, max_retry: Option<i32>) -> Result<TxResponse> {
+    let output = secretcli_output(vec_str_to_vec_string(command), max_retry)?;
+    let response = TxResponse {
+        stderr: output.stderr,
+        ..parse_output(output.value)?
+    };
+    PENDING_TXS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .insert(response.txhash.clone(), time::Instant::now());
+    Ok(response)
 }
 
 ///
 /// Queries the hash information
 ///
-fn query_hash(hash: String) -> Result<TxQuery> {
+pub(crate) fn query_hash(hash: String) -> Result<TxQuery> {
+    #[cfg(feature = "sandbox")]
+    if crate::sandbox::is_active() {
+        return crate::sandbox::tx_query(&hash);
+    }
     let command = vec!["q", "tx", &hash];
     let a = secretcli_run(vec_str_to_vec_string(command), None)?;
-    serde_json::from_value(a)
+    let tx: TxQuery = parse_output(a)?;
+    // Spans that don't declare these fields ignore them
+    let span = Span::current();
+    span.record("txhash", tx.txhash.as_str());
+    span.record("gas_used", tx.gas_used);
+    let broadcast = PENDING_TXS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .remove(&tx.txhash);
+    if let Some(broadcast) = broadcast {
+        analytics::record_tx(&tx, broadcast.elapsed());
+        crate::metrics::record_tx(tx.gas_used);
+        // Failed txs have no logs
+        if tx.logs.is_empty() {
+            crate::metrics::record_failure("tx_failed");
+        }
+    }
+    Ok(tx)
+}
+
+///
+/// Records the time spent in the span as its `duration_ms` field once dropped
+///
+struct SpanTimer {
+    span: Span,
+    start: time::Instant,
+}
+
+fn time_span() -> SpanTimer {
+    SpanTimer {
+        span: Span::current(),
+        start: time::Instant::now(),
+    }
+}
+
+impl Drop for SpanTimer {
+    fn drop(&mut self) {
+        self.span
+            .record("duration_ms", self.start.elapsed().as_millis() as u64);
+    }
 }
 
 ///
 /// Computes the hash information
 ///
-fn compute_hash(hash: String) -> Result<TxCompute> {
+pub(crate) fn compute_hash(hash: String) -> Result<TxCompute> {
+    #[cfg(feature = "sandbox")]
+    if crate::sandbox::is_active() {
+        return crate::sandbox::tx_compute(&hash);
+    }
     let command = vec!["q", "compute", "tx", &hash];
 
-    serde_json::from_value(secretcli_run(vec_str_to_vec_string(command), None)?)
+    parse_output(secretcli_run(vec_str_to_vec_string(command), None)?)
+}
+
+///
+/// Height of the latest block
+///
+pub fn latest_block_height() -> Result<u64> {
+    let command = vec!["query", "block"];
+    let block = secretcli_run(vec_str_to_vec_string(command), None)?;
+    block
+        .pointer("/block/header/height")
+        .or_else(|| block.pointer("/sdk_block/header/height"))
+        .and_then(Value::as_str)
+        .and_then(|height| height.parse().ok())
+        .ok_or_else(|| serde::de::Error::custom("block has no height"))
+}
+
+///
+/// Every tx included in the block at the given height
+///
+pub fn txs_at_height(height: u64) -> Result<Vec<TxQuery>> {
+    search_txs(&format!("tx.height={}", height))
+}
+
+///
+/// Every tx matching the events query, oldest first
+///
+/// # Arguments
+///
+/// * 'events' - Events to match, e.g. `message.sender=secret1..`, joined with `&`
+///
+pub fn search_txs(events: &str) -> Result<Vec<TxQuery>> {
+    let mut txs = vec![];
+    for page in 1.. {
+        let response = search_txs_page(events, page)?;
+        let empty = response.txs.is_empty();
+        txs.extend(response.txs);
+        if empty || txs.len() as u64 >= response.total_count {
+            break;
+        }
+    }
+    Ok(txs)
+}
+
+///
+/// Walks the txs matching the events query newest first, fetching pages only until `visit`
+/// returns false, so looking for recent txs doesn't go through the whole history
+///
+/// # Arguments
+///
+/// * 'events' - Events to match, e.g. `message.sender=secret1..`, joined with `&`
+/// * 'visit' - Called with every tx, returns whether to keep going
+///
+pub fn search_txs_newest_first<F>(events: &str, mut visit: F) -> Result<()>
+where
+    F: FnMut(TxQuery) -> bool,
+{
+    // The node pages oldest first, the first page tells how many there are
+    let first = search_txs_page(events, 1)?;
+    let pages = (first.total_count + TX_PAGE_LIMIT - 1) / TX_PAGE_LIMIT;
+    let mut visit_page =
+        |response: TxSearchResponse| response.txs.into_iter().rev().all(&mut visit);
+    for page in (2..=pages).rev() {
+        if !visit_page(search_txs_page(events, page)?) {
+            return Ok(());
+        }
+    }
+    visit_page(first);
+    Ok(())
+}
+
+/// Txs per page of tx searches
+const TX_PAGE_LIMIT: u64 = 100;
+
+fn search_txs_page(events: &str, page: u64) -> Result<TxSearchResponse> {
+    let page = page.to_string();
+    let limit = TX_PAGE_LIMIT.to_string();
+    let command = vec![
+        "query", "txs", "--events", events, "--page", &page, "--limit", &limit,
+    ];
+    parse_output(secretcli_run(vec_str_to_vec_string(command), None)?)
 }
 
 ///
 /// Lists all uploaded contracts
 ///
-fn list_code() -> Result<Vec<ListCodeResponse>> {
+pub fn list_code() -> Result<Vec<ListCodeResponse>> {
     let command = vec!["query", "compute", "list-code"];
 
-    serde_json::from_value(secretcli_run(vec_str_to_vec_string(command), None)?)
+    parse_output(Value::Array(query_all_pages(command, "code_infos")?))
+}
+
+///
+/// Code id, creator, label and admin of a contract
+///
+pub fn contract_info(address: &str) -> Result<Value> {
+    let command = vec!["query", "compute", "contract", address];
+
+    secretcli_run(vec_str_to_vec_string(command), None)
 }
 
 pub fn list_contracts_by_code(code: String) -> Result<Vec<ListContractCode>> {
     let command = vec!["query", "compute", "list-contract-by-code", &code];
 
-    serde_json::from_value(secretcli_run(vec_str_to_vec_string(command), None)?)
+    parse_output(Value::Array(query_all_pages(command, "contract_infos")?))
+}
+
+///
+/// Lists uploaded code matching the filter, out of every page of the listing
+///
+pub fn list_code_filtered(filter: ListFilter) -> Result<impl Iterator<Item = ListCodeResponse>> {
+    Ok(list_code()?
+        .into_iter()
+        .filter(move |code| filter.matches_code(code)))
+}
+
+///
+/// Lists the contracts instantiated from a code id matching the filter
+///
+pub fn list_contracts_by_code_filtered(
+    code: String,
+    filter: ListFilter,
+) -> Result<impl Iterator<Item = ListContractCode>> {
+    Ok(list_contracts_by_code(code)?
+        .into_iter()
+        .filter(move |contract| filter.matches_contract(contract)))
+}
+
+///
+/// Downloads the wasm stored under a code id, e.g. to [diff_wasm] it against a local build
+///
+/// # Arguments
+///
+/// * 'code_id' - The code to download
+/// * 'path' - File the wasm is written to, its directory is created if missing
+///
+pub fn download_code(code_id: u64, path: impl AsRef<Path>) -> Result<()> {
+    network_config().require_local("Downloading code")?;
+    let path = path.as_ref();
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        create_dir_all(dir).map_err(serde_json::Error::io)?;
+    }
+    let code_id = code_id.to_string();
+    let command = vec![
+        "query",
+        "compute",
+        "code",
+        &code_id,
+        &path.to_string_lossy(),
+    ];
+
+    // The wasm goes to the file, there is no JSON to parse
+    secretcli_exec(vec_str_to_vec_string(command), None).map_err(|err| {
+        serde::de::Error::custom(format!("Could not download code {}: {}", code_id, err))
+    })?;
+    Ok(())
 }
 
 fn trim_newline(s: &mut String) {
@@ -163,62 +490,105 @@ fn trim_newline(s: &mut String) {
 /// * 'acc' - The requested account
 ///
 pub fn account_address(acc: &str) -> Result<String> {
-    let command = vec_str_to_vec_string(vec!["keys", "show", "-a", acc]);
-
-    let retry = 40;
-    let mut cli = Command::new(SECRETCLI);
-    if !command.is_empty() {
-        cli.args(command);
-    }
+    key_address(acc).map_err(serde::de::Error::custom)
+}
 
-    let mut result = cli.output().expect("Unexpected error");
+/// Addresses [key_address] looked up, per keyring and key name
+static KEY_ADDRESSES: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
 
-    // We wait cause sometimes the query/action takes a while
-    for _ in 0..retry {
-        if !result.stderr.is_empty() {
-            thread::sleep(time::Duration::from_secs(1));
-        } else {
-            break;
-        }
-        result = cli.output().expect("Unexpected error");
+///
+/// Same as [account_address] with the reason it failed. Only failures that may be transient
+/// are retried, a missing key or locked keyring fails right away.
+///
+/// Each key is looked up once per keyring, so signing many txs with the same key doesn't
+/// run `keys show` every time
+///
+/// # Arguments
+///
+/// * 'acc' - The requested account
+///
+pub fn key_address(acc: &str) -> std::result::Result<String, KeyError> {
+    let keyring_args = network_config()
+        .keyring_args()
+        .map_err(|err| KeyError::Other(err.to_string()))?;
+    let key = format!("{}/{}", keyring_args.join(" "), acc);
+    let hit = KEY_ADDRESSES
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .get(&key)
+        .cloned();
+    if let Some(address) = hit {
+        return Ok(address);
     }
 
-    let out = result.stdout;
-
-    let mut s: String = String::from_utf8_lossy(&out).parse().unwrap();
+    let output = run_keys_command(acc, &["keys", "show", "-a", acc], 40)?;
+    let mut s: String = String::from_utf8_lossy(&output.stdout).to_string();
 
     // Sometimes the resulting string has a newline, so we trim that
     trim_newline(&mut s);
 
+    KEY_ADDRESSES
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .insert(key, s.clone());
     Ok(s)
 }
 
-pub fn create_key_account(name: &str) -> Result<()> {
-    let command = vec_str_to_vec_string(vec!["keys", "add", name]);
+///
+/// Checks that the keyring's address for an account matches the one independently derived
+/// from its mnemonic
+///
+/// # Arguments
+///
+/// * 'acc' - The account in the keyring
+/// * 'mnemonic' - The mnemonic the account was imported from
+///
+pub fn verify_account_address(acc: &str, mnemonic: &str) -> Result<bool> {
+    Ok(account_address(acc)? == address_from_mnemonic(mnemonic)?)
+}
 
-    let retry = 40;
-    let mut cli = Command::new(SECRETCLI);
-    if !command.is_empty() {
-        cli.args(command);
-    }
+pub fn create_key_account(name: &str) -> Result<()> {
+    run_keys_command(name, &["keys", "add", name], 40).map_err(serde::de::Error::custom)?;
+    Ok(())
+}
 
-    let mut result = cli.output().expect("Unexpected error");
+///
+/// The flag the installed secretd takes coins sent to a contract with, `--amount` unless its
+/// help for the subcommand only lists `--funds`. Looked up once per network
+///
+fn funds_flag(subcommand: &str) -> String {
+    cached(
+        &format!("funds_flag/{}", subcommand),
+        time::Duration::MAX,
+        || {
+            let output = network_config()
+                .command(["tx", "compute", subcommand, "--help"])
+                .output()
+                .map_err(serde_json::Error::io)?;
+            let help = String::from_utf8_lossy(&output.stdout);
+            Ok(if !help.contains("--amount") && help.contains("--funds") {
+                "--funds".to_string()
+            } else {
+                "--amount".to_string()
+            })
+        },
+    )
+    .unwrap_or_else(|_| "--amount".to_string())
+}
 
-    // We wait cause sometimes the query/action takes a while
-    for _ in 0..retry {
-        if !result.stderr.is_empty() {
-            thread::sleep(time::Duration::from_secs(1));
-        } else {
-            break;
-        }
-        result = cli.output().expect("Unexpected error");
+///
+/// Passing the code hash saves secretd a lookup per command to encrypt the message
+///
+fn code_hash_args(contract: &NetContract) -> Vec<&str> {
+    if contract.code_hash.is_empty() {
+        vec![]
+    } else {
+        vec!["--code-hash", &contract.code_hash]
     }
-
-    Ok(())
 }
 
 ///
-/// Instantiate a contract
+/// Instantiate a contract and wait for the tx to be confirmed
 ///
 /// # Arguments
 ///
@@ -228,16 +598,38 @@ pub fn create_key_account(name: &str) -> Result<()> {
 /// * 'sender' - Msg sender
 /// * 'gas' - Gas price to use, defaults to 8000000
 /// * 'backend' - Keyring backend defaults to none
+/// * 'funds' - Coins sent to the contract along with the init msg
 ///
 fn instantiate_contract<Init: serde::Serialize>(
     contract: &NetContract,
     msg: Init,
     label: &str,
-    sender: &str,
+    sender: impl AsRef<str>,
     gas: Option<&str>,
     backend: Option<&str>,
-) -> Result<TxResponse> {
+    funds: &[TxCoin],
+) -> Result<TxQuery> {
+    let sender = sender.as_ref();
     let message = serde_json::to_string(&msg)?;
+    let funds = validate_funds(funds)?;
+    let amount = coins_arg(&funds);
+    let flag = funds_flag("instantiate");
+    let msg = serde_json::to_value(&msg)?;
+    let context = TxContext {
+        kind: TxKind::Instantiate,
+        contract,
+        msg: &msg,
+        sender,
+        funds: &funds,
+    };
+    hooks::run_before(&context)?;
+
+    #[cfg(feature = "sandbox")]
+    if crate::sandbox::is_active() {
+        let tx = crate::sandbox::instantiate(contract, &msg, label, sender, &funds)?;
+        hooks::run_after(&context, &tx)?;
+        return Ok(tx);
+    }
 
     let mut command = vec![
         "tx",
@@ -262,12 +654,18 @@ fn instantiate_contract<Init: serde::Serialize>(
         command.push(backend);
     }
 
-    command.push("-y");
+    if !funds.is_empty() {
+        command.push(&flag);
+        command.push(&amount);
+    }
 
-    let response: TxResponse =
-        serde_json::from_value(secretcli_run(vec_str_to_vec_string(command), None)?)?;
+    command.extend(code_hash_args(contract));
 
-    Ok(response)
+    command.push("-y");
+
+    let tx = query_hash(broadcast(command, None)?.txhash)?;
+    hooks::run_after(&context, &tx)?;
+    Ok(tx)
 }
 
 ///
@@ -280,34 +678,96 @@ fn instantiate_contract<Init: serde::Serialize>(
 ///
 pub fn store_and_return_contract(
     contract_file: &str,
-    sender: &str,
+    sender: impl AsRef<str>,
     store_gas: Option<&str>,
     backend: Option<&str>,
 ) -> Result<StoredContract> {
-    let store_response = store_contract(contract_file, Option::from(sender), store_gas, backend)?;
-    let store_query = query_hash(store_response.txhash)?;
-    let mut contract = StoredContract {
-        id: "".to_string(),
-        code_hash: "".to_string(),
-    };
-
-    for attribute in &store_query.logs[0].events[0].attributes {
-        if attribute.msg_key == "code_id" {
-            contract.id = attribute.value.clone();
-            break;
-        }
-    }
+    let sender = sender.as_ref();
+    store_and_return_contract_with_permission(contract_file, sender, None, store_gas, backend)
+}
 
-    let listed_contracts = list_code()?;
+///
+/// Store the given contract restricting who may instantiate it
+///
+/// * 'contract_file' - Contract file to store
+/// * 'sender' - Msg sender
+/// * 'permission' - Who may instantiate the code, defaults to the chain's default
+/// * 'store_gas' - Gas price to use when storing the contract, defaults to 10000000
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn store_and_return_contract_with_permission(
+    contract_file: &str,
+    sender: impl AsRef<str>,
+    permission: Option<&InstantiatePermission>,
+    store_gas: Option<&str>,
+    backend: Option<&str>,
+) -> Result<StoredContract> {
+    let sender = sender.as_ref();
+    Ok(store_and_confirm(contract_file, sender, permission, store_gas, backend)?.into())
+}
 
-    for item in listed_contracts {
-        if item.code_id.to_string() == contract.id {
-            contract.code_hash = item.code_hash;
-            break;
-        }
+///
+/// Store the given contract, wait for the tx to be included and extract its code id and hash
+///
+/// * 'contract_file' - Contract file to store
+/// * 'sender' - Msg sender
+/// * 'permission' - Who may instantiate the code, defaults to the chain's default
+/// * 'store_gas' - Gas price to use when storing the contract, defaults to 10000000
+/// * 'backend' - Keyring backend defaults to none
+///
+#[instrument(
+    name = "store",
+    skip_all,
+    fields(contract_file = %contract_file, txhash = Empty, gas_used = Empty, duration_ms = Empty)
+)]
+pub fn store_and_confirm(
+    contract_file: &str,
+    sender: impl AsRef<str>,
+    permission: Option<&InstantiatePermission>,
+    store_gas: Option<&str>,
+    backend: Option<&str>,
+) -> Result<StoredCode> {
+    let sender = sender.as_ref();
+    let _timer = time_span();
+    #[cfg(feature = "sandbox")]
+    if crate::sandbox::is_active() {
+        return crate::sandbox::store(contract_file);
     }
+    let store_response = store_contract(
+        contract_file,
+        Option::from(sender),
+        store_gas,
+        backend,
+        permission,
+    )?;
+    let store_query = query_hash(store_response.txhash)?;
 
-    Ok(contract)
+    let code_id = store_query
+        .logs
+        .iter()
+        .flat_map(|log| &log.events)
+        .flat_map(|event| &event.attributes)
+        .find(|attribute| attribute.msg_key == "code_id")
+        .ok_or_else(|| {
+            serde::de::Error::custom(format!("No code_id found in tx {}", store_query.txhash))
+        })?
+        .value
+        .parse::<u64>()
+        .map_err(serde::de::Error::custom)?;
+
+    let code_hash = list_code()?
+        .into_iter()
+        .find(|item| item.code_id == code_id as u128)
+        .map(|item| item.code_hash)
+        .ok_or_else(|| {
+            serde::de::Error::custom(format!("Code {} not found in the code list", code_id))
+        })?;
+
+    Ok(StoredCode {
+        code_id,
+        code_hash,
+        txhash: store_query.txhash,
+    })
 }
 
 ///
@@ -325,43 +785,36 @@ pub fn store_and_return_contract(
 /// * `report` - Records the contract`s message and instantiation price
 ///
 #[allow(clippy::too_many_arguments)]
+#[instrument(
+    name = "init",
+    skip_all,
+    fields(label = %label, txhash = Empty, gas_used = Empty, duration_ms = Empty)
+)]
 pub fn init<Message: serde::Serialize>(
     msg: &Message,
     contract_file: &str,
     label: &str,
-    sender: &str,
+    sender: impl AsRef<str>,
     store_gas: Option<&str>,
     init_gas: Option<&str>,
     backend: Option<&str>,
     report: &mut Vec<Report>,
 ) -> Result<NetContract> {
+    let sender = sender.as_ref();
+    let _timer = time_span();
     io::stdout().flush();
-    let store_response = store_contract(contract_file, Option::from(&*sender), store_gas, backend)?;
-    let store_query = query_hash(store_response.txhash)?;
-    let mut contract = NetContract {
-        label: label.to_string(),
-        id: "".to_string(),
-        address: "".to_string(),
-        code_hash: "".to_string(),
-    };
-
-    // Look for the code ID
-    for attribute in &store_query.logs[0].events[0].attributes {
-        if attribute.msg_key == "code_id" {
-            contract.id = attribute.value.clone();
-            break;
-        }
-    }
+    // Resolving the code hash right away lets the instantiation skip looking it up
+    let code = store_and_confirm(contract_file, sender, None, store_gas, backend)?;
+    let mut contract = NetContract::new(label, code.code_id.to_string(), "", code.code_hash);
 
     // Instantiate and get the info
-    let tx = instantiate_contract(&contract, msg, label, sender, init_gas, backend)?;
-    let init_query = query_hash(tx.txhash)?;
+    let init_query = instantiate_contract(&contract, msg, label, sender, init_gas, backend, &[])?;
 
     // Include the instantiation info in the report
     report.push(Report {
         msg_type: "Instantiate".to_string(),
         message: serde_json::to_string(&msg)?,
-        gas_used: init_query.gas_used,
+        gas_used: init_query.gas_used.to_string(),
     });
 
     // Look for the contract's address
@@ -371,21 +824,11 @@ pub fn init<Message: serde::Serialize>(
             break;
         }
     }
-    // Look for the contract's code hash
-    let listed_contracts = list_code()?;
-
-    // Find the code_hash
-    for item in listed_contracts {
-        if item.code_id.to_string() == contract.id {
-            contract.code_hash = item.code_hash;
-            break;
-        }
-    }
     Ok(contract)
 }
 
 ///
-/// Executes a contract's handle
+/// Executes a contract's handle and waits for the tx to be confirmed
 ///
 /// # Arguments
 ///
@@ -394,18 +837,39 @@ pub fn init<Message: serde::Serialize>(
 /// * 'sender' - Msg sender
 /// * 'gas' - Gas price to use, defaults to 8000000
 /// * 'backend' - Keyring backend defaults to none
-/// * 'amount' - Included L1 tokens to send, defaults to none
+/// * 'amount' - Included L1 tokens to send, e.g. `100uscrt,5ibc/27A6..`, defaults to none
 ///
 fn execute_contract<Handle: serde::Serialize>(
     contract: &NetContract,
     msg: Handle,
-    sender: &str,
+    sender: impl AsRef<str>,
     gas: Option<&str>,
     backend: Option<&str>,
     amount: Option<&str>,
     max_tries: Option<i32>,
-) -> Result<TxResponse> {
+) -> Result<TxQuery> {
+    let sender = sender.as_ref();
     let message = serde_json::to_string(&msg)?;
+    let funds = validate_funds(&parse_coins(amount.unwrap_or_default())?)?;
+    let amount = coins_arg(&funds);
+    let flag = funds_flag("execute");
+    coverage::record(contract, EntryPoint::Execute, &msg);
+    let msg = serde_json::to_value(&msg)?;
+    let context = TxContext {
+        kind: TxKind::Execute,
+        contract,
+        msg: &msg,
+        sender,
+        funds: &funds,
+    };
+    hooks::run_before(&context)?;
+
+    #[cfg(feature = "sandbox")]
+    if crate::sandbox::is_active() {
+        let tx = crate::sandbox::execute(contract, &msg, sender, &funds)?;
+        hooks::run_after(&context, &tx)?;
+        return Ok(tx);
+    }
 
     let mut command = vec![
         "tx",
@@ -428,17 +892,18 @@ fn execute_contract<Handle: serde::Serialize>(
         command.push(backend);
     }
 
-    if let Some(amount) = amount {
-        command.push("--amount");
-        command.push(amount);
+    if !funds.is_empty() {
+        command.push(&flag);
+        command.push(&amount);
     }
 
-    command.push("-y");
+    command.extend(code_hash_args(contract));
 
-    let response: TxResponse =
-        serde_json::from_value(secretcli_run(vec_str_to_vec_string(command), max_tries)?)?;
+    command.push("-y");
 
-    Ok(response)
+    let tx = query_hash(broadcast(command, max_tries)?.txhash)?;
+    hooks::run_after(&context, &tx)?;
+    Ok(tx)
 }
 
 ///
@@ -455,17 +920,30 @@ fn execute_contract<Handle: serde::Serialize>(
 /// * `report` - Records the contract`s message and handle price
 ///
 #[allow(clippy::too_many_arguments)]
+#[instrument(
+    name = "execute",
+    skip_all,
+    fields(
+        label = %contract.label,
+        address = %contract.address,
+        txhash = Empty,
+        gas_used = Empty,
+        duration_ms = Empty
+    )
+)]
 pub fn handle<Message: serde::Serialize + Clone>(
     msg: Message,
     contract: &NetContract,
-    sender: &str,
+    sender: impl AsRef<str>,
     gas: Option<&str>,
     backend: Option<&str>,
     amount: Option<&str>,
     report: &mut Vec<Report>,
     max_tries: Option<i32>,
 ) -> Result<(TxCompute, TxQuery)> {
-    let tx = execute_contract(
+    let sender = sender.as_ref();
+    let _timer = time_span();
+    let queried_response = execute_contract(
         contract,
         msg.clone(),
         sender,
@@ -474,15 +952,13 @@ pub fn handle<Message: serde::Serialize + Clone>(
         amount,
         max_tries,
     )?;
-
-    let computed_response = compute_hash(tx.txhash.clone())?;
-    let queried_response = query_hash(tx.txhash)?;
+    let computed_response = compute_hash(queried_response.txhash.clone())?;
 
     // Include the instantiation info in the report
     report.push(Report {
         msg_type: "Handle".to_string(),
         message: serde_json::to_string(&msg)?,
-        gas_used: queried_response.gas_used.clone(),
+        gas_used: queried_response.gas_used.to_string(),
     });
 
     Ok((computed_response, queried_response))
@@ -496,100 +972,377 @@ pub fn handle<Message: serde::Serialize + Clone>(
 /// * 'contract' - The contract to query
 /// * 'msg' - The query to serialize, must have serde::Serialized
 ///
+#[instrument(
+    name = "query",
+    skip_all,
+    fields(label = %contract.label, address = %contract.address, duration_ms = Empty)
+)]
 pub fn query<Query: serde::Serialize, Response: serde::de::DeserializeOwned>(
     contract: &NetContract,
     msg: Query,
     max_tries: Option<i32>,
 ) -> Result<Response> {
-    let command = vec_str_to_vec_string(vec![
-        "query",
-        "compute",
-        "query",
-        &contract.address,
-        &serde_json::to_string(&msg)?,
-    ]);
-
-    let response: Result<Response> = serde_json::from_value(secretcli_run(command, max_tries)?);
+    let _timer = time_span();
+    coverage::record(contract, EntryPoint::Query, &msg);
+    #[cfg(feature = "sandbox")]
+    if crate::sandbox::is_active() {
+        return crate::sandbox::query(contract, &msg);
+    }
+    let msg = serde_json::to_string(&msg)?;
+    let mut command = vec!["query", "compute", "query", &contract.address, &msg];
+    command.extend(code_hash_args(contract));
+    let command = vec_str_to_vec_string(command);
+
+    let start = time::Instant::now();
+    let output = secretcli_run(command, max_tries);
+    crate::metrics::observe_query(start.elapsed());
+    let response: Result<Response> = serde_json::from_value(output?);
     response
 }
 
 ///
-/// Create a signed permit
+/// Queries a given contract and returns the decrypted response as secretd prints it, without
+/// parsing it, so trailing padding is kept
 ///
 /// # Arguments
 ///
-/// * 'tx' - The message to sign
-/// * 'signer' - The key of the signer
+/// * 'contract' - The contract to query
+/// * 'msg' - The query to serialize
 ///
-pub fn create_permit<Tx: serde::Serialize>(tx: Tx, signer: &str) -> Result<SignedTx> {
-    let msg = serde_json::to_string(&tx)?;
-
-    // send to a file
-    let mut file = File::create("./tx_to_sign").unwrap();
-    file.write_all(msg.as_bytes()).unwrap();
-
-    let command = vec!["tx", "sign-doc", "tx_to_sign", "--from", signer];
-
-    let response: SignedTx =
-        serde_json::from_value(secretcli_run(vec_str_to_vec_string(command), None)?)?;
-
-    Ok(response)
+pub fn query_bytes<Query: serde::Serialize>(
+    contract: &NetContract,
+    msg: Query,
+    max_tries: Option<i32>,
+) -> Result<Vec<u8>> {
+    coverage::record(contract, EntryPoint::Query, &msg);
+    #[cfg(feature = "sandbox")]
+    if crate::sandbox::is_active() {
+        return crate::sandbox::query_bytes(contract, &msg);
+    }
+    let msg = serde_json::to_string(&msg)?;
+    let mut command = vec!["query", "compute", "query", &contract.address, &msg];
+    command.extend(code_hash_args(contract));
+
+    let mut stdout = secretcli_exec(vec_str_to_vec_string(command), max_tries)?.value;
+    // secretd ends its output with a newline of its own
+    if stdout.last() == Some(&b'\n') {
+        stdout.pop();
+    }
+    Ok(stdout)
 }
 
 ///
-/// Trait that allows contract instantiate to be used in test scripts
+/// Runs the queries concurrently, the results are in the same order as the queries
 ///
 /// # Arguments
 ///
-/// * 'contract' - The contract to interact with
-/// * 'label' - The contract label
-/// * 'sender' - Msg sender
-/// * 'gas' - Gas price to use, defaults to 8000000
-/// * 'backend' - Keyring backend defaults to none
+/// * 'contract' - The contract to query
+/// * 'msgs' - The queries to serialize
 ///
-pub trait TestInit: serde::Serialize {
-    fn t_init(
-        &self,
-        contract: &NetContract,
-        label: &str,
-        sender: &str,
+pub fn multi_query<Query: serde::Serialize + Sync>(
+    contract: &NetContract,
+    msgs: Vec<Query>,
+    max_tries: Option<i32>,
+) -> Vec<Result<Value>> {
+    thread::scope(|scope| {
+        let queries: Vec<_> = msgs
+            .iter()
+            .map(|msg| scope.spawn(move || query::<_, Value>(contract, msg, max_tries)))
+            .collect();
+        queries
+            .into_iter()
+            .map(|query| query.join().expect("query thread panicked"))
+            .collect()
+    })
+}
+
+///
+/// Sends the queries as one `{"batch":{"queries":[...]}}` query, for contracts that answer
+/// it with a secret-utils `BatchQuery`
+///
+/// # Arguments
+///
+/// * 'contract' - The contract to query
+/// * 'msgs' - The queries to serialize
+///
+pub fn query_batch<Query: serde::Serialize>(
+    contract: &NetContract,
+    msgs: Vec<Query>,
+    max_tries: Option<i32>,
+) -> Result<Vec<Result<Value>>> {
+    let results: Vec<BatchQueryResult> = query(
+        contract,
+        serde_json::json!({ "batch": { "queries": msgs } }),
+        max_tries,
+    )?;
+    Ok(results.iter().map(BatchQueryResult::decode).collect())
+}
+
+///
+/// Sends the conventional `{"status":{}}` health query, contracts answer it once they are
+/// usable. An answer with `"ready": false` means the contract is up but still setting itself up
+///
+pub fn contract_status(contract: &NetContract) -> Result<Value> {
+    query(contract, serde_json::json!({ "status": {} }), Some(0))
+}
+
+///
+/// Polls the contract's status query until it reports the contract ready, for contracts that
+/// finish setting up through submessages after being instantiated
+///
+/// # Arguments
+///
+/// * 'contract' - The contract to wait for
+/// * 'timeout' - How long to keep polling before failing
+///
+pub fn wait_until_contract_ready(contract: &NetContract, timeout: time::Duration) -> Result<Value> {
+    poll_until_ready(
+        &contract.address,
+        timeout,
+        time::Duration::from_secs(1),
+        || contract_status(contract),
+    )
+}
+
+fn poll_until_ready<F>(
+    address: &str,
+    timeout: time::Duration,
+    interval: time::Duration,
+    mut status: F,
+) -> Result<Value>
+where
+    F: FnMut() -> Result<Value>,
+{
+    let deadline = time::Instant::now() + timeout;
+    loop {
+        let status = status();
+        if status.as_ref().map_or(false, is_ready) {
+            return status;
+        }
+        if time::Instant::now() >= deadline {
+            return Err(serde::de::Error::custom(format!(
+                "Contract {} was not ready after {:?}: {}",
+                address,
+                timeout,
+                match status {
+                    Ok(answer) => answer.to_string(),
+                    Err(err) => err.to_string(),
+                }
+            )));
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// Any answer to the status query counts as ready unless it says `"ready": false`
+fn is_ready(answer: &Value) -> bool {
+    let answer = answer.get("status").unwrap_or(answer);
+    answer.get("ready").and_then(Value::as_bool).unwrap_or(true)
+}
+
+///
+/// Reads a contract's raw storage entry, bypassing its query handler.
+/// Useful for debugging storage layouts, only works on nodes that expose raw state.
+///
+/// # Arguments
+///
+/// * 'contract' - The contract to read from
+/// * 'key' - The storage key and its encoding
+///
+pub fn query_contract_raw(contract: &NetContract, key: StateKey) -> Result<Option<Vec<u8>>> {
+    let (key, encoding) = key.as_arg();
+    let command = vec_str_to_vec_string(vec![
+        "query",
+        "compute",
+        "contract-state",
+        "raw",
+        &contract.address,
+        key,
+        encoding,
+    ]);
+
+    let response: ContractStateRaw = parse_output(secretcli_run(command, None)?)?;
+    match response.data {
+        None => Ok(None),
+        Some(data) if data.is_empty() => Ok(None),
+        Some(data) => base64::decode(data)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+///
+/// Signs a SNIP-24 query permit, the result can be passed as is to a contract's permit queries
+///
+/// # Arguments
+///
+/// * 'params' - The permit to sign
+/// * 'signer' - The key of the signer
+///
+pub fn create_permit(params: PermitParams, signer: impl AsRef<str>) -> Result<Permit> {
+    let signer = signer.as_ref();
+    let signature = sign_doc(params.sign_doc(), signer)?;
+    Ok(Permit { params, signature })
+}
+
+///
+/// Signs a SNIP-24 query permit delegating read access to another address, e.g. a dapp
+/// backend, which queries with the permit and its [relayer_proof]
+///
+/// # Arguments
+///
+/// * 'params' - The permit to sign, its delegate is overwritten
+/// * 'delegate' - Address allowed to query with the permit
+/// * 'signer' - The key of the signer
+///
+pub fn create_delegated_permit(
+    mut params: PermitParams,
+    delegate: &str,
+    signer: impl AsRef<str>,
+) -> Result<Permit> {
+    let signer = signer.as_ref();
+    params.delegate = Some(delegate.to_string());
+    create_permit(params, signer)
+}
+
+///
+/// Proves the relayer is the delegate of a permit, by signing the permit's signature and
+/// the proof's expiry following ADR-36. Anyone holding the proof and the permit can query as
+/// the delegate until it expires, keep the expiry short.
+///
+/// # Arguments
+///
+/// * 'permit' - The delegated permit
+/// * 'relayer' - The key of the delegate
+/// * 'expires' - Block time in seconds from which contracts reject the proof
+///
+pub fn relayer_proof(
+    permit: &Permit,
+    relayer: impl AsRef<str>,
+    expires: u64,
+) -> Result<RelayerProof> {
+    let relayer = relayer.as_ref();
+    let signature = sign_adr36(&permit.relayer_proof_data(expires)?, relayer)?;
+    Ok(RelayerProof { expires, signature })
+}
+
+///
+/// Signs arbitrary data following ADR-36, e.g. to prove ownership of an account to a
+/// contract or an off-chain service
+///
+/// # Arguments
+///
+/// * 'message' - The data to sign
+/// * 'signer' - The key of the signer
+///
+pub fn sign_adr36(message: &[u8], signer: impl AsRef<str>) -> Result<SignedTx> {
+    let signer = signer.as_ref();
+    let address = account_address(signer)?;
+    sign_doc(adr36_sign_doc(&address, message), signer)
+}
+
+///
+/// Signs an arbitrary amino sign doc
+///
+/// # Arguments
+///
+/// * 'tx' - The message to sign
+/// * 'signer' - The key of the signer
+///
+pub fn sign_doc<Tx: serde::Serialize>(tx: Tx, signer: impl AsRef<str>) -> Result<SignedTx> {
+    let signer = signer.as_ref();
+    let msg = serde_json::to_string(&tx)?;
+
+    // send to a file
+    let mut file = File::create("./tx_to_sign").unwrap();
+    file.write_all(msg.as_bytes()).unwrap();
+
+    let command = vec!["tx", "sign-doc", "tx_to_sign", "--from", signer];
+
+    let response: SignedTx = parse_output(secretcli_run(vec_str_to_vec_string(command), None)?)?;
+
+    Ok(response)
+}
+
+///
+/// Trait that allows contract instantiate to be used in test scripts
+///
+/// # Arguments
+///
+/// * 'contract' - The contract to interact with
+/// * 'label' - The contract label
+/// * 'sender' - Msg sender
+/// * 'gas' - Gas price to use, defaults to 8000000
+/// * 'backend' - Keyring backend defaults to none
+///
+pub trait TestInit: serde::Serialize {
+    fn t_init(
+        &self,
+        contract: &NetContract,
+        label: &str,
+        sender: impl AsRef<str>,
+        gas: Option<&str>,
+        backend: Option<&str>,
+    ) -> Result<TxQuery> {
+        self.t_init_with_funds(contract, label, sender, gas, backend, &[])
+    }
+
+    ///
+    /// Same as [TestInit::t_init] sending coins to the contract along with the message
+    ///
+    fn t_init_with_funds(
+        &self,
+        contract: &NetContract,
+        label: &str,
+        sender: impl AsRef<str>,
         gas: Option<&str>,
         backend: Option<&str>,
+        funds: &[TxCoin],
     ) -> Result<TxQuery> {
-        let tx = instantiate_contract(contract, self, label, sender, gas, backend)?;
-        query_hash(tx.txhash)
+        let sender = sender.as_ref();
+        instantiate_contract(contract, self, label, sender, gas, backend, funds)
     }
 
     fn inst_init(
         &self,
         contract_file: &str,
         label: &str,
-        sender: &str,
+        sender: impl AsRef<str>,
         store_gas: Option<&str>,
         init_gas: Option<&str>,
         backend: Option<&str>,
     ) -> Result<NetContract> {
-        let store_response =
-            store_contract(contract_file, Option::from(sender), store_gas, backend)?;
-
-        let store_query = query_hash(store_response.txhash)?;
-
-        let mut contract = NetContract {
-            label: label.to_string(),
-            id: "".to_string(),
-            address: "".to_string(),
-            code_hash: "".to_string(),
-        };
-
-        // Look for the code ID
-        for attribute in &store_query.logs[0].events[0].attributes {
-            if attribute.msg_key == "code_id" {
-                contract.id = attribute.value.clone();
-                break;
-            }
-        }
+        self.inst_init_with_funds(
+            contract_file,
+            label,
+            sender,
+            store_gas,
+            init_gas,
+            backend,
+            &[],
+        )
+    }
 
-        let init_query = self.t_init(&contract, label, sender, init_gas, backend)?;
+    ///
+    /// Same as [TestInit::inst_init] sending coins to the contract along with the message
+    ///
+    #[allow(clippy::too_many_arguments)]
+    fn inst_init_with_funds(
+        &self,
+        contract_file: &str,
+        label: &str,
+        sender: impl AsRef<str>,
+        store_gas: Option<&str>,
+        init_gas: Option<&str>,
+        backend: Option<&str>,
+        funds: &[TxCoin],
+    ) -> Result<NetContract> {
+        let sender = sender.as_ref();
+        let code = store_and_confirm(contract_file, sender, None, store_gas, backend)?;
+        let mut contract = NetContract::new(label, code.code_id.to_string(), "", code.code_hash);
+
+        let init_query =
+            self.t_init_with_funds(&contract, label, sender, init_gas, backend, funds)?;
 
         // Look for the contract's address
         for attribute in &init_query.logs[0].events[0].attributes {
@@ -599,16 +1352,6 @@ pub trait TestInit: serde::Serialize {
             }
         }
 
-        // Look for the contract's code hash
-        let listed_contracts = list_code()?;
-
-        for item in listed_contracts {
-            if item.code_id.to_string() == contract.id {
-                contract.code_hash = item.code_hash;
-                break;
-            }
-        }
-
         Ok(contract)
     }
 
@@ -620,10 +1363,25 @@ pub trait TestInit: serde::Serialize {
         sender: Option<&str>,
         backend: Option<&str>,
         name: Option<&str>,
+    ) -> Result<NetContract> {
+        self.init_with_funds(contract, sender, backend, name, &[])
+    }
+
+    ///
+    /// Same as [TestInit::init] sending coins to the contract along with the message
+    ///
+    #[cfg(feature = "helpers")]
+    fn init_with_funds<Contract: Deployable>(
+        &self,
+        contract: &mut Contract,
+        sender: Option<&str>,
+        backend: Option<&str>,
+        name: Option<&str>,
+        funds: &[TxCoin],
     ) -> Result<NetContract> {
         use crate::utils::generate_label;
 
-        let info = init_cache(
+        let info = init_cache_with_funds(
             self,
             contract.file(),
             generate_label(10).as_str(),
@@ -632,6 +1390,7 @@ pub trait TestInit: serde::Serialize {
             GAS.into(),
             Some(backend.unwrap_or_else(|| contract.backend())),
             name,
+            funds,
         )?;
 
         contract.set_info(&info);
@@ -639,15 +1398,192 @@ pub trait TestInit: serde::Serialize {
     }
 }
 
+///
+/// Instantiates an already stored contract
+///
+/// # Arguments
+///
+/// * `msg` - Contract's init message
+/// * 'contract' - The stored contract, its code id and code hash are used
+/// * 'label' - The contract label, see [crate::utils::unique_label]
+/// * 'sender' - Msg sender
+/// * 'gas' - Gas price to use, defaults to 10000000
+/// * 'backend' - Keyring backend defaults to none
+///
 pub fn test_init<Message: serde::Serialize>(
     msg: Message,
     contract: &NetContract,
     label: &str,
-    sender: &str,
+    sender: impl AsRef<str>,
+    gas: Option<&str>,
+    backend: Option<&str>,
+) -> Result<TxQuery> {
+    test_init_with_funds(msg, contract, label, sender, gas, backend, &[])
+}
+
+///
+/// Same as [test_init] sending coins to the contract along with the init message
+///
+#[instrument(
+    name = "instantiate",
+    skip_all,
+    fields(
+        label = %label,
+        code_id = %contract.id,
+        txhash = Empty,
+        gas_used = Empty,
+        duration_ms = Empty
+    )
+)]
+pub fn test_init_with_funds<Message: serde::Serialize>(
+    msg: Message,
+    contract: &NetContract,
+    label: &str,
+    sender: impl AsRef<str>,
     gas: Option<&str>,
     backend: Option<&str>,
+    funds: &[TxCoin],
 ) -> Result<TxQuery> {
-    let tx = instantiate_contract(contract, msg, label, sender, gas, backend)?;
+    let sender = sender.as_ref();
+    let _timer = time_span();
+    instantiate_contract(contract, msg, label, sender, gas, backend, funds)
+}
+
+///
+/// Whether the installed secretd has `instantiate2`, secretd builds follow the chain they target
+///
+pub fn instantiate2_supported() -> bool {
+    network_config()
+        .command(["tx", "compute", "--help"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains("instantiate2"))
+        .unwrap_or(false)
+}
+
+///
+/// Instantiates a contract at an address known beforehand, so factories and deployments can
+/// reference contracts that don't exist yet
+///
+/// # Arguments
+///
+/// * 'msg' - The init msg to serialize
+/// * 'contract' - The stored contract, its code id and code hash are used
+/// * 'label' - The contract label
+/// * 'salt' - Salt the address is derived from, between 1 and 64 bytes
+/// * 'sender' - Msg sender, the address is derived from it too
+/// * 'gas' - Gas price to use, defaults to 10000000
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn instantiate2<Message: serde::Serialize>(
+    msg: Message,
+    contract: &NetContract,
+    label: &str,
+    salt: &[u8],
+    sender: impl AsRef<str>,
+    gas: Option<&str>,
+    backend: Option<&str>,
+) -> Result<NetContract> {
+    let sender = sender.as_ref();
+    if !instantiate2_supported() {
+        return Err(serde::de::Error::custom(
+            "instantiate2 is not supported by this secretd or chain",
+        ));
+    }
+    let address = predict_contract_address(
+        COMPUTE_MODULE,
+        &contract.code_hash,
+        &account_address(sender)?,
+        salt,
+    )?;
+    let message = serde_json::to_string(&msg)?;
+    let salt: String = salt.iter().map(|b| format!("{:02x}", b)).collect();
+
+    let mut command = vec![
+        "tx",
+        "compute",
+        "instantiate2",
+        &contract.id,
+        &message,
+        &salt,
+        "--hex",
+        "--from",
+        sender,
+        "--label",
+        label,
+        "--gas",
+        gas.unwrap_or(STORE_GAS),
+    ];
+
+    if let Some(backend) = backend {
+        command.push("--keyring-backend");
+        command.push(backend);
+    }
+
+    command.extend(code_hash_args(contract));
+
+    command.push("-y");
+
+    let tx = broadcast(command, None)?;
+    let tx = query_hash(tx.txhash)?;
+    if tx.contract_address().as_deref() != Some(address.as_str()) {
+        return Err(serde::de::Error::custom(format!(
+            "{} instantiated {:?} instead of the predicted {}",
+            tx.txhash,
+            tx.contract_address(),
+            address
+        )));
+    }
+    let mut contract = contract.clone();
+    contract.address = address;
+    contract.label = label.to_string();
+    Ok(contract)
+}
+
+///
+/// Migrates a contract to new code, the sender must be the contract's admin
+///
+/// # Arguments
+///
+/// * 'contract' - The contract to migrate
+/// * 'code_id' - The code to migrate to
+/// * 'msg' - The migrate msg to serialize
+/// * 'sender' - Msg sender
+/// * 'gas' - Gas price to use, defaults to 10000000
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn migrate_contract<Migrate: serde::Serialize>(
+    contract: &NetContract,
+    code_id: u64,
+    msg: &Migrate,
+    sender: impl AsRef<str>,
+    gas: Option<&str>,
+    backend: Option<&str>,
+) -> Result<TxQuery> {
+    let sender = sender.as_ref();
+    let message = serde_json::to_string(msg)?;
+    let code_id = code_id.to_string();
+
+    let mut command = vec![
+        "tx",
+        "compute",
+        "migrate",
+        &contract.address,
+        &code_id,
+        &message,
+        "--from",
+        sender,
+        "--gas",
+        gas.unwrap_or(STORE_GAS),
+    ];
+
+    if let Some(backend) = backend {
+        command.push("--keyring-backend");
+        command.push(backend);
+    }
+
+    command.push("-y");
+
+    let tx = broadcast(command, None)?;
     query_hash(tx.txhash)
 }
 
@@ -656,38 +1592,56 @@ pub fn init_cache<Message: serde::Serialize>(
     msg: Message,
     contract_file: &str,
     label: &str,
-    sender: &str,
+    sender: impl AsRef<str>,
     store_gas: Option<&str>,
     init_gas: Option<&str>,
     backend: Option<&str>,
     name: Option<&str>,
 ) -> Result<NetContract> {
+    init_cache_with_funds(
+        msg,
+        contract_file,
+        label,
+        sender,
+        store_gas,
+        init_gas,
+        backend,
+        name,
+        &[],
+    )
+}
+
+///
+/// Same as [init_cache] sending coins to the contract along with the init message when it
+/// isn't cached yet
+///
+#[allow(clippy::too_many_arguments)]
+pub fn init_cache_with_funds<Message: serde::Serialize>(
+    msg: Message,
+    contract_file: &str,
+    label: &str,
+    sender: impl AsRef<str>,
+    store_gas: Option<&str>,
+    init_gas: Option<&str>,
+    backend: Option<&str>,
+    name: Option<&str>,
+    funds: &[TxCoin],
+) -> Result<NetContract> {
+    let sender = sender.as_ref();
     match load_cached_contract(name) {
         Ok(c) => Ok(c),
         _ => {
-            let store_response =
-                store_contract(contract_file, Option::from(sender), store_gas, backend)?;
-            let store_query = query_hash(store_response.txhash)?;
-            let mut contract = NetContract {
-                label: label.to_string(),
-                id: "".to_string(),
-                address: "".to_string(),
-                code_hash: "".to_string(),
-            };
-            // Look for the code ID
-            for attribute in &store_query.logs[0].events[0].attributes {
-                if attribute.msg_key == "code_id" {
-                    contract.id = attribute.value.clone();
-                    break;
-                }
-            }
-            let init_query = test_init(&msg, &contract, label, sender, init_gas, backend)?;
+            let code = store_and_confirm(contract_file, sender, None, store_gas, backend)?;
+            let mut contract =
+                NetContract::new(label, code.code_id.to_string(), "", code.code_hash);
+            let init_query =
+                test_init_with_funds(&msg, &contract, label, sender, init_gas, backend, funds)?;
 
             if init_query.raw_log.contains("failed to execute message") {
-                println!(
+                print_warning(&format!(
                     "Tx Hash (call secretd q compute tx <hash> to see encrypted error) {}",
                     &init_query.txhash
-                );
+                ));
             }
 
             // Look for the contract's address
@@ -697,22 +1651,11 @@ pub fn init_cache<Message: serde::Serialize>(
                     break;
                 }
             }
-            // Look for the contract's code hash
-            let listed_contracts = list_code()?;
-
-            for item in listed_contracts {
-                if item.code_id.to_string() == contract.id {
-                    contract.code_hash = item.code_hash;
-                    break;
-                }
-            }
-
-            println!("{}", "Deployed contract".on_green());
-            print_contract(&contract);
+            report(|reporter| reporter.contract("Deployed contract", &contract));
 
             match name {
                 Some(n) => save_contract(n, &contract),
-                None    => println!("This contract deployment will not be cached because a name was not provided upon instantiation."),
+                None    => print_warning("This contract deployment will not be cached because a name was not provided upon instantiation."),
             }
             Ok(contract)
         }
@@ -734,15 +1677,31 @@ pub trait TestHandle: serde::Serialize {
     fn t_handle(
         &self,
         contract: &NetContract,
-        sender: &str,
+        sender: impl AsRef<str>,
         gas: Option<&str>,
         backend: Option<&str>,
         amount: Option<&str>,
     ) -> Result<TxCompute> {
+        let sender = sender.as_ref();
         let tx = execute_contract(contract, self, sender, gas, backend, amount, None)?;
+        compute_hash(tx.txhash)
+    }
 
-        let response: Result<TxCompute> = compute_hash(tx.txhash);
-        response
+    ///
+    /// Same as [TestHandle::t_handle] but decodes the contract's `HandleAnswer` envelope,
+    /// failing if its status isn't success
+    ///
+    fn t_handle_answer<Answer: DeserializeOwned>(
+        &self,
+        contract: &NetContract,
+        sender: impl AsRef<str>,
+        gas: Option<&str>,
+        backend: Option<&str>,
+        amount: Option<&str>,
+    ) -> Result<Answer> {
+        let sender = sender.as_ref();
+        self.t_handle(contract, sender, gas, backend, amount)?
+            .decode_handle_answer()
     }
 
     #[cfg(feature = "helpers")]
@@ -771,31 +1730,545 @@ pub trait TestHandle: serde::Serialize {
 ///
 /// Function equivalent of the TestHandle trait
 ///
+#[instrument(
+    name = "execute",
+    skip_all,
+    fields(
+        label = %contract.label,
+        address = %contract.address,
+        txhash = Empty,
+        gas_used = Empty,
+        duration_ms = Empty
+    )
+)]
 pub fn test_contract_handle<Message: serde::Serialize>(
     msg: &Message,
     contract: &NetContract,
-    sender: &str,
+    sender: impl AsRef<str>,
     gas: Option<&str>,
     backend: Option<&str>,
     amount: Option<&str>,
 ) -> Result<(TxCompute, TxQuery)> {
-    let tx = execute_contract(contract, msg, sender, gas, backend, amount, None)?;
-
-    let computed_response = compute_hash(tx.txhash.clone())?;
-    let queried_response = query_hash(tx.txhash)?;
+    let sender = sender.as_ref();
+    let _timer = time_span();
+    let queried_response = execute_contract(contract, msg, sender, gas, backend, amount, None)?;
+    let computed_response = compute_hash(queried_response.txhash.clone())?;
     if queried_response
         .raw_log
         .contains("failed to execute message")
     {
-        println!("Raw ;pg {}", &queried_response.raw_log);
-        println!(
-            "Tx Hash (call secretd q compute tx <hash> to see encrypted error) {}",
-            &queried_response.txhash
-        );
+        print_warning(&format!(
+            "Raw log {}\nTx Hash (call secretd q compute tx <hash> to see encrypted error) {}",
+            &queried_response.raw_log, &queried_response.txhash
+        ));
     }
     Ok((computed_response, queried_response))
 }
 
+///
+/// Same as [test_contract_handle] sending several coins along with the message
+///
+pub fn test_contract_handle_with_funds<Message: serde::Serialize>(
+    msg: &Message,
+    contract: &NetContract,
+    sender: impl AsRef<str>,
+    gas: Option<&str>,
+    backend: Option<&str>,
+    funds: &[TxCoin],
+) -> Result<(TxCompute, TxQuery)> {
+    let sender = sender.as_ref();
+    let amount = coins_arg(funds);
+    let amount = if funds.is_empty() {
+        None
+    } else {
+        Some(amount.as_str())
+    };
+    test_contract_handle(msg, contract, sender, gas, backend, amount)
+}
+
+///
+/// Same as [test_contract_handle] but decodes the contract's returned data into `Response`
+///
+pub fn test_contract_handle_decoded<Message: serde::Serialize, Response: DeserializeOwned>(
+    msg: &Message,
+    contract: &NetContract,
+    sender: impl AsRef<str>,
+    gas: Option<&str>,
+    backend: Option<&str>,
+    amount: Option<&str>,
+) -> Result<(Response, TxQuery)> {
+    let sender = sender.as_ref();
+    let (computed_response, queried_response) =
+        test_contract_handle(msg, contract, sender, gas, backend, amount)?;
+    Ok((computed_response.decode_answer()?, queried_response))
+}
+
+///
+/// Wraps native SCRT into a deposit token such as sSCRT
+///
+/// # Arguments
+///
+/// * 'token' - The wrapped token contract
+/// * 'amount' - Amount of uscrt to deposit
+/// * 'sender' - Msg sender
+/// * 'gas' - Gas price to use, defaults to 8000000
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn wrap_scrt(
+    token: &NetContract,
+    amount: u128,
+    sender: impl AsRef<str>,
+    gas: Option<&str>,
+    backend: Option<&str>,
+) -> Result<(TxCompute, TxQuery)> {
+    let sender = sender.as_ref();
+    test_contract_handle(
+        &WrappedTokenMsg::deposit(),
+        token,
+        sender,
+        gas,
+        backend,
+        Some(&format!("{}uscrt", amount)),
+    )
+}
+
+///
+/// Redeems a deposit token such as sSCRT back into native SCRT
+///
+/// # Arguments
+///
+/// * 'token' - The wrapped token contract
+/// * 'amount' - Amount of tokens to burn
+/// * 'sender' - Msg sender
+/// * 'gas' - Gas price to use, defaults to 8000000
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn unwrap_scrt(
+    token: &NetContract,
+    amount: u128,
+    sender: impl AsRef<str>,
+    gas: Option<&str>,
+    backend: Option<&str>,
+) -> Result<(TxCompute, TxQuery)> {
+    let sender = sender.as_ref();
+    test_contract_handle(
+        &WrappedTokenMsg::redeem(amount),
+        token,
+        sender,
+        gas,
+        backend,
+        None,
+    )
+}
+
+///
+/// Sends native coins over IBC with the transfer module
+///
+/// # Arguments
+///
+/// * 'channel' - The local channel, e.g. channel-0
+/// * 'receiver' - Address on the remote chain
+/// * 'amount' - Coins to send, e.g. 100uscrt or an ibc/<HASH> denom
+/// * 'sender' - Msg sender
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn ibc_transfer(
+    channel: &str,
+    receiver: &str,
+    amount: &str,
+    sender: impl AsRef<str>,
+    backend: Option<&str>,
+) -> Result<TxQuery> {
+    let sender = sender.as_ref();
+    let mut command = vec![
+        "tx",
+        "ibc-transfer",
+        "transfer",
+        "transfer",
+        channel,
+        receiver,
+        amount,
+        "--from",
+        sender,
+        "-y",
+    ];
+
+    if let Some(backend) = backend {
+        command.push("--keyring-backend");
+        command.push(backend);
+    }
+
+    let tx = broadcast(command, None)?;
+    query_hash(tx.txhash)
+}
+
+///
+/// Registers an interchain account owned by the sender on the chain behind the connection
+///
+/// # Arguments
+///
+/// * 'connection' - The local connection to the host chain, e.g. connection-0
+/// * 'owner' - Msg sender, owns the account
+/// * 'version' - Channel version, defaults to what the controller picks
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn register_interchain_account(
+    connection: &str,
+    owner: &str,
+    version: Option<&str>,
+    backend: Option<&str>,
+) -> Result<TxQuery> {
+    let mut command = vec![
+        "tx",
+        "interchain-accounts",
+        "controller",
+        "register",
+        connection,
+        "--from",
+        owner,
+        "-y",
+    ];
+
+    if let Some(version) = version {
+        command.push("--version");
+        command.push(version);
+    }
+
+    if let Some(backend) = backend {
+        command.push("--keyring-backend");
+        command.push(backend);
+    }
+
+    let tx = broadcast(command, None)?;
+    query_hash(tx.txhash)
+}
+
+///
+/// Builds the ICA packet data executing the messages on the host chain, ready for
+/// [send_interchain_tx]. Runs offline
+///
+/// # Arguments
+///
+/// * 'msgs' - A proto JSON message with its `@type`, or a list of them
+/// * 'memo' - Memo of the host chain tx
+///
+pub fn interchain_packet_data(msgs: &Value, memo: Option<&str>) -> Result<Value> {
+    let msgs = serde_json::to_string(msgs)?;
+    let mut command = vec![
+        "tx",
+        "interchain-accounts",
+        "host",
+        "generate-packet-data",
+        &msgs,
+    ];
+
+    if let Some(memo) = memo {
+        command.push("--memo");
+        command.push(memo);
+    }
+
+    let output = network_config()
+        .command(command)
+        .output()
+        .map_err(serde_json::Error::io)?;
+    if !output.status.success() {
+        return Err(serde::de::Error::custom(format!(
+            "Could not generate the packet data: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    serde_json::from_slice(&output.stdout)
+}
+
+///
+/// Sends a tx through the owner's interchain account
+///
+/// # Arguments
+///
+/// * 'connection' - The local connection to the host chain, e.g. connection-0
+/// * 'packet' - Packet data from [interchain_packet_data]
+/// * 'owner' - Msg sender, owns the account
+/// * 'timeout' - Relative packet timeout in nanoseconds, defaults to the controller's
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn send_interchain_tx(
+    connection: &str,
+    packet: &Value,
+    owner: &str,
+    timeout: Option<u64>,
+    backend: Option<&str>,
+) -> Result<TxQuery> {
+    // secretd reads the packet data from a file
+    let packet_file = std::env::temp_dir().join(format!("ica_packet_{}.json", nanoid::nanoid!()));
+    std::fs::write(&packet_file, serde_json::to_vec(packet)?).map_err(serde_json::Error::io)?;
+    let packet_path = packet_file.to_string_lossy().to_string();
+    let timeout = timeout.map(|timeout| timeout.to_string());
+
+    let mut command = vec![
+        "tx",
+        "interchain-accounts",
+        "controller",
+        "send-tx",
+        connection,
+        &packet_path,
+        "--from",
+        owner,
+        "-y",
+    ];
+
+    if let Some(timeout) = &timeout {
+        command.push("--relative-packet-timeout");
+        command.push(timeout);
+    }
+
+    if let Some(backend) = backend {
+        command.push("--keyring-backend");
+        command.push(backend);
+    }
+
+    let result = broadcast(command, None);
+    let _ = std::fs::remove_file(&packet_file);
+    let tx = result?;
+    query_hash(tx.txhash)
+}
+
+///
+/// Address of the owner's interchain account on the host chain, fails until the ICA channel
+/// handshake completed
+///
+/// # Arguments
+///
+/// * 'owner' - Address of the account owner
+/// * 'connection' - The local connection to the host chain, e.g. connection-0
+///
+pub fn interchain_account(owner: &str, connection: &str) -> Result<String> {
+    let command = vec![
+        "query",
+        "interchain-accounts",
+        "controller",
+        "interchain-account",
+        owner,
+        connection,
+    ];
+    let account: InterchainAccount =
+        parse_output(secretcli_run(vec_str_to_vec_string(command), Some(0))?)?;
+    Ok(account.address)
+}
+
+///
+/// Params of the ICA controller or host module
+///
+/// # Arguments
+///
+/// * 'submodule' - `controller` or `host`
+///
+pub fn interchain_accounts_params(submodule: &str) -> Result<Value> {
+    let command = vec!["query", "interchain-accounts", submodule, "params"];
+    secretcli_run(vec_str_to_vec_string(command), None)
+}
+
+///
+/// Creates a vesting account holding coins that unlock until the end time, funded by the
+/// sender. The account must not exist yet
+///
+/// # Arguments
+///
+/// * 'to' - Address of the new account
+/// * 'amount' - Coins to vest, e.g. 1000000uscrt
+/// * 'end_time' - When the last coins unlock
+/// * 'delayed' - Unlock everything at the end time instead of continuously
+/// * 'sender' - Msg sender
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn create_vesting_account(
+    to: &str,
+    amount: &str,
+    end_time: DateTime<Utc>,
+    delayed: bool,
+    sender: impl AsRef<str>,
+    backend: Option<&str>,
+) -> Result<TxQuery> {
+    let sender = sender.as_ref();
+    let amount = coins_arg(&validate_funds(&parse_coins(amount)?)?);
+    let end_time = end_time.timestamp().to_string();
+    let mut command = vec![
+        "tx",
+        "vesting",
+        "create-vesting-account",
+        to,
+        &amount,
+        &end_time,
+        "--from",
+        sender,
+        "-y",
+    ];
+
+    if delayed {
+        command.push("--delayed");
+    }
+
+    if let Some(backend) = backend {
+        command.push("--keyring-backend");
+        command.push(backend);
+    }
+
+    let tx = broadcast(command, None)?;
+    query_hash(tx.txhash)
+}
+
+///
+/// Every entry of a listing, following `pagination.next_key` until the last page. The first
+/// page is queried as is, the next ones with `--offset` so listings that aren't paginated
+/// never see pagination flags.
+///
+/// # Arguments
+///
+/// * 'command' - The query, without pagination flags
+/// * 'field' - Field holding a page's entries, bare lists are taken as the whole listing
+///
+fn query_all_pages(command: Vec<&str>, field: &str) -> Result<Vec<Value>> {
+    let mut entries = vec![];
+    loop {
+        let offset = entries.len().to_string();
+        let mut args = command.clone();
+        if !entries.is_empty() {
+            args.extend(["--offset", &offset]);
+        }
+        let response = secretcli_run(vec_str_to_vec_string(args), None)?;
+        if !add_page(response, field, &mut entries)? {
+            return Ok(entries);
+        }
+    }
+}
+
+/// Adds the entries of a page of a listing, returns whether there are more pages
+fn add_page(mut response: Value, field: &str, entries: &mut Vec<Value>) -> Result<bool> {
+    let has_next = response
+        .pointer("/pagination/next_key")
+        .map_or(false, |key| !key.is_null() && key != "");
+    let page = match response {
+        Value::Array(page) => page,
+        _ => match response.get_mut(field).map(Value::take) {
+            Some(Value::Array(page)) => page,
+            // SDKs leave out empty lists
+            Some(Value::Null) | None if !has_next => vec![],
+            _ => {
+                return Err(serde::de::Error::custom(format!(
+                    "Unexpected page of {}: {}",
+                    field, response
+                )))
+            }
+        },
+    };
+    let empty = page.is_empty();
+    entries.extend(page);
+    Ok(has_next && !empty)
+}
+
+/// Unwraps responses newer SDKs nest under a field, e.g. `{"params":{..}}`
+fn unwrap_response(mut response: Value, field: &str) -> Value {
+    match response.get_mut(field) {
+        Some(inner) => inner.take(),
+        None => response,
+    }
+}
+
+///
+/// Params of the slashing module, e.g. how many blocks a validator may miss
+///
+pub fn slashing_params() -> Result<SlashingParams> {
+    let command = vec!["query", "slashing", "params"];
+    let response = secretcli_run(vec_str_to_vec_string(command), None)?;
+    parse_output(unwrap_response(response, "params"))
+}
+
+///
+/// Signing infos of every validator that ever signed a block
+///
+pub fn signing_infos() -> Result<Vec<ValidatorSigningInfo>> {
+    let command = vec!["query", "slashing", "signing-infos"];
+    query_all_pages(command, "info")?
+        .into_iter()
+        .map(parse_output)
+        .collect()
+}
+
+///
+/// Signing info of one validator
+///
+/// # Arguments
+///
+/// * 'consensus_address' - The validator's `secretvalcons1...` address
+///
+pub fn signing_info(consensus_address: &str) -> Result<ValidatorSigningInfo> {
+    signing_infos()?
+        .into_iter()
+        .find(|info| info.address == consensus_address)
+        .ok_or_else(|| {
+            serde::de::Error::custom(format!("No signing info for {}", consensus_address))
+        })
+}
+
+///
+/// Every piece of evidence submitted, e.g. double signs
+///
+pub fn evidence() -> Result<Vec<Evidence>> {
+    let command = vec!["query", "evidence"];
+    query_all_pages(command, "evidence")?
+        .into_iter()
+        .map(parse_output)
+        .collect()
+}
+
+///
+/// Bank metadata of a denom, e.g. its display unit and exponent
+///
+/// # Arguments
+///
+/// * 'denom' - The base denom, e.g. `uscrt` or `ibc/27A6...`
+///
+pub fn denom_metadata(denom: &str) -> Result<DenomMetadata> {
+    let command = vec!["query", "bank", "denom-metadata", "--denom", denom];
+    let response = secretcli_run(vec_str_to_vec_string(command), None)?;
+    parse_output(unwrap_response(response, "metadata"))
+}
+
+///
+/// Bank metadata of every denom that has any
+///
+pub fn denoms_metadata() -> Result<Vec<DenomMetadata>> {
+    let command = vec!["query", "bank", "denom-metadata"];
+    query_all_pages(command, "metadatas")?
+        .into_iter()
+        .map(parse_output)
+        .collect()
+}
+
+///
+/// Total supply of every denom on chain, IBC vouchers included
+///
+pub fn total_supply() -> Result<Vec<TxCoin>> {
+    let command = vec!["query", "bank", "total"];
+    serde_json::from_value(Value::Array(query_all_pages(command, "supply")?))
+}
+
+///
+/// Total supply of one denom, zero if none was ever minted
+///
+/// # Arguments
+///
+/// * 'denom' - The base denom, e.g. `uscrt` or `ibc/27A6...`
+///
+pub fn supply_of(denom: &str) -> Result<TxCoin> {
+    let command = vec!["query", "bank", "total", "--denom", denom];
+    let mut response = secretcli_run(vec_str_to_vec_string(command), None)?;
+    // Newer SDKs nest the coin under `amount`, older ones answer with the coin itself
+    if response.get("amount").map_or(false, Value::is_object) {
+        response = unwrap_response(response, "amount");
+    }
+    serde_json::from_value(response)
+}
+
 ///
 /// Trait that allows contract query enums to be used in test scripts
 ///
@@ -816,14 +2289,14 @@ pub fn load_cached_contract(contract_name: Option<&str>) -> std::io::Result<NetC
             let file = File::open(cached_contract_path)?;
             let reader = BufReader::new(file);
             let contract: NetContract = serde_json::from_reader(reader)?;
-            println!("Using cached contract");
+            report(|reporter| reporter.contract("Using cached contract", &contract));
             Ok(contract)
         }
         _ => Err(Error::new(ErrorKind::Other, "No cached contract found")),
     }
 }
 
-const CONTRACT_CACHE_DIR: &str = "../cached_contracts/";
+pub(crate) const CONTRACT_CACHE_DIR: &str = "../cached_contracts/";
 pub fn save_contract(name: &str, contract: &NetContract) {
     let dir_exists: bool = Path::new(CONTRACT_CACHE_DIR).is_dir();
     if !dir_exists {
@@ -832,4 +2305,83 @@ pub fn save_contract(name: &str, contract: &NetContract) {
     let cached_contract_path: &str = &(CONTRACT_CACHE_DIR.to_owned() + name);
     let file = File::create(cached_contract_path).unwrap();
     let _serialized_contract = serde_json::to_writer(file, contract);
+    if let Err(err) = record_contract(name, contract) {
+        print_warning(&format!(
+            "Could not add {} to the address book: {}",
+            name, err
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_add_page() {
+        let mut entries = vec![];
+        let first = json!({ "code_infos": [1, 2], "pagination": { "next_key": "AAE=" } });
+        assert!(add_page(first, "code_infos", &mut entries).unwrap());
+        let last = json!({ "code_infos": [3], "pagination": { "next_key": null } });
+        assert!(!add_page(last, "code_infos", &mut entries).unwrap());
+        assert_eq!(entries, vec![json!(1), json!(2), json!(3)]);
+
+        // unpaginated listings and empty ones are single pages
+        assert!(!add_page(json!([4]), "code_infos", &mut entries).unwrap());
+        assert!(!add_page(json!({ "pagination": {} }), "code_infos", &mut entries).unwrap());
+        assert_eq!(entries.len(), 4);
+        // a page without entries but a next key isn't taken as the end
+        assert!(add_page(
+            json!({ "pagination": { "next_key": "AAE=" } }),
+            "code_infos",
+            &mut entries
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_is_ready() {
+        assert!(is_ready(&json!({ "status": { "ready": true } })));
+        assert!(is_ready(&json!({ "ready": true, "version": "1.0" })));
+        assert!(!is_ready(&json!({ "status": { "ready": false } })));
+        assert!(!is_ready(&json!({ "ready": false })));
+        // contracts answering without a ready flag are up
+        assert!(is_ready(&json!({ "status": "ok" })));
+        assert!(is_ready(&json!({})));
+    }
+
+    #[test]
+    fn test_poll_until_ready() {
+        let no_wait = time::Duration::ZERO;
+        let mut answers = vec![
+            Err(serde::de::Error::custom("contract not found")),
+            Ok(json!({ "status": { "ready": false } })),
+            Ok(json!({ "status": { "ready": true } })),
+        ]
+        .into_iter();
+        let ready = poll_until_ready("secret1c", time::Duration::from_secs(60), no_wait, || {
+            answers.next().unwrap()
+        })
+        .unwrap();
+        assert_eq!(ready, json!({ "status": { "ready": true } }));
+        assert_eq!(answers.len(), 0);
+
+        // the last answer is in the timeout error
+        let mut polls = 0;
+        let err = poll_until_ready("secret1c", no_wait, no_wait, || {
+            polls += 1;
+            Ok(json!({ "ready": false }))
+        })
+        .unwrap_err()
+        .to_string();
+        assert_eq!(polls, 1);
+        assert!(err.contains("secret1c was not ready"));
+        assert!(err.contains(r#"{"ready":false}"#));
+        let err = poll_until_ready("secret1c", no_wait, no_wait, || {
+            Err(serde::de::Error::custom("connection refused"))
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("connection refused"));
+    }
 }
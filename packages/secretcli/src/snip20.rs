@@ -0,0 +1,1204 @@
+use crate::{
+    cli_types::{NetContract, Permit, TxCoin, TxCompute, TxQuery},
+    events::{ContractEvent, EventIndexer},
+    secretcli::{account_address, query, store_and_confirm, test_contract_handle, test_init},
+    utils::{generate_label, unique_label},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Result, Value};
+use std::{path::Path, process::Command, sync::OnceLock};
+
+/// Where the fixture looks for the reference SNIP-20 wasm, overridable with `SNIP20_WASM`
+pub const SNIP20_WASM: &str = "./compiled/snip20.wasm.gz";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InitialBalance {
+    pub address: String,
+    pub amount: String,
+}
+
+/// The wasm at `file`, downloaded first if it's missing
+fn resolve_wasm(file: String, download_url: Option<&str>) -> Result<String> {
+    if Path::new(&file).exists() {
+        return Ok(file);
+    }
+    let url = download_url
+        .ok_or_else(|| serde::de::Error::custom(format!("Contract wasm not found at {}", file)))?;
+    let status = Command::new("curl")
+        .args(["-sSfL", "--create-dirs", "-o", file.as_str(), url])
+        .status()
+        .map_err(serde::de::Error::custom)?;
+    if !status.success() {
+        return Err(serde::de::Error::custom(format!(
+            "Could not download {}",
+            url
+        )));
+    }
+    Ok(file)
+}
+
+///
+/// Settings for the receiver [deploy_receiver] instantiates. No receiver ships with this
+/// crate, `wasm` is the contract whose receiver is tested. By default it's instantiated with
+/// the token's address and code hash so it can register itself with the token.
+///
+#[derive(Debug, Clone)]
+pub struct ReceiverFixture {
+    /// Local wasm of the receiver
+    pub wasm: String,
+    /// Downloaded to `wasm` if the file doesn't exist yet
+    pub download_url: Option<String>,
+    /// Instantiate message, defaults to `{"snip20": {"address", "code_hash"}}` of the token
+    pub init_msg: Option<Value>,
+}
+
+impl ReceiverFixture {
+    pub fn new(wasm: impl Into<String>) -> Self {
+        ReceiverFixture {
+            wasm: wasm.into(),
+            download_url: None,
+            init_msg: None,
+        }
+    }
+
+    /// Resolves the wasm file, downloading it first if needed
+    pub fn wasm_file(&self) -> Result<String> {
+        resolve_wasm(self.wasm.clone(), self.download_url.as_deref())
+    }
+
+    pub fn init_msg(&self, token: &Snip20) -> Value {
+        self.init_msg.clone().unwrap_or_else(|| {
+            json!({ "snip20": {
+                "address": token.contract.address,
+                "code_hash": token.contract.code_hash,
+            } })
+        })
+    }
+}
+
+///
+/// Settings for the token [deploy_snip20] instantiates
+///
+#[derive(Debug, Clone)]
+pub struct Snip20Fixture {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    /// Local wasm of the reference SNIP-20, defaults to `SNIP20_WASM`
+    pub wasm: Option<String>,
+    /// Downloaded to `wasm` if the file doesn't exist yet
+    pub download_url: Option<String>,
+}
+
+impl Default for Snip20Fixture {
+    fn default() -> Self {
+        Snip20Fixture {
+            name: "Test Token".to_string(),
+            symbol: "TEST".to_string(),
+            decimals: 6,
+            wasm: None,
+            download_url: None,
+        }
+    }
+}
+
+impl Snip20Fixture {
+    /// Resolves the wasm file, downloading it first if needed
+    pub fn wasm_file(&self) -> Result<String> {
+        let file = self
+            .wasm
+            .clone()
+            .or_else(|| std::env::var("SNIP20_WASM").ok())
+            .unwrap_or_else(|| SNIP20_WASM.to_string());
+        resolve_wasm(file, self.download_url.as_deref())
+    }
+
+    ///
+    /// The reference SNIP-20 instantiate message, with every feature enabled
+    ///
+    /// # Arguments
+    ///
+    /// * 'admin' - Token admin, also allowed to mint
+    /// * 'balances' - Accounts and amounts minted at instantiation
+    ///
+    pub fn init_msg(&self, admin: &str, balances: &[(&str, u128)]) -> Value {
+        let initial_balances: Vec<InitialBalance> = balances
+            .iter()
+            .map(|(address, amount)| InitialBalance {
+                address: address.to_string(),
+                amount: amount.to_string(),
+            })
+            .collect();
+        json!({
+            "name": self.name,
+            "admin": admin,
+            "symbol": self.symbol,
+            "decimals": self.decimals,
+            "initial_balances": initial_balances,
+            "prng_seed": base64::encode(generate_label(32)),
+            "config": {
+                "public_total_supply": true,
+                "enable_deposit": true,
+                "enable_redeem": true,
+                "enable_mint": true,
+                "enable_burn": true,
+            },
+        })
+    }
+}
+
+///
+/// One transfer of a [Snip20::batch_transfer]
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TransferAction {
+    pub recipient: String,
+    pub amount: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+impl TransferAction {
+    pub fn new(recipient: impl Into<String>, amount: u128) -> Self {
+        TransferAction {
+            recipient: recipient.into(),
+            amount: amount.to_string(),
+            memo: None,
+        }
+    }
+
+    pub fn memo(mut self, memo: impl Into<String>) -> Self {
+        self.memo = Some(memo.into());
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Allowance {
+    pub spender: String,
+    pub owner: String,
+    pub allowance: String,
+    /// Expiration as seconds since epoch, `None` if it never expires
+    pub expiration: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GivenAllowance {
+    pub spender: String,
+    pub allowance: String,
+    pub expiration: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AllowancesGiven {
+    pub owner: String,
+    pub allowances: Vec<GivenAllowance>,
+    /// Total number of allowances, across all pages
+    pub count: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedAllowance {
+    pub owner: String,
+    pub allowance: String,
+    pub expiration: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AllowancesReceived {
+    pub spender: String,
+    pub allowances: Vec<ReceivedAllowance>,
+    /// Total number of allowances, across all pages
+    pub count: u32,
+}
+
+///
+/// How a private SNIP-20 query is authenticated
+///
+#[derive(Debug, Clone)]
+pub enum QueryAuth {
+    ViewingKey {
+        address: String,
+        key: String,
+    },
+    /// A SNIP-24 permit, see [crate::secretcli::create_permit]
+    Permit(Permit),
+}
+
+impl QueryAuth {
+    pub fn viewing_key(address: impl Into<String>, key: impl Into<String>) -> Self {
+        QueryAuth::ViewingKey {
+            address: address.into(),
+            key: key.into(),
+        }
+    }
+}
+
+///
+/// A transfer as listed by the legacy `transfer_history` query
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Tx {
+    pub id: u64,
+    pub from: String,
+    pub sender: String,
+    pub receiver: String,
+    pub coins: TxCoin,
+    pub memo: Option<String>,
+    pub block_time: Option<u64>,
+    pub block_height: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TxAction {
+    Transfer {
+        from: String,
+        sender: String,
+        recipient: String,
+    },
+    Mint {
+        minter: String,
+        recipient: String,
+    },
+    Burn {
+        burner: String,
+        owner: String,
+    },
+    Deposit {},
+    Redeem {},
+}
+
+///
+/// Any balance change, as listed by the `transaction_history` query
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RichTx {
+    pub id: u64,
+    pub action: TxAction,
+    pub coins: TxCoin,
+    pub memo: Option<String>,
+    pub block_time: u64,
+    pub block_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TransferHistory {
+    pub txs: Vec<Tx>,
+    /// Number of transfers across all pages, not returned by older tokens
+    pub total: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TransactionHistory {
+    pub txs: Vec<RichTx>,
+    /// Number of transactions across all pages, not returned by older tokens
+    pub total: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    /// `None` unless the token made its total supply public
+    pub total_supply: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TokenConfig {
+    pub public_total_supply: bool,
+    pub deposit_enabled: bool,
+    pub redeem_enabled: bool,
+    pub mint_enabled: bool,
+    pub burn_enabled: bool,
+}
+
+///
+/// Raw amount of a decimal amount, e.g. `"1.5"` with 6 decimals is 1500000
+///
+/// # Arguments
+///
+/// * 'human' - Amount with at most `decimals` fractional digits
+/// * 'decimals' - Decimals of the token
+///
+pub fn to_raw_amount(human: &str, decimals: u8) -> Result<u128> {
+    let invalid = || serde::de::Error::custom(format!("Invalid amount {}", human));
+    let (whole, fraction) = human.split_once('.').unwrap_or((human, ""));
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    if fraction.len() > decimals as usize {
+        return Err(serde::de::Error::custom(format!(
+            "{} has more than {} decimals",
+            human, decimals
+        )));
+    }
+    let digits = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+    digits.parse().map_err(|_| invalid())
+}
+
+///
+/// Decimal amount of a raw amount without trailing zeros, e.g. 1500000 with 6 decimals is
+/// `"1.5"`. Errors if a unit of the token doesn't fit in a u128.
+///
+pub fn to_human_amount(raw: u128, decimals: u8) -> Result<String> {
+    let unit = 10u128.checked_pow(decimals as u32).ok_or_else(|| {
+        serde::de::Error::custom(format!("{} decimals overflow a u128 amount", decimals))
+    })?;
+    let fraction = format!("{:0>width$}", raw % unit, width = decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+    Ok(if fraction.is_empty() {
+        (raw / unit).to_string()
+    } else {
+        format!("{}.{}", raw / unit, fraction)
+    })
+}
+
+///
+/// Client for a deployed SNIP-20
+///
+/// Set `memo` or `padding` to add them to the messages that accept them, e.g.
+/// `token.clone().memo("rent").transfer(..)`
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Snip20 {
+    pub contract: NetContract,
+    #[serde(skip)]
+    pub memo: Option<String>,
+    #[serde(skip)]
+    pub padding: Option<String>,
+    /// Fetched on first use, they don't change after instantiation
+    #[serde(skip)]
+    info: OnceLock<TokenInfo>,
+    #[serde(skip)]
+    config: OnceLock<TokenConfig>,
+}
+
+impl Snip20 {
+    pub fn new(contract: NetContract) -> Self {
+        Snip20 {
+            contract,
+            memo: None,
+            padding: None,
+            info: OnceLock::new(),
+            config: OnceLock::new(),
+        }
+    }
+
+    pub fn memo(mut self, memo: impl Into<String>) -> Self {
+        self.memo = Some(memo.into());
+        self
+    }
+
+    pub fn padding(mut self, padding: impl Into<String>) -> Self {
+        self.padding = Some(padding.into());
+        self
+    }
+
+    /// Wraps the fields in the message variant, adding the padding and, if the message
+    /// accepts one, the memo
+    fn msg(&self, variant: &str, mut fields: Value, with_memo: bool) -> Value {
+        if with_memo {
+            if let Some(memo) = &self.memo {
+                fields["memo"] = json!(memo);
+            }
+        }
+        if let Some(padding) = &self.padding {
+            fields["padding"] = json!(padding);
+        }
+        json!({ variant: fields })
+    }
+
+    fn execute(
+        &self,
+        msg: Value,
+        sender: impl AsRef<str>,
+        backend: Option<&str>,
+    ) -> Result<TxCompute> {
+        let (compute, _) = test_contract_handle(&msg, &self.contract, sender, None, backend, None)?;
+        Ok(compute)
+    }
+
+    fn transfer_msg(&self, recipient: &str, amount: u128) -> Value {
+        self.msg(
+            "transfer",
+            json!({ "recipient": recipient, "amount": amount.to_string() }),
+            true,
+        )
+    }
+
+    pub fn transfer(
+        &self,
+        recipient: &str,
+        amount: u128,
+        sender: impl AsRef<str>,
+        backend: Option<&str>,
+    ) -> Result<TxCompute> {
+        self.execute(self.transfer_msg(recipient, amount), sender, backend)
+    }
+
+    fn send_msg<Msg: Serialize>(
+        &self,
+        owner: Option<&str>,
+        recipient: &NetContract,
+        amount: u128,
+        msg: Option<&Msg>,
+    ) -> Result<Value> {
+        let msg = msg
+            .map(|msg| serde_json::to_vec(msg).map(base64::encode))
+            .transpose()?;
+        let mut fields = json!({
+            "recipient": recipient.address,
+            "recipient_code_hash": recipient.code_hash,
+            "amount": amount.to_string(),
+            "msg": msg,
+        });
+        Ok(match owner {
+            Some(owner) => {
+                fields["owner"] = json!(owner);
+                self.msg("send_from", fields, true)
+            }
+            None => self.msg("send", fields, true),
+        })
+    }
+
+    ///
+    /// Sends tokens to a contract, calling its receive handler with `msg`
+    ///
+    pub fn send<Msg: Serialize>(
+        &self,
+        recipient: &NetContract,
+        amount: u128,
+        msg: Option<&Msg>,
+        sender: impl AsRef<str>,
+        backend: Option<&str>,
+    ) -> Result<TxCompute> {
+        self.execute(
+            self.send_msg(None, recipient, amount, msg)?,
+            sender,
+            backend,
+        )
+    }
+
+    pub fn mint(
+        &self,
+        recipient: &str,
+        amount: u128,
+        sender: impl AsRef<str>,
+        backend: Option<&str>,
+    ) -> Result<TxCompute> {
+        self.execute(
+            self.msg(
+                "mint",
+                json!({ "recipient": recipient, "amount": amount.to_string() }),
+                true,
+            ),
+            sender,
+            backend,
+        )
+    }
+
+    pub fn set_viewing_key(
+        &self,
+        key: &str,
+        sender: impl AsRef<str>,
+        backend: Option<&str>,
+    ) -> Result<TxCompute> {
+        self.execute(
+            self.msg("set_viewing_key", json!({ "key": key }), false),
+            sender,
+            backend,
+        )
+    }
+
+    ///
+    /// Registers the code hash the token calls `receive` on when tokens are sent to the
+    /// sender. Receiver contracts normally do this themselves on instantiation.
+    ///
+    pub fn register_receive(
+        &self,
+        code_hash: &str,
+        sender: impl AsRef<str>,
+        backend: Option<&str>,
+    ) -> Result<TxCompute> {
+        self.execute(
+            self.msg("register_receive", json!({ "code_hash": code_hash }), false),
+            sender,
+            backend,
+        )
+    }
+
+    fn allowance_msg(
+        &self,
+        variant: &str,
+        spender: &str,
+        amount: u128,
+        expiration: Option<u64>,
+    ) -> Value {
+        self.msg(
+            variant,
+            json!({
+                "spender": spender,
+                "amount": amount.to_string(),
+                "expiration": expiration,
+            }),
+            false,
+        )
+    }
+
+    ///
+    /// Lets `spender` transfer up to `amount` more of the sender's tokens
+    ///
+    /// # Arguments
+    ///
+    /// * 'spender' - Address allowed to spend
+    /// * 'amount' - Added to the current allowance
+    /// * 'expiration' - Seconds since epoch after which the allowance can't be used, replaces
+    ///   the current expiration if set
+    /// * 'sender' - Owner of the tokens
+    /// * 'backend' - Keyring backend defaults to none
+    ///
+    pub fn increase_allowance(
+        &self,
+        spender: &str,
+        amount: u128,
+        expiration: Option<u64>,
+        sender: impl AsRef<str>,
+        backend: Option<&str>,
+    ) -> Result<TxCompute> {
+        self.execute(
+            self.allowance_msg("increase_allowance", spender, amount, expiration),
+            sender,
+            backend,
+        )
+    }
+
+    ///
+    /// Lowers the allowance of `spender`, to zero if `amount` exceeds it
+    ///
+    pub fn decrease_allowance(
+        &self,
+        spender: &str,
+        amount: u128,
+        expiration: Option<u64>,
+        sender: impl AsRef<str>,
+        backend: Option<&str>,
+    ) -> Result<TxCompute> {
+        self.execute(
+            self.allowance_msg("decrease_allowance", spender, amount, expiration),
+            sender,
+            backend,
+        )
+    }
+
+    fn transfer_from_msg(&self, owner: &str, recipient: &str, amount: u128) -> Value {
+        self.msg(
+            "transfer_from",
+            json!({ "owner": owner, "recipient": recipient, "amount": amount.to_string() }),
+            true,
+        )
+    }
+
+    ///
+    /// Transfers `owner`'s tokens using the sender's allowance
+    ///
+    pub fn transfer_from(
+        &self,
+        owner: &str,
+        recipient: &str,
+        amount: u128,
+        sender: impl AsRef<str>,
+        backend: Option<&str>,
+    ) -> Result<TxCompute> {
+        self.execute(
+            self.transfer_from_msg(owner, recipient, amount),
+            sender,
+            backend,
+        )
+    }
+
+    ///
+    /// Sends `owner`'s tokens to a contract using the sender's allowance, calling its receive
+    /// handler with `msg`
+    ///
+    pub fn send_from<Msg: Serialize>(
+        &self,
+        owner: &str,
+        recipient: &NetContract,
+        amount: u128,
+        msg: Option<&Msg>,
+        sender: impl AsRef<str>,
+        backend: Option<&str>,
+    ) -> Result<TxCompute> {
+        self.execute(
+            self.send_msg(Some(owner), recipient, amount, msg)?,
+            sender,
+            backend,
+        )
+    }
+
+    fn batch_transfer_msg(&self, actions: &[TransferAction]) -> Result<Value> {
+        Ok(self.msg(
+            "batch_transfer",
+            json!({ "actions": serde_json::to_value(actions)? }),
+            false,
+        ))
+    }
+
+    ///
+    /// Several transfers in one message, each with its own optional memo
+    ///
+    pub fn batch_transfer(
+        &self,
+        actions: &[TransferAction],
+        sender: impl AsRef<str>,
+        backend: Option<&str>,
+    ) -> Result<TxCompute> {
+        self.execute(self.batch_transfer_msg(actions)?, sender, backend)
+    }
+
+    /// Queries the token and unwraps the response variant
+    fn query_answer<Response: serde::de::DeserializeOwned>(
+        &self,
+        msg: Value,
+        variant: &str,
+    ) -> Result<Response> {
+        let mut response: Value = query(&self.contract, msg, None)?;
+        match response.get_mut(variant) {
+            Some(answer) => serde_json::from_value(answer.take()),
+            None => Err(serde::de::Error::custom(format!(
+                "Unexpected {} response {}",
+                variant, response
+            ))),
+        }
+    }
+
+    /// Builds a private query, authenticated by viewing key or wrapped in `with_permit`
+    fn authenticated_msg(&self, variant: &str, mut fields: Value, auth: &QueryAuth) -> Value {
+        match auth {
+            QueryAuth::ViewingKey { address, key } => {
+                fields["address"] = json!(address);
+                fields["key"] = json!(key);
+                json!({ variant: fields })
+            }
+            QueryAuth::Permit(permit) => json!({ "with_permit": {
+                "permit": permit,
+                "query": { variant: fields },
+            } }),
+        }
+    }
+
+    ///
+    /// Name, symbol and decimals of the token, queried once and cached. The total supply is
+    /// as of that query.
+    ///
+    pub fn token_info(&self) -> Result<TokenInfo> {
+        if let Some(info) = self.info.get() {
+            return Ok(info.clone());
+        }
+        let info: TokenInfo = self.query_answer(json!({ "token_info": {} }), "token_info")?;
+        Ok(self.info.get_or_init(|| info).clone())
+    }
+
+    ///
+    /// Enabled features of the token, queried once and cached
+    ///
+    pub fn token_config(&self) -> Result<TokenConfig> {
+        if let Some(config) = self.config.get() {
+            return Ok(config.clone());
+        }
+        let config: TokenConfig =
+            self.query_answer(json!({ "token_config": {} }), "token_config")?;
+        Ok(self.config.get_or_init(|| config).clone())
+    }
+
+    pub fn decimals(&self) -> Result<u8> {
+        Ok(self.token_info()?.decimals)
+    }
+
+    ///
+    /// Raw amount of a decimal amount in the token's decimals, `to_raw("1.5")`
+    ///
+    pub fn to_raw(&self, human: &str) -> Result<u128> {
+        to_raw_amount(human, self.decimals()?)
+    }
+
+    ///
+    /// Decimal amount of a raw amount in the token's decimals
+    ///
+    pub fn to_human(&self, raw: u128) -> Result<String> {
+        to_human_amount(raw, self.decimals()?)
+    }
+
+    ///
+    /// Balance of `address`, which needs `key` set as its viewing key
+    ///
+    pub fn balance(&self, address: &str, key: &str) -> Result<u128> {
+        let response: Value = self.query_answer(
+            json!({ "balance": { "address": address, "key": key } }),
+            "balance",
+        )?;
+        response["amount"]
+            .as_str()
+            .ok_or_else(|| {
+                serde::de::Error::custom(format!("Unexpected balance response {}", response))
+            })?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+
+    ///
+    /// Allowance `owner` gave `spender`, `key` is the viewing key of either of them
+    ///
+    pub fn allowance(&self, owner: &str, spender: &str, key: &str) -> Result<Allowance> {
+        self.query_answer(
+            json!({ "allowance": { "owner": owner, "spender": spender, "key": key } }),
+            "allowance",
+        )
+    }
+
+    ///
+    /// Allowances `owner` gave, a page at a time
+    ///
+    /// # Arguments
+    ///
+    /// * 'owner' - Owner of the tokens
+    /// * 'key' - Viewing key of the owner
+    /// * 'page' - Page to return, starting at 0
+    /// * 'page_size' - Allowances per page
+    ///
+    pub fn allowances_given(
+        &self,
+        owner: &str,
+        key: &str,
+        page: Option<u32>,
+        page_size: u32,
+    ) -> Result<AllowancesGiven> {
+        self.query_answer(
+            json!({ "allowances_given": {
+                "owner": owner,
+                "key": key,
+                "page": page,
+                "page_size": page_size,
+            } }),
+            "allowances_given",
+        )
+    }
+
+    ///
+    /// Allowances `spender` received, a page at a time
+    ///
+    pub fn allowances_received(
+        &self,
+        spender: &str,
+        key: &str,
+        page: Option<u32>,
+        page_size: u32,
+    ) -> Result<AllowancesReceived> {
+        self.query_answer(
+            json!({ "allowances_received": {
+                "spender": spender,
+                "key": key,
+                "page": page,
+                "page_size": page_size,
+            } }),
+            "allowances_received",
+        )
+    }
+
+    ///
+    /// Transfers in and out of the authenticated account, newest first
+    ///
+    /// # Arguments
+    ///
+    /// * 'auth' - Viewing key or permit of the account
+    /// * 'page' - Page to return, starting at 0
+    /// * 'page_size' - Transfers per page
+    ///
+    pub fn transfer_history(
+        &self,
+        auth: &QueryAuth,
+        page: Option<u32>,
+        page_size: u32,
+    ) -> Result<TransferHistory> {
+        self.query_answer(
+            self.authenticated_msg(
+                "transfer_history",
+                json!({ "page": page, "page_size": page_size }),
+                auth,
+            ),
+            "transfer_history",
+        )
+    }
+
+    ///
+    /// Every balance change of the authenticated account, including mints, burns, deposits
+    /// and redeems, newest first
+    ///
+    pub fn transaction_history(
+        &self,
+        auth: &QueryAuth,
+        page: Option<u32>,
+        page_size: u32,
+    ) -> Result<TransactionHistory> {
+        self.query_answer(
+            self.authenticated_msg(
+                "transaction_history",
+                json!({ "page": page, "page_size": page_size }),
+                auth,
+            ),
+            "transaction_history",
+        )
+    }
+}
+
+///
+/// Deploys a fresh reference SNIP-20 and mints the given balances, the usual first step of a
+/// protocol integration test
+///
+/// # Arguments
+///
+/// * 'fixture' - Token settings and where to find the wasm
+/// * 'admin' - Key that stores, instantiates and administrates the token
+/// * 'balances' - Accounts and amounts to mint
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn deploy_snip20(
+    fixture: &Snip20Fixture,
+    admin: impl AsRef<str>,
+    balances: &[(&str, u128)],
+    backend: Option<&str>,
+) -> Result<Snip20> {
+    let admin = admin.as_ref();
+    let admin_address = account_address(admin)?;
+    let contract = instantiate(
+        &fixture.wasm_file()?,
+        fixture.init_msg(&admin_address, balances),
+        &fixture.symbol,
+        admin,
+        backend,
+    )?;
+    Ok(Snip20::new(contract))
+}
+
+///
+/// Deploys a receiver for the token, whose `receive` handler [send_to_receiver] then checks
+/// is called
+///
+/// # Arguments
+///
+/// * 'fixture' - Receiver wasm and instantiate message
+/// * 'token' - Token the receiver registers with
+/// * 'admin' - Key that stores and instantiates the receiver
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn deploy_receiver(
+    fixture: &ReceiverFixture,
+    token: &Snip20,
+    admin: impl AsRef<str>,
+    backend: Option<&str>,
+) -> Result<NetContract> {
+    let admin = admin.as_ref();
+    instantiate(
+        &fixture.wasm_file()?,
+        fixture.init_msg(token),
+        "snip20-receiver",
+        admin,
+        backend,
+    )
+}
+
+///
+/// Sends tokens to the receiver and returns the events its `receive` handler emitted, failing
+/// if the token didn't route the send to it
+///
+/// # Arguments
+///
+/// * 'token' - The token to send
+/// * 'receiver' - The receiving contract
+/// * 'amount' - Amount to send
+/// * 'msg' - Passed on to the receiver's `receive`
+/// * 'sender' - Key of the account sending the tokens
+/// * 'backend' - Keyring backend defaults to none
+///
+pub fn send_to_receiver<Msg: Serialize>(
+    token: &Snip20,
+    receiver: &NetContract,
+    amount: u128,
+    msg: Option<&Msg>,
+    sender: impl AsRef<str>,
+    backend: Option<&str>,
+) -> Result<Vec<ContractEvent>> {
+    let sender = sender.as_ref();
+    let (compute, query) = test_contract_handle(
+        &token.send_msg(None, receiver, amount, msg)?,
+        &token.contract,
+        sender,
+        None,
+        backend,
+        None,
+    )?;
+    let events = receiver_events(&query, Some(&compute), &receiver.address);
+    if events.is_empty() {
+        return Err(serde::de::Error::custom(format!(
+            "Send to {} did not call its receive handler, was the code hash registered?",
+            receiver.address
+        )));
+    }
+    Ok(events)
+}
+
+fn receiver_events(
+    query: &TxQuery,
+    compute: Option<&TxCompute>,
+    receiver: &str,
+) -> Vec<ContractEvent> {
+    let mut indexer = EventIndexer::default();
+    indexer.index_tx(query, compute);
+    indexer
+        .events_for(receiver)
+        .all()
+        .iter()
+        .map(|event| (*event).clone())
+        .collect()
+}
+
+/// Stores and instantiates a contract, returning it with its new address
+fn instantiate(
+    wasm: &str,
+    init_msg: Value,
+    label: &str,
+    admin: impl AsRef<str>,
+    backend: Option<&str>,
+) -> Result<NetContract> {
+    let admin = admin.as_ref();
+    let code = store_and_confirm(wasm, admin, None, None, backend)?;
+    let label = unique_label(label);
+    let mut contract = NetContract::new(&label, code.code_id.to_string(), "", code.code_hash);
+
+    let init_query = test_init(init_msg, &contract, &label, admin, None, backend)?;
+    contract.address = init_query.contract_address().ok_or_else(|| {
+        serde::de::Error::custom(format!(
+            "Instantiating {} failed: {}",
+            label, init_query.raw_log
+        ))
+    })?;
+    Ok(contract)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_init_msg() {
+        let msg = Snip20Fixture::default().init_msg("secret1admin", &[("secret1a", 100)]);
+        assert_eq!(msg["symbol"], "TEST");
+        assert_eq!(msg["admin"], "secret1admin");
+        assert_eq!(
+            msg["initial_balances"],
+            json!([{ "address": "secret1a", "amount": "100" }])
+        );
+        assert_eq!(msg["config"]["enable_mint"], true);
+    }
+
+    #[test]
+    fn test_memo_and_padding() {
+        let token = Snip20::new(NetContract::new("token", "1", "secret1token", "hash"));
+        assert_eq!(
+            token.transfer_msg("secret1a", 5),
+            json!({ "transfer": { "recipient": "secret1a", "amount": "5" } })
+        );
+
+        let token = token.memo("rent").padding("____");
+        assert_eq!(
+            token.transfer_from_msg("secret1owner", "secret1a", 5),
+            json!({ "transfer_from": {
+                "owner": "secret1owner",
+                "recipient": "secret1a",
+                "amount": "5",
+                "memo": "rent",
+                "padding": "____",
+            } })
+        );
+        // allowances take no memo
+        assert_eq!(
+            token.allowance_msg(
+                "increase_allowance",
+                "secret1spender",
+                10,
+                Some(1_700_000_000)
+            ),
+            json!({ "increase_allowance": {
+                "spender": "secret1spender",
+                "amount": "10",
+                "expiration": 1_700_000_000,
+                "padding": "____",
+            } })
+        );
+    }
+
+    #[test]
+    fn test_send_from_and_batch() {
+        let token = Snip20::new(NetContract::new("token", "1", "secret1token", "hash"));
+        let pool = NetContract::new("pool", "2", "secret1pool", "pool_hash");
+        let msg = token
+            .send_msg(
+                Some("secret1owner"),
+                &pool,
+                7,
+                Some(&json!({ "deposit": {} })),
+            )
+            .unwrap();
+        assert_eq!(msg["send_from"]["owner"], "secret1owner");
+        assert_eq!(msg["send_from"]["recipient_code_hash"], "pool_hash");
+        assert_eq!(msg["send_from"]["msg"], base64::encode(r#"{"deposit":{}}"#));
+
+        let actions = [
+            TransferAction::new("secret1a", 1),
+            TransferAction::new("secret1b", 2).memo("second"),
+        ];
+        assert_eq!(
+            token.batch_transfer_msg(&actions).unwrap(),
+            json!({ "batch_transfer": { "actions": [
+                { "recipient": "secret1a", "amount": "1" },
+                { "recipient": "secret1b", "amount": "2", "memo": "second" },
+            ] } })
+        );
+    }
+
+    #[test]
+    fn test_allowance_responses() {
+        let given: AllowancesGiven = serde_json::from_value(json!({
+            "owner": "secret1owner",
+            "allowances": [{ "spender": "secret1a", "allowance": "10", "expiration": null }],
+            "count": 1,
+        }))
+        .unwrap();
+        assert_eq!(given.allowances[0].allowance, "10");
+        assert_eq!(given.allowances[0].expiration, None);
+    }
+
+    #[test]
+    fn test_history_queries() {
+        let token = Snip20::new(NetContract::new("token", "1", "secret1token", "hash"));
+        let auth = QueryAuth::viewing_key("secret1a", "key");
+        assert_eq!(
+            token.authenticated_msg("transfer_history", json!({ "page_size": 10 }), &auth),
+            json!({ "transfer_history": {
+                "address": "secret1a",
+                "key": "key",
+                "page_size": 10,
+            } })
+        );
+
+        let history: TransactionHistory = serde_json::from_value(json!({
+            "txs": [
+                {
+                    "id": 2,
+                    "action": { "transfer": {
+                        "from": "secret1a",
+                        "sender": "secret1a",
+                        "recipient": "secret1b",
+                    } },
+                    "coins": { "denom": "TEST", "amount": "5" },
+                    "memo": null,
+                    "block_time": 1_700_000_000,
+                    "block_height": 12,
+                },
+                {
+                    "id": 1,
+                    "action": { "deposit": {} },
+                    "coins": { "denom": "uscrt", "amount": "100" },
+                    "memo": null,
+                    "block_time": 1_699_999_990,
+                    "block_height": 10,
+                },
+            ],
+            "total": 2,
+        }))
+        .unwrap();
+        assert_eq!(history.txs[0].coins.amount, 5);
+        assert_eq!(history.txs[1].action, TxAction::Deposit {});
+        assert!(matches!(
+            &history.txs[0].action,
+            TxAction::Transfer { recipient, .. } if recipient == "secret1b"
+        ));
+    }
+
+    #[test]
+    fn test_receiver_fixture() {
+        let token = Snip20::new(NetContract::new("token", "1", "secret1token", "hash"));
+        assert_eq!(
+            ReceiverFixture::new("receiver.wasm").init_msg(&token),
+            json!({ "snip20": { "address": "secret1token", "code_hash": "hash" } })
+        );
+        let custom = ReceiverFixture {
+            init_msg: Some(json!({ "count": 0 })),
+            ..ReceiverFixture::new("receiver.wasm")
+        };
+        assert_eq!(custom.init_msg(&token), json!({ "count": 0 }));
+        assert_eq!(
+            token
+                .clone()
+                .padding("__")
+                .msg("register_receive", json!({ "code_hash": "h" }), false),
+            json!({ "register_receive": { "code_hash": "h", "padding": "__" } })
+        );
+    }
+
+    #[test]
+    fn test_amounts() {
+        assert_eq!(to_raw_amount("1.5", 6).unwrap(), 1_500_000);
+        assert_eq!(to_raw_amount("2", 6).unwrap(), 2_000_000);
+        assert_eq!(to_raw_amount(".25", 2).unwrap(), 25);
+        assert_eq!(to_raw_amount("7.", 0).unwrap(), 7);
+        assert_eq!(to_raw_amount("0.000001", 6).unwrap(), 1);
+        assert!(to_raw_amount("0.0000001", 6).is_err());
+        assert!(to_raw_amount("-1", 6).is_err());
+        assert!(to_raw_amount("1.2.3", 6).is_err());
+        assert!(to_raw_amount(".", 6).is_err());
+        assert!(to_raw_amount("", 6).is_err());
+
+        assert_eq!(to_human_amount(1_500_000, 6).unwrap(), "1.5");
+        assert_eq!(to_human_amount(2_000_000, 6).unwrap(), "2");
+        assert_eq!(to_human_amount(1, 6).unwrap(), "0.000001");
+        assert_eq!(to_human_amount(42, 0).unwrap(), "42");
+        assert_eq!(to_human_amount(1, 38).unwrap(), format!("0.{:0>38}", 1));
+        assert!(to_human_amount(1, 39).is_err());
+        assert!(to_human_amount(1, u8::MAX).is_err());
+    }
+
+    #[test]
+    fn test_cached_token_info() {
+        let token = Snip20::new(NetContract::new("token", "1", "secret1token", "hash"));
+        let info = TokenInfo {
+            name: "Test Token".to_string(),
+            symbol: "TEST".to_string(),
+            decimals: 6,
+            total_supply: None,
+        };
+        token.info.set(info.clone()).unwrap();
+        // served from the cache, no node needed, and shared between test threads
+        std::thread::scope(|scope| {
+            scope.spawn(|| assert_eq!(token.token_info().unwrap(), info));
+            scope.spawn(|| assert_eq!(token.to_raw("0.5").unwrap(), 500_000));
+        });
+        assert_eq!(token.to_human(1_250_000).unwrap(), "1.25");
+    }
+
+    #[test]
+    fn test_missing_wasm() {
+        let fixture = Snip20Fixture {
+            wasm: Some("./does-not-exist.wasm".to_string()),
+            ..Default::default()
+        };
+        assert!(fixture.wasm_file().is_err());
+    }
+}
@@ -0,0 +1,323 @@
+use crate::{
+    cli_types::{TxCompute, TxQuery},
+    events::{ContractEvent, EventIndexer},
+    reporter::report,
+    secretcli::{compute_hash, query_hash},
+};
+use serde_json::Result;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallKind {
+    Execute,
+    /// The contract handling the reply to one of its submessages
+    Reply,
+}
+
+///
+/// One contract execution of a tx, with the calls it triggered
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallNode {
+    pub contract: String,
+    pub kind: CallKind,
+    /// Gas is only reported for the whole tx, so only the message a root call handles has it
+    pub gas_used: Option<u64>,
+    /// The plain `wasm` attributes and custom events the execution emitted
+    pub events: Vec<ContractEvent>,
+    pub children: Vec<CallNode>,
+}
+
+impl CallNode {
+    fn new(event: ContractEvent, kind: CallKind) -> Self {
+        CallNode {
+            contract: event.contract.clone(),
+            kind,
+            gas_used: None,
+            events: vec![event],
+            children: vec![],
+        }
+    }
+
+    /// Every contract executed under this call, this one included, in execution order
+    pub fn contracts(&self) -> Vec<&str> {
+        let mut contracts = vec![self.contract.as_str()];
+        for child in &self.children {
+            contracts.extend(child.contracts());
+        }
+        contracts
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        let kind = match self.kind {
+            CallKind::Execute => "execute",
+            CallKind::Reply => "reply",
+        };
+        write!(f, "{}{} {}", indent, kind, self.contract)?;
+        if let Some(gas) = self.gas_used {
+            write!(f, " (gas {})", gas)?;
+        }
+        writeln!(f)?;
+        for event in &self.events {
+            let prefix = match event.event_type.strip_prefix("wasm-") {
+                Some(custom) => format!("[{}] ", custom),
+                None => String::new(),
+            };
+            for (key, value) in &event.attributes {
+                writeln!(f, "{}  {}{} = {}", indent, prefix, key, value)?;
+            }
+        }
+        for child in &self.children {
+            child.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// The tree of contract executions behind a tx, one root per message.
+///
+/// It is rebuilt from the order of the contracts in the tx's wasm events, which is the order
+/// they were executed in. A contract that is already on the current call path is taken as
+/// the reply to its submessage, anything else as a call made by the previous contract.
+/// The logs don't tell a nested call apart from a message the parent sent next, so a call
+/// after a call that didn't reply shows up nested under it.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallTrace {
+    pub txhash: String,
+    pub height: u64,
+    pub gas_wanted: u64,
+    pub gas_used: u64,
+    pub roots: Vec<CallNode>,
+}
+
+impl CallTrace {
+    ///
+    /// Builds the trace of a tx, with decrypted attributes when the compute query is given.
+    /// Decrypted logs aren't split by message, so they are only used for single message txs.
+    ///
+    pub fn from_tx(tx: &TxQuery, decrypted: Option<&TxCompute>) -> Self {
+        let mut roots = vec![];
+        let decrypted = decrypted.filter(|_| tx.logs.len() <= 1);
+        for log in &tx.logs {
+            let message = TxQuery {
+                logs: vec![log.clone()],
+                ..tx.clone()
+            };
+            let mut indexer = EventIndexer::new(tx.height);
+            indexer.index_tx(&message, decrypted);
+            roots.extend(build_tree(indexer.events()));
+        }
+        if let [root] = roots.as_mut_slice() {
+            root.gas_used = Some(tx.gas_used);
+        }
+
+        CallTrace {
+            txhash: tx.txhash.clone(),
+            height: tx.height,
+            gas_wanted: tx.gas_wanted,
+            gas_used: tx.gas_used,
+            roots,
+        }
+    }
+}
+
+impl fmt::Display for CallTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "tx {} at height {}, gas used {} of {}",
+            self.txhash, self.height, self.gas_used, self.gas_wanted
+        )?;
+        for (index, root) in self.roots.iter().enumerate() {
+            writeln!(f, "message {}", index)?;
+            root.fmt_indented(f, 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn build_tree(events: &[ContractEvent]) -> Vec<CallNode> {
+    let mut roots: Vec<CallNode> = vec![];
+    // Child indexes from the root down to the contract currently executing
+    let mut path: Vec<usize> = vec![];
+
+    for event in events.iter().filter(|event| event.event_type == "wasm") {
+        if roots.is_empty() {
+            roots.push(CallNode::new(event.clone(), CallKind::Execute));
+            path.push(0);
+            continue;
+        }
+        let root = roots.last_mut().unwrap();
+        let contracts: Vec<String> = nodes_on_path(root, &path[1..])
+            .iter()
+            .map(|node| node.contract.clone())
+            .collect();
+        match contracts
+            .iter()
+            .rposition(|contract| *contract == event.contract)
+        {
+            Some(depth) => {
+                path.truncate(depth + 1);
+                node_at(root, &path[1..])
+                    .children
+                    .push(CallNode::new(event.clone(), CallKind::Reply));
+            }
+            None => {
+                let parent = node_at(root, &path[1..]);
+                parent
+                    .children
+                    .push(CallNode::new(event.clone(), CallKind::Execute));
+                path.push(parent.children.len() - 1);
+            }
+        }
+    }
+
+    // Custom events are merged by type, so they can only be attached to the contract's
+    // last execution
+    for event in events.iter().filter(|event| event.event_type != "wasm") {
+        match roots
+            .iter_mut()
+            .find_map(|root| last_call_of(root, &event.contract))
+        {
+            Some(node) => node.events.push(event.clone()),
+            None => roots.push(CallNode::new(event.clone(), CallKind::Execute)),
+        }
+    }
+    roots
+}
+
+fn nodes_on_path<'a>(root: &'a CallNode, path: &[usize]) -> Vec<&'a CallNode> {
+    let mut nodes = vec![root];
+    let mut node = root;
+    for &index in path {
+        node = &node.children[index];
+        nodes.push(node);
+    }
+    nodes
+}
+
+fn node_at<'a>(root: &'a mut CallNode, path: &[usize]) -> &'a mut CallNode {
+    path.iter()
+        .fold(root, |node, &index| &mut node.children[index])
+}
+
+fn last_call_of<'a>(node: &'a mut CallNode, contract: &str) -> Option<&'a mut CallNode> {
+    let child = node
+        .children
+        .iter()
+        .rposition(|child| child.contracts().contains(&contract));
+    match child {
+        Some(index) => last_call_of(&mut node.children[index], contract),
+        None if node.contract == contract => Some(node),
+        None => None,
+    }
+}
+
+///
+/// Trace of the contract calls behind a tx, decrypted when it was sent from a local key
+///
+/// # Arguments
+///
+/// * 'txhash' - Hash of the tx
+///
+pub fn trace_tx(txhash: &str) -> Result<CallTrace> {
+    let tx = query_hash(txhash.to_string())?;
+    let decrypted = compute_hash(txhash.to_string()).ok();
+    Ok(CallTrace::from_tx(&tx, decrypted.as_ref()))
+}
+
+///
+/// Prints the contract call tree of a tx and returns it for further inspection
+///
+/// # Arguments
+///
+/// * 'txhash' - Hash of the tx
+///
+pub fn dump_trace(txhash: &str) -> Result<CallTrace> {
+    let trace = trace_tx(txhash)?;
+    report(|reporter| reporter.info(&trace.to_string()));
+    Ok(trace)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TX: &str = r#"{
+        "height": "42",
+        "txhash": "A1B2C3",
+        "gas_wanted": "200000",
+        "gas_used": "150000",
+        "logs": [{
+            "msg_index": 0,
+            "log": "",
+            "events": [
+                {"type": "wasm", "attributes": [
+                    {"key": "contract_address", "value": "secret1router"},
+                    {"key": "action", "value": "swap"},
+                    {"key": "contract_address", "value": "secret1pair"},
+                    {"key": "action", "value": "swap"},
+                    {"key": "contract_address", "value": "secret1token"},
+                    {"key": "action", "value": "transfer"},
+                    {"key": "contract_address", "value": "secret1router"},
+                    {"key": "action", "value": "swap_reply"},
+                    {"key": "contract_address", "value": "secret1fees"},
+                    {"key": "action", "value": "collect"}
+                ]},
+                {"type": "wasm-swap", "attributes": [
+                    {"key": "contract_address", "value": "secret1pair"},
+                    {"key": "amount", "value": "100"}
+                ]}
+            ]
+        }],
+        "timestamp": "2022-01-01T00:00:00Z"
+    }"#;
+
+    #[test]
+    fn test_call_tree() {
+        let tx: TxQuery = serde_json::from_str(TX).unwrap();
+        let trace = CallTrace::from_tx(&tx, None);
+        assert_eq!(trace.roots.len(), 1);
+
+        let router = &trace.roots[0];
+        assert_eq!(router.kind, CallKind::Execute);
+        assert_eq!(router.gas_used, Some(150000));
+        assert_eq!(
+            router.contracts(),
+            vec![
+                "secret1router",
+                "secret1pair",
+                "secret1token",
+                "secret1router",
+                "secret1fees"
+            ]
+        );
+        assert_eq!(router.children.len(), 3);
+
+        let pair = &router.children[0];
+        assert_eq!(pair.events.len(), 2);
+        assert_eq!(pair.events[1].attribute("amount"), Some("100"));
+        assert_eq!(pair.children[0].contract, "secret1token");
+
+        // calls made from the reply stay under the router
+        let reply = &router.children[1];
+        assert_eq!(reply.kind, CallKind::Reply);
+        assert_eq!(reply.events[0].attribute("action"), Some("swap_reply"));
+        assert!(reply.children.is_empty());
+        assert_eq!(router.children[2].contract, "secret1fees");
+    }
+
+    #[test]
+    fn test_dump() {
+        let tx: TxQuery = serde_json::from_str(TX).unwrap();
+        let dump = CallTrace::from_tx(&tx, None).to_string();
+        assert!(dump.starts_with("tx A1B2C3 at height 42, gas used 150000 of 200000\nmessage 0\n"));
+        assert!(dump.contains("  execute secret1router (gas 150000)\n    action = swap\n"));
+        assert!(dump
+            .contains("    execute secret1pair\n      action = swap\n      [swap] amount = 100\n"));
+        assert!(dump.contains("    reply secret1router\n      action = swap_reply\n"));
+    }
+}
@@ -1,49 +1,58 @@
 use crate::{
     cli_types::{NetContract, TxQuery},
-    secretcli::{init_cache, test_contract_handle},
+    reporter::report,
+    secretcli::{init_cache, query, test_contract_handle},
 };
+use chrono::Utc;
 use colored::*;
 use serde::Serialize;
-use std::fmt::Display;
+use serde_json::{Result, Value};
+use std::{
+    any::type_name,
+    fmt::{self, Display},
+};
 
 pub fn print_header(header: &str) {
-    println!("{}", header.on_blue());
+    report(|reporter| reporter.header(header));
 }
 
 pub fn print_warning(warn: &str) {
-    println!("{}", warn.on_yellow());
+    report(|reporter| reporter.warning(warn));
 }
 
 pub fn print_contract(contract: &NetContract) {
-    println!(
-        "\tLabel: {}\n\tID: {}\n\tAddress: {}\n\tHash: {}",
-        contract.label, contract.id, contract.address, contract.code_hash
-    );
+    report(|reporter| reporter.contract("Contract", contract));
 }
 
+/// Reports the item as a value named after its type
 pub fn print_struct<Printable: Serialize>(item: Printable) {
-    println!("{}", serde_json::to_string_pretty(&item).unwrap());
+    let name = type_name::<Printable>();
+    let name = name.split('<').next().unwrap_or(name);
+    let name = name.rsplit("::").next().unwrap_or(name);
+    let value = serde_json::to_value(&item).unwrap();
+    report(|reporter| reporter.value(name, &value));
 }
 
 pub fn print_vec<Type: Display>(prefix: &str, vec: Vec<Type>) {
-    for e in vec.iter().take(1) {
-        print!("{}{}", prefix, e);
-    }
-    for e in vec.iter().skip(1) {
-        print!(", {}", e);
-    }
-    println!();
+    let items: Vec<String> = vec.iter().map(ToString::to_string).collect();
+    let line = if items.is_empty() {
+        String::new()
+    } else {
+        format!("{}{}", prefix, items.join(", "))
+    };
+    report(|reporter| reporter.info(&line));
 }
 
 pub fn test_contract_init_and_debug<Message: serde::Serialize>(
     msg: &Message,
     file: &str,
-    sender: &str,
+    sender: impl AsRef<str>,
     store_gas: Option<&str>,
     init_gas: Option<&str>,
     backend: Option<&str>,
     name: Option<&str>,
 ) {
+    let sender = sender.as_ref();
     let result = init_cache(
         &msg,
         file,
@@ -56,34 +65,34 @@ pub fn test_contract_init_and_debug<Message: serde::Serialize>(
     );
 
     match result {
-        Ok(contract) => {
-            println!("Contract address: {}", contract.address);
-            println!("Contract code hash: {}", contract.code_hash);
-        }
-        Err(err) => {
-            println!("{}", err);
-        }
+        Ok(contract) => print_contract(&contract),
+        Err(err) => print_warning(&err.to_string()),
     }
 }
 
 pub fn test_contract_handle_and_debug<Message: serde::Serialize>(
     msg: &Message,
     contract: &NetContract,
-    sender: &str,
+    sender: impl AsRef<str>,
     gas: Option<&str>,
     backend: Option<&str>,
     amount: Option<&str>,
 ) {
+    let sender = sender.as_ref();
     let result = test_contract_handle(&msg, contract, sender, gas, backend, amount);
 
     match result {
-        Ok((compute, query)) => {
-            println!("{} {}", query.gas_used, query.gas_wanted);
-            println!("ComputeResponse {}", compute.answers[0].input);
-        }
-        Err(err) => {
-            println!("{}", err);
-        }
+        Ok((compute, query)) => report(|reporter| {
+            reporter.value(
+                "Handle",
+                &serde_json::json!({
+                    "gas_used": query.gas_used,
+                    "gas_wanted": query.gas_wanted,
+                    "input": compute.answers.first().map(|answer| &answer.input),
+                }),
+            )
+        }),
+        Err(err) => print_warning(&err.to_string()),
     }
 }
 
@@ -91,6 +100,139 @@ pub fn assert_handle_failure(tx_query: TxQuery) -> bool {
     tx_query.raw_log.contains("failed to execute message")
 }
 
+/// A single difference between two JSON values
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonChange {
+    Added {
+        path: String,
+        value: Value,
+    },
+    Removed {
+        path: String,
+        value: Value,
+    },
+    Changed {
+        path: String,
+        before: Value,
+        after: Value,
+    },
+}
+
+impl Display for JsonChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonChange::Added { path, value } => {
+                write!(f, "{}", format!("+ {}: {}", path, value).green())
+            }
+            JsonChange::Removed { path, value } => {
+                write!(f, "{}", format!("- {}: {}", path, value).red())
+            }
+            JsonChange::Changed {
+                path,
+                before,
+                after,
+            } => write!(
+                f,
+                "{}",
+                format!("~ {}: {} -> {}", path, before, after).yellow()
+            ),
+        }
+    }
+}
+
+/// Structural diff of two JSON values, paths are written as `field.nested[index]`
+pub fn json_diff(before: &Value, after: &Value) -> Vec<JsonChange> {
+    let mut changes = vec![];
+    diff_at("", before, after, &mut changes);
+    changes
+}
+
+fn diff_at(path: &str, before: &Value, after: &Value, changes: &mut Vec<JsonChange>) {
+    let field_path = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            for (key, value) in before {
+                match after.get(key) {
+                    Some(new_value) => diff_at(&field_path(key), value, new_value, changes),
+                    None => changes.push(JsonChange::Removed {
+                        path: field_path(key),
+                        value: value.clone(),
+                    }),
+                }
+            }
+            for (key, value) in after {
+                if !before.contains_key(key) {
+                    changes.push(JsonChange::Added {
+                        path: field_path(key),
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(before), Value::Array(after)) => {
+            for i in 0..before.len().max(after.len()) {
+                let index_path = format!("{}[{}]", path, i);
+                match (before.get(i), after.get(i)) {
+                    (Some(old), Some(new)) => diff_at(&index_path, old, new, changes),
+                    (Some(old), None) => changes.push(JsonChange::Removed {
+                        path: index_path,
+                        value: old.clone(),
+                    }),
+                    (None, Some(new)) => changes.push(JsonChange::Added {
+                        path: index_path,
+                        value: new.clone(),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if before != after => changes.push(JsonChange::Changed {
+            path: path.to_string(),
+            before: before.clone(),
+            after: after.clone(),
+        }),
+        _ => {}
+    }
+}
+
+///
+/// Queries the contract, runs the given actions and queries it again, printing
+/// every change in the query result
+///
+/// # Arguments
+///
+/// * 'contract' - The contract to query
+/// * 'msg' - The query to run before and after
+/// * 'actions' - State transitions to observe
+///
+pub fn query_diff<Query: Serialize, F: FnOnce() -> Result<()>>(
+    contract: &NetContract,
+    msg: &Query,
+    actions: F,
+) -> Result<Vec<JsonChange>> {
+    let before: Value = query(contract, msg, None)?;
+    actions()?;
+    let after: Value = query(contract, msg, None)?;
+
+    let changes = json_diff(&before, &after);
+    report(|reporter| {
+        if changes.is_empty() {
+            reporter.header("No changes in query result");
+        }
+        for change in &changes {
+            reporter.info(&change.to_string());
+        }
+    });
+    Ok(changes)
+}
+
 pub const LABEL_ALPHABET: [char; 62] = [
     '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i',
     'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'A', 'B',
@@ -102,6 +244,19 @@ pub fn generate_label(size: usize) -> String {
     nanoid::nanoid!(size, &LABEL_ALPHABET)
 }
 
+///
+/// Labels must be unique on chain, this appends the current time and a random nonce to `base`
+/// so reruns of the same script don't collide
+///
+pub fn unique_label(base: &str) -> String {
+    format!(
+        "{}-{}-{}",
+        base,
+        Utc::now().timestamp_millis(),
+        generate_label(6)
+    )
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -111,4 +266,49 @@ mod test {
         let length: usize = 20;
         assert_eq!(length, generate_label(length).capacity())
     }
+
+    #[test]
+    fn test_unique_label() {
+        let label = unique_label("treasury");
+        assert!(label.starts_with("treasury-"));
+        assert_ne!(label, unique_label("treasury"));
+    }
+
+    #[test]
+    fn test_json_diff() {
+        let before = serde_json::json!({
+            "balance": { "amount": "100", "denom": "uscrt" },
+            "holders": ["a", "b"],
+            "paused": false
+        });
+        let after = serde_json::json!({
+            "balance": { "amount": "150", "denom": "uscrt" },
+            "holders": ["a"],
+            "minter": "c"
+        });
+
+        assert_eq!(json_diff(&before, &before), vec![]);
+        assert_eq!(
+            json_diff(&before, &after),
+            vec![
+                JsonChange::Changed {
+                    path: "balance.amount".to_string(),
+                    before: "100".into(),
+                    after: "150".into(),
+                },
+                JsonChange::Removed {
+                    path: "holders[1]".to_string(),
+                    value: "b".into(),
+                },
+                JsonChange::Removed {
+                    path: "paused".to_string(),
+                    value: false.into(),
+                },
+                JsonChange::Added {
+                    path: "minter".to_string(),
+                    value: "c".into(),
+                },
+            ]
+        );
+    }
 }
@@ -0,0 +1,171 @@
+use crate::{
+    cli_types::address_from_mnemonic, keyring::recover_key, network::network_config,
+    secretcli::account_address,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Result;
+use std::fmt;
+
+///
+/// A key with its address and keyring backend, looked up once instead of on every call.
+///
+/// Tx functions take their sender as `impl AsRef<str>`, so `&wallet` can be passed as the
+/// sender together with `wallet.backend()`.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Wallet {
+    name: String,
+    address: String,
+    backend: Option<String>,
+}
+
+impl Wallet {
+    ///
+    /// A wallet for a key whose address is already known, nothing is looked up
+    ///
+    pub fn new(name: impl Into<String>, address: impl Into<String>) -> Self {
+        Wallet {
+            name: name.into(),
+            address: address.into(),
+            backend: None,
+        }
+    }
+
+    ///
+    /// A wallet for a key in the active keyring, looking up its address
+    ///
+    pub fn from_keyring(name: &str) -> Result<Self> {
+        Ok(Self::new(name, account_address(name)?))
+    }
+
+    ///
+    /// Imports the mnemonic into the active keyring, unless the key is already there
+    ///
+    /// # Arguments
+    ///
+    /// * 'name' - Name of the key in the keyring
+    /// * 'mnemonic' - The key's mnemonic
+    ///
+    pub fn from_mnemonic(name: &str, mnemonic: &str) -> Result<Self> {
+        Self::from_mnemonic_with_backend(name, mnemonic, None)
+    }
+
+    ///
+    /// Like [Wallet::from_mnemonic] for a keyring backend other than the default
+    ///
+    pub fn from_mnemonic_with_backend(
+        name: &str,
+        mnemonic: &str,
+        backend: Option<&str>,
+    ) -> Result<Self> {
+        let address = address_from_mnemonic(mnemonic)?;
+        let mut args = network_config().keyring_args()?;
+        if let (true, Some(backend)) = (args.is_empty(), backend) {
+            args = vec!["--keyring-backend".to_string(), backend.to_string()];
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        if let Err(err) = recover_key(name, mnemonic, &args) {
+            // Importing fails if the name is taken, which is fine if it's this key
+            if account_address(name)? != address {
+                return Err(err);
+            }
+        }
+        let wallet = Self::new(name, address);
+        Ok(match backend {
+            Some(backend) => wallet.with_backend(backend),
+            None => wallet,
+        })
+    }
+
+    pub fn with_backend(mut self, backend: impl Into<String>) -> Self {
+        self.backend = Some(backend.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Keyring backend to pass along with the wallet, `None` for the default
+    pub fn backend(&self) -> Option<&str> {
+        self.backend.as_deref()
+    }
+}
+
+impl AsRef<str> for Wallet {
+    fn as_ref(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for Wallet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.address)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_wallet_display() {
+        let wallet = Wallet::new("alice", "secret1alice").with_backend("test");
+        assert_eq!(wallet.address(), "secret1alice");
+        assert_eq!(wallet.backend(), Some("test"));
+        assert_eq!(wallet.to_string(), "alice (secret1alice)");
+    }
+
+    #[cfg(feature = "sandbox")]
+    #[test]
+    fn test_wallet_as_sender() -> Result<()> {
+        use crate::{
+            cli_types::NetContract,
+            padding::handle_lengths,
+            sandbox::{activate, deactivate, Sandbox},
+            secretcli::{store_and_confirm, test_init},
+        };
+        use cosmwasm_std::{
+            to_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+        };
+        use secret_multi_test::ContractWrapper;
+
+        fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+            Ok(Response::new())
+        }
+        // Answers with the sender, so the test sees who signed
+        fn execute(_: DepsMut, _: Env, info: MessageInfo, _: Empty) -> StdResult<Response> {
+            Ok(Response::new().set_data(to_binary(info.sender.as_str())?))
+        }
+        fn query(_: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+            to_binary("")
+        }
+
+        activate(Sandbox::new().register(
+            "echo.wasm",
+            Box::new(ContractWrapper::new_with_empty(execute, instantiate, query)),
+        ));
+        let wallet = Wallet::new("alice", "secret1alice").with_backend("test");
+        let code = store_and_confirm("echo.wasm", &wallet, None, None, wallet.backend())?;
+        let mut contract = NetContract::new("echo", code.code_id.to_string(), "", code.code_hash);
+        let init = test_init(
+            &Empty {},
+            &contract,
+            "echo",
+            &wallet,
+            None,
+            wallet.backend(),
+        )?;
+        contract.address = init.contract_address().unwrap();
+
+        let lengths = handle_lengths(&contract, &[Empty {}], &wallet, wallet.backend())?;
+        // the sandbox signs with the key name, `"alice"` with its quotes
+        assert_eq!(lengths[0].len, wallet.name().len() + 2);
+        deactivate();
+        Ok(())
+    }
+}
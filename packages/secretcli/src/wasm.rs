@@ -0,0 +1,231 @@
+use crate::utils::{generate_label, print_warning};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::de::Error;
+use serde_json::Result;
+use std::{
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+const WASM_MAGIC: &[u8] = b"\0asm";
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+pub fn is_wasm(bytes: &[u8]) -> bool {
+    bytes.starts_with(WASM_MAGIC)
+}
+
+pub fn is_gzip(bytes: &[u8]) -> bool {
+    bytes.starts_with(GZIP_MAGIC)
+}
+
+///
+/// Names of the custom sections of a wasm module
+///
+pub fn custom_sections(wasm: &[u8]) -> Result<Vec<String>> {
+    if !is_wasm(wasm) || wasm.len() < 8 {
+        return Err(Error::custom("not a wasm module"));
+    }
+    let mut names = vec![];
+    let mut rest = &wasm[8..];
+    while !rest.is_empty() {
+        let id = rest[0];
+        rest = &rest[1..];
+        let size = read_leb128(&mut rest)? as usize;
+        if size > rest.len() {
+            return Err(Error::custom("truncated wasm section"));
+        }
+        let (mut section, next) = rest.split_at(size);
+        if id == 0 {
+            let len = read_leb128(&mut section)? as usize;
+            let name = section
+                .get(..len)
+                .ok_or_else(|| Error::custom("truncated wasm section name"))?;
+            names.push(String::from_utf8_lossy(name).to_string());
+        }
+        rest = next;
+    }
+    Ok(names)
+}
+
+fn read_leb128(bytes: &mut &[u8]) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes
+            .split_first()
+            .ok_or_else(|| Error::custom("truncated wasm module"))?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(Error::custom("invalid wasm length"))
+}
+
+///
+/// Whether the module went through the optimizer, which strips the names and debug info
+/// a plain cargo build keeps
+///
+pub fn is_optimized(wasm: &[u8]) -> Result<bool> {
+    Ok(!custom_sections(wasm)?
+        .iter()
+        .any(|name| name == "name" || name.starts_with(".debug")))
+}
+
+///
+/// Reads a wasm module, gzipped files are decompressed
+///
+pub fn read_module(path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    let bytes = fs::read(path).map_err(serde_json::Error::io)?;
+    if !is_gzip(&bytes) {
+        return Ok(bytes);
+    }
+    let mut wasm = vec![];
+    GzDecoder::new(bytes.as_slice())
+        .read_to_end(&mut wasm)
+        .map_err(serde_json::Error::io)?;
+    Ok(wasm)
+}
+
+pub fn gzip(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(vec![], Compression::best());
+    encoder.write_all(bytes).map_err(serde_json::Error::io)?;
+    encoder.finish().map_err(serde_json::Error::io)
+}
+
+///
+/// A file ready to be uploaded. Gzipped copies made by [prepare_upload] are deleted when it
+/// drops, keep it alive until the store tx was broadcast.
+///
+pub struct Upload {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl Upload {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Upload {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+///
+/// The file to upload for a contract. Already compressed files are uploaded as they are,
+/// wasm modules are gzipped into the temp directory when `compress` is set, secretd
+/// accepts both.
+///
+/// # Arguments
+///
+/// * 'contract' - Path to the `.wasm` or `.wasm.gz` file
+/// * 'compress' - Gzip plain wasm modules before uploading
+///
+pub fn prepare_upload(contract: &str, compress: bool) -> Result<Upload> {
+    let as_is = Upload {
+        path: PathBuf::from(contract),
+        temporary: false,
+    };
+    let bytes = fs::read(contract).map_err(serde_json::Error::io)?;
+    if is_gzip(&bytes) {
+        return Ok(as_is);
+    }
+    if !is_wasm(&bytes) {
+        return Err(Error::custom(format!("{} is not a wasm module", contract)));
+    }
+    if !is_optimized(&bytes)? {
+        print_warning(&format!(
+            "{} is not optimized, run it through the optimizer to cut the store fee",
+            contract
+        ));
+    }
+    if !compress {
+        return Ok(as_is);
+    }
+
+    let file_name = Path::new(contract)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "contract.wasm".to_string());
+    // Unique per call, parallel uploads of the same file must not write over each other
+    let compressed =
+        std::env::temp_dir().join(format!("secretcli-{}-{}.gz", generate_label(8), file_name));
+    let upload = Upload {
+        path: compressed,
+        temporary: true,
+    };
+    fs::write(&upload.path, gzip(&bytes)?).map_err(serde_json::Error::io)?;
+    Ok(upload)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn module(custom: &[&str]) -> Vec<u8> {
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        // empty type section
+        wasm.extend([1, 1, 0]);
+        for name in custom {
+            wasm.extend([0, name.len() as u8 + 3, name.len() as u8]);
+            wasm.extend(name.as_bytes());
+            wasm.extend([1, 2]);
+        }
+        wasm
+    }
+
+    #[test]
+    fn test_custom_sections() {
+        assert_eq!(
+            custom_sections(&module(&["name", ".debug_info"])).unwrap(),
+            vec!["name", ".debug_info"]
+        );
+        assert!(!is_optimized(&module(&["name"])).unwrap());
+        assert!(!is_optimized(&module(&[".debug_line"])).unwrap());
+        assert!(is_optimized(&module(&["producers"])).unwrap());
+        assert!(is_optimized(&module(&[])).unwrap());
+
+        let mut truncated = module(&["name"]);
+        truncated.pop();
+        assert!(custom_sections(&truncated).is_err());
+        assert!(custom_sections(b"not wasm").is_err());
+    }
+
+    #[test]
+    fn test_gzip() {
+        let wasm = module(&["producers"]);
+        let compressed = gzip(&wasm).unwrap();
+        assert!(is_gzip(&compressed));
+        assert!(!is_wasm(&compressed));
+
+        let mut decompressed = vec![];
+        GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, wasm);
+    }
+
+    #[test]
+    fn test_upload_cleanup() {
+        let wasm = std::env::temp_dir().join(format!("secretcli-{}.wasm", generate_label(8)));
+        fs::write(&wasm, module(&["producers"])).unwrap();
+        let contract = wasm.to_string_lossy().to_string();
+
+        let compressed = prepare_upload(&contract, true).unwrap();
+        let path = compressed.path().to_path_buf();
+        assert!(is_gzip(&fs::read(&path).unwrap()));
+        drop(compressed);
+        assert!(!path.exists());
+
+        // the contract itself is never deleted
+        drop(prepare_upload(&contract, false).unwrap());
+        assert!(wasm.exists());
+        fs::remove_file(&wasm).unwrap();
+    }
+}
@@ -1,3 +1,4 @@
+mod map;
 mod serialization;
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
@@ -162,6 +163,7 @@ criterion_group!(
 );
 criterion_main!(
     serialization::benches,
+    map::benches,
     //signed_int_key,
     //unsigned_int_key
 );
@@ -0,0 +1,54 @@
+use cosmwasm_std::testing::MockStorage;
+use criterion::{black_box, criterion_group, Criterion};
+
+use secret_storage_plus::Map;
+
+const TABLE: Map<(&str, u32), u64> = Map::new("config_table");
+const ENTRIES: u32 = 1000;
+
+fn bench_batch_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Map batch operations");
+
+    group.bench_function("save in a loop", |b| {
+        b.iter(|| {
+            let mut store = MockStorage::new();
+            for i in 0..ENTRIES {
+                TABLE.save(&mut store, ("fees", i), &(i as u64)).unwrap();
+            }
+            black_box(store);
+        });
+    });
+
+    group.bench_function("insert_many", |b| {
+        let values: Vec<u64> = (0..ENTRIES as u64).collect();
+        b.iter(|| {
+            let mut store = MockStorage::new();
+            TABLE
+                .insert_many(&mut store, (0..ENTRIES).map(|i| ("fees", i)).zip(&values))
+                .unwrap();
+            black_box(store);
+        });
+    });
+
+    group.bench_function("remove in a loop", |b| {
+        b.iter(|| {
+            let mut store = MockStorage::new();
+            for i in 0..ENTRIES {
+                TABLE.remove(&mut store, ("fees", i));
+            }
+            black_box(store);
+        });
+    });
+
+    group.bench_function("remove_many", |b| {
+        b.iter(|| {
+            let mut store = MockStorage::new();
+            TABLE.remove_many(&mut store, (0..ENTRIES).map(|i| ("fees", i)));
+            black_box(store);
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_batch_insert);
@@ -0,0 +1,124 @@
+//! `AddrKey` stores addresses in their canonical (fixed length) form instead of the
+//! variable length bech32 string, which is shorter and can't collide with the next key part.
+use cosmwasm_std::{Addr, Api, CanonicalAddr, StdResult};
+
+use crate::de::KeyDeserialize;
+use crate::keys::{Key, Prefixer, PrimaryKey};
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AddrKey(CanonicalAddr);
+
+impl AddrKey {
+    pub fn new(api: &dyn Api, addr: &Addr) -> StdResult<Self> {
+        Ok(AddrKey(api.addr_canonicalize(addr.as_str())?))
+    }
+
+    pub fn from_canonical(addr: CanonicalAddr) -> Self {
+        AddrKey(addr)
+    }
+
+    pub fn as_canonical(&self) -> &CanonicalAddr {
+        &self.0
+    }
+
+    /// Converts the stored key back to the human readable address
+    pub fn to_addr(&self, api: &dyn Api) -> StdResult<Addr> {
+        api.addr_humanize(&self.0)
+    }
+}
+
+impl From<CanonicalAddr> for AddrKey {
+    fn from(addr: CanonicalAddr) -> Self {
+        AddrKey(addr)
+    }
+}
+
+impl<'a> PrimaryKey<'a> for AddrKey {
+    type Prefix = ();
+    type SubPrefix = ();
+    type Suffix = Self;
+    type SuperSuffix = Self;
+
+    fn key(&self) -> Vec<Key> {
+        vec![Key::Ref(self.0.as_slice())]
+    }
+}
+
+impl<'a> Prefixer<'a> for AddrKey {
+    fn prefix(&self) -> Vec<Key> {
+        vec![Key::Ref(self.0.as_slice())]
+    }
+}
+
+impl KeyDeserialize for AddrKey {
+    type Output = AddrKey;
+
+    #[inline(always)]
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        Ok(AddrKey(CanonicalAddr::from(value)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Map;
+    use cosmwasm_std::testing::{MockApi, MockStorage};
+
+    const BALANCES: Map<AddrKey, u64> = Map::new("balances");
+    const ALLOWANCES: Map<(AddrKey, AddrKey), u64> = Map::new("allowances");
+
+    #[test]
+    fn keys_are_fixed_length() -> StdResult<()> {
+        let api = MockApi::default();
+        let alice = AddrKey::new(&api, &Addr::unchecked("alice"))?;
+        let bob = AddrKey::new(&api, &Addr::unchecked("bobbobbobbob"))?;
+
+        assert_eq!(alice.key()[0].as_ref().len(), bob.key()[0].as_ref().len());
+        assert_eq!(alice.to_addr(&api)?, Addr::unchecked("alice"));
+        Ok(())
+    }
+
+    #[test]
+    fn map_with_addr_keys() -> StdResult<()> {
+        let api = MockApi::default();
+        let mut store = MockStorage::new();
+        let alice = AddrKey::new(&api, &Addr::unchecked("alice"))?;
+        let bob = AddrKey::new(&api, &Addr::unchecked("bob"))?;
+
+        BALANCES.save(&mut store, alice.clone(), &10)?;
+        ALLOWANCES.save(&mut store, (alice.clone(), bob.clone()), &5)?;
+
+        assert_eq!(BALANCES.load(&store, alice.clone())?, 10);
+        assert_eq!(BALANCES.may_load(&store, bob.clone())?, None);
+        assert_eq!(ALLOWANCES.load(&store, (alice, bob))?, 5);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn read_back_as_addr() -> StdResult<()> {
+        use cosmwasm_std::Order;
+
+        let api = MockApi::default();
+        let mut store = MockStorage::new();
+        for name in ["alice", "bob", "carol"] {
+            BALANCES.save(&mut store, AddrKey::new(&api, &Addr::unchecked(name))?, &1)?;
+        }
+
+        let mut holders = BALANCES
+            .keys(&store, None, None, Order::Ascending)
+            .map(|k| k?.to_addr(&api))
+            .collect::<StdResult<Vec<_>>>()?;
+        holders.sort();
+        assert_eq!(
+            holders,
+            vec![
+                Addr::unchecked("alice"),
+                Addr::unchecked("bob"),
+                Addr::unchecked("carol")
+            ]
+        );
+        Ok(())
+    }
+}
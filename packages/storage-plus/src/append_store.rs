@@ -90,7 +90,7 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
     /// gets the element at pos if within bounds
     pub fn get_at(&self, storage: &dyn Storage, pos: u32) -> StdResult<T> {
         let len = self.get_len(storage)?;
-        if pos > len {
+        if pos >= len {
             return Err(StdError::generic_err("AppendStore access out of bounds"));
         }
         self.get_at_unchecked(storage, pos)
@@ -136,6 +136,7 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
     pub fn pop(&self, storage: &mut dyn Storage) -> StdResult<T> {
         if let Some(len) = self.get_len(storage)?.checked_sub(1) {
             let item = self.get_at_unchecked(storage, len);
+            self.remove_impl(storage, &len.to_be_bytes());
             self.set_len(storage, len);
             item
         } else {
@@ -154,7 +155,7 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
         let len = self.get_len(storage)?;
 
         if pos >= len {
-            return Err(StdError::generic_err("DequeStorage access out of bounds"));
+            return Err(StdError::generic_err("AppendStore access out of bounds"));
         }
         let item = self.get_at_unchecked(storage, pos);
 
@@ -162,9 +163,30 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
             let element_to_shift = self.get_at_unchecked(storage, i + 1)?;
             self.set_at_unchecked(storage, i, &element_to_shift)?;
         }
+        self.remove_impl(storage, &(len - 1).to_be_bytes());
         self.set_len(storage, len - 1);
         item
     }
+    /// Remove an element from the collection at the specified position in constant time.
+    ///
+    /// The last element takes the place of the removed one, so this does not preserve ordering.
+    pub fn swap_remove(&self, storage: &mut dyn Storage, pos: u32) -> StdResult<T> {
+        let len = self.get_len(storage)?;
+
+        if pos >= len {
+            return Err(StdError::generic_err("AppendStore access out of bounds"));
+        }
+        let item = self.get_at_unchecked(storage, pos)?;
+
+        let last = len - 1;
+        if pos != last {
+            let last_item = self.get_at_unchecked(storage, last)?;
+            self.set_at_unchecked(storage, pos, &last_item)?;
+        }
+        self.remove_impl(storage, &last.to_be_bytes());
+        self.set_len(storage, last);
+        Ok(item)
+    }
     /// Returns a readonly iterator
     pub fn iter(&self, storage: &'a dyn Storage) -> StdResult<AppendStoreIter<T, Ser>> {
         let len = self.get_len(storage)?;
@@ -229,6 +251,17 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
         storage.set(&prefixed_key, &Ser::serialize(value)?);
         Ok(())
     }
+
+    /// Removes an item from storage
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - a mutable reference to the storage this item is in
+    /// * `key` - a byte slice representing the key to access the stored item
+    fn remove_impl(&self, storage: &mut dyn Storage, key: &[u8]) {
+        let prefixed_key = [self.as_slice(), key].concat();
+        storage.remove(&prefixed_key);
+    }
 }
 
 /// An iterator over the contents of the append store.
@@ -668,6 +701,51 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_swap_remove() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let append_store: AppendStore<i32> = AppendStore::new("test");
+        for i in 1..=5 {
+            append_store.push(&mut storage, &i)?;
+        }
+
+        assert!(append_store.swap_remove(&mut storage, 5).is_err());
+
+        assert_eq!(append_store.swap_remove(&mut storage, 1), Ok(2));
+        assert_eq!(append_store.get_len(&storage)?, 4);
+        let values: StdResult<Vec<i32>> = append_store.iter(&storage)?.collect();
+        assert_eq!(values?, vec![1, 5, 3, 4]);
+
+        // removing the tail does not move anything
+        assert_eq!(append_store.swap_remove(&mut storage, 3), Ok(4));
+        let values: StdResult<Vec<i32>> = append_store.iter(&storage)?.collect();
+        assert_eq!(values?, vec![1, 5, 3]);
+
+        // the vacated slot is pruned from storage
+        assert!(append_store.get_at(&storage, 3).is_err());
+        assert_eq!(
+            storage.get(&[b"test".as_slice(), &3u32.to_be_bytes()].concat()),
+            None
+        );
+
+        append_store.push(&mut storage, &6)?;
+        assert_eq!(append_store.get_at(&storage, 3), Ok(6));
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_at_out_of_bounds() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let append_store: AppendStore<i32> = AppendStore::new("test");
+        append_store.push(&mut storage, &1)?;
+        append_store.push(&mut storage, &2)?;
+        append_store.pop(&mut storage)?;
+
+        assert_eq!(append_store.get_at(&storage, 0), Ok(1));
+        assert!(append_store.get_at(&storage, 1).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_paging() -> StdResult<()> {
         let mut storage = MockStorage::new();
@@ -0,0 +1,376 @@
+//! Building blocks for Dutch and English auctions: price decay curves, a bid book that keeps
+//! the bids of every auction ordered by amount, and settlement helpers.
+//! Times are block times in seconds, prices are always rounded down.
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Order, StdError, StdResult, Storage, Uint128};
+
+use crate::{Item, Map};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceCurve {
+    /// Decays continuously from `start_price` at `start` to `floor_price` at `end`
+    Linear {
+        start_price: Uint128,
+        floor_price: Uint128,
+        start: u64,
+        end: u64,
+    },
+    /// Drops by `decrement` at the end of every `step` seconds until it hits `floor_price`
+    Stepped {
+        start_price: Uint128,
+        floor_price: Uint128,
+        start: u64,
+        step: u64,
+        decrement: Uint128,
+    },
+}
+
+impl PriceCurve {
+    pub fn price_at(&self, at: u64) -> Uint128 {
+        match *self {
+            PriceCurve::Linear {
+                start_price,
+                floor_price,
+                start,
+                end,
+            } => {
+                if at <= start {
+                    start_price
+                } else if at >= end {
+                    floor_price
+                } else {
+                    let decay = start_price
+                        .saturating_sub(floor_price)
+                        .multiply_ratio(at - start, end - start);
+                    start_price.saturating_sub(decay).max(floor_price)
+                }
+            }
+            PriceCurve::Stepped {
+                start_price,
+                floor_price,
+                start,
+                step,
+                decrement,
+            } => {
+                if at < start {
+                    return start_price;
+                }
+                let steps = match (at - start).checked_div(step) {
+                    Some(steps) => Uint128::from(steps),
+                    // steps of zero length have all ended
+                    None => return floor_price,
+                };
+                let decay = decrement.checked_mul(steps).unwrap_or(Uint128::MAX);
+                start_price.saturating_sub(decay).max(floor_price)
+            }
+        }
+    }
+}
+
+/// Checks a Dutch auction purchase at the current price, returns the price and the change
+/// to send back to the buyer
+pub fn dutch_purchase(
+    curve: &PriceCurve,
+    at: u64,
+    offered: Uint128,
+) -> StdResult<(Uint128, Uint128)> {
+    let price = curve.price_at(at);
+    let change = offered.checked_sub(price).map_err(|_| {
+        StdError::generic_err(format!(
+            "insufficient funds: price={}, offered={}",
+            price, offered
+        ))
+    })?;
+    Ok((price, change))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Bid {
+    pub bidder: Addr,
+    pub amount: Uint128,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settlement {
+    /// Highest bid, `None` when there were no bids or none met the reserve
+    pub winner: Option<Bid>,
+    /// Every other bid, to be refunded
+    pub refunds: Vec<Bid>,
+}
+
+pub struct BidBook<'a> {
+    /// (auction, amount, inverted sequence) -> bidder, so that ranging in descending order
+    /// yields the highest bid first and the earliest one first among equal bids
+    bids: Map<'a, (u64, u128, u64), Addr>,
+    /// (auction, bidder) -> (amount, sequence) of the bidder's current bid
+    bidders: Map<'a, (u64, Addr), (Uint128, u64)>,
+    seq: Item<'a, u64>,
+}
+
+impl<'a> BidBook<'a> {
+    pub const fn new(
+        bids_namespace: &'a str,
+        bidders_namespace: &'a str,
+        seq_namespace: &'a str,
+    ) -> Self {
+        BidBook {
+            bids: Map::new(bids_namespace),
+            bidders: Map::new(bidders_namespace),
+            seq: Item::new(seq_namespace),
+        }
+    }
+
+    pub fn bid_of(&self, store: &dyn Storage, auction: u64, bidder: &Addr) -> StdResult<Uint128> {
+        Ok(self
+            .bidders
+            .may_load(store, (auction, bidder.clone()))?
+            .map(|(amount, _)| amount)
+            .unwrap_or_default())
+    }
+
+    pub fn highest(&self, store: &dyn Storage, auction: u64) -> StdResult<Option<Bid>> {
+        self.bids(store, auction, 1).map(|mut bids| bids.pop())
+    }
+
+    /// The smallest amount a new bid must reach: the reserve while there are no bids, the
+    /// highest bid plus `increment` afterwards
+    pub fn min_bid(
+        &self,
+        store: &dyn Storage,
+        auction: u64,
+        reserve: Uint128,
+        increment: Uint128,
+    ) -> StdResult<Uint128> {
+        Ok(match self.highest(store, auction)? {
+            Some(highest) => highest.amount.checked_add(increment)?,
+            None => reserve,
+        })
+    }
+
+    /// Up to `limit` bids, highest first
+    pub fn bids(&self, store: &dyn Storage, auction: u64, limit: usize) -> StdResult<Vec<Bid>> {
+        self.bids
+            .sub_prefix(auction)
+            .range(store, None, None, Order::Descending)
+            .take(limit)
+            .map(|item| {
+                let ((amount, _), bidder) = item?;
+                Ok(Bid {
+                    bidder,
+                    amount: Uint128::new(amount),
+                })
+            })
+            .collect()
+    }
+
+    /// Records the bid, replacing the bidder's previous one which it must exceed.
+    /// Returns the previous amount so that only the difference needs to be paid in.
+    pub fn place(
+        &self,
+        store: &mut dyn Storage,
+        auction: u64,
+        bidder: &Addr,
+        amount: Uint128,
+    ) -> StdResult<Uint128> {
+        let previous = self.bidders.may_load(store, (auction, bidder.clone()))?;
+        let previous_amount = match previous {
+            Some((previous_amount, seq)) => {
+                if amount <= previous_amount {
+                    return Err(StdError::generic_err(format!(
+                        "bid must exceed the previous one: previous={}, bid={}",
+                        previous_amount, amount
+                    )));
+                }
+                self.bids
+                    .remove(store, (auction, previous_amount.u128(), u64::MAX - seq));
+                previous_amount
+            }
+            None => Uint128::zero(),
+        };
+
+        let seq = self.seq.may_load(store)?.unwrap_or_default();
+        self.seq.save(store, &(seq + 1))?;
+        self.bids
+            .save(store, (auction, amount.u128(), u64::MAX - seq), bidder)?;
+        self.bidders
+            .save(store, (auction, bidder.clone()), &(amount, seq))?;
+        Ok(previous_amount)
+    }
+
+    /// Removes the bidder's bid and returns its amount
+    pub fn withdraw(
+        &self,
+        store: &mut dyn Storage,
+        auction: u64,
+        bidder: &Addr,
+    ) -> StdResult<Uint128> {
+        let (amount, seq) = self
+            .bidders
+            .may_load(store, (auction, bidder.clone()))?
+            .ok_or_else(|| StdError::generic_err(format!("{} has no bid", bidder)))?;
+        self.bids
+            .remove(store, (auction, amount.u128(), u64::MAX - seq));
+        self.bidders.remove(store, (auction, bidder.clone()));
+        Ok(amount)
+    }
+
+    /// Closes an English auction: the highest bid wins if it meets the reserve and all the
+    /// others are to be refunded. The auction's bids are cleared.
+    pub fn settle(
+        &self,
+        store: &mut dyn Storage,
+        auction: u64,
+        reserve: Uint128,
+    ) -> StdResult<Settlement> {
+        let mut refunds = self.bids(store, auction, usize::MAX)?;
+        for bid in &refunds {
+            self.withdraw(store, auction, &bid.bidder)?;
+        }
+        let winner = match refunds.first() {
+            Some(highest) if highest.amount >= reserve => Some(refunds.remove(0)),
+            _ => None,
+        };
+        Ok(Settlement { winner, refunds })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    const BOOK: BidBook = BidBook::new("bids", "bidders", "bid_seq");
+
+    fn bid(bidder: &str, amount: u128) -> Bid {
+        Bid {
+            bidder: Addr::unchecked(bidder),
+            amount: Uint128::new(amount),
+        }
+    }
+
+    #[test]
+    fn linear_curve() {
+        let curve = PriceCurve::Linear {
+            start_price: Uint128::new(1000),
+            floor_price: Uint128::new(200),
+            start: 100,
+            end: 200,
+        };
+        assert_eq!(curve.price_at(0), Uint128::new(1000));
+        assert_eq!(curve.price_at(100), Uint128::new(1000));
+        assert_eq!(curve.price_at(150), Uint128::new(600));
+        assert_eq!(curve.price_at(199), Uint128::new(208));
+        assert_eq!(curve.price_at(200), Uint128::new(200));
+        assert_eq!(curve.price_at(10_000), Uint128::new(200));
+    }
+
+    #[test]
+    fn stepped_curve() {
+        let curve = PriceCurve::Stepped {
+            start_price: Uint128::new(1000),
+            floor_price: Uint128::new(250),
+            start: 100,
+            step: 60,
+            decrement: Uint128::new(100),
+        };
+        assert_eq!(curve.price_at(50), Uint128::new(1000));
+        assert_eq!(curve.price_at(159), Uint128::new(1000));
+        assert_eq!(curve.price_at(160), Uint128::new(900));
+        assert_eq!(curve.price_at(400), Uint128::new(500));
+        assert_eq!(curve.price_at(u64::MAX), Uint128::new(250));
+
+        let (price, change) = dutch_purchase(&curve, 160, Uint128::new(1000)).unwrap();
+        assert_eq!((price, change), (Uint128::new(900), Uint128::new(100)));
+        assert!(dutch_purchase(&curve, 160, Uint128::new(899)).is_err());
+    }
+
+    #[test]
+    fn bids_are_ordered() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let alice = Addr::unchecked("alice");
+
+        assert_eq!(BOOK.highest(&storage, 1)?, None);
+        assert_eq!(
+            BOOK.min_bid(&storage, 1, Uint128::new(50), Uint128::new(10))?,
+            Uint128::new(50)
+        );
+
+        assert_eq!(
+            BOOK.place(&mut storage, 1, &alice, Uint128::new(100))?,
+            Uint128::zero()
+        );
+        BOOK.place(&mut storage, 1, &Addr::unchecked("bob"), Uint128::new(300))?;
+        BOOK.place(
+            &mut storage,
+            1,
+            &Addr::unchecked("carol"),
+            Uint128::new(300),
+        )?;
+        BOOK.place(
+            &mut storage,
+            2,
+            &Addr::unchecked("dave"),
+            Uint128::new(1000),
+        )?;
+
+        // equal bids are ranked by time
+        assert_eq!(
+            BOOK.bids(&storage, 1, 10)?,
+            vec![bid("bob", 300), bid("carol", 300), bid("alice", 100)]
+        );
+        assert_eq!(
+            BOOK.min_bid(&storage, 1, Uint128::new(50), Uint128::new(10))?,
+            Uint128::new(310)
+        );
+
+        assert!(BOOK
+            .place(&mut storage, 1, &alice, Uint128::new(100))
+            .is_err());
+        assert_eq!(
+            BOOK.place(&mut storage, 1, &alice, Uint128::new(400))?,
+            Uint128::new(100)
+        );
+        assert_eq!(BOOK.highest(&storage, 1)?, Some(bid("alice", 400)));
+        assert_eq!(BOOK.bid_of(&storage, 1, &alice)?, Uint128::new(400));
+
+        assert_eq!(BOOK.withdraw(&mut storage, 1, &alice)?, Uint128::new(400));
+        assert!(BOOK.withdraw(&mut storage, 1, &alice).is_err());
+        assert_eq!(BOOK.highest(&storage, 1)?, Some(bid("bob", 300)));
+        assert_eq!(BOOK.highest(&storage, 2)?, Some(bid("dave", 1000)));
+        Ok(())
+    }
+
+    #[test]
+    fn settlement() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        BOOK.place(
+            &mut storage,
+            1,
+            &Addr::unchecked("alice"),
+            Uint128::new(100),
+        )?;
+        BOOK.place(&mut storage, 1, &Addr::unchecked("bob"), Uint128::new(200))?;
+        BOOK.place(&mut storage, 2, &Addr::unchecked("carol"), Uint128::new(50))?;
+
+        assert_eq!(
+            BOOK.settle(&mut storage, 1, Uint128::new(150))?,
+            Settlement {
+                winner: Some(bid("bob", 200)),
+                refunds: vec![bid("alice", 100)],
+            }
+        );
+        assert_eq!(BOOK.bids(&storage, 1, 10)?, vec![]);
+
+        // the reserve wasn't met, everyone gets refunded
+        assert_eq!(
+            BOOK.settle(&mut storage, 2, Uint128::new(100))?,
+            Settlement {
+                winner: None,
+                refunds: vec![bid("carol", 50)],
+            }
+        );
+        Ok(())
+    }
+}
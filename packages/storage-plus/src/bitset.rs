@@ -0,0 +1,194 @@
+//! A `Bitset` packs flags into chunks of 1024 bits, one storage entry per chunk, so tracking
+//! millions of airdrop claims costs a few thousand entries instead of one per claim.
+//! The number of set bits is kept next to the chunks.
+use cosmwasm_std::{StdResult, Storage};
+
+#[cfg(feature = "iterator")]
+use crate::Bound;
+use crate::{Bincode2, Item, Map};
+
+const WORD_BITS: u64 = 64;
+pub(crate) const CHUNK_WORDS: usize = 16;
+/// Bits stored in one storage entry
+pub const BITSET_CHUNK_BITS: u64 = WORD_BITS * CHUNK_WORDS as u64;
+
+/// The chunk holding a bit, the word within the chunk and the bit's mask in that word
+pub(crate) fn locate(index: u64) -> (u64, usize, u64) {
+    let chunk = index / BITSET_CHUNK_BITS;
+    let bit = index % BITSET_CHUNK_BITS;
+    (chunk, (bit / WORD_BITS) as usize, 1 << (bit % WORD_BITS))
+}
+
+pub struct Bitset<'a> {
+    chunks: Map<'a, u64, Vec<u64>, Bincode2>,
+    count: Item<'a, u64>,
+}
+
+impl<'a> Bitset<'a> {
+    pub const fn new(chunks_namespace: &'a str, count_key: &'a str) -> Self {
+        Bitset {
+            chunks: Map::new(chunks_namespace),
+            count: Item::new(count_key),
+        }
+    }
+
+    fn load_chunk(&self, store: &dyn Storage, chunk: u64) -> StdResult<Vec<u64>> {
+        Ok(self
+            .chunks
+            .may_load(store, chunk)?
+            .unwrap_or_else(|| vec![0; CHUNK_WORDS]))
+    }
+
+    pub fn get(&self, store: &dyn Storage, index: u64) -> StdResult<bool> {
+        let (chunk, word, mask) = locate(index);
+        Ok(match self.chunks.may_load(store, chunk)? {
+            Some(words) => words[word] & mask != 0,
+            None => false,
+        })
+    }
+
+    /// Sets the bit and returns whether it was clear before, so a claim can be checked and
+    /// recorded at once
+    pub fn set(&self, store: &mut dyn Storage, index: u64) -> StdResult<bool> {
+        self.write(store, index, true)
+    }
+
+    /// Clears the bit and returns whether it was set before
+    pub fn unset(&self, store: &mut dyn Storage, index: u64) -> StdResult<bool> {
+        self.write(store, index, false)
+    }
+
+    fn write(&self, store: &mut dyn Storage, index: u64, value: bool) -> StdResult<bool> {
+        let (chunk, word, mask) = locate(index);
+        let mut words = self.load_chunk(store, chunk)?;
+        if (words[word] & mask != 0) == value {
+            return Ok(false);
+        }
+        words[word] ^= mask;
+        if words.iter().all(|&w| w == 0) {
+            self.chunks.remove(store, chunk);
+        } else {
+            self.chunks.save(store, chunk, &words)?;
+        }
+
+        let count = self.count_ones(store)?;
+        self.count
+            .save(store, &if value { count + 1 } else { count - 1 })?;
+        Ok(true)
+    }
+
+    pub fn count_ones(&self, store: &dyn Storage) -> StdResult<u64> {
+        Ok(self.count.may_load(store)?.unwrap_or_default())
+    }
+
+    /// Indexes of the set bits in ascending order, starting at `start`
+    #[cfg(feature = "iterator")]
+    pub fn iter_set<'c>(
+        &self,
+        store: &'c dyn Storage,
+        start: u64,
+    ) -> Box<dyn Iterator<Item = StdResult<u64>> + 'c> {
+        let first_chunk = start / BITSET_CHUNK_BITS;
+        let chunks = self.chunks.range(
+            store,
+            Some(Bound::inclusive(first_chunk)),
+            None,
+            cosmwasm_std::Order::Ascending,
+        );
+        Box::new(
+            chunks
+                .flat_map(|chunk| -> Vec<StdResult<u64>> {
+                    let (chunk, words) = match chunk {
+                        Ok(chunk) => chunk,
+                        Err(err) => return vec![Err(err)],
+                    };
+                    let mut set = vec![];
+                    for (word, bits) in words.iter().enumerate() {
+                        for bit in 0..WORD_BITS {
+                            if bits & (1 << bit) != 0 {
+                                set.push(Ok(chunk * BITSET_CHUNK_BITS
+                                    + word as u64 * WORD_BITS
+                                    + bit));
+                            }
+                        }
+                    }
+                    set
+                })
+                .filter(move |index| !matches!(index, Ok(index) if *index < start)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    const CLAIMED: Bitset = Bitset::new("claimed", "claimed_count");
+
+    #[test]
+    fn set_and_get() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        assert!(!CLAIMED.get(&storage, 5)?);
+        assert_eq!(CLAIMED.count_ones(&storage)?, 0);
+
+        assert!(CLAIMED.set(&mut storage, 5)?);
+        assert!(!CLAIMED.set(&mut storage, 5)?);
+        assert!(CLAIMED.set(&mut storage, 63)?);
+        assert!(CLAIMED.set(&mut storage, 64)?);
+        assert!(CLAIMED.set(&mut storage, 3_000_000)?);
+        assert!(CLAIMED.get(&storage, 5)?);
+        assert!(CLAIMED.get(&storage, 3_000_000)?);
+        assert!(!CLAIMED.get(&storage, 6)?);
+        assert!(!CLAIMED.get(&storage, 2_999_999)?);
+        assert_eq!(CLAIMED.count_ones(&storage)?, 4);
+
+        assert!(CLAIMED.unset(&mut storage, 3_000_000)?);
+        assert!(!CLAIMED.unset(&mut storage, 3_000_000)?);
+        assert!(!CLAIMED.get(&storage, 3_000_000)?);
+        assert_eq!(CLAIMED.count_ones(&storage)?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn bits_share_chunks() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        for index in 0..BITSET_CHUNK_BITS {
+            CLAIMED.set(&mut storage, index)?;
+        }
+        CLAIMED.set(&mut storage, BITSET_CHUNK_BITS)?;
+        assert_eq!(
+            CLAIMED.chunks.may_load(&storage, 0)?,
+            Some(vec![u64::MAX; 16])
+        );
+        assert_eq!(
+            CLAIMED.chunks.may_load(&storage, 1)?,
+            Some({
+                let mut words = vec![0; 16];
+                words[0] = 1;
+                words
+            })
+        );
+
+        // empty chunks are removed
+        CLAIMED.unset(&mut storage, BITSET_CHUNK_BITS)?;
+        assert_eq!(CLAIMED.chunks.may_load(&storage, 1)?, None);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn iterate_set_bits() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        for index in [3u64, 64, 1023, 1024, 5000].iter() {
+            CLAIMED.set(&mut storage, *index)?;
+        }
+        let all: StdResult<Vec<_>> = CLAIMED.iter_set(&storage, 0).collect();
+        assert_eq!(all?, vec![3, 64, 1023, 1024, 5000]);
+        let from: StdResult<Vec<_>> = CLAIMED.iter_set(&storage, 64).collect();
+        assert_eq!(from?, vec![64, 1023, 1024, 5000]);
+        let from: StdResult<Vec<_>> = CLAIMED.iter_set(&storage, 1025).collect();
+        assert_eq!(from?, vec![5000]);
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+//! A `BloomFilter` answers "probably seen before" with a fixed amount of storage, e.g. to
+//! reject replayed nonces without keeping every nonce.
+//!
+//! The trade-offs: it never forgets an item it was given (no false negatives) but may claim
+//! to have seen an item it wasn't given, at the configured rate once `capacity` items are in.
+//! Past capacity the rate climbs, so the filter works in generations: `rotate` starts a new
+//! one, items are checked against the current and the previous generation, and the
+//! generation before that is deleted. Rotate at least every `capacity` inserts, knowing
+//! that items from two generations ago are forgotten.
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+
+use crate::bitset::{locate, BITSET_CHUNK_BITS, CHUNK_WORDS};
+use crate::{Bincode2, Item, Map};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BloomConfig {
+    /// Size of each generation in bits, at least 1
+    pub bits: u64,
+    /// Bits set per item, at least 1
+    pub hashes: u32,
+}
+
+impl BloomConfig {
+    /// Sizes the filter for `capacity` items with at most one false positive in `one_in`
+    /// checks. Uses integer approximations of the optimal sizes, slightly oversizing the
+    /// filter rather than exceeding the rate.
+    pub fn new(capacity: u64, one_in: u64) -> StdResult<Self> {
+        if capacity == 0 || one_in < 2 {
+            return Err(StdError::generic_err(
+                "capacity must be positive and the false positive rate below 1 in 2",
+            ));
+        }
+        // k = log2(1 / p), m = n * k / ln(2)
+        let hashes = 64 - (one_in - 1).leading_zeros();
+        let bits = capacity
+            .checked_mul(u64::from(hashes) * 14427)
+            .map(|bits| bits / 10000 + 1)
+            .ok_or_else(|| StdError::generic_err("bloom filter too large"))?;
+        Ok(BloomConfig { bits, hashes })
+    }
+
+    /// The bit positions of an item. Part of the stored format, changing it invalidates
+    /// existing filters.
+    pub fn positions(&self, item: &[u8]) -> Vec<u64> {
+        let hash = Sha256::digest(item);
+        let mut first = [0u8; 8];
+        let mut second = [0u8; 8];
+        first.copy_from_slice(&hash[..8]);
+        second.copy_from_slice(&hash[8..16]);
+        let (h1, h2) = (u64::from_be_bytes(first), u64::from_be_bytes(second) | 1);
+        (0..u64::from(self.hashes))
+            .map(|i| h1.wrapping_add(i.wrapping_mul(h2)) % self.bits)
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BloomState {
+    pub config: BloomConfig,
+    pub generation: u64,
+    /// Items inserted in the current generation
+    pub inserted: u64,
+}
+
+pub struct BloomFilter<'a> {
+    /// Chunks of bits keyed by (generation, chunk)
+    bits: Map<'a, (u64, u64), Vec<u64>, Bincode2>,
+    state: Item<'a, BloomState>,
+}
+
+impl<'a> BloomFilter<'a> {
+    pub const fn new(bits_namespace: &'a str, state_key: &'a str) -> Self {
+        BloomFilter {
+            bits: Map::new(bits_namespace),
+            state: Item::new(state_key),
+        }
+    }
+
+    pub fn initialize(&self, store: &mut dyn Storage, config: BloomConfig) -> StdResult<()> {
+        if self.state.may_load(store)?.is_some() {
+            return Err(StdError::generic_err("bloom filter already initialized"));
+        }
+        if config.bits == 0 || config.hashes == 0 {
+            return Err(StdError::generic_err(
+                "bloom filter needs at least one bit and one hash",
+            ));
+        }
+        self.state.save(
+            store,
+            &BloomState {
+                config,
+                generation: 0,
+                inserted: 0,
+            },
+        )
+    }
+
+    pub fn load(&self, store: &dyn Storage) -> StdResult<BloomState> {
+        self.state.load(store)
+    }
+
+    fn has_bit(&self, store: &dyn Storage, generation: u64, position: u64) -> StdResult<bool> {
+        let (chunk, word, mask) = locate(position);
+        let words = self.bits.may_load(store, (generation, chunk))?;
+        Ok(words.map_or(false, |words| words[word] & mask != 0))
+    }
+
+    fn contains_in(
+        &self,
+        store: &dyn Storage,
+        generation: u64,
+        positions: &[u64],
+    ) -> StdResult<bool> {
+        for &position in positions {
+            if !self.has_bit(store, generation, position)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Whether the item was probably inserted in the current or previous generation
+    pub fn contains(&self, store: &dyn Storage, item: &[u8]) -> StdResult<bool> {
+        let state = self.state.load(store)?;
+        let positions = state.config.positions(item);
+        if self.contains_in(store, state.generation, &positions)? {
+            return Ok(true);
+        }
+        match state.generation.checked_sub(1) {
+            Some(previous) => self.contains_in(store, previous, &positions),
+            None => Ok(false),
+        }
+    }
+
+    /// Adds the item and returns whether it was new, i.e. `false` for a probable replay
+    pub fn insert(&self, store: &mut dyn Storage, item: &[u8]) -> StdResult<bool> {
+        if self.contains(store, item)? {
+            return Ok(false);
+        }
+        let mut state = self.state.load(store)?;
+        for position in state.config.positions(item) {
+            let (chunk, word, mask) = locate(position);
+            let key = (state.generation, chunk);
+            let mut words = self
+                .bits
+                .may_load(store, key)?
+                .unwrap_or_else(|| vec![0; CHUNK_WORDS]);
+            words[word] |= mask;
+            self.bits.save(store, key, &words)?;
+        }
+        state.inserted += 1;
+        self.state.save(store, &state)?;
+        Ok(true)
+    }
+
+    /// Starts a new generation and deletes the one before the previous
+    pub fn rotate(&self, store: &mut dyn Storage) -> StdResult<BloomState> {
+        let mut state = self.state.load(store)?;
+        if let Some(forgotten) = state.generation.checked_sub(1) {
+            let chunks = (state.config.bits + BITSET_CHUNK_BITS - 1) / BITSET_CHUNK_BITS;
+            for chunk in 0..chunks {
+                self.bits.remove(store, (forgotten, chunk));
+            }
+        }
+        state.generation += 1;
+        state.inserted = 0;
+        self.state.save(store, &state)?;
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::to_vec;
+
+    const NONCES: BloomFilter = BloomFilter::new("nonces", "nonces_state");
+
+    #[test]
+    fn sizing() -> StdResult<()> {
+        assert_eq!(
+            BloomConfig::new(1000, 100)?,
+            BloomConfig {
+                bits: 10099,
+                hashes: 7
+            }
+        );
+        assert_eq!(BloomConfig::new(1000, 128)?.hashes, 7);
+        assert_eq!(BloomConfig::new(1000, 129)?.hashes, 8);
+        assert!(BloomConfig::new(0, 100).is_err());
+        assert!(BloomConfig::new(1000, 1).is_err());
+        assert!(BloomConfig::new(u64::MAX, 100).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn stable_format() -> StdResult<()> {
+        // positions and state are persisted, they must not change between versions
+        let config = BloomConfig::new(1000, 100)?;
+        assert_eq!(
+            config.positions(b"nonce-1"),
+            vec![1196, 1597, 2892, 3293, 3694, 4989, 5390]
+        );
+        assert_eq!(
+            to_vec(&BloomState {
+                config,
+                generation: 2,
+                inserted: 5
+            })?,
+            br#"{"config":{"bits":10099,"hashes":7},"generation":2,"inserted":5}"#.to_vec()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn replays_are_caught() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        assert!(NONCES.insert(&mut storage, b"nonce").is_err());
+        NONCES.initialize(&mut storage, BloomConfig::new(100, 1000)?)?;
+        assert!(NONCES
+            .initialize(&mut storage, BloomConfig::new(100, 1000)?)
+            .is_err());
+
+        for i in 0..100u32 {
+            assert!(NONCES.insert(&mut storage, &i.to_be_bytes())?);
+        }
+        for i in 0..100u32 {
+            assert!(NONCES.contains(&storage, &i.to_be_bytes())?);
+            assert!(!NONCES.insert(&mut storage, &i.to_be_bytes())?);
+        }
+        let false_positives = (100..10_100u32)
+            .filter(|i| NONCES.contains(&storage, &i.to_be_bytes()).unwrap())
+            .count();
+        assert!(false_positives < 20, "{} false positives", false_positives);
+        assert_eq!(NONCES.load(&storage)?.inserted, 100);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_empty_config() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        for config in [
+            BloomConfig { bits: 0, hashes: 7 },
+            BloomConfig {
+                bits: 10099,
+                hashes: 0,
+            },
+        ]
+        .iter()
+        {
+            assert!(NONCES.initialize(&mut storage, *config).is_err());
+        }
+        assert!(NONCES.load(&storage).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rotation_forgets_old_generations() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        NONCES.initialize(&mut storage, BloomConfig::new(100, 1000)?)?;
+        NONCES.insert(&mut storage, b"first")?;
+
+        let state = NONCES.rotate(&mut storage)?;
+        assert_eq!((state.generation, state.inserted), (1, 0));
+        assert!(NONCES.contains(&storage, b"first")?);
+        NONCES.insert(&mut storage, b"second")?;
+
+        NONCES.rotate(&mut storage)?;
+        assert!(!NONCES.contains(&storage, b"first")?);
+        assert!(NONCES.contains(&storage, b"second")?);
+        assert!(NONCES.bits.may_load(&storage, (0, 0))?.is_none());
+        Ok(())
+    }
+}
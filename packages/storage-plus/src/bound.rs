@@ -2,9 +2,10 @@
 
 use cosmwasm_std::Addr;
 use std::marker::PhantomData;
+use std::ops::{Bound as RangeBound, RangeBounds};
 
 use crate::de::KeyDeserialize;
-use crate::{Prefixer, PrimaryKey};
+use crate::{AddrKey, Prefixer, PrimaryKey};
 
 /// `RawBound` is used to define the two ends of a range, more explicit than `Option<u8>`.
 /// `None` means that we don't limit that side of the range at all.
@@ -38,6 +39,23 @@ impl<'a, K: PrimaryKey<'a>> Bound<'a, K> {
         Self::Exclusive((k.into(), PhantomData))
     }
 
+    /// Converts a Rust range into the `(min, max)` bounds taken by `range`, e.g.
+    /// `let (min, max) = Bound::from_range(100u64..200);`
+    ///
+    /// Integer keys are stored big-endian (with the sign bit flipped for signed types), so the
+    /// byte order of keys matches their numeric order and the range selects the expected keys.
+    pub fn from_range<R: RangeBounds<K>>(range: R) -> (Option<Self>, Option<Self>)
+    where
+        K: Clone,
+    {
+        let convert = |bound: RangeBound<&K>| match bound {
+            RangeBound::Included(k) => Some(Bound::inclusive(k.clone())),
+            RangeBound::Excluded(k) => Some(Bound::exclusive(k.clone())),
+            RangeBound::Unbounded => None,
+        };
+        (convert(range.start_bound()), convert(range.end_bound()))
+    }
+
     pub fn to_raw_bound(&self) -> RawBound {
         match self {
             Bound::Inclusive((k, _)) => RawBound::Inclusive(k.joined_key()),
@@ -168,6 +186,15 @@ impl<'a> Bounder<'a> for Addr {
     }
 }
 
+impl<'a> Bounder<'a> for AddrKey {
+    fn inclusive_bound(self) -> Option<Bound<'a, Self>> {
+        Some(Bound::inclusive(self))
+    }
+    fn exclusive_bound(self) -> Option<Bound<'a, Self>> {
+        Some(Bound::exclusive(self))
+    }
+}
+
 macro_rules! integer_bound {
     (for $($t:ty),+) => {
         $(impl<'a> Bounder<'a> for $t {
@@ -0,0 +1,113 @@
+//! The current block height and time, as seen by time-dependent storage components.
+//! Contracts pass their `Env` (or `env.block`), unit tests a `MockClock` they can move forward.
+use cosmwasm_std::{BlockInfo, Env, Timestamp};
+
+pub trait Clock {
+    fn height(&self) -> u64;
+
+    fn time(&self) -> Timestamp;
+
+    /// Block time in seconds
+    fn seconds(&self) -> u64 {
+        self.time().seconds()
+    }
+}
+
+impl Clock for BlockInfo {
+    fn height(&self) -> u64 {
+        self.height
+    }
+
+    fn time(&self) -> Timestamp {
+        self.time
+    }
+}
+
+impl Clock for Env {
+    fn height(&self) -> u64 {
+        self.block.height
+    }
+
+    fn time(&self) -> Timestamp {
+        self.block.time
+    }
+}
+
+/// A clock that only moves when told to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MockClock {
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+impl MockClock {
+    pub fn new(height: u64, seconds: u64) -> Self {
+        MockClock {
+            height,
+            time: Timestamp::from_seconds(seconds),
+        }
+    }
+
+    /// A clock at the given time, the height is only used by snapshots and histories
+    pub fn at(seconds: u64) -> Self {
+        Self::new(1, seconds)
+    }
+
+    pub fn set(&mut self, height: u64, seconds: u64) -> &mut Self {
+        *self = Self::new(height, seconds);
+        self
+    }
+
+    pub fn advance_blocks(&mut self, blocks: u64) -> &mut Self {
+        self.height += blocks;
+        self
+    }
+
+    pub fn advance_seconds(&mut self, seconds: u64) -> &mut Self {
+        self.time = self.time.plus_seconds(seconds);
+        self
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new(1, 0)
+    }
+}
+
+impl Clock for MockClock {
+    fn height(&self) -> u64 {
+        self.height
+    }
+
+    fn time(&self) -> Timestamp {
+        self.time
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::mock_env;
+
+    #[test]
+    fn env_and_block_agree() {
+        let env = mock_env();
+        assert_eq!(env.height(), env.block.height);
+        assert_eq!(env.seconds(), env.block.time.seconds());
+        assert_eq!(env.block.height(), env.block.height);
+        assert_eq!(env.block.seconds(), env.block.time.seconds());
+    }
+
+    #[test]
+    fn mock_clock_moves_on_demand() {
+        let mut clock = MockClock::at(100);
+        assert_eq!((clock.height(), clock.seconds()), (1, 100));
+
+        clock.advance_blocks(2).advance_seconds(10);
+        assert_eq!((clock.height(), clock.seconds()), (3, 110));
+
+        clock.set(10, 5);
+        assert_eq!(clock, MockClock::new(10, 5));
+    }
+}
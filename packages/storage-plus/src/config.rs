@@ -0,0 +1,201 @@
+//! A `Config` is an Item holding a contract's configuration, updated through partial-update
+//! messages that only name the fields they change.
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmwasm_std::{from_slice, to_vec, Attribute, StdResult, Storage};
+
+use crate::serialization::json_object_fields;
+use crate::{Item, Json, Serde};
+
+/// A message carrying new values for some of the fields of `T`, usually a struct of `Option`s
+pub trait PartialUpdate<T> {
+    fn apply_to(self, config: &mut T);
+}
+
+pub struct Config<'a, T, Ser = Json>
+where
+    Ser: Serde,
+{
+    item: Item<'a, T, Ser>,
+}
+
+impl<'a, T, Ser: Serde> Config<'a, T, Ser> {
+    pub const fn new(storage_key: &'a str) -> Self {
+        Config {
+            item: Item::new(storage_key),
+        }
+    }
+}
+
+impl<'a, T, Ser> Config<'a, T, Ser>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    pub fn save(&self, store: &mut dyn Storage, config: &T) -> StdResult<()> {
+        self.item.save(store, config)
+    }
+
+    pub fn load(&self, store: &dyn Storage) -> StdResult<T> {
+        self.item.load(store)
+    }
+
+    pub fn may_load(&self, store: &dyn Storage) -> StdResult<Option<T>> {
+        self.item.may_load(store)
+    }
+
+    /// Merges `partial` into the stored config once `authorize` accepts the current config,
+    /// which is where admin checks go. Returns one `config.<field>` attribute per changed field,
+    /// holding its new value.
+    pub fn update_fields<P, A>(
+        &self,
+        store: &mut dyn Storage,
+        partial: P,
+        authorize: A,
+    ) -> StdResult<Vec<Attribute>>
+    where
+        P: PartialUpdate<T>,
+        A: FnOnce(&T) -> StdResult<()>,
+    {
+        let mut config = self.item.load(store)?;
+        authorize(&config)?;
+        let before = json_object_fields(&to_vec(&config)?)?;
+        partial.apply_to(&mut config);
+        let after = json_object_fields(&to_vec(&config)?)?;
+        self.item.save(store, &config)?;
+
+        let mut attributes = vec![];
+        for (field, value) in &after {
+            let unchanged = before.iter().any(|(f, v)| f == field && v == value);
+            if !unchanged {
+                attributes.push(Attribute::new(
+                    format!("config.{}", field),
+                    attribute_value(value),
+                ));
+            }
+        }
+        for (field, _) in &before {
+            if !after.iter().any(|(f, _)| f == field) {
+                attributes.push(Attribute::new(format!("config.{}", field), "null"));
+            }
+        }
+        Ok(attributes)
+    }
+}
+
+/// Strings are emitted without their quotes, anything else as JSON
+fn attribute_value(json: &[u8]) -> String {
+    match from_slice::<String>(json) {
+        Ok(string) => string,
+        Err(_) => String::from_utf8_lossy(json).into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::{Addr, StdError, Uint128};
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    struct Settings {
+        admin: Addr,
+        fee: Uint128,
+        paused: bool,
+    }
+
+    #[derive(Default)]
+    struct UpdateSettings {
+        admin: Option<Addr>,
+        fee: Option<Uint128>,
+        paused: Option<bool>,
+    }
+
+    impl PartialUpdate<Settings> for UpdateSettings {
+        fn apply_to(self, config: &mut Settings) {
+            if let Some(admin) = self.admin {
+                config.admin = admin;
+            }
+            if let Some(fee) = self.fee {
+                config.fee = fee;
+            }
+            if let Some(paused) = self.paused {
+                config.paused = paused;
+            }
+        }
+    }
+
+    const CONFIG: Config<Settings> = Config::new("config");
+
+    fn only_admin(sender: &str) -> impl FnOnce(&Settings) -> StdResult<()> + '_ {
+        move |config| {
+            if config.admin == sender {
+                Ok(())
+            } else {
+                Err(StdError::generic_err("unauthorized"))
+            }
+        }
+    }
+
+    fn setup(storage: &mut MockStorage) {
+        CONFIG
+            .save(
+                storage,
+                &Settings {
+                    admin: Addr::unchecked("admin"),
+                    fee: Uint128::new(5),
+                    paused: false,
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn update_emits_changed_fields() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        setup(&mut storage);
+
+        let update = UpdateSettings {
+            fee: Some(Uint128::new(7)),
+            paused: Some(false),
+            ..Default::default()
+        };
+        let attributes = CONFIG.update_fields(&mut storage, update, only_admin("admin"))?;
+        assert_eq!(attributes, vec![Attribute::new("config.fee", "7")]);
+        assert_eq!(CONFIG.load(&storage)?.fee, Uint128::new(7));
+
+        let update = UpdateSettings {
+            admin: Some(Addr::unchecked("next")),
+            paused: Some(true),
+            ..Default::default()
+        };
+        let attributes = CONFIG.update_fields(&mut storage, update, only_admin("admin"))?;
+        assert_eq!(
+            attributes,
+            vec![
+                Attribute::new("config.admin", "next"),
+                Attribute::new("config.paused", "true"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejected_update_is_not_saved() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        setup(&mut storage);
+
+        let update = UpdateSettings {
+            paused: Some(true),
+            ..Default::default()
+        };
+        let err = CONFIG
+            .update_fields(&mut storage, update, only_admin("mallory"))
+            .unwrap_err();
+        assert_eq!(err, StdError::generic_err("unauthorized"));
+        assert!(!CONFIG.load(&storage)?.paused);
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+//! A `DeadMansSwitch` hands admin rights over to a recovery address once the admin has been
+//! inactive for longer than the configured timeout.
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, StdError, StdResult, Storage};
+
+use crate::{Clock, Item};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SwitchState {
+    pub admin: Addr,
+    pub recovery: Addr,
+    /// Seconds of admin inactivity after which the recovery address may take over
+    pub timeout: u64,
+    /// Block time in seconds of the admin's last action
+    pub last_activity: u64,
+}
+
+impl SwitchState {
+    pub fn expires_at(&self) -> u64 {
+        self.last_activity.saturating_add(self.timeout)
+    }
+
+    pub fn is_expired(&self, clock: &impl Clock) -> bool {
+        clock.seconds() >= self.expires_at()
+    }
+}
+
+pub struct DeadMansSwitch<'a> {
+    state: Item<'a, SwitchState>,
+}
+
+impl<'a> DeadMansSwitch<'a> {
+    pub const fn new(storage_key: &'a str) -> Self {
+        DeadMansSwitch {
+            state: Item::new(storage_key),
+        }
+    }
+
+    pub fn initialize(
+        &self,
+        store: &mut dyn Storage,
+        clock: &impl Clock,
+        admin: Addr,
+        recovery: Addr,
+        timeout: u64,
+    ) -> StdResult<()> {
+        self.state.save(
+            store,
+            &SwitchState {
+                admin,
+                recovery,
+                timeout,
+                last_activity: clock.seconds(),
+            },
+        )
+    }
+
+    pub fn load(&self, store: &dyn Storage) -> StdResult<SwitchState> {
+        self.state.load(store)
+    }
+
+    /// Errors unless `sender` is the admin, and records the call as admin activity.
+    /// Use it to gate every admin action so the switch only fires on real inactivity.
+    pub fn assert_admin(
+        &self,
+        store: &mut dyn Storage,
+        clock: &impl Clock,
+        sender: &Addr,
+    ) -> StdResult<()> {
+        let mut state = self.state.load(store)?;
+        if state.admin != *sender {
+            return Err(StdError::generic_err("unauthorized"));
+        }
+        state.last_activity = clock.seconds();
+        self.state.save(store, &state)
+    }
+
+    pub fn set_recovery(
+        &self,
+        store: &mut dyn Storage,
+        clock: &impl Clock,
+        sender: &Addr,
+        recovery: Addr,
+    ) -> StdResult<()> {
+        self.assert_admin(store, clock, sender)?;
+        self.state.update(store, |mut state| -> StdResult<_> {
+            state.recovery = recovery;
+            Ok(state)
+        })?;
+        Ok(())
+    }
+
+    pub fn set_timeout(
+        &self,
+        store: &mut dyn Storage,
+        clock: &impl Clock,
+        sender: &Addr,
+        timeout: u64,
+    ) -> StdResult<()> {
+        self.assert_admin(store, clock, sender)?;
+        self.state.update(store, |mut state| -> StdResult<_> {
+            state.timeout = timeout;
+            Ok(state)
+        })?;
+        Ok(())
+    }
+
+    /// Makes the recovery address the admin once the switch expired, returns the previous admin.
+    /// The new admin starts with a fresh activity timer and stays the recovery address until it
+    /// sets another one.
+    pub fn claim(
+        &self,
+        store: &mut dyn Storage,
+        clock: &impl Clock,
+        sender: &Addr,
+    ) -> StdResult<Addr> {
+        let state = self.state.load(store)?;
+        if state.recovery != *sender {
+            return Err(StdError::generic_err("unauthorized"));
+        }
+        if !state.is_expired(clock) {
+            return Err(StdError::generic_err(format!(
+                "admin is still active, recovery possible at {}",
+                state.expires_at()
+            )));
+        }
+        self.state.save(
+            store,
+            &SwitchState {
+                admin: state.recovery.clone(),
+                recovery: state.recovery,
+                timeout: state.timeout,
+                last_activity: clock.seconds(),
+            },
+        )?;
+        Ok(state.admin)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MockClock;
+    use cosmwasm_std::testing::MockStorage;
+
+    const SWITCH: DeadMansSwitch = DeadMansSwitch::new("switch");
+    const DAY: u64 = 24 * 60 * 60;
+
+    #[test]
+    fn recovery_after_inactivity() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let mut clock = MockClock::default();
+        let admin = Addr::unchecked("admin");
+        let recovery = Addr::unchecked("recovery");
+        SWITCH.initialize(&mut storage, &clock, admin.clone(), recovery.clone(), DAY)?;
+
+        // too early, and only the recovery address may claim
+        clock.advance_seconds(DAY - 1);
+        assert!(SWITCH.claim(&mut storage, &clock, &recovery).is_err());
+        assert!(SWITCH.claim(&mut storage, &clock, &admin).is_err());
+
+        // admin activity resets the timer
+        SWITCH.assert_admin(&mut storage, &clock, &admin)?;
+        assert!(SWITCH
+            .claim(&mut storage, clock.advance_seconds(1), &recovery)
+            .is_err());
+
+        clock.advance_seconds(DAY - 1);
+        let previous = SWITCH.claim(&mut storage, &clock, &recovery)?;
+        assert_eq!(previous, admin);
+        let state = SWITCH.load(&storage)?;
+        assert_eq!(state.admin, recovery);
+        assert_eq!(state.last_activity, 2 * DAY - 1);
+
+        assert!(SWITCH
+            .assert_admin(&mut storage, clock.advance_seconds(1), &admin)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn admin_updates_settings() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let mut clock = MockClock::default();
+        let admin = Addr::unchecked("admin");
+        SWITCH.initialize(
+            &mut storage,
+            &clock,
+            admin.clone(),
+            Addr::unchecked("recovery"),
+            DAY,
+        )?;
+
+        let other = Addr::unchecked("other");
+        clock.advance_seconds(1);
+        assert!(SWITCH
+            .set_recovery(&mut storage, &clock, &other, other.clone())
+            .is_err());
+        SWITCH.set_recovery(&mut storage, &clock, &admin, other.clone())?;
+        SWITCH.set_timeout(&mut storage, clock.advance_seconds(1), &admin, 2 * DAY)?;
+
+        let state = SWITCH.load(&storage)?;
+        assert_eq!(state.recovery, other);
+        assert_eq!(state.timeout, 2 * DAY);
+        assert_eq!(state.expires_at(), 2 + 2 * DAY);
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+//! An `EnumerableMap` is a `Map` that also remembers the order its keys were inserted in,
+//! so contracts can page over its entries without the iterator feature or a manual key list.
+//!
+//! Keys are kept in an [AppendStore] and each key's position in a secondary map, so
+//! inserting and reading are constant cost. Removing shifts every key inserted after the
+//! removed one to keep the insertion order compact.
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmwasm_std::{StdResult, Storage};
+
+use crate::de::KeyDeserialize;
+use crate::keys::PrimaryKey;
+use crate::path::Path;
+use crate::{AppendStore, Json, Map, Serde};
+
+pub struct EnumerableMap<'a, K, T, Ser = Json>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    namespace: &'a str,
+    primary: Map<'a, K, T, Ser>,
+    /// joined key -> position in `keys`
+    positions: Map<'a, Vec<u8>, u32>,
+    /// joined keys in insertion order
+    keys: AppendStore<'a, Vec<u8>>,
+}
+
+impl<'a, K, T, Ser> EnumerableMap<'a, K, T, Ser>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    pub const fn new(
+        namespace: &'a str,
+        positions_namespace: &'a str,
+        keys_namespace: &'a str,
+    ) -> Self {
+        EnumerableMap {
+            namespace,
+            primary: Map::new(namespace),
+            positions: Map::new(positions_namespace),
+            keys: AppendStore::new(keys_namespace),
+        }
+    }
+}
+
+impl<'a, K, T, Ser> EnumerableMap<'a, K, T, Ser>
+where
+    T: Serialize + DeserializeOwned,
+    K: PrimaryKey<'a>,
+    Ser: Serde,
+{
+    pub fn save(&self, store: &mut dyn Storage, k: K, data: &T) -> StdResult<()> {
+        let joined = k.joined_key();
+        if !self.positions.has(store, joined.clone()) {
+            let position = self.keys.get_len(store)?;
+            self.keys.push(store, &joined)?;
+            self.positions.save(store, joined, &position)?;
+        }
+        self.primary.save(store, k, data)
+    }
+
+    /// Removes the entry and closes the gap it leaves in the insertion order
+    pub fn remove(&self, store: &mut dyn Storage, k: K) -> StdResult<()> {
+        let joined = k.joined_key();
+        if let Some(position) = self.positions.may_load(store, joined.clone())? {
+            self.keys.remove(store, position)?;
+            self.positions.remove(store, joined);
+            // every key after the removed one moved down by one
+            for i in position..self.keys.get_len(store)? {
+                let shifted = self.keys.get_at(store, i)?;
+                self.positions.save(store, shifted, &i)?;
+            }
+        }
+        self.primary.remove(store, k);
+        Ok(())
+    }
+
+    pub fn load(&self, store: &dyn Storage, k: K) -> StdResult<T> {
+        self.primary.load(store, k)
+    }
+
+    pub fn may_load(&self, store: &dyn Storage, k: K) -> StdResult<Option<T>> {
+        self.primary.may_load(store, k)
+    }
+
+    pub fn has(&self, store: &dyn Storage, k: K) -> bool {
+        self.primary.has(store, k)
+    }
+
+    /// Amount of entries in the map
+    pub fn len(&self, store: &dyn Storage) -> StdResult<u32> {
+        self.keys.get_len(store)
+    }
+
+    pub fn is_empty(&self, store: &dyn Storage) -> StdResult<bool> {
+        self.keys.is_empty(store)
+    }
+
+    /// Position of the key in insertion order, if present
+    pub fn position(&self, store: &dyn Storage, k: K) -> StdResult<Option<u32>> {
+        self.positions.may_load(store, k.joined_key())
+    }
+}
+
+impl<'a, K, T, Ser> EnumerableMap<'a, K, T, Ser>
+where
+    T: Serialize + DeserializeOwned,
+    K: PrimaryKey<'a> + KeyDeserialize,
+    Ser: Serde,
+{
+    /// Entry at the given position in insertion order
+    pub fn get_at(&self, store: &dyn Storage, pos: u32) -> StdResult<(K::Output, T)> {
+        let joined = self.keys.get_at(store, pos)?;
+        let data = Path::<T, Ser>::new(self.namespace.as_bytes(), &[&joined]).load(store)?;
+        Ok((K::from_vec(joined)?, data))
+    }
+
+    /// Returns up to `size` entries in insertion order, starting at page `start_page`
+    pub fn paging(
+        &self,
+        store: &dyn Storage,
+        start_page: u32,
+        size: u32,
+    ) -> StdResult<Vec<(K::Output, T)>> {
+        let len = self.len(store)?;
+        let start = start_page.saturating_mul(size).min(len);
+        let end = start.saturating_add(size).min(len);
+        (start..end).map(|pos| self.get_at(store, pos)).collect()
+    }
+
+    /// Returns up to `size` keys in insertion order, starting at page `start_page`
+    pub fn paging_keys(
+        &self,
+        store: &dyn Storage,
+        start_page: u32,
+        size: u32,
+    ) -> StdResult<Vec<K::Output>> {
+        let len = self.len(store)?;
+        let start = start_page.saturating_mul(size).min(len);
+        let end = start.saturating_add(size).min(len);
+        (start..end)
+            .map(|pos| K::from_vec(self.keys.get_at(store, pos)?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::{Addr, Uint128};
+
+    const HOLDERS: EnumerableMap<&Addr, Uint128> =
+        EnumerableMap::new("holders", "holders__pos", "holders__keys");
+    const ALLOWANCES: EnumerableMap<(&str, &str), u64> =
+        EnumerableMap::new("allow", "allow__pos", "allow__keys");
+
+    #[test]
+    fn keeps_insertion_order() -> StdResult<()> {
+        let mut store = MockStorage::new();
+        let (bob, alice, carol) = (
+            Addr::unchecked("bob"),
+            Addr::unchecked("alice"),
+            Addr::unchecked("carol"),
+        );
+
+        HOLDERS.save(&mut store, &bob, &Uint128::new(10))?;
+        HOLDERS.save(&mut store, &alice, &Uint128::new(20))?;
+        HOLDERS.save(&mut store, &carol, &Uint128::new(30))?;
+        // updating an entry keeps its position
+        HOLDERS.save(&mut store, &bob, &Uint128::new(15))?;
+
+        assert_eq!(HOLDERS.len(&store)?, 3);
+        assert_eq!(
+            HOLDERS.paging(&store, 0, 2)?,
+            vec![
+                (bob.clone(), Uint128::new(15)),
+                (alice.clone(), Uint128::new(20))
+            ]
+        );
+        assert_eq!(
+            HOLDERS.paging(&store, 1, 2)?,
+            vec![(carol.clone(), Uint128::new(30))]
+        );
+        assert_eq!(HOLDERS.paging(&store, 2, 2)?, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn remove_compacts_order() -> StdResult<()> {
+        let mut store = MockStorage::new();
+        let (bob, alice, carol) = (
+            Addr::unchecked("bob"),
+            Addr::unchecked("alice"),
+            Addr::unchecked("carol"),
+        );
+
+        HOLDERS.save(&mut store, &bob, &Uint128::new(10))?;
+        HOLDERS.save(&mut store, &alice, &Uint128::new(20))?;
+        HOLDERS.save(&mut store, &carol, &Uint128::new(30))?;
+
+        HOLDERS.remove(&mut store, &bob)?;
+        assert!(!HOLDERS.has(&store, &bob));
+        assert_eq!(HOLDERS.len(&store)?, 2);
+        assert_eq!(HOLDERS.position(&store, &bob)?, None);
+        assert_eq!(HOLDERS.position(&store, &alice)?, Some(0));
+        assert_eq!(HOLDERS.position(&store, &carol)?, Some(1));
+        assert_eq!(
+            HOLDERS.paging_keys(&store, 0, 10)?,
+            vec![alice.clone(), carol.clone()]
+        );
+
+        // removing a missing key is a no-op
+        HOLDERS.remove(&mut store, &bob)?;
+        assert_eq!(HOLDERS.len(&store)?, 2);
+
+        HOLDERS.save(&mut store, &bob, &Uint128::new(5))?;
+        assert_eq!(HOLDERS.get_at(&store, 2)?, (bob, Uint128::new(5)));
+        Ok(())
+    }
+
+    #[test]
+    fn composite_keys() -> StdResult<()> {
+        let mut store = MockStorage::new();
+
+        ALLOWANCES.save(&mut store, ("owner", "spender"), &5)?;
+        ALLOWANCES.save(&mut store, ("owner", "spender2"), &7)?;
+
+        assert_eq!(ALLOWANCES.load(&store, ("owner", "spender2"))?, 7);
+        assert_eq!(
+            ALLOWANCES.get_at(&store, 0)?,
+            (("owner".to_string(), "spender".to_string()), 5)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,335 @@
+//! An `Escrow` holds funds on behalf of users until they're released to a counterparty or
+//! refunded, balances are tracked per (owner, asset) for native coins and SNIP-20 tokens.
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{
+    to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, MessageInfo, StdError, StdResult, Storage,
+    Uint128, WasmMsg,
+};
+
+use crate::Map;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Asset {
+    Native(String),
+    Snip20(Addr),
+}
+
+impl Asset {
+    fn key(&self) -> String {
+        match self {
+            Asset::Native(denom) => format!("native:{}", denom),
+            Asset::Snip20(token) => format!("snip20:{}", token),
+        }
+    }
+
+    #[cfg(feature = "iterator")]
+    fn from_key(key: &str) -> StdResult<Self> {
+        match key.split_once(':') {
+            Some(("native", denom)) => Ok(Asset::Native(denom.to_string())),
+            Some(("snip20", token)) => Ok(Asset::Snip20(Addr::unchecked(token))),
+            _ => Err(StdError::generic_err(format!("invalid escrow key {}", key))),
+        }
+    }
+}
+
+/// The `receive` callback a SNIP-20 token sends to registered contracts on `send`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Snip20ReceiveMsg {
+    pub sender: Addr,
+    pub from: Addr,
+    pub amount: Uint128,
+    #[serde(default)]
+    pub memo: Option<String>,
+    #[serde(default)]
+    pub msg: Option<Binary>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Snip20Msg<'a> {
+    Transfer { recipient: &'a str, amount: Uint128 },
+}
+
+pub struct Escrow<'a> {
+    balances: Map<'a, (Addr, String), Uint128>,
+    /// Code hashes of the tokens the escrow accepts
+    tokens: Map<'a, Addr, String>,
+}
+
+impl<'a> Escrow<'a> {
+    pub const fn new(balances_namespace: &'a str, tokens_namespace: &'a str) -> Self {
+        Escrow {
+            balances: Map::new(balances_namespace),
+            tokens: Map::new(tokens_namespace),
+        }
+    }
+
+    /// Accepts deposits of the token, its code hash is needed to send it out again
+    pub fn register_token(
+        &self,
+        store: &mut dyn Storage,
+        token: &Addr,
+        code_hash: String,
+    ) -> StdResult<()> {
+        self.tokens.save(store, token.clone(), &code_hash)
+    }
+
+    pub fn balance(&self, store: &dyn Storage, owner: &Addr, asset: &Asset) -> StdResult<Uint128> {
+        Ok(self
+            .balances
+            .may_load(store, (owner.clone(), asset.key()))?
+            .unwrap_or_default())
+    }
+
+    /// Credits the owner, returns the new balance
+    pub fn deposit(
+        &self,
+        store: &mut dyn Storage,
+        owner: &Addr,
+        asset: &Asset,
+        amount: Uint128,
+    ) -> StdResult<Uint128> {
+        if let Asset::Snip20(token) = asset {
+            if !self.tokens.has(store, token.clone()) {
+                return Err(StdError::generic_err(format!(
+                    "{} is not an accepted token",
+                    token
+                )));
+            }
+        }
+        self.balances.update(
+            store,
+            (owner.clone(), asset.key()),
+            |balance| -> StdResult<_> { Ok(balance.unwrap_or_default().checked_add(amount)?) },
+        )
+    }
+
+    /// Credits the sender with all the coins sent along with the message
+    pub fn deposit_funds(&self, store: &mut dyn Storage, info: &MessageInfo) -> StdResult<()> {
+        if info.funds.is_empty() {
+            return Err(StdError::generic_err("no funds sent"));
+        }
+        for coin in &info.funds {
+            self.deposit(
+                store,
+                &info.sender,
+                &Asset::Native(coin.denom.clone()),
+                coin.amount,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Credits the tokens of a SNIP-20 `receive` to their previous owner and returns it.
+    /// `token` must be the sender of the receive message, i.e. the token contract itself.
+    pub fn receive(
+        &self,
+        store: &mut dyn Storage,
+        token: &Addr,
+        msg: &Snip20ReceiveMsg,
+    ) -> StdResult<Addr> {
+        self.deposit(store, &msg.from, &Asset::Snip20(token.clone()), msg.amount)?;
+        Ok(msg.from.clone())
+    }
+
+    /// Debits the owner and returns the message that sends the funds to the recipient
+    pub fn release(
+        &self,
+        store: &mut dyn Storage,
+        owner: &Addr,
+        asset: &Asset,
+        amount: Uint128,
+        recipient: &Addr,
+    ) -> StdResult<CosmosMsg> {
+        if amount.is_zero() {
+            return Err(StdError::generic_err("cannot release zero funds"));
+        }
+        let key = (owner.clone(), asset.key());
+        let balance = self
+            .balances
+            .may_load(store, key.clone())?
+            .unwrap_or_default();
+        let remaining = balance.checked_sub(amount).map_err(|_| {
+            StdError::generic_err(format!(
+                "insufficient funds in escrow: balance={}, required={}",
+                balance, amount
+            ))
+        })?;
+        if remaining.is_zero() {
+            self.balances.remove(store, key);
+        } else {
+            self.balances.save(store, key, &remaining)?;
+        }
+
+        Ok(match asset {
+            Asset::Native(denom) => BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![Coin::new(amount.u128(), denom)],
+            }
+            .into(),
+            Asset::Snip20(token) => WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                code_hash: self.tokens.load(store, token.clone())?,
+                msg: to_binary(&Snip20Msg::Transfer {
+                    recipient: recipient.as_str(),
+                    amount,
+                })?,
+                funds: vec![],
+            }
+            .into(),
+        })
+    }
+
+    /// Sends the funds back to their owner
+    pub fn refund(
+        &self,
+        store: &mut dyn Storage,
+        owner: &Addr,
+        asset: &Asset,
+        amount: Uint128,
+    ) -> StdResult<CosmosMsg> {
+        self.release(store, owner, asset, amount, owner)
+    }
+
+    /// Everything the owner has in escrow
+    #[cfg(feature = "iterator")]
+    pub fn holdings(&self, store: &dyn Storage, owner: &Addr) -> StdResult<Vec<(Asset, Uint128)>> {
+        self.balances
+            .prefix(owner.clone())
+            .range(store, None, None, cosmwasm_std::Order::Ascending)
+            .map(|item| {
+                let (key, amount) = item?;
+                Ok((Asset::from_key(&key)?, amount))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::coins;
+    use cosmwasm_std::testing::{mock_info, MockStorage};
+
+    const ESCROW: Escrow = Escrow::new("escrow", "escrow_tokens");
+
+    #[test]
+    fn native_funds() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+        let scrt = Asset::Native("uscrt".to_string());
+
+        assert!(ESCROW
+            .deposit_funds(&mut storage, &mock_info("alice", &[]))
+            .is_err());
+        ESCROW.deposit_funds(&mut storage, &mock_info("alice", &coins(100, "uscrt")))?;
+        assert_eq!(ESCROW.balance(&storage, &alice, &scrt)?, Uint128::new(100));
+
+        assert!(ESCROW
+            .release(&mut storage, &alice, &scrt, Uint128::new(101), &bob)
+            .is_err());
+        let msg = ESCROW.release(&mut storage, &alice, &scrt, Uint128::new(60), &bob)?;
+        assert_eq!(
+            msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "bob".to_string(),
+                amount: coins(60, "uscrt"),
+            })
+        );
+        let msg = ESCROW.refund(&mut storage, &alice, &scrt, Uint128::new(40))?;
+        assert_eq!(
+            msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alice".to_string(),
+                amount: coins(40, "uscrt"),
+            })
+        );
+        assert_eq!(ESCROW.balance(&storage, &alice, &scrt)?, Uint128::zero());
+        Ok(())
+    }
+
+    #[test]
+    fn snip20_funds() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let token = Addr::unchecked("token");
+        let alice = Addr::unchecked("alice");
+        let receive = Snip20ReceiveMsg {
+            sender: alice.clone(),
+            from: alice.clone(),
+            amount: Uint128::new(500),
+            memo: None,
+            msg: None,
+        };
+
+        assert!(ESCROW.receive(&mut storage, &token, &receive).is_err());
+        ESCROW.register_token(&mut storage, &token, "hash".to_string())?;
+        assert_eq!(ESCROW.receive(&mut storage, &token, &receive)?, alice);
+
+        let asset = Asset::Snip20(token.clone());
+        let msg = ESCROW.release(
+            &mut storage,
+            &alice,
+            &asset,
+            Uint128::new(200),
+            &Addr::unchecked("bob"),
+        )?;
+        match msg {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                code_hash,
+                msg,
+                funds,
+            }) => {
+                assert_eq!(contract_addr, "token");
+                assert_eq!(code_hash, "hash");
+                assert!(funds.is_empty());
+                assert_eq!(
+                    msg.as_slice(),
+                    br#"{"transfer":{"recipient":"bob","amount":"200"}}"#
+                );
+            }
+            msg => panic!("unexpected message {:?}", msg),
+        }
+        assert_eq!(ESCROW.balance(&storage, &alice, &asset)?, Uint128::new(300));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn holdings() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let token = Addr::unchecked("token");
+        let alice = Addr::unchecked("alice");
+        ESCROW.register_token(&mut storage, &token, "hash".to_string())?;
+        ESCROW.deposit(
+            &mut storage,
+            &alice,
+            &Asset::Native("ibc/27A6".to_string()),
+            Uint128::new(1),
+        )?;
+        ESCROW.deposit(
+            &mut storage,
+            &alice,
+            &Asset::Snip20(token.clone()),
+            Uint128::new(2),
+        )?;
+        ESCROW.deposit(
+            &mut storage,
+            &Addr::unchecked("bob"),
+            &Asset::Native("uscrt".to_string()),
+            Uint128::new(3),
+        )?;
+
+        assert_eq!(
+            ESCROW.holdings(&storage, &alice)?,
+            vec![
+                (Asset::Native("ibc/27A6".to_string()), Uint128::new(1)),
+                (Asset::Snip20(token), Uint128::new(2)),
+            ]
+        );
+        Ok(())
+    }
+}
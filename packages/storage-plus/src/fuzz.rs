@@ -0,0 +1,428 @@
+#![cfg(feature = "fuzz")]
+//! Randomized testing for storage components, not meant for contract builds.
+//!
+//! A [FuzzTarget] generates operations, applies them to the component and to an in-memory
+//! model, and checks the two agree. The [Fuzzer] runs random operation sequences on a fresh
+//! `MockStorage`, checking invariants every few steps and that failed operations leave storage
+//! untouched. A failing sequence is shrunk to the fewest operations that still fail.
+//!
+//! ```ignore
+//! if let Err(failure) = Fuzzer::new(42).with_runs(200).run(&QueueTarget) {
+//!     panic!("{}", failure);
+//! }
+//! ```
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use cosmwasm_std::testing::MockStorage;
+use cosmwasm_std::{Order, StdResult, Storage};
+
+pub trait FuzzTarget {
+    type Op: Clone + fmt::Debug;
+    /// What the component should hold, fresh for every run
+    type Model: Default;
+
+    /// A random operation, the model can be used to pick keys that exist
+    fn generate(&self, rng: &mut FuzzRng, model: &Self::Model) -> Self::Op;
+
+    /// Applies the operation to the component, and to the model if it succeeds
+    fn apply(
+        &self,
+        storage: &mut dyn Storage,
+        model: &mut Self::Model,
+        op: &Self::Op,
+    ) -> StdResult<()>;
+
+    /// Checks the component's invariants and that it agrees with the model
+    fn check(&self, storage: &dyn Storage, model: &Self::Model) -> Result<(), String>;
+}
+
+/// Small deterministic generator (splitmix64), so a failing seed reproduces everywhere
+#[derive(Debug, Clone)]
+pub struct FuzzRng {
+    state: u64,
+}
+
+impl FuzzRng {
+    pub fn new(seed: u64) -> Self {
+        FuzzRng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..bound`, `bound` must be positive
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// True `percent` times out of a hundred
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    /// A random element, `None` for an empty slice
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        items.get(self.below(items.len() as u64) as usize)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Failure<Op> {
+    /// Seed of the run that failed
+    pub seed: u64,
+    /// The shrunk sequence, its last operation is where it failed
+    pub ops: Vec<Op>,
+    pub reason: String,
+}
+
+impl<Op: fmt::Debug> fmt::Display for Failure<Op> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "fuzz run with seed {} failed: {}",
+            self.seed, self.reason
+        )?;
+        for (step, op) in self.ops.iter().enumerate() {
+            writeln!(f, "  {:>4}: {:?}", step, op)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Fuzzer {
+    seed: u64,
+    runs: u32,
+    steps: u32,
+    check_every: u32,
+}
+
+impl Fuzzer {
+    /// 100 runs of 50 operations, checking after every operation
+    pub fn new(seed: u64) -> Self {
+        Fuzzer {
+            seed,
+            runs: 100,
+            steps: 50,
+            check_every: 1,
+        }
+    }
+
+    pub fn with_runs(mut self, runs: u32) -> Self {
+        self.runs = runs;
+        self
+    }
+
+    pub fn with_steps(mut self, steps: u32) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Checks invariants every `steps` operations and after the last one
+    pub fn with_check_every(mut self, steps: u32) -> Self {
+        self.check_every = steps.max(1);
+        self
+    }
+
+    pub fn run<T: FuzzTarget>(&self, target: &T) -> Result<(), Failure<T::Op>> {
+        let mut seeds = FuzzRng::new(self.seed);
+        for _ in 0..self.runs {
+            let seed = seeds.next_u64();
+            let mut rng = FuzzRng::new(seed);
+            let mut ops = vec![];
+            let outcome = self.execute(target, |step, model| {
+                if step >= self.steps as usize {
+                    return None;
+                }
+                ops.push(target.generate(&mut rng, model));
+                ops.last().cloned()
+            });
+            if let Some((step, reason)) = outcome {
+                ops.truncate(step + 1);
+                let (ops, reason) = self.shrink(target, ops, reason);
+                return Err(Failure { seed, ops, reason });
+            }
+        }
+        Ok(())
+    }
+
+    /// Replays a sequence, e.g. one printed by a [Failure]
+    pub fn replay<T: FuzzTarget>(&self, target: &T, ops: &[T::Op]) -> Result<(), String> {
+        match self.execute(target, |step, _| ops.get(step).cloned()) {
+            Some((step, reason)) => Err(format!("step {}: {}", step, reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Runs the operations from `next` and returns the step that failed and why
+    fn execute<T, F>(&self, target: &T, mut next: F) -> Option<(usize, String)>
+    where
+        T: FuzzTarget,
+        F: FnMut(usize, &T::Model) -> Option<T::Op>,
+    {
+        let mut storage = MockStorage::new();
+        let mut model = T::Model::default();
+        let mut step = 0;
+        while let Some(op) = next(step, &model) {
+            let before = snapshot(&storage);
+            let applied = catch_unwind(AssertUnwindSafe(|| {
+                target.apply(&mut storage, &mut model, &op)
+            }));
+            match applied {
+                Err(panic) => return Some((step, format!("panicked: {}", panic_message(panic)))),
+                Ok(Err(err)) => {
+                    let after = snapshot(&storage);
+                    if before != after {
+                        return Some((
+                            step,
+                            format!("failed with {} but changed {}", err, diff(&before, &after)),
+                        ));
+                    }
+                }
+                Ok(Ok(())) => {}
+            }
+            step += 1;
+            if step % self.check_every as usize == 0 {
+                if let Some(reason) = self.check(target, &storage, &model) {
+                    return Some((step - 1, reason));
+                }
+            }
+        }
+        if step > 0 && step % self.check_every as usize != 0 {
+            if let Some(reason) = self.check(target, &storage, &model) {
+                return Some((step - 1, reason));
+            }
+        }
+        None
+    }
+
+    fn check<T: FuzzTarget>(
+        &self,
+        target: &T,
+        storage: &MockStorage,
+        model: &T::Model,
+    ) -> Option<String> {
+        match catch_unwind(AssertUnwindSafe(|| target.check(storage, model))) {
+            Ok(Ok(())) => None,
+            Ok(Err(reason)) => Some(reason),
+            Err(panic) => Some(format!("check panicked: {}", panic_message(panic))),
+        }
+    }
+
+    /// Drops chunks of operations, halving the chunk size, while the sequence still fails
+    fn shrink<T: FuzzTarget>(
+        &self,
+        target: &T,
+        mut ops: Vec<T::Op>,
+        mut reason: String,
+    ) -> (Vec<T::Op>, String) {
+        let mut chunk = ops.len() / 2;
+        while chunk > 0 {
+            let mut start = 0;
+            while start < ops.len() {
+                let end = (start + chunk).min(ops.len());
+                let candidate: Vec<T::Op> =
+                    ops[..start].iter().chain(&ops[end..]).cloned().collect();
+                match self.execute(target, |step, _| candidate.get(step).cloned()) {
+                    Some((step, why)) => {
+                        ops = candidate[..=step].to_vec();
+                        reason = why;
+                    }
+                    None => start += chunk,
+                }
+            }
+            chunk /= 2;
+        }
+        (ops, reason)
+    }
+}
+
+fn snapshot(storage: &MockStorage) -> BTreeMap<Vec<u8>, Vec<u8>> {
+    storage.range(None, None, Order::Ascending).collect()
+}
+
+fn diff(before: &BTreeMap<Vec<u8>, Vec<u8>>, after: &BTreeMap<Vec<u8>, Vec<u8>>) -> String {
+    let mut changes = vec![];
+    for (key, value) in after {
+        match before.get(key) {
+            None => changes.push(format!("+{}", String::from_utf8_lossy(key))),
+            Some(old) if old != value => changes.push(format!("~{}", String::from_utf8_lossy(key))),
+            _ => {}
+        }
+    }
+    for key in before.keys().filter(|key| !after.contains_key(*key)) {
+        changes.push(format!("-{}", String::from_utf8_lossy(key)));
+    }
+    changes.join(", ")
+}
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Item, LinkedList};
+    use cosmwasm_std::StdError;
+    use std::collections::VecDeque;
+
+    const QUEUE: LinkedList<u32> = LinkedList::new("queue", "queue_state");
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum QueueOp {
+        PushBack(u32),
+        PushFront(u32),
+        PopFront,
+        Remove(u64),
+    }
+
+    #[derive(Default)]
+    struct QueueModel {
+        items: VecDeque<(u64, u32)>,
+        next_id: u64,
+    }
+
+    struct QueueTarget;
+
+    impl FuzzTarget for QueueTarget {
+        type Op = QueueOp;
+        type Model = QueueModel;
+
+        fn generate(&self, rng: &mut FuzzRng, model: &QueueModel) -> QueueOp {
+            match rng.below(4) {
+                0 => QueueOp::PushBack(rng.below(100) as u32),
+                1 => QueueOp::PushFront(rng.below(100) as u32),
+                2 => QueueOp::PopFront,
+                // sometimes an id that was never handed out
+                _ => QueueOp::Remove(rng.below(model.next_id + 1)),
+            }
+        }
+
+        fn apply(
+            &self,
+            storage: &mut dyn Storage,
+            model: &mut QueueModel,
+            op: &QueueOp,
+        ) -> StdResult<()> {
+            match op {
+                QueueOp::PushBack(value) => {
+                    let id = QUEUE.push_back(storage, *value)?;
+                    model.items.push_back((id, *value));
+                    model.next_id += 1;
+                }
+                QueueOp::PushFront(value) => {
+                    let id = QUEUE.push_front(storage, *value)?;
+                    model.items.push_front((id, *value));
+                    model.next_id += 1;
+                }
+                QueueOp::PopFront => {
+                    QUEUE.pop_front(storage)?;
+                    model.items.pop_front();
+                }
+                QueueOp::Remove(id) => {
+                    QUEUE.remove(storage, *id)?;
+                    model.items.retain(|(item, _)| item != id);
+                }
+            }
+            Ok(())
+        }
+
+        fn check(&self, storage: &dyn Storage, model: &QueueModel) -> Result<(), String> {
+            let stored: StdResult<Vec<_>> = QUEUE.iter(storage).unwrap().collect();
+            let expected: Vec<_> = model.items.iter().cloned().collect();
+            if stored.map_err(|err| err.to_string())? != expected {
+                return Err("queue differs from model".to_string());
+            }
+            if QUEUE.len(storage).unwrap() != expected.len() as u64 {
+                return Err("wrong length".to_string());
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn linked_list_matches_model() {
+        let fuzzer = Fuzzer::new(7).with_runs(50).with_steps(40);
+        if let Err(failure) = fuzzer.run(&QueueTarget) {
+            panic!("{}", failure);
+        }
+    }
+
+    /// A counter that writes before validating, and breaks its invariant past 3
+    struct LeakyCounter;
+
+    const COUNTER: Item<u32> = Item::new("counter");
+    const TOUCHED: Item<bool> = Item::new("touched");
+
+    impl FuzzTarget for LeakyCounter {
+        type Op = u32;
+        type Model = ();
+
+        fn generate(&self, rng: &mut FuzzRng, _: &()) -> u32 {
+            rng.below(3) as u32
+        }
+
+        fn apply(&self, storage: &mut dyn Storage, _: &mut (), op: &u32) -> StdResult<()> {
+            if *op == 0 {
+                TOUCHED.save(storage, &true)?;
+                return Err(StdError::generic_err("zero increment"));
+            }
+            let count = COUNTER.may_load(storage)?.unwrap_or_default();
+            COUNTER.save(storage, &(count + op))
+        }
+
+        fn check(&self, storage: &dyn Storage, _: &()) -> Result<(), String> {
+            match COUNTER.may_load(storage).unwrap() {
+                Some(count) if count > 3 => Err(format!("counter at {}", count)),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn failures_are_shrunk() {
+        let failure = Fuzzer::new(1)
+            .with_steps(30)
+            .run(&LeakyCounter)
+            .unwrap_err();
+        // either a single failed increment that wrote anyway, or two increments past 3
+        assert!(failure.ops.len() <= 2, "{}", failure);
+        assert!(Fuzzer::new(0).replay(&LeakyCounter, &failure.ops).is_err());
+        assert!(failure.to_string().contains("seed"));
+    }
+
+    #[test]
+    fn replays_sequences() {
+        assert!(Fuzzer::new(0).replay(&LeakyCounter, &[1, 2]).is_ok());
+
+        let err = Fuzzer::new(0).replay(&LeakyCounter, &[1, 0]).unwrap_err();
+        assert!(err.starts_with("step 1: failed with"), "{}", err);
+        assert!(err.ends_with("but changed +touched"), "{}", err);
+
+        // checked once at the end only
+        let err = Fuzzer::new(0)
+            .with_check_every(10)
+            .replay(&LeakyCounter, &[2, 1, 1])
+            .unwrap_err();
+        assert_eq!(err, "step 2: counter at 4");
+    }
+}
@@ -0,0 +1,160 @@
+//! A `History` is an append-only log of typed events, stamped with the block they happened in
+//! and read back newest first, like the SNIP-20 transfer history.
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+use cosmwasm_std::{Addr, StdError, StdResult, Storage};
+
+use crate::{AppendStore, Clock, Json, Serde};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry<E> {
+    /// Position of the entry in its history, starting at 0
+    pub id: u64,
+    pub block_height: u64,
+    /// Block time in seconds
+    pub block_time: u64,
+    pub event: E,
+}
+
+pub struct History<'a, E, Ser = Json>
+where
+    E: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    entries: AppendStore<'a, HistoryEntry<E>, Ser>,
+}
+
+impl<'a, E: Serialize + DeserializeOwned, Ser: Serde> History<'a, E, Ser> {
+    pub const fn new(namespace: &'a str) -> Self {
+        History {
+            entries: AppendStore::new(namespace),
+        }
+    }
+
+    /// The history of a single user, stored under this history's namespace
+    pub fn for_user(&self, user: &Addr) -> Self {
+        History {
+            entries: self.entries.add_suffix(user.as_str()),
+        }
+    }
+
+    /// Records the event at the current block and returns its id
+    pub fn push(&self, storage: &mut dyn Storage, clock: &impl Clock, event: E) -> StdResult<u64> {
+        let id = self.entries.get_len(storage)? as u64;
+        self.entries.push(
+            storage,
+            &HistoryEntry {
+                id,
+                block_height: clock.height(),
+                block_time: clock.seconds(),
+                event,
+            },
+        )?;
+        Ok(id)
+    }
+
+    pub fn len(&self, storage: &dyn Storage) -> StdResult<u32> {
+        self.entries.get_len(storage)
+    }
+
+    pub fn is_empty(&self, storage: &dyn Storage) -> StdResult<bool> {
+        self.entries.is_empty(storage)
+    }
+
+    pub fn get(&self, storage: &dyn Storage, id: u64) -> StdResult<HistoryEntry<E>> {
+        let pos = u32::try_from(id)
+            .map_err(|_| StdError::generic_err(format!("no history entry {}", id)))?;
+        self.entries.get_at(storage, pos)
+    }
+
+    /// Returns up to `page_size` entries, newest first, skipping `page` pages
+    pub fn page(
+        &self,
+        storage: &dyn Storage,
+        page: u32,
+        page_size: u32,
+    ) -> StdResult<Vec<HistoryEntry<E>>> {
+        let len = self.entries.get_len(storage)?;
+        let skip = page.saturating_mul(page_size);
+        let newest = match len.checked_sub(skip) {
+            Some(end) => end,
+            None => return Ok(vec![]),
+        };
+        let oldest = newest.saturating_sub(page_size);
+        (oldest..newest)
+            .rev()
+            .map(|pos| self.entries.get_at(storage, pos))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+    enum Event {
+        Transfer { to: String, amount: u128 },
+        ConfigChange,
+    }
+
+    const HISTORY: History<Event> = History::new("history");
+
+    #[test]
+    fn pages_newest_first() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let mut block = mock_env().block;
+
+        for amount in 0..5 {
+            HISTORY.push(
+                &mut storage,
+                &block,
+                Event::Transfer {
+                    to: "bob".to_string(),
+                    amount,
+                },
+            )?;
+            block.height += 1;
+        }
+        let id = HISTORY.push(&mut storage, &block, Event::ConfigChange)?;
+        assert_eq!(id, 5);
+        assert_eq!(HISTORY.len(&storage)?, 6);
+
+        let first = HISTORY.page(&storage, 0, 4)?;
+        assert_eq!(
+            first.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![5, 4, 3, 2]
+        );
+        assert_eq!(first[0].event, Event::ConfigChange);
+        assert_eq!(first[0].block_height, block.height);
+        assert_eq!(first[0].block_time, block.time.seconds());
+
+        let second = HISTORY.page(&storage, 1, 4)?;
+        assert_eq!(second.iter().map(|e| e.id).collect::<Vec<_>>(), vec![1, 0]);
+        assert!(HISTORY.page(&storage, 2, 4)?.is_empty());
+
+        assert_eq!(HISTORY.get(&storage, 5)?.event, Event::ConfigChange);
+        assert!(HISTORY.get(&storage, 6).is_err());
+        // ids past u32 don't wrap around to existing entries
+        assert!(HISTORY.get(&storage, u32::MAX as u64 + 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn per_user_histories() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let block = mock_env().block;
+        let (alice, bob) = (Addr::unchecked("alice"), Addr::unchecked("bob"));
+
+        HISTORY
+            .for_user(&alice)
+            .push(&mut storage, &block, Event::ConfigChange)?;
+        assert_eq!(HISTORY.for_user(&alice).len(&storage)?, 1);
+        assert!(HISTORY.for_user(&bob).is_empty(&storage)?);
+        assert!(HISTORY.is_empty(&storage)?);
+        Ok(())
+    }
+}
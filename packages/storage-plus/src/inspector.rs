@@ -0,0 +1,240 @@
+#![cfg(feature = "debug")]
+//! Storage usage introspection, meant for tests and for analysing exported contract state.
+//!
+//! ```ignore
+//! let report = StorageInspector::new()
+//!     .with_namespaces(&["config", "history"])
+//!     .storage(&deps.storage)
+//!     .report();
+//! println!("{}", report);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[cfg(feature = "iterator")]
+use cosmwasm_std::{Order, Storage};
+
+/// Usage of a single namespace
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamespaceUsage {
+    pub keys: u64,
+    /// Size of keys and values combined
+    pub bytes: u64,
+    /// Biggest entries as (key, entry size), largest first
+    pub largest: Vec<(Vec<u8>, u64)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StorageInspector {
+    namespaces: BTreeMap<String, NamespaceUsage>,
+    known: Vec<String>,
+    max_largest: usize,
+}
+
+impl Default for StorageInspector {
+    fn default() -> Self {
+        StorageInspector {
+            namespaces: BTreeMap::new(),
+            known: vec![],
+            max_largest: 5,
+        }
+    }
+}
+
+impl StorageInspector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many of the largest entries to keep per namespace, defaults to 5
+    pub fn with_largest(mut self, amount: usize) -> Self {
+        self.max_largest = amount;
+        self
+    }
+
+    /// Namespaces of stores that don't length prefix their keys (Item, AppendStore, DequeStore),
+    /// keys starting with one of them are grouped under it
+    pub fn with_namespaces(mut self, namespaces: &[&str]) -> Self {
+        self.known.extend(namespaces.iter().map(|n| n.to_string()));
+        // longest first so nested namespaces win
+        self.known.sort_by(|a, b| b.len().cmp(&a.len()));
+        self
+    }
+
+    /// Inspects exported raw state
+    pub fn records<I: IntoIterator<Item = (Vec<u8>, Vec<u8>)>>(mut self, records: I) -> Self {
+        for (key, value) in records {
+            self.add(&key, &value);
+        }
+        self
+    }
+
+    /// Inspects every entry of the storage
+    #[cfg(feature = "iterator")]
+    pub fn storage(self, storage: &dyn Storage) -> Self {
+        self.records(storage.range(None, None, Order::Ascending))
+    }
+
+    pub fn add(&mut self, key: &[u8], value: &[u8]) {
+        let size = (key.len() + value.len()) as u64;
+        let max_largest = self.max_largest;
+        let namespace = match self.known.iter().find(|n| key.starts_with(n.as_bytes())) {
+            Some(known) => known.clone(),
+            None => namespace_of(key),
+        };
+        let usage = self.namespaces.entry(namespace).or_default();
+        usage.keys += 1;
+        usage.bytes += size;
+
+        let pos = usage.largest.partition_point(|(_, s)| *s >= size);
+        if pos < max_largest {
+            usage.largest.insert(pos, (key.to_vec(), size));
+            usage.largest.truncate(max_largest);
+        }
+    }
+
+    pub fn report(&self) -> StorageReport {
+        let mut namespaces: Vec<_> = self
+            .namespaces
+            .iter()
+            .map(|(name, usage)| (name.clone(), usage.clone()))
+            .collect();
+        namespaces.sort_by(|(_, a), (_, b)| b.bytes.cmp(&a.bytes));
+        StorageReport { namespaces }
+    }
+}
+
+/// Usage per namespace, biggest namespaces first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageReport {
+    pub namespaces: Vec<(String, NamespaceUsage)>,
+}
+
+impl StorageReport {
+    pub fn total_keys(&self) -> u64 {
+        self.namespaces.iter().map(|(_, usage)| usage.keys).sum()
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.namespaces.iter().map(|(_, usage)| usage.bytes).sum()
+    }
+
+    pub fn get(&self, namespace: &str) -> Option<&NamespaceUsage> {
+        self.namespaces
+            .iter()
+            .find(|(name, _)| name == namespace)
+            .map(|(_, usage)| usage)
+    }
+}
+
+impl fmt::Display for StorageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} keys, {} bytes",
+            self.total_keys(),
+            self.total_bytes()
+        )?;
+        for (name, usage) in &self.namespaces {
+            writeln!(
+                f,
+                "  {:<24} {:>8} keys {:>10} bytes",
+                name, usage.keys, usage.bytes
+            )?;
+            for (key, size) in &usage.largest {
+                writeln!(f, "    {:>10} bytes  {}", size, printable(key))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Guesses the namespace of a raw key.
+///
+/// Map keys start with a length prefixed namespace, other keys are grouped by their leading
+/// identifier characters.
+fn namespace_of(key: &[u8]) -> String {
+    if key.len() > 2 {
+        let len = u16::from_be_bytes([key[0], key[1]]) as usize;
+        if len > 0 && len <= key.len() - 2 && is_identifier(&key[2..2 + len]) {
+            return String::from_utf8_lossy(&key[2..2 + len]).to_string();
+        }
+    }
+    let end = key
+        .iter()
+        .position(|b| !(b.is_ascii_alphanumeric() || *b == b'_' || *b == b'-'))
+        .unwrap_or(key.len());
+    if end == 0 {
+        return "<binary>".to_string();
+    }
+    String::from_utf8_lossy(&key[..end]).to_string()
+}
+
+fn is_identifier(bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .all(|b| b.is_ascii_alphanumeric() || *b == b'_' || *b == b'-')
+}
+
+fn printable(key: &[u8]) -> String {
+    key.iter()
+        .map(|b| {
+            if b.is_ascii_graphic() {
+                (*b as char).to_string()
+            } else {
+                format!("\\x{:02x}", b)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{AppendStore, Item, Map};
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::Storage;
+
+    #[test]
+    fn counts_per_namespace() {
+        let mut storage = MockStorage::new();
+        const CONFIG: Item<String> = Item::new("config");
+        const BALANCES: Map<&str, u64> = Map::new("balances");
+        let history: AppendStore<String> = AppendStore::new("history");
+
+        CONFIG.save(&mut storage, &"admin".to_string()).unwrap();
+        BALANCES.save(&mut storage, "alice", &1).unwrap();
+        BALANCES.save(&mut storage, "bob", &1_000_000).unwrap();
+        history.push(&mut storage, &"a".repeat(100)).unwrap();
+
+        let mut records = vec![];
+        for key in [
+            CONFIG.as_slice().to_vec(),
+            BALANCES.key("alice").to_vec(),
+            BALANCES.key("bob").to_vec(),
+            [b"history".as_slice(), b"len"].concat(),
+            [b"history".as_slice(), &0u32.to_be_bytes()].concat(),
+        ] {
+            let value = storage.get(&key).unwrap();
+            records.push((key, value));
+        }
+
+        let report = StorageInspector::new()
+            .with_largest(1)
+            .with_namespaces(&["config", "history"])
+            .records(records)
+            .report();
+        assert_eq!(report.total_keys(), 5);
+        // history holds the biggest entry so it's listed first
+        assert_eq!(report.namespaces[0].0, "history");
+
+        let balances = report.get("balances").unwrap();
+        assert_eq!(balances.keys, 2);
+        assert_eq!(balances.largest.len(), 1);
+        assert_eq!(balances.largest[0].0, BALANCES.key("bob").to_vec());
+
+        assert_eq!(report.get("config").unwrap().keys, 1);
+        assert!(report.to_string().starts_with("5 keys"));
+    }
+}
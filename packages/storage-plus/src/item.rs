@@ -16,6 +16,8 @@ pub struct Item<'a, T, Ser = Json>
 {
     // this is full key - no need to length-prefix it, we only store one item
     storage_key: &'a [u8],
+    /// needed if any suffixes were added to the original key.
+    prefix: Option<Vec<u8>>,
     // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
     data_type: PhantomData<T>,
     serialization_type: PhantomData<*const Ser>,
@@ -28,6 +30,7 @@ where
     pub const fn new(storage_key: &'a str) -> Self {
         Item {
             storage_key: storage_key.as_bytes(),
+            prefix: None,
             data_type: PhantomData,
             serialization_type: PhantomData,
         }
@@ -41,29 +44,43 @@ where
 {
     // this gets the path of the data to use elsewhere
     pub fn as_slice(&self) -> &[u8] {
-        self.storage_key
+        match &self.prefix {
+            Some(prefix) => prefix,
+            None => self.storage_key,
+        }
+    }
+
+    /// This is used to produce a new Item under a suffixed key. This can be used when you want
+    /// to associate an Item to each user and still define the Item as a constant
+    pub fn add_suffix(&self, suffix: &str) -> Self {
+        Item {
+            storage_key: self.storage_key,
+            prefix: Some([self.as_slice(), suffix.as_bytes()].concat()),
+            data_type: PhantomData,
+            serialization_type: PhantomData,
+        }
     }
 
     /// save will serialize the model and store, returns an error on serialization issues
     pub fn save(&self, store: &mut dyn Storage, data: &T) -> StdResult<()> {
-        store.set(self.storage_key, &Ser::serialize(data)?);
+        store.set(self.as_slice(), &Ser::serialize(data)?);
         Ok(())
     }
 
     pub fn remove(&self, store: &mut dyn Storage) {
-        store.remove(self.storage_key);
+        store.remove(self.as_slice());
     }
 
     /// load will return an error if no data is set at the given key, or on parse error
     pub fn load(&self, store: &dyn Storage) -> StdResult<T> {
-        let value = store.get(self.storage_key);
+        let value = store.get(self.as_slice());
         must_deserialize::<T, Ser>(&value)
     }
 
     /// may_load will parse the data stored at the key if present, returns `Ok(None)` if no data there.
     /// returns an error on issues parsing
     pub fn may_load(&self, store: &dyn Storage) -> StdResult<Option<T>> {
-        let value = store.get(self.storage_key);
+        let value = store.get(self.as_slice());
         may_deserialize::<T, Ser>(&value)
     }
 
@@ -96,7 +113,7 @@ where
         let request = WasmQuery::Smart {
             contract_addr: remote_contract.into(),
             code_hash: remote_contract_code_hash,
-            msg: self.storage_key.into(),
+            msg: self.as_slice().into(),
         };
         querier.query(&request.into())
     }
@@ -0,0 +1,75 @@
+#![cfg(feature = "iterator")]
+//! Controls what happens when an entry fails to deserialize while iterating a store.
+
+use cosmwasm_std::{Record, StdError, StdResult};
+
+/// What to do with entries that fail to deserialize
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodePolicy {
+    /// Return the error and stop iterating, this is what `range` does
+    FailFast,
+    /// Silently skip entries that can't be deserialized
+    SkipCorrupt,
+    /// Return the raw key and value of entries that can't be deserialized
+    YieldRaw,
+}
+
+/// An entry that could not be deserialized, keeping its raw contents around for reporting
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorruptEntry {
+    /// Key relative to the iterated prefix
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    pub error: StdError,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decoded<K, T> {
+    Valid(K, T),
+    Corrupt(CorruptEntry),
+}
+
+impl<K, T> Decoded<K, T> {
+    /// Returns the deserialized entry, or None if it was corrupt
+    pub fn valid(self) -> Option<(K, T)> {
+        match self {
+            Decoded::Valid(k, t) => Some((k, t)),
+            Decoded::Corrupt(_) => None,
+        }
+    }
+}
+
+/// Deserializes every raw record with `de_fn`, handling failures according to `policy`
+pub(crate) fn decode_with_policy<'a, K, T, F>(
+    records: Box<dyn Iterator<Item = Record> + 'a>,
+    policy: DecodePolicy,
+    de_fn: F,
+) -> Box<dyn Iterator<Item = StdResult<Decoded<K, T>>> + 'a>
+where
+    K: 'a,
+    T: 'a,
+    F: Fn(Record) -> StdResult<(K, T)> + 'a,
+{
+    let decoded = records
+        .filter_map(
+            move |(key, value)| match de_fn((key.clone(), value.clone())) {
+                Ok((k, t)) => Some(Ok(Decoded::Valid(k, t))),
+                Err(error) => match policy {
+                    DecodePolicy::FailFast => Some(Err(error)),
+                    DecodePolicy::SkipCorrupt => None,
+                    DecodePolicy::YieldRaw => {
+                        Some(Ok(Decoded::Corrupt(CorruptEntry { key, value, error })))
+                    }
+                },
+            },
+        )
+        // FailFast stops right after the first error
+        .scan(false, |failed, item| {
+            if *failed {
+                return None;
+            }
+            *failed = item.is_err();
+            Some(item)
+        });
+    Box::new(decoded)
+}
@@ -0,0 +1,124 @@
+#![cfg(feature = "legacy-storage")]
+//! Adapters exposing the pre-1.0 Secret `Storage`/`ReadonlyStorage` traits as a cosmwasm-std 1.x
+//! [Storage], so contracts still on the old toolchain can use the same storage types.
+//!
+//! ```ignore
+//! CONFIG.save(&mut LegacyStorage::new(&mut deps.storage), &config)?;
+//! let config = CONFIG.load(&LegacyReadonlyStorage::new(&deps.storage))?;
+//! ```
+
+use cosmwasm_std::Storage;
+#[cfg(feature = "iterator")]
+use cosmwasm_std::{Order, Record};
+use legacy_std::{ReadonlyStorage as LegacyReadonly, Storage as LegacyStore};
+
+#[cfg(feature = "iterator")]
+fn legacy_order(order: Order) -> legacy_std::Order {
+    match order {
+        Order::Ascending => legacy_std::Order::Ascending,
+        Order::Descending => legacy_std::Order::Descending,
+    }
+}
+
+/// Read and write access to a legacy storage
+pub struct LegacyStorage<'a, S: LegacyStore> {
+    storage: &'a mut S,
+}
+
+impl<'a, S: LegacyStore> LegacyStorage<'a, S> {
+    pub fn new(storage: &'a mut S) -> Self {
+        Self { storage }
+    }
+}
+
+impl<'a, S: LegacyStore> Storage for LegacyStorage<'a, S> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.storage.get(key)
+    }
+
+    #[cfg(feature = "iterator")]
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        self.storage.range(start, end, legacy_order(order))
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.storage.set(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.storage.remove(key)
+    }
+}
+
+/// Read only access to a legacy storage, writing through it panics
+pub struct LegacyReadonlyStorage<'a, S: LegacyReadonly> {
+    storage: &'a S,
+}
+
+impl<'a, S: LegacyReadonly> LegacyReadonlyStorage<'a, S> {
+    pub fn new(storage: &'a S) -> Self {
+        Self { storage }
+    }
+}
+
+impl<'a, S: LegacyReadonly> Storage for LegacyReadonlyStorage<'a, S> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.storage.get(key)
+    }
+
+    #[cfg(feature = "iterator")]
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        self.storage.range(start, end, legacy_order(order))
+    }
+
+    fn set(&mut self, _key: &[u8], _value: &[u8]) {
+        panic!("Can not write to a LegacyReadonlyStorage");
+    }
+
+    fn remove(&mut self, _key: &[u8]) {
+        panic!("Can not write to a LegacyReadonlyStorage");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Item, Map};
+    use legacy_std::testing::MockStorage as LegacyMockStorage;
+
+    const COUNT: Item<u64> = Item::new("count");
+    const BALANCES: Map<&str, u128> = Map::new("balances");
+
+    #[test]
+    fn item_and_map_on_legacy_storage() {
+        let mut legacy = LegacyMockStorage::new();
+
+        COUNT
+            .save(&mut LegacyStorage::new(&mut legacy), &7)
+            .unwrap();
+        BALANCES
+            .save(&mut LegacyStorage::new(&mut legacy), "alice", &100)
+            .unwrap();
+
+        let readonly = LegacyReadonlyStorage::new(&legacy);
+        assert_eq!(COUNT.load(&readonly).unwrap(), 7);
+        assert_eq!(BALANCES.load(&readonly, "alice").unwrap(), 100);
+        assert_eq!(BALANCES.may_load(&readonly, "bob").unwrap(), None);
+
+        // data is written with the same layout as on the 1.x storage
+        assert_eq!(
+            legacy.get(COUNT.as_slice()),
+            Some(cosmwasm_std::to_vec(&7u64).unwrap())
+        );
+    }
+}
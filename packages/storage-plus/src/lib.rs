@@ -1,31 +1,71 @@
+mod addr_key;
 mod append_store;
+#[cfg(feature = "iterator")]
+mod auction;
+mod bitset;
+mod bloom;
 mod bound;
+mod clock;
+mod config;
+mod dead_mans_switch;
 mod de;
 mod de_old;
 mod deque_store;
 mod endian;
+mod enumerable_map;
+mod escrow;
+mod fuzz;
 mod helpers;
+mod history;
 mod indexed_map;
 mod indexed_snapshot;
 mod indexes;
+mod inspector;
 mod int_key;
 mod item;
 mod iter_helpers;
+mod iter_policy;
 mod keys;
 mod keys_old;
+mod legacy;
+mod linked_list;
+mod lru;
 mod map;
+mod nonce;
 mod path;
 mod prefix;
+mod prefix_sum;
 mod serialization;
 mod snapshot;
+mod stats;
 mod traits;
+mod user_store;
+mod versioned;
+mod vesting;
+mod viewing_key;
+#[cfg(feature = "iterator")]
+mod voting;
+mod weighted_picker;
 
+pub use addr_key::AddrKey;
 pub use append_store::AppendStore;
 #[cfg(feature = "iterator")]
+pub use auction::{dutch_purchase, Bid, BidBook, PriceCurve, Settlement};
+pub use bitset::{Bitset, BITSET_CHUNK_BITS};
+pub use bloom::{BloomConfig, BloomFilter, BloomState};
+#[cfg(feature = "iterator")]
 pub use bound::{Bound, Bounder, PrefixBound, RawBound};
+pub use clock::{Clock, MockClock};
+pub use config::{Config, PartialUpdate};
+pub use dead_mans_switch::{DeadMansSwitch, SwitchState};
 pub use de::KeyDeserialize;
 pub use deque_store::DequeStore;
 pub use endian::Endian;
+pub use enumerable_map::EnumerableMap;
+pub use escrow::{Asset, Escrow, Snip20ReceiveMsg};
+#[cfg(feature = "fuzz")]
+pub use fuzz::{Failure, FuzzRng, FuzzTarget, Fuzzer};
+pub use history::{History, HistoryEntry};
 #[cfg(feature = "iterator")]
 pub use indexed_map::{IndexList, IndexedMap};
 #[cfg(feature = "iterator")]
@@ -36,18 +76,36 @@ pub use indexes::Index;
 pub use indexes::MultiIndex;
 #[cfg(feature = "iterator")]
 pub use indexes::UniqueIndex;
+#[cfg(feature = "debug")]
+pub use inspector::{NamespaceUsage, StorageInspector, StorageReport};
 pub use int_key::CwIntKey;
 pub use item::Item;
+#[cfg(feature = "iterator")]
+pub use iter_policy::{CorruptEntry, DecodePolicy, Decoded};
 pub use keys::{Key, Prefixer, PrimaryKey};
 pub use keys_old::IntKeyOld;
+#[cfg(feature = "legacy-storage")]
+pub use legacy::{LegacyReadonlyStorage, LegacyStorage};
+pub use linked_list::{LinkedList, LinkedListIter, Node};
+pub use lru::{LruEntry, LruMap};
 pub use map::Map;
+pub use nonce::{NonceOrder, NonceRegistry};
 pub use path::Path;
 pub use traits::{GenericItemStorage, GenericMapStorage, ItemStorage, MapStorage, NaiveItemStorage, NaiveMapStorage};
 #[cfg(feature = "iterator")]
 pub use prefix::{range_with_prefix, Prefix};
-pub use serialization::{Bincode2, Json, Serde};
+pub use prefix_sum::PrefixSum;
+pub use serialization::{canonicalize_json, Bincode2, Json, Serde};
+pub use user_store::{Suffixable, UserStore};
+pub use versioned::{from_envelope, to_envelope, Versioned};
+pub use vesting::{Vesting, VestingAccount, VestingSchedule};
+pub use viewing_key::{Capabilities, ViewingKeys};
+#[cfg(feature = "iterator")]
+pub use voting::{Proposal, ProposalStatus, Vote, VotingPower, VotingRules};
+pub use weighted_picker::WeightedPicker;
 #[cfg(feature = "iterator")]
 pub use snapshot::{SnapshotItem, SnapshotMap, Strategy};
+pub use stats::{Ema, EmaState, Extremes, MinMax, RunningStats, WelfordStats};
 
 #[cfg(test)]
 pub use rstest_reuse;
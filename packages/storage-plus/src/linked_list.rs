@@ -0,0 +1,314 @@
+//! A `LinkedList` is a doubly linked list whose nodes are addressed by the id returned when
+//! they were inserted. Holding on to ids, contracts can insert next to or remove any node in
+//! O(1), e.g. for orderbook price levels or least recently used eviction.
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+
+use crate::{Item, Map};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Node<T> {
+    pub value: T,
+    pub prev: Option<u64>,
+    pub next: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ListState {
+    head: Option<u64>,
+    tail: Option<u64>,
+    len: u64,
+    /// Ids are never reused, so a stale id can't point at someone else's node
+    next_id: u64,
+}
+
+pub struct LinkedList<'a, T> {
+    nodes: Map<'a, u64, Node<T>>,
+    state: Item<'a, ListState>,
+}
+
+impl<'a, T> LinkedList<'a, T> {
+    pub const fn new(nodes_namespace: &'a str, state_key: &'a str) -> Self {
+        LinkedList {
+            nodes: Map::new(nodes_namespace),
+            state: Item::new(state_key),
+        }
+    }
+}
+
+impl<'a, T> LinkedList<'a, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn load_state(&self, store: &dyn Storage) -> StdResult<ListState> {
+        Ok(self.state.may_load(store)?.unwrap_or_default())
+    }
+
+    pub fn len(&self, store: &dyn Storage) -> StdResult<u64> {
+        Ok(self.load_state(store)?.len)
+    }
+
+    pub fn is_empty(&self, store: &dyn Storage) -> StdResult<bool> {
+        Ok(self.len(store)? == 0)
+    }
+
+    pub fn head(&self, store: &dyn Storage) -> StdResult<Option<u64>> {
+        Ok(self.load_state(store)?.head)
+    }
+
+    pub fn tail(&self, store: &dyn Storage) -> StdResult<Option<u64>> {
+        Ok(self.load_state(store)?.tail)
+    }
+
+    pub fn get(&self, store: &dyn Storage, id: u64) -> StdResult<Node<T>> {
+        self.nodes
+            .may_load(store, id)?
+            .ok_or_else(|| StdError::not_found(format!("list node {}", id)))
+    }
+
+    pub fn may_get(&self, store: &dyn Storage, id: u64) -> StdResult<Option<Node<T>>> {
+        self.nodes.may_load(store, id)
+    }
+
+    /// Replaces the value of a node, keeping its position
+    pub fn set(&self, store: &mut dyn Storage, id: u64, value: T) -> StdResult<()> {
+        let mut node = self.get(store, id)?;
+        node.value = value;
+        self.nodes.save(store, id, &node)
+    }
+
+    pub fn push_front(&self, store: &mut dyn Storage, value: T) -> StdResult<u64> {
+        let head = self.load_state(store)?.head;
+        self.link(store, value, None, head)
+    }
+
+    pub fn push_back(&self, store: &mut dyn Storage, value: T) -> StdResult<u64> {
+        let tail = self.load_state(store)?.tail;
+        self.link(store, value, tail, None)
+    }
+
+    pub fn insert_after(&self, store: &mut dyn Storage, id: u64, value: T) -> StdResult<u64> {
+        let next = self.get(store, id)?.next;
+        self.link(store, value, Some(id), next)
+    }
+
+    pub fn insert_before(&self, store: &mut dyn Storage, id: u64, value: T) -> StdResult<u64> {
+        let prev = self.get(store, id)?.prev;
+        self.link(store, value, prev, Some(id))
+    }
+
+    /// Removes the node and returns its value
+    pub fn remove(&self, store: &mut dyn Storage, id: u64) -> StdResult<T> {
+        let node = self.get(store, id)?;
+        self.unlink(store, &node)?;
+        self.nodes.remove(store, id);
+        let mut state = self.load_state(store)?;
+        state.len -= 1;
+        self.state.save(store, &state)?;
+        Ok(node.value)
+    }
+
+    pub fn pop_front(&self, store: &mut dyn Storage) -> StdResult<Option<(u64, T)>> {
+        match self.head(store)? {
+            Some(id) => Ok(Some((id, self.remove(store, id)?))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn pop_back(&self, store: &mut dyn Storage) -> StdResult<Option<(u64, T)>> {
+        match self.tail(store)? {
+            Some(id) => Ok(Some((id, self.remove(store, id)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Moves an existing node to the front, keeping its id
+    pub fn move_to_front(&self, store: &mut dyn Storage, id: u64) -> StdResult<()> {
+        let mut node = self.get(store, id)?;
+        if node.prev.is_none() {
+            return Ok(());
+        }
+        self.unlink(store, &node)?;
+        let mut state = self.load_state(store)?;
+        node.prev = None;
+        node.next = state.head;
+        if let Some(head) = state.head {
+            self.update_node(store, head, |head| head.prev = Some(id))?;
+        }
+        state.head = Some(id);
+        self.nodes.save(store, id, &node)?;
+        self.state.save(store, &state)
+    }
+
+    /// Node ids and values from head to tail
+    pub fn iter<'c>(&'c self, store: &'c dyn Storage) -> StdResult<LinkedListIter<'a, 'c, T>> {
+        Ok(LinkedListIter {
+            list: self,
+            store,
+            next: self.head(store)?,
+            forward: true,
+        })
+    }
+
+    /// Node ids and values from tail to head
+    pub fn iter_rev<'c>(&'c self, store: &'c dyn Storage) -> StdResult<LinkedListIter<'a, 'c, T>> {
+        Ok(LinkedListIter {
+            list: self,
+            store,
+            next: self.tail(store)?,
+            forward: false,
+        })
+    }
+
+    fn update_node<F>(&self, store: &mut dyn Storage, id: u64, action: F) -> StdResult<()>
+    where
+        F: FnOnce(&mut Node<T>),
+    {
+        let mut node = self.get(store, id)?;
+        action(&mut node);
+        self.nodes.save(store, id, &node)
+    }
+
+    /// Stores a new node between `prev` and `next`, which must be neighbours
+    fn link(
+        &self,
+        store: &mut dyn Storage,
+        value: T,
+        prev: Option<u64>,
+        next: Option<u64>,
+    ) -> StdResult<u64> {
+        let mut state = self.load_state(store)?;
+        let id = state.next_id;
+        match prev {
+            Some(prev) => self.update_node(store, prev, |node| node.next = Some(id))?,
+            None => state.head = Some(id),
+        }
+        match next {
+            Some(next) => self.update_node(store, next, |node| node.prev = Some(id))?,
+            None => state.tail = Some(id),
+        }
+        self.nodes.save(store, id, &Node { value, prev, next })?;
+        state.next_id += 1;
+        state.len += 1;
+        self.state.save(store, &state)?;
+        Ok(id)
+    }
+
+    /// Connects the node's neighbours to each other, leaving the node itself as is
+    fn unlink(&self, store: &mut dyn Storage, node: &Node<T>) -> StdResult<()> {
+        let mut state = self.load_state(store)?;
+        match node.prev {
+            Some(prev) => self.update_node(store, prev, |prev| prev.next = node.next)?,
+            None => state.head = node.next,
+        }
+        match node.next {
+            Some(next) => self.update_node(store, next, |next| next.prev = node.prev)?,
+            None => state.tail = node.prev,
+        }
+        self.state.save(store, &state)
+    }
+}
+
+pub struct LinkedListIter<'a, 'c, T> {
+    list: &'c LinkedList<'a, T>,
+    store: &'c dyn Storage,
+    next: Option<u64>,
+    forward: bool,
+}
+
+impl<'a, 'c, T> Iterator for LinkedListIter<'a, 'c, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    type Item = StdResult<(u64, T)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next?;
+        match self.list.get(self.store, id) {
+            Ok(node) => {
+                self.next = if self.forward { node.next } else { node.prev };
+                Some(Ok((id, node.value)))
+            }
+            Err(err) => {
+                self.next = None;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    const LEVELS: LinkedList<String> = LinkedList::new("levels", "levels_state");
+
+    fn values(storage: &dyn Storage) -> Vec<String> {
+        LEVELS
+            .iter(storage)
+            .unwrap()
+            .map(|item| item.unwrap().1)
+            .collect()
+    }
+
+    #[test]
+    fn insert_and_remove() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        assert!(LEVELS.is_empty(&storage)?);
+        assert_eq!(LEVELS.pop_front(&mut storage)?, None);
+
+        let b = LEVELS.push_back(&mut storage, "b".to_string())?;
+        let d = LEVELS.push_back(&mut storage, "d".to_string())?;
+        let a = LEVELS.push_front(&mut storage, "a".to_string())?;
+        let c = LEVELS.insert_after(&mut storage, b, "c".to_string())?;
+        LEVELS.insert_before(&mut storage, a, "start".to_string())?;
+        assert_eq!(values(&storage), vec!["start", "a", "b", "c", "d"]);
+        assert_eq!(LEVELS.len(&storage)?, 5);
+
+        assert_eq!(LEVELS.remove(&mut storage, c)?, "c");
+        assert!(LEVELS.remove(&mut storage, c).is_err());
+        assert_eq!(LEVELS.get(&storage, b)?.next, Some(d));
+        assert_eq!(LEVELS.get(&storage, d)?.prev, Some(b));
+
+        assert_eq!(LEVELS.pop_back(&mut storage)?, Some((d, "d".to_string())));
+        assert_eq!(LEVELS.tail(&storage)?, Some(b));
+        LEVELS.set(&mut storage, b, "B".to_string())?;
+        assert_eq!(values(&storage), vec!["start", "a", "B"]);
+        assert_eq!(LEVELS.len(&storage)?, 3);
+
+        // ids are not reused
+        let e = LEVELS.push_back(&mut storage, "e".to_string())?;
+        assert_eq!(e, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn move_and_iterate_back() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let ids: Vec<u64> = ["a", "b", "c"]
+            .iter()
+            .map(|v| LEVELS.push_back(&mut storage, v.to_string()).unwrap())
+            .collect();
+
+        LEVELS.move_to_front(&mut storage, ids[2])?;
+        assert_eq!(values(&storage), vec!["c", "a", "b"]);
+        assert_eq!(LEVELS.tail(&storage)?, Some(ids[1]));
+        LEVELS.move_to_front(&mut storage, ids[2])?;
+        LEVELS.move_to_front(&mut storage, ids[1])?;
+        assert_eq!(values(&storage), vec!["b", "c", "a"]);
+
+        let back: StdResult<Vec<_>> = LEVELS.iter_rev(&storage)?.collect();
+        assert_eq!(
+            back?,
+            vec![
+                (ids[0], "a".to_string()),
+                (ids[2], "c".to_string()),
+                (ids[1], "b".to_string())
+            ]
+        );
+        Ok(())
+    }
+}
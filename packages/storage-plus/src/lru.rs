@@ -0,0 +1,185 @@
+//! An `LruMap` is a map holding at most `capacity` entries. Inserting into a full map evicts
+//! the least recently used entry, which suits caching expensive cross-contract query results.
+//! Recency is tracked in a [LinkedList], most recently used first.
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+use cosmwasm_std::{Binary, StdError, StdResult, Storage};
+
+use crate::{LinkedList, Map, PrimaryKey};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LruEntry<V> {
+    /// The entry's key as stored in the index, kept to unindex the entry on eviction
+    pub key: Binary,
+    pub value: V,
+}
+
+pub struct LruMap<'a, K, V> {
+    /// Joined key to the entry's node in `entries`
+    index: Map<'a, Vec<u8>, u64>,
+    entries: LinkedList<'a, LruEntry<V>>,
+    capacity: u64,
+    key_type: PhantomData<K>,
+}
+
+impl<'a, K, V> LruMap<'a, K, V> {
+    pub const fn new(
+        index_namespace: &'a str,
+        entries_namespace: &'a str,
+        entries_state_key: &'a str,
+        capacity: u64,
+    ) -> Self {
+        LruMap {
+            index: Map::new(index_namespace),
+            entries: LinkedList::new(entries_namespace, entries_state_key),
+            capacity,
+            key_type: PhantomData,
+        }
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+}
+
+impl<'a, K, V> LruMap<'a, K, V>
+where
+    K: PrimaryKey<'a>,
+    V: Serialize + DeserializeOwned,
+{
+    pub fn len(&self, store: &dyn Storage) -> StdResult<u64> {
+        self.entries.len(store)
+    }
+
+    pub fn is_empty(&self, store: &dyn Storage) -> StdResult<bool> {
+        self.entries.is_empty(store)
+    }
+
+    /// The value without marking it as used, for queries
+    pub fn peek(&self, store: &dyn Storage, key: K) -> StdResult<Option<V>> {
+        match self.index.may_load(store, key.joined_key())? {
+            Some(id) => Ok(Some(self.entries.get(store, id)?.value.value)),
+            None => Ok(None),
+        }
+    }
+
+    /// The value, marking it as the most recently used
+    pub fn get(&self, store: &mut dyn Storage, key: K) -> StdResult<Option<V>> {
+        match self.index.may_load(store, key.joined_key())? {
+            Some(id) => {
+                self.entries.move_to_front(store, id)?;
+                Ok(Some(self.entries.get(store, id)?.value.value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Stores the value as the most recently used entry. Returns the least recently used
+    /// entry if it had to be evicted to make room.
+    pub fn insert(
+        &self,
+        store: &mut dyn Storage,
+        key: K,
+        value: V,
+    ) -> StdResult<Option<LruEntry<V>>> {
+        if self.capacity == 0 {
+            return Err(StdError::generic_err("LRU map has no capacity"));
+        }
+        let key = key.joined_key();
+        if let Some(id) = self.index.may_load(store, key.clone())? {
+            self.entries.set(
+                store,
+                id,
+                LruEntry {
+                    key: key.into(),
+                    value,
+                },
+            )?;
+            self.entries.move_to_front(store, id)?;
+            return Ok(None);
+        }
+
+        let evicted = if self.entries.len(store)? >= self.capacity {
+            match self.entries.pop_back(store)? {
+                Some((_, entry)) => {
+                    self.index.remove(store, entry.key.to_vec());
+                    Some(entry)
+                }
+                None => None,
+            }
+        } else {
+            None
+        };
+        let id = self.entries.push_front(
+            store,
+            LruEntry {
+                key: key.clone().into(),
+                value,
+            },
+        )?;
+        self.index.save(store, key, &id)?;
+        Ok(evicted)
+    }
+
+    pub fn remove(&self, store: &mut dyn Storage, key: K) -> StdResult<Option<V>> {
+        let key = key.joined_key();
+        match self.index.may_load(store, key.clone())? {
+            Some(id) => {
+                self.index.remove(store, key);
+                Ok(Some(self.entries.remove(store, id)?.value))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    const PRICES: LruMap<&str, u64> = LruMap::new("prices", "prices_lru", "prices_lru_state", 2);
+
+    #[test]
+    fn evicts_least_recently_used() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        assert_eq!(PRICES.insert(&mut storage, "scrt", 1)?, None);
+        assert_eq!(PRICES.insert(&mut storage, "atom", 2)?, None);
+        assert_eq!(PRICES.len(&storage)?, 2);
+
+        // using scrt makes atom the least recently used
+        assert_eq!(PRICES.get(&mut storage, "scrt")?, Some(1));
+        let evicted = PRICES.insert(&mut storage, "osmo", 3)?.unwrap();
+        assert_eq!(evicted.value, 2);
+        assert_eq!(evicted.key.as_slice(), b"atom");
+        assert_eq!(PRICES.peek(&storage, "atom")?, None);
+        assert_eq!(PRICES.len(&storage)?, 2);
+
+        // peeking doesn't count as a use
+        assert_eq!(PRICES.peek(&storage, "scrt")?, Some(1));
+        assert_eq!(PRICES.insert(&mut storage, "atom", 4)?.unwrap().value, 1);
+        assert_eq!(PRICES.peek(&storage, "osmo")?, Some(3));
+        assert_eq!(PRICES.peek(&storage, "atom")?, Some(4));
+        Ok(())
+    }
+
+    #[test]
+    fn update_and_remove() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        PRICES.insert(&mut storage, "scrt", 1)?;
+        PRICES.insert(&mut storage, "atom", 2)?;
+
+        // updating an entry uses it and never evicts
+        assert_eq!(PRICES.insert(&mut storage, "scrt", 5)?, None);
+        assert_eq!(PRICES.insert(&mut storage, "osmo", 3)?.unwrap().value, 2);
+        assert_eq!(PRICES.peek(&storage, "scrt")?, Some(5));
+
+        assert_eq!(PRICES.remove(&mut storage, "scrt")?, Some(5));
+        assert_eq!(PRICES.remove(&mut storage, "scrt")?, None);
+        assert_eq!(PRICES.len(&storage)?, 1);
+        assert_eq!(PRICES.insert(&mut storage, "atom", 2)?, None);
+        Ok(())
+    }
+}
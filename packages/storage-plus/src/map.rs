@@ -7,10 +7,12 @@ use crate::{Serde, Json};
 use crate::bound::{Bound, PrefixBound};
 #[cfg(feature = "iterator")]
 use crate::de::KeyDeserialize;
-use crate::helpers::query_raw;
+use crate::helpers::{encode_length, query_raw};
 #[cfg(feature = "iterator")]
 use crate::iter_helpers::{deserialize_kv, deserialize_v};
 #[cfg(feature = "iterator")]
+use crate::iter_policy::{DecodePolicy, Decoded};
+#[cfg(feature = "iterator")]
 use crate::keys::Prefixer;
 use crate::keys::{Key, PrimaryKey};
 use crate::path::Path;
@@ -21,6 +23,8 @@ use cosmwasm_std::{from_slice, Addr, CustomQuery, QuerierWrapper, StdError, StdR
 #[derive(Debug, Clone)]
 pub struct Map<'a, K, T, Ser = Json> {
     namespace: &'a [u8],
+    /// needed if any suffixes were added to the original namespace.
+    prefix: Option<Vec<u8>>,
     // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
     key_type: PhantomData<K>,
     data_type: PhantomData<T>,
@@ -33,14 +37,30 @@ impl<'a, K, T, Ser> Map<'a, K, T, Ser>
     pub const fn new(namespace: &'a str) -> Self {
         Map {
             namespace: namespace.as_bytes(),
+            prefix: None,
             data_type: PhantomData,
             key_type: PhantomData,
             serialization_type: PhantomData,
         }
     }
 
-    pub fn namespace(&self) -> &'a [u8] {
-        self.namespace
+    pub fn namespace(&self) -> &[u8] {
+        match &self.prefix {
+            Some(prefix) => prefix,
+            None => self.namespace,
+        }
+    }
+
+    /// This is used to produce a new Map under a suffixed namespace. This can be used when you
+    /// want to associate a Map to each user and still define the Map as a constant
+    pub fn add_suffix(&self, suffix: &str) -> Self {
+        Map {
+            namespace: self.namespace,
+            prefix: Some([self.namespace(), suffix.as_bytes()].concat()),
+            data_type: PhantomData,
+            key_type: PhantomData,
+            serialization_type: PhantomData,
+        }
     }
 }
 
@@ -52,14 +72,14 @@ where
 {
     pub fn key(&self, k: K) -> Path<T, Ser> {
         Path::new(
-            self.namespace,
+            self.namespace(),
             &k.key().iter().map(Key::as_ref).collect::<Vec<_>>(),
         )
     }
 
     #[cfg(feature = "iterator")]
     pub(crate) fn no_prefix_raw(&self) -> Prefix<Vec<u8>, T, K> {
-        Prefix::new(self.namespace, &[])
+        Prefix::new(self.namespace(), &[])
     }
 
     pub fn save(&self, store: &mut dyn Storage, k: K, data: &T) -> StdResult<()> {
@@ -70,6 +90,49 @@ where
         self.key(k).remove(store)
     }
 
+    /// Saves every key/value pair of the batch, computing the namespace prefix only once.
+    /// Stops at the first serialization error, entries before it stay saved.
+    pub fn insert_many<'v, I>(&self, store: &mut dyn Storage, entries: I) -> StdResult<()>
+    where
+        I: IntoIterator<Item = (K, &'v T)>,
+        T: 'v,
+    {
+        let prefix = self.namespace_prefix();
+        for (k, data) in entries {
+            store.set(&Self::raw_key(&prefix, k), &Ser::serialize(data)?);
+        }
+        Ok(())
+    }
+
+    /// Removes every key of the batch, computing the namespace prefix only once
+    pub fn remove_many<I>(&self, store: &mut dyn Storage, keys: I)
+    where
+        I: IntoIterator<Item = K>,
+    {
+        let prefix = self.namespace_prefix();
+        for k in keys {
+            store.remove(&Self::raw_key(&prefix, k));
+        }
+    }
+
+    /// Length prefixed namespace, shared by every key of this map
+    fn namespace_prefix(&self) -> Vec<u8> {
+        [&encode_length(self.namespace())[..], self.namespace()].concat()
+    }
+
+    /// Same key as [Map::key] builds, reusing a precomputed namespace prefix
+    fn raw_key(prefix: &[u8], k: K) -> Vec<u8> {
+        let keys = k.key();
+        let (last, sub_names) = keys.split_last().expect("keys are never empty");
+        let mut out = prefix.to_vec();
+        for namespace in sub_names {
+            out.extend_from_slice(&encode_length(namespace.as_ref()));
+            out.extend_from_slice(namespace.as_ref());
+        }
+        out.extend_from_slice(last.as_ref());
+        out
+    }
+
     /// load will return an error if no data is set at the given key, or on parse error
     pub fn load(&self, store: &dyn Storage, k: K) -> StdResult<T> {
         self.key(k).load(store)
@@ -99,6 +162,21 @@ where
         self.key(k).update(store, action)
     }
 
+    /// Loads the data at the given key if present, otherwise stores and returns the value
+    /// produced by `default`. Nothing is written when `default` returns an error.
+    pub fn get_or_insert_with<F, E>(
+        &self,
+        store: &mut dyn Storage,
+        k: K,
+        default: F,
+    ) -> Result<T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: From<StdError>,
+    {
+        self.key(k).get_or_insert_with(store, default)
+    }
+
     /// If you import the proper Map from the remote contract, this will let you read the data
     /// from a remote contract in a type-safe way using WasmQuery::RawQuery
     pub fn query<Q: CustomQuery>(
@@ -125,11 +203,11 @@ where
     K: PrimaryKey<'a>,
 {
     pub fn sub_prefix(&self, p: K::SubPrefix) -> Prefix<K::SuperSuffix, T, K::SuperSuffix> {
-        Prefix::new(self.namespace, &p.prefix())
+        Prefix::new(self.namespace(), &p.prefix())
     }
 
     pub fn prefix(&self, p: K::Prefix) -> Prefix<K::Suffix, T, K::Suffix> {
-        Prefix::new(self.namespace, &p.prefix())
+        Prefix::new(self.namespace(), &p.prefix())
     }
 }
 
@@ -159,7 +237,7 @@ where
         'a: 'c,
     {
         let mapped =
-            namespaced_prefix_range(store, self.namespace, min, max, order).map(deserialize_v);
+            namespaced_prefix_range(store, self.namespace(), min, max, order).map(deserialize_v);
         Box::new(mapped)
     }
 }
@@ -189,13 +267,13 @@ where
         K: 'c,
         K::Output: 'static,
     {
-        let mapped = namespaced_prefix_range(store, self.namespace, min, max, order)
+        let mapped = namespaced_prefix_range(store, self.namespace(), min, max, order)
             .map(deserialize_kv::<K, T>);
         Box::new(mapped)
     }
 
     fn no_prefix(&self) -> Prefix<K, T, K> {
-        Prefix::new(self.namespace, &[])
+        Prefix::new(self.namespace(), &[])
     }
 }
 
@@ -252,6 +330,24 @@ where
         self.no_prefix().range(store, min, max, order)
     }
 
+    /// Like `range`, but entries that fail to deserialize are handled according to `policy`.
+    /// Useful for migrations that need to walk and report damaged state.
+    pub fn range_with_policy<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound<'a, K>>,
+        max: Option<Bound<'a, K>>,
+        order: cosmwasm_std::Order,
+        policy: DecodePolicy,
+    ) -> Box<dyn Iterator<Item = StdResult<Decoded<K::Output, T>>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'static,
+    {
+        self.no_prefix()
+            .range_with_policy(store, min, max, order, policy)
+    }
+
     pub fn keys<'c>(
         &self,
         store: &'c dyn Storage,
@@ -1046,6 +1142,105 @@ mod test {
         );
     }
 
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_integer_keys_in_numeric_order() {
+        const BY_U64: Map<u64, u8> = Map::new("by_u64");
+        const BY_I64: Map<i64, u8> = Map::new("by_i64");
+        const BY_U128: Map<u128, u8> = Map::new("by_u128");
+        let mut store = MockStorage::new();
+
+        // these would sort differently as strings or little-endian bytes
+        let unsigned = [5u64, 99, 100, 150, 199, 200, 256, 1000, u64::MAX];
+        for k in unsigned {
+            BY_U64.save(&mut store, k, &0).unwrap();
+            BY_U128.save(&mut store, k as u128 * 1_000_000, &0).unwrap();
+        }
+        let (min, max) = Bound::from_range(100u64..200);
+        let keys: StdResult<Vec<_>> = BY_U64.keys(&store, min, max, Order::Ascending).collect();
+        assert_eq!(keys.unwrap(), vec![100, 150, 199]);
+
+        let (min, max) = Bound::from_range(100u64..=200);
+        let keys: StdResult<Vec<_>> = BY_U64.keys(&store, min, max, Order::Descending).collect();
+        assert_eq!(keys.unwrap(), vec![200, 199, 150, 100]);
+
+        let (min, max) = Bound::from_range(256_000_000u128..);
+        let keys: StdResult<Vec<_>> = BY_U128.keys(&store, min, max, Order::Ascending).collect();
+        assert_eq!(
+            keys.unwrap(),
+            vec![256_000_000, 1_000_000_000, u64::MAX as u128 * 1_000_000]
+        );
+
+        for k in [i64::MIN, -1000, -1, 0, 1, 150, i64::MAX] {
+            BY_I64.save(&mut store, k, &0).unwrap();
+        }
+        let (min, max) = Bound::from_range(-1000i64..150);
+        let keys: StdResult<Vec<_>> = BY_I64.keys(&store, min, max, Order::Ascending).collect();
+        assert_eq!(keys.unwrap(), vec![-1000, -1, 0, 1]);
+
+        let (min, max) = Bound::from_range(..0i64);
+        let keys: StdResult<Vec<_>> = BY_I64.keys(&store, min, max, Order::Ascending).collect();
+        assert_eq!(keys.unwrap(), vec![i64::MIN, -1000, -1]);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_with_decode_policy() {
+        let mut store = MockStorage::new();
+
+        let data = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+        PEOPLE_ID.save(&mut store, 1, &data).unwrap();
+        store.set(&PEOPLE_ID.key(2), b"not json");
+        PEOPLE_ID.save(&mut store, 3, &data).unwrap();
+
+        // plain range aborts on the corrupt entry
+        let all: StdResult<Vec<_>> = PEOPLE_ID
+            .range(&store, None, None, Order::Ascending)
+            .collect();
+        assert!(all.is_err());
+
+        let all: Vec<_> = PEOPLE_ID
+            .range_with_policy(&store, None, None, Order::Ascending, DecodePolicy::FailFast)
+            .collect();
+        assert_eq!(2, all.len());
+        assert_eq!(all[0], Ok(Decoded::Valid(1, data.clone())));
+        assert!(all[1].is_err());
+
+        let all: StdResult<Vec<_>> = PEOPLE_ID
+            .range_with_policy(
+                &store,
+                None,
+                None,
+                Order::Ascending,
+                DecodePolicy::SkipCorrupt,
+            )
+            .collect();
+        assert_eq!(
+            all.unwrap(),
+            vec![
+                Decoded::Valid(1, data.clone()),
+                Decoded::Valid(3, data.clone())
+            ]
+        );
+
+        let all: StdResult<Vec<_>> = PEOPLE_ID
+            .range_with_policy(&store, None, None, Order::Ascending, DecodePolicy::YieldRaw)
+            .collect();
+        let all = all.unwrap();
+        assert_eq!(3, all.len());
+        match &all[1] {
+            Decoded::Corrupt(entry) => {
+                assert_eq!(entry.key, 2u32.to_cw_bytes().to_vec());
+                assert_eq!(entry.value, b"not json".to_vec());
+            }
+            valid => panic!("Expected a corrupt entry, got {:?}", valid),
+        }
+        assert_eq!(all[2], Decoded::Valid(3, data));
+    }
+
     #[test]
     #[cfg(feature = "iterator")]
     fn range_triple_key() {
@@ -1160,6 +1355,56 @@ mod test {
         assert_eq!(20, loaded);
     }
 
+    #[rstest]
+    fn insert_and_remove_many(
+        #[values(ALLOWANCE, B_ALLOWANCE)] allowance: Map<AKey, u64, impl Serde>,
+    ) {
+        let mut store = MockStorage::new();
+        let keys: [(&[u8], &[u8]); 3] = [
+            (b"owner", b"spender"),
+            (b"owner", b"spender2"),
+            (b"owner2", b"spender"),
+        ];
+
+        allowance
+            .insert_many(&mut store, keys.iter().copied().zip(&[1, 2, 3]))
+            .unwrap();
+        assert_eq!(1, allowance.load(&store, keys[0]).unwrap());
+        assert_eq!(2, allowance.load(&store, keys[1]).unwrap());
+        assert_eq!(3, allowance.load(&store, keys[2]).unwrap());
+
+        allowance.remove_many(&mut store, keys[..2].iter().copied());
+        assert!(!allowance.has(&store, keys[0]));
+        assert!(!allowance.has(&store, keys[1]));
+        assert_eq!(3, allowance.load(&store, keys[2]).unwrap());
+    }
+
+    #[rstest]
+    fn get_or_insert_with(#[values(ALLOWANCE, B_ALLOWANCE)] allowance: Map<AKey, u64, impl Serde>) {
+        let mut store = MockStorage::new();
+        let key: (&[u8], &[u8]) = (b"owner", b"spender");
+
+        // a failing default leaves the key empty
+        let err = allowance
+            .get_or_insert_with(&mut store, key, || Err(StdError::generic_err("no default")))
+            .unwrap_err();
+        assert_eq!(err, StdError::generic_err("no default"));
+        assert!(!allowance.has(&store, key));
+
+        let value = allowance
+            .get_or_insert_with(&mut store, key, || -> StdResult<_> { Ok(10) })
+            .unwrap();
+        assert_eq!(10, value);
+        assert_eq!(10, allowance.load(&store, key).unwrap());
+
+        // existing values are returned untouched
+        let value = allowance
+            .get_or_insert_with(&mut store, key, || -> StdResult<_> { Ok(99) })
+            .unwrap();
+        assert_eq!(10, value);
+        assert_eq!(10, allowance.load(&store, key).unwrap());
+    }
+
     #[rstest]
     fn readme_works(#[values(PEOPLE, B_PEOPLE)] people: Map<Key, Data, impl Serde>) -> StdResult<()> {
         let mut store = MockStorage::new();
@@ -0,0 +1,138 @@
+//! A `NonceRegistry` guards off-chain signed messages, e.g. meta-transactions or orders
+//! verified with secret-utils `verify_adr36`, against replays. Every signed message carries a
+//! nonce of its signer, and the contract consumes the nonce before acting on the message.
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, StdError, StdResult, Storage};
+
+use crate::Map;
+
+const WORD_BITS: u64 = 64;
+
+/// Which nonces a registry accepts
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NonceOrder {
+    /// Only the next nonce, so messages are processed in the order they were signed and one
+    /// counter per address is stored
+    Sequential,
+    /// Any nonce not used yet, so orders can be filled out of order. Used nonces are kept in
+    /// a bitmap of 64 nonces per storage entry, signers should count up from 0 to keep it dense.
+    Unordered,
+}
+
+pub struct NonceRegistry<'a> {
+    order: NonceOrder,
+    /// One past the highest nonce consumed per address
+    next: Map<'a, &'a Addr, u64>,
+    used: Map<'a, (&'a Addr, u64), u64>,
+}
+
+impl<'a> NonceRegistry<'a> {
+    pub const fn new(next_namespace: &'a str, used_namespace: &'a str, order: NonceOrder) -> Self {
+        NonceRegistry {
+            order,
+            next: Map::new(next_namespace),
+            used: Map::new(used_namespace),
+        }
+    }
+
+    /// The nonce `addr` should sign its next message with
+    pub fn next_nonce(&self, store: &dyn Storage, addr: &Addr) -> StdResult<u64> {
+        Ok(self.next.may_load(store, addr)?.unwrap_or_default())
+    }
+
+    pub fn is_used(&self, store: &dyn Storage, addr: &Addr, nonce: u64) -> StdResult<bool> {
+        match self.order {
+            NonceOrder::Sequential => Ok(nonce < self.next_nonce(store, addr)?),
+            NonceOrder::Unordered => {
+                let word = self.used.may_load(store, (addr, nonce / WORD_BITS))?;
+                Ok(word.unwrap_or_default() & (1 << (nonce % WORD_BITS)) != 0)
+            }
+        }
+    }
+
+    /// Marks the nonce used, errors if it was used before or, for sequential registries,
+    /// isn't the next one. Call it before acting on the signed message.
+    pub fn consume(&self, store: &mut dyn Storage, addr: &Addr, nonce: u64) -> StdResult<()> {
+        let next = self.next_nonce(store, addr)?;
+        if self.order == NonceOrder::Sequential && nonce != next {
+            return Err(StdError::generic_err(format!(
+                "invalid nonce {}, expected {}",
+                nonce, next
+            )));
+        }
+        // Checked before anything is written, so a failed call leaves no trace
+        let new_next = nonce
+            .checked_add(1)
+            .ok_or_else(|| StdError::generic_err("nonce overflow"))?;
+        if self.order == NonceOrder::Unordered {
+            let key = (addr, nonce / WORD_BITS);
+            let word = self.used.may_load(store, key)?.unwrap_or_default();
+            let mask = 1 << (nonce % WORD_BITS);
+            if word & mask != 0 {
+                return Err(StdError::generic_err(format!(
+                    "nonce {} was already used",
+                    nonce
+                )));
+            }
+            self.used.save(store, key, &(word | mask))?;
+        }
+        if new_next > next {
+            self.next.save(store, addr, &new_next)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    const SEQUENTIAL: NonceRegistry =
+        NonceRegistry::new("next_nonce", "used_nonces", NonceOrder::Sequential);
+    const UNORDERED: NonceRegistry =
+        NonceRegistry::new("next_nonce", "used_nonces", NonceOrder::Unordered);
+
+    #[test]
+    fn sequential() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+
+        SEQUENTIAL.consume(&mut storage, &alice, 0)?;
+        // replays and gaps are rejected
+        assert!(SEQUENTIAL.consume(&mut storage, &alice, 0).is_err());
+        assert!(SEQUENTIAL.consume(&mut storage, &alice, 2).is_err());
+        SEQUENTIAL.consume(&mut storage, &alice, 1)?;
+        assert_eq!(SEQUENTIAL.next_nonce(&storage, &alice)?, 2);
+        assert!(SEQUENTIAL.is_used(&storage, &alice, 1)?);
+        assert!(!SEQUENTIAL.is_used(&storage, &alice, 2)?);
+
+        // every address counts on its own
+        assert_eq!(SEQUENTIAL.next_nonce(&storage, &bob)?, 0);
+        SEQUENTIAL.consume(&mut storage, &bob, 0)?;
+        Ok(())
+    }
+
+    #[test]
+    fn unordered() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let alice = Addr::unchecked("alice");
+
+        UNORDERED.consume(&mut storage, &alice, 70)?;
+        UNORDERED.consume(&mut storage, &alice, 3)?;
+        UNORDERED.consume(&mut storage, &alice, 4)?;
+        assert!(UNORDERED.consume(&mut storage, &alice, 3).is_err());
+        assert!(UNORDERED.consume(&mut storage, &alice, 70).is_err());
+        assert!(UNORDERED.is_used(&storage, &alice, 4)?);
+        assert!(!UNORDERED.is_used(&storage, &alice, 5)?);
+        assert!(!UNORDERED.is_used(&storage, &Addr::unchecked("bob"), 3)?);
+        assert_eq!(UNORDERED.next_nonce(&storage, &alice)?, 71);
+
+        // the last nonce can't be consumed and isn't marked used either
+        assert!(UNORDERED.consume(&mut storage, &alice, u64::MAX).is_err());
+        assert!(!UNORDERED.is_used(&storage, &alice, u64::MAX)?);
+        Ok(())
+    }
+}
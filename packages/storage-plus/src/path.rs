@@ -99,4 +99,21 @@ where
         self.save(store, &output)?;
         Ok(output)
     }
+
+    /// Loads the data if present, otherwise stores and returns the value produced by `default`.
+    /// Nothing is written when `default` returns an error.
+    pub fn get_or_insert_with<F, E>(&self, store: &mut dyn Storage, default: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: From<StdError>,
+    {
+        match self.may_load(store)? {
+            Some(value) => Ok(value),
+            None => {
+                let value = default()?;
+                self.save(store, &value)?;
+                Ok(value)
+            }
+        }
+    }
 }
@@ -10,6 +10,7 @@ use crate::bound::{PrefixBound, RawBound};
 use crate::de::KeyDeserialize;
 use crate::helpers::{namespaces_with_key, nested_namespaces_with_key};
 use crate::iter_helpers::{concat, deserialize_kv, deserialize_v, trim};
+use crate::iter_policy::{decode_with_policy, DecodePolicy, Decoded};
 use crate::keys::Key;
 use crate::{Bound, Prefixer, PrimaryKey};
 
@@ -165,6 +166,31 @@ where
         Box::new(mapped)
     }
 
+    /// Like `range`, but entries that fail to deserialize are handled according to `policy`
+    pub fn range_with_policy<'a>(
+        &self,
+        store: &'a dyn Storage,
+        min: Option<Bound<'b, B>>,
+        max: Option<Bound<'b, B>>,
+        order: Order,
+        policy: DecodePolicy,
+    ) -> Box<dyn Iterator<Item = StdResult<Decoded<K::Output, T>>> + 'a>
+    where
+        T: 'a,
+        K::Output: 'static,
+    {
+        let de_fn = self.de_fn_kv;
+        let pk_name = self.pk_name.clone();
+        let records = range_with_prefix(
+            store,
+            &self.storage_prefix,
+            min.map(|b| b.to_raw_bound()),
+            max.map(|b| b.to_raw_bound()),
+            order,
+        );
+        decode_with_policy(records, policy, move |kv| (de_fn)(store, &pk_name, kv))
+    }
+
     pub fn keys<'a>(
         &self,
         store: &'a dyn Storage,
@@ -0,0 +1,237 @@
+//! A `PrefixSum` holds a value for each index in `0..size` and answers "sum of the values
+//! below `end`" in O(log size) storage reads, e.g. cumulative stake for pro-rata payouts.
+//! Values live in a Fenwick tree where only non-zero nodes are stored, so a large index space
+//! costs nothing until it's used.
+use cosmwasm_std::{StdError, StdResult, Storage};
+
+use crate::Map;
+
+pub(crate) fn lowest_bit(i: u64) -> u64 {
+    i & i.wrapping_neg()
+}
+
+fn node(tree: &Map<u64, u128>, store: &dyn Storage, i: u64) -> StdResult<u128> {
+    Ok(tree.may_load(store, i)?.unwrap_or_default())
+}
+
+/// Sum of the first `count` values of a 1-based tree
+pub(crate) fn tree_sum(
+    tree: &Map<u64, u128>,
+    store: &dyn Storage,
+    mut count: u64,
+) -> StdResult<u128> {
+    let mut sum = 0u128;
+    while count > 0 {
+        sum += node(tree, store, count)?;
+        count -= lowest_bit(count);
+    }
+    Ok(sum)
+}
+
+/// Adds `delta` to, or takes it from, the value at 1-based `position` in a tree of `bound`
+/// nodes. The caller checks the total can't overflow and the value can't underflow, which
+/// bounds every node on the way.
+pub(crate) fn tree_update(
+    tree: &Map<u64, u128>,
+    store: &mut dyn Storage,
+    mut position: u64,
+    bound: u64,
+    delta: u128,
+    increase: bool,
+) -> StdResult<()> {
+    while position <= bound {
+        let current = node(tree, store, position)?;
+        let updated = if increase {
+            current + delta
+        } else {
+            current - delta
+        };
+        if updated == 0 {
+            tree.remove(store, position);
+        } else {
+            tree.save(store, position, &updated)?;
+        }
+        position = match position.checked_add(lowest_bit(position)) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    Ok(())
+}
+
+/// The number of leading values whose sum is at most `target`, i.e. the 0-based index of
+/// the value whose cumulative range contains `target`
+pub(crate) fn tree_search(
+    tree: &Map<u64, u128>,
+    store: &dyn Storage,
+    bound: u64,
+    mut target: u128,
+) -> StdResult<u64> {
+    if bound == 0 {
+        return Ok(0);
+    }
+    let mut position = 0u64;
+    let mut step = 1u64 << (63 - bound.leading_zeros());
+    while step > 0 {
+        let next = position + step;
+        if next <= bound {
+            let node = node(tree, store, next)?;
+            if node <= target {
+                position = next;
+                target -= node;
+            }
+        }
+        step >>= 1;
+    }
+    Ok(position)
+}
+
+pub struct PrefixSum<'a> {
+    /// Fenwick tree nodes, 1-based
+    tree: Map<'a, u64, u128>,
+    size: u64,
+}
+
+impl<'a> PrefixSum<'a> {
+    pub const fn new(tree_namespace: &'a str, size: u64) -> Self {
+        PrefixSum {
+            tree: Map::new(tree_namespace),
+            size,
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn check_index(&self, index: u64) -> StdResult<()> {
+        if index >= self.size {
+            return Err(StdError::generic_err(format!(
+                "index {} out of bounds for size {}",
+                index, self.size
+            )));
+        }
+        Ok(())
+    }
+
+    /// Sum of the values at indexes below `end`, capped at `size`
+    pub fn sum(&self, store: &dyn Storage, end: u64) -> StdResult<u128> {
+        tree_sum(&self.tree, store, end.min(self.size))
+    }
+
+    /// Sum of the values at indexes in `start..end`
+    pub fn range_sum(&self, store: &dyn Storage, start: u64, end: u64) -> StdResult<u128> {
+        if start >= end {
+            return Ok(0);
+        }
+        Ok(self.sum(store, end)? - self.sum(store, start)?)
+    }
+
+    pub fn total(&self, store: &dyn Storage) -> StdResult<u128> {
+        self.sum(store, self.size)
+    }
+
+    pub fn get(&self, store: &dyn Storage, index: u64) -> StdResult<u128> {
+        self.check_index(index)?;
+        self.range_sum(store, index, index + 1)
+    }
+
+    pub fn add(&self, store: &mut dyn Storage, index: u64, delta: u128) -> StdResult<()> {
+        self.check_index(index)?;
+        if self.total(store)?.checked_add(delta).is_none() {
+            return Err(StdError::generic_err("prefix sum overflow"));
+        }
+        tree_update(&self.tree, store, index + 1, self.size, delta, true)
+    }
+
+    pub fn sub(&self, store: &mut dyn Storage, index: u64, delta: u128) -> StdResult<()> {
+        if self.get(store, index)? < delta {
+            return Err(StdError::generic_err(format!(
+                "value at index {} is below {}",
+                index, delta
+            )));
+        }
+        tree_update(&self.tree, store, index + 1, self.size, delta, false)
+    }
+
+    /// Overwrites the value at `index`
+    pub fn set(&self, store: &mut dyn Storage, index: u64, value: u128) -> StdResult<()> {
+        let current = self.get(store, index)?;
+        if value >= current {
+            self.add(store, index, value - current)
+        } else {
+            self.sub(store, index, current - value)
+        }
+    }
+
+    /// The index whose cumulative range `sum(index)..sum(index + 1)` contains `target`, or
+    /// `None` past the total
+    pub fn find(&self, store: &dyn Storage, target: u128) -> StdResult<Option<u64>> {
+        if target >= self.total(store)? {
+            return Ok(None);
+        }
+        tree_search(&self.tree, store, self.size, target).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    const STAKE: PrefixSum = PrefixSum::new("stake", 1 << 40);
+
+    #[test]
+    fn sums_over_large_index_space() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        assert_eq!(STAKE.total(&storage)?, 0);
+
+        STAKE.add(&mut storage, 0, 10)?;
+        STAKE.add(&mut storage, 7, 5)?;
+        STAKE.add(&mut storage, 1_000_000, 20)?;
+        STAKE.add(&mut storage, (1 << 40) - 1, 1)?;
+        STAKE.add(&mut storage, 7, 5)?;
+        assert!(STAKE.add(&mut storage, 1 << 40, 1).is_err());
+
+        assert_eq!(STAKE.sum(&storage, 0)?, 0);
+        assert_eq!(STAKE.sum(&storage, 1)?, 10);
+        assert_eq!(STAKE.sum(&storage, 8)?, 20);
+        assert_eq!(STAKE.sum(&storage, 1_000_000)?, 20);
+        assert_eq!(STAKE.sum(&storage, 1_000_001)?, 40);
+        assert_eq!(STAKE.total(&storage)?, 41);
+        assert_eq!(STAKE.sum(&storage, u64::MAX)?, 41);
+        assert_eq!(STAKE.range_sum(&storage, 1, 1_000_001)?, 30);
+        assert_eq!(STAKE.get(&storage, 7)?, 10);
+        assert_eq!(STAKE.get(&storage, 8)?, 0);
+        assert!(STAKE.add(&mut storage, 3, u128::MAX).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn sub_and_find() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        STAKE.add(&mut storage, 2, 3)?;
+        STAKE.add(&mut storage, 5, 4)?;
+        STAKE.add(&mut storage, 9, 1)?;
+
+        assert_eq!(STAKE.find(&storage, 0)?, Some(2));
+        assert_eq!(STAKE.find(&storage, 2)?, Some(2));
+        assert_eq!(STAKE.find(&storage, 3)?, Some(5));
+        assert_eq!(STAKE.find(&storage, 7)?, Some(9));
+        assert_eq!(STAKE.find(&storage, 8)?, None);
+
+        assert!(STAKE.sub(&mut storage, 2, 4).is_err());
+        STAKE.sub(&mut storage, 2, 3)?;
+        STAKE.set(&mut storage, 9, 6)?;
+        assert_eq!(STAKE.total(&storage)?, 10);
+        assert_eq!(STAKE.find(&storage, 0)?, Some(5));
+        assert_eq!(STAKE.find(&storage, 4)?, Some(9));
+
+        // emptied nodes are removed
+        STAKE.set(&mut storage, 5, 0)?;
+        STAKE.set(&mut storage, 9, 0)?;
+        assert_eq!(STAKE.total(&storage)?, 0);
+        assert_eq!(STAKE.tree.may_load(&storage, 1 << 39)?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+//! Canonical JSON: object members sorted by key and no insignificant whitespace.
+//!
+//! Numbers are copied verbatim and never parsed, so their representation can't drift
+//! between versions (and no float code ends up in the contract).
+
+use cosmwasm_std::{StdError, StdResult};
+
+/// Rewrites a JSON document in canonical form. Object members are sorted by their encoded key
+/// bytes, which is stable since keys are always escaped the same way by the serializer.
+pub fn canonicalize_json(json: &[u8]) -> StdResult<Vec<u8>> {
+    let mut parser = Parser {
+        input: json,
+        pos: 0,
+    };
+    let mut out = Vec::with_capacity(json.len());
+    parser.skip_whitespace();
+    parser.value(&mut out)?;
+    parser.skip_whitespace();
+    if parser.pos != json.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(out)
+}
+
+/// Splits a JSON object into its fields, values are returned as canonical JSON
+pub(crate) fn json_object_fields(json: &[u8]) -> StdResult<Vec<(String, Vec<u8>)>> {
+    let mut parser = Parser {
+        input: json,
+        pos: 0,
+    };
+    parser.skip_whitespace();
+    let members = parser.members()?;
+    members
+        .into_iter()
+        .map(|(key, value)| Ok((cosmwasm_std::from_slice::<String>(&key)?, value)))
+        .collect()
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, msg: &str) -> StdError {
+        StdError::parse_err("canonical json", format!("{} at byte {}", msg, self.pos))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\n' | b'\r' | b'\t') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> StdResult<()> {
+        if self.peek() != Some(byte) {
+            return Err(self.error(&format!("expected '{}'", byte as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self, out: &mut Vec<u8>) -> StdResult<()> {
+        match self.peek() {
+            Some(b'{') => self.object(out),
+            Some(b'[') => self.array(out),
+            Some(b'"') => self.string(out),
+            Some(_) => self.scalar(out),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn object(&mut self, out: &mut Vec<u8>) -> StdResult<()> {
+        let mut members = self.members()?;
+        members.sort_by(|(a, _), (b, _)| a.cmp(b));
+        out.push(b'{');
+        for (i, (key, value)) in members.iter().enumerate() {
+            if i > 0 {
+                out.push(b',');
+            }
+            out.extend_from_slice(key);
+            out.push(b':');
+            out.extend_from_slice(value);
+        }
+        out.push(b'}');
+        Ok(())
+    }
+
+    /// Parses an object into its (raw key, canonical value) members
+    fn members(&mut self) -> StdResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.expect(b'{')?;
+        let mut members = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(members);
+        }
+        loop {
+            self.skip_whitespace();
+            let mut key = vec![];
+            self.string(&mut key)?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            self.skip_whitespace();
+            let mut value = vec![];
+            self.value(&mut value)?;
+            members.push((key, value));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(members);
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self, out: &mut Vec<u8>) -> StdResult<()> {
+        self.expect(b'[')?;
+        out.push(b'[');
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+        } else {
+            loop {
+                self.skip_whitespace();
+                self.value(out)?;
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(b',') => {
+                        self.pos += 1;
+                        out.push(b',');
+                    }
+                    Some(b']') => {
+                        self.pos += 1;
+                        break;
+                    }
+                    _ => return Err(self.error("expected ',' or ']'")),
+                }
+            }
+        }
+        out.push(b']');
+        Ok(())
+    }
+
+    /// Copies a string including its quotes, escapes are kept as they are
+    fn string(&mut self, out: &mut Vec<u8>) -> StdResult<()> {
+        let start = self.pos;
+        self.expect(b'"')?;
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'\\') => self.pos += 2,
+                Some(_) => self.pos += 1,
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+        out.extend_from_slice(&self.input[start..self.pos]);
+        Ok(())
+    }
+
+    /// Numbers, booleans and null
+    fn scalar(&mut self, out: &mut Vec<u8>) -> StdResult<()> {
+        let start = self.pos;
+        while let Some(b) = self.peek() {
+            if matches!(b, b',' | b']' | b'}' | b' ' | b'\n' | b'\r' | b'\t') {
+                break;
+            }
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error("expected a value"));
+        }
+        out.extend_from_slice(&self.input[start..self.pos]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Json, Serde};
+    use serde::Serialize;
+    use std::collections::HashMap;
+
+    #[test]
+    fn json_strategy_is_canonical() {
+        #[derive(Serialize)]
+        struct Config {
+            owner: String,
+            limits: HashMap<String, u64>,
+        }
+
+        let config = Config {
+            owner: "admin".to_string(),
+            limits: (0..20).map(|i| (format!("k{:02}", i), i)).collect(),
+        };
+        let stored = Json::serialize(&config).unwrap();
+        let limits: Vec<String> = (0..20).map(|i| format!("\"k{:02}\":{}", i, i)).collect();
+        let expected = format!(r#"{{"limits":{{{}}},"owner":"admin"}}"#, limits.join(","));
+        assert_eq!(String::from_utf8(stored).unwrap(), expected);
+    }
+
+    #[test]
+    fn sorts_nested_objects() {
+        let json = br#" { "b": 1, "a": { "d": [3, {"z": null, "y": true}], "c": "x,}" } } "#;
+        assert_eq!(
+            canonicalize_json(json).unwrap(),
+            br#"{"a":{"c":"x,}","d":[3,{"y":true,"z":null}]},"b":1}"#.to_vec()
+        );
+    }
+
+    #[test]
+    fn keeps_escapes_and_numbers() {
+        let json = br#"{"k\"2":"\\","k":"1000000000000000000000000","n":-12}"#;
+        assert_eq!(
+            canonicalize_json(json).unwrap(),
+            br#"{"k":"1000000000000000000000000","k\"2":"\\","n":-12}"#.to_vec()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        assert!(canonicalize_json(b"{\"a\":1").is_err());
+        assert!(canonicalize_json(b"[1,2] 3").is_err());
+        assert!(canonicalize_json(b"\"open").is_err());
+    }
+}
@@ -4,13 +4,18 @@ use cosmwasm_std::StdResult;
 
 use crate::Serde;
 
-/// Use json for serialization
+use super::canonical::canonicalize_json;
+
+/// Use json for serialization.
+///
+/// Values are written as canonical JSON (sorted object keys) so that the stored bytes only
+/// depend on the value, which keeps hash-of-state checks stable across versions.
 #[derive(Copy, Clone, Debug)]
 pub struct Json;
 
 impl Serde for Json {
     fn serialize<T: Serialize>(obj: &T) -> StdResult<Vec<u8>> {
-        cosmwasm_std::to_vec(obj)
+        canonicalize_json(&cosmwasm_std::to_vec(obj)?)
     }
 
     fn deserialize<T: DeserializeOwned>(data: &[u8]) -> StdResult<T> {
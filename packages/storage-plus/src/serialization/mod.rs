@@ -2,6 +2,7 @@ use serde::{de::DeserializeOwned, Serialize};
 use cosmwasm_std::StdResult;
 
 mod bincode2;
+mod canonical;
 mod json;
 
 /// This trait represents the ability to both serialize and deserialize using a specific format.
@@ -16,4 +17,6 @@ pub trait Serde {
 }
 
 pub use self::bincode2::Bincode2;
+pub use self::canonical::canonicalize_json;
+pub(crate) use self::canonical::json_object_fields;
 pub use self::json::Json;
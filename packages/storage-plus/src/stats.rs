@@ -0,0 +1,315 @@
+//! Running statistics kept in a single storage entry and updated in O(1) per sample:
+//! an exponential moving average for smoothing oracle prices, mean and variance with
+//! Welford's algorithm, and the smallest and largest value seen.
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Decimal, OverflowError, OverflowOperation, StdError, StdResult, Storage};
+
+use crate::Item;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EmaState {
+    /// Weight of each new sample, in (0, 1]
+    pub alpha: Decimal,
+    /// `None` until the first sample, which becomes the average as is
+    pub value: Option<Decimal>,
+}
+
+pub struct Ema<'a> {
+    state: Item<'a, EmaState>,
+}
+
+impl<'a> Ema<'a> {
+    pub const fn new(storage_key: &'a str) -> Self {
+        Ema {
+            state: Item::new(storage_key),
+        }
+    }
+
+    /// Sets the smoothing factor, a higher `alpha` follows new samples more closely.
+    /// Keeps the current average, if any.
+    pub fn initialize(&self, store: &mut dyn Storage, alpha: Decimal) -> StdResult<()> {
+        if alpha.is_zero() || alpha > Decimal::one() {
+            return Err(StdError::generic_err("alpha must be in (0, 1]"));
+        }
+        let value = self.state.may_load(store)?.and_then(|state| state.value);
+        self.state.save(store, &EmaState { alpha, value })
+    }
+
+    /// Folds in a sample and returns the new average
+    pub fn update(&self, store: &mut dyn Storage, sample: Decimal) -> StdResult<Decimal> {
+        let mut state = self.state.load(store)?;
+        let value = match state.value {
+            Some(value) => checked_add(
+                sample.checked_mul(state.alpha)?,
+                value.checked_mul(Decimal::one() - state.alpha)?,
+            )?,
+            None => sample,
+        };
+        state.value = Some(value);
+        self.state.save(store, &state)?;
+        Ok(value)
+    }
+
+    pub fn load(&self, store: &dyn Storage) -> StdResult<EmaState> {
+        self.state.load(store)
+    }
+
+    /// The current average, `None` before the first sample
+    pub fn value(&self, store: &dyn Storage) -> StdResult<Option<Decimal>> {
+        Ok(self.state.load(store)?.value)
+    }
+}
+
+/// Mean and variance of the samples seen so far. Every update rounds down at the 18th
+/// decimal, so the results can be off by a few units in the last place.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RunningStats {
+    pub count: u64,
+    pub mean: Decimal,
+    /// Sum of the squared differences from the mean
+    pub m2: Decimal,
+}
+
+impl Default for RunningStats {
+    fn default() -> Self {
+        RunningStats {
+            count: 0,
+            mean: Decimal::zero(),
+            m2: Decimal::zero(),
+        }
+    }
+}
+
+impl RunningStats {
+    /// Folds in a sample. Fails without changing the statistics if the sum of squares
+    /// no longer fits in a `Decimal`.
+    pub fn add(&mut self, sample: Decimal) -> StdResult<()> {
+        let count = self
+            .count
+            .checked_add(1)
+            .ok_or_else(|| StdError::generic_err("too many samples"))?;
+        let weight = Decimal::from_ratio(1u128, count);
+        // The mean moves towards the sample, and both differences have the same sign
+        let (mean, old_delta, new_delta) = if sample >= self.mean {
+            let old_delta = sample - self.mean;
+            let mean = self.mean + old_delta.checked_mul(weight)?;
+            (mean, old_delta, sample - mean)
+        } else {
+            let old_delta = self.mean - sample;
+            let mean = self.mean - old_delta.checked_mul(weight)?;
+            (mean, old_delta, mean - sample)
+        };
+        self.m2 = checked_add(self.m2, old_delta.checked_mul(new_delta)?)?;
+        self.mean = mean;
+        self.count = count;
+        Ok(())
+    }
+
+    /// Variance of all the samples seen
+    pub fn variance(&self) -> Decimal {
+        match self.count {
+            0 => Decimal::zero(),
+            count => self.m2 * Decimal::from_ratio(1u128, count),
+        }
+    }
+
+    /// Unbiased variance estimate, for when the samples are a subset of the population
+    pub fn sample_variance(&self) -> Decimal {
+        match self.count {
+            0 | 1 => Decimal::zero(),
+            count => self.m2 * Decimal::from_ratio(1u128, count - 1),
+        }
+    }
+}
+
+pub struct WelfordStats<'a> {
+    stats: Item<'a, RunningStats>,
+}
+
+impl<'a> WelfordStats<'a> {
+    pub const fn new(storage_key: &'a str) -> Self {
+        WelfordStats {
+            stats: Item::new(storage_key),
+        }
+    }
+
+    pub fn update(&self, store: &mut dyn Storage, sample: Decimal) -> StdResult<RunningStats> {
+        let mut stats = self.load(store)?;
+        stats.add(sample)?;
+        self.stats.save(store, &stats)?;
+        Ok(stats)
+    }
+
+    /// The statistics so far, empty before the first sample
+    pub fn load(&self, store: &dyn Storage) -> StdResult<RunningStats> {
+        Ok(self.stats.may_load(store)?.unwrap_or_default())
+    }
+
+    pub fn reset(&self, store: &mut dyn Storage) {
+        self.stats.remove(store)
+    }
+}
+
+fn checked_add(a: Decimal, b: Decimal) -> StdResult<Decimal> {
+    if b > Decimal::MAX - a {
+        return Err(OverflowError::new(OverflowOperation::Add, a, b).into());
+    }
+    Ok(a + b)
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Extremes<T> {
+    pub min: T,
+    pub max: T,
+}
+
+pub struct MinMax<'a, T> {
+    extremes: Item<'a, Extremes<T>>,
+}
+
+impl<'a, T> MinMax<'a, T> {
+    pub const fn new(storage_key: &'a str) -> Self {
+        MinMax {
+            extremes: Item::new(storage_key),
+        }
+    }
+}
+
+impl<'a, T> MinMax<'a, T>
+where
+    T: Serialize + DeserializeOwned + PartialOrd + Clone,
+{
+    pub fn update(&self, store: &mut dyn Storage, sample: T) -> StdResult<Extremes<T>> {
+        let extremes = match self.extremes.may_load(store)? {
+            Some(Extremes { min, max }) => Extremes {
+                min: if sample < min { sample.clone() } else { min },
+                max: if sample > max { sample } else { max },
+            },
+            None => Extremes {
+                min: sample.clone(),
+                max: sample,
+            },
+        };
+        self.extremes.save(store, &extremes)?;
+        Ok(extremes)
+    }
+
+    /// The smallest and largest samples, `None` before the first one
+    pub fn load(&self, store: &dyn Storage) -> StdResult<Option<Extremes<T>>> {
+        self.extremes.may_load(store)
+    }
+
+    pub fn reset(&self, store: &mut dyn Storage) {
+        self.extremes.remove(store)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::Uint128;
+
+    const PRICE: Ema = Ema::new("price");
+    const STATS: WelfordStats = WelfordStats::new("stats");
+    const RANGE: MinMax<Uint128> = MinMax::new("range");
+
+    fn dec(value: u64) -> Decimal {
+        Decimal::from_ratio(value, 1u128)
+    }
+
+    fn assert_close(actual: Decimal, expected: Decimal) {
+        let diff = if actual > expected {
+            actual - expected
+        } else {
+            expected - actual
+        };
+        assert!(
+            diff < Decimal::from_ratio(1u128, 1_000_000_000_000u128),
+            "{} is not close to {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn ema() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        assert!(PRICE.update(&mut storage, dec(10)).is_err());
+        assert!(PRICE.initialize(&mut storage, Decimal::zero()).is_err());
+        assert!(PRICE.initialize(&mut storage, dec(2)).is_err());
+
+        PRICE.initialize(&mut storage, Decimal::percent(50))?;
+        assert_eq!(PRICE.value(&storage)?, None);
+        assert_eq!(PRICE.update(&mut storage, dec(10))?, dec(10));
+        assert_eq!(PRICE.update(&mut storage, dec(20))?, dec(15));
+        assert_eq!(PRICE.update(&mut storage, dec(5))?, dec(10));
+
+        // changing alpha keeps the average
+        PRICE.initialize(&mut storage, Decimal::percent(10))?;
+        assert_eq!(PRICE.update(&mut storage, dec(20))?, dec(11));
+        Ok(())
+    }
+
+    #[test]
+    fn welford() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        assert_eq!(STATS.load(&storage)?, RunningStats::default());
+        assert_eq!(STATS.load(&storage)?.variance(), Decimal::zero());
+
+        let mut stats = RunningStats::default();
+        for sample in [2u64, 4, 4, 4, 5, 5, 7, 9].iter() {
+            stats = STATS.update(&mut storage, dec(*sample))?;
+        }
+        assert_eq!(stats.count, 8);
+        assert_close(stats.mean, dec(5));
+        assert_close(stats.variance(), dec(4));
+        assert_close(stats.sample_variance(), Decimal::from_ratio(32u128, 7u128));
+        assert_eq!(STATS.load(&storage)?, stats);
+
+        STATS.reset(&mut storage);
+        assert_eq!(STATS.load(&storage)?.count, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn overflow() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        STATS.update(&mut storage, Decimal::zero())?;
+        let before = STATS.load(&storage)?;
+        // the squared difference doesn't fit, the stats are left as they were
+        assert!(STATS.update(&mut storage, Decimal::MAX).is_err());
+        assert_eq!(STATS.load(&storage)?, before);
+
+        let mut stats = RunningStats::default();
+        stats.add(Decimal::MAX)?;
+        stats.add(Decimal::MAX)?;
+        assert_eq!(stats.mean, Decimal::MAX);
+        assert_eq!(stats.variance(), Decimal::zero());
+
+        PRICE.initialize(&mut storage, Decimal::percent(50))?;
+        PRICE.update(&mut storage, Decimal::MAX)?;
+        assert!(PRICE.update(&mut storage, Decimal::MAX)? > dec(1));
+        Ok(())
+    }
+
+    #[test]
+    fn min_max() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        assert_eq!(RANGE.load(&storage)?, None);
+
+        for sample in [5u128, 3, 9, 4].iter() {
+            RANGE.update(&mut storage, Uint128::new(*sample))?;
+        }
+        assert_eq!(
+            RANGE.load(&storage)?,
+            Some(Extremes {
+                min: Uint128::new(3),
+                max: Uint128::new(9),
+            })
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+//! Per-account storage layout: one store instance per user, derived from a single constant.
+use cosmwasm_std::Addr;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{AppendStore, DequeStore, Item, Map, Serde};
+
+/// Stores that can be namespaced further with [Suffixable::add_suffix]
+pub trait Suffixable: Sized {
+    fn add_suffix(&self, suffix: &str) -> Self;
+}
+
+impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> Suffixable for AppendStore<'a, T, Ser> {
+    fn add_suffix(&self, suffix: &str) -> Self {
+        AppendStore::add_suffix(self, suffix)
+    }
+}
+
+impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> Suffixable for DequeStore<'a, T, Ser> {
+    fn add_suffix(&self, suffix: &str) -> Self {
+        DequeStore::add_suffix(self, suffix)
+    }
+}
+
+impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> Suffixable for Item<'a, T, Ser> {
+    fn add_suffix(&self, suffix: &str) -> Self {
+        Item::add_suffix(self, suffix)
+    }
+}
+
+impl<'a, K, T, Ser: Serde> Suffixable for Map<'a, K, T, Ser> {
+    fn add_suffix(&self, suffix: &str) -> Self {
+        Map::add_suffix(self, suffix)
+    }
+}
+
+/// Hands out a store per user, all under the same base namespace.
+///
+/// ```ignore
+/// const HISTORY: UserStore<AppendStore<Tx>> = UserStore::new(AppendStore::new("history"));
+///
+/// let history = HISTORY.for_user(&info.sender);
+/// history.push(deps.storage, &tx)?;
+/// ```
+///
+/// The returned store computes its prefix once, so keep it around rather than calling
+/// `for_user` for every access.
+pub struct UserStore<S: Suffixable> {
+    base: S,
+}
+
+impl<S: Suffixable> UserStore<S> {
+    pub const fn new(base: S) -> Self {
+        UserStore { base }
+    }
+
+    /// The store holding `user`'s data
+    pub fn for_user(&self, user: &Addr) -> S {
+        self.base.add_suffix(user.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::StdResult;
+
+    const HISTORY: UserStore<AppendStore<u64>> = UserStore::new(AppendStore::new("history"));
+    const BALANCE: UserStore<Item<u128>> = UserStore::new(Item::new("balance"));
+    const ALLOWANCES: UserStore<Map<&str, u64>> = UserStore::new(Map::new("allowances"));
+
+    #[test]
+    fn stores_are_isolated_per_user() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let (alice, bob) = (Addr::unchecked("alice"), Addr::unchecked("bob"));
+
+        let alice_history = HISTORY.for_user(&alice);
+        alice_history.push(&mut storage, &1)?;
+        alice_history.push(&mut storage, &2)?;
+        HISTORY.for_user(&bob).push(&mut storage, &3)?;
+        assert_eq!(HISTORY.for_user(&alice).get_len(&storage)?, 2);
+        assert_eq!(HISTORY.for_user(&bob).get_at(&storage, 0)?, 3);
+
+        BALANCE.for_user(&alice).save(&mut storage, &100)?;
+        assert_eq!(BALANCE.for_user(&alice).load(&storage)?, 100);
+        assert_eq!(BALANCE.for_user(&bob).may_load(&storage)?, None);
+
+        ALLOWANCES
+            .for_user(&alice)
+            .save(&mut storage, "spender", &5)?;
+        assert_eq!(ALLOWANCES.for_user(&alice).load(&storage, "spender")?, 5);
+        assert!(!ALLOWANCES.for_user(&bob).has(&storage, "spender"));
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+//! Opt-in schema versioning for stored values.
+//!
+//! A versioned value is stored inside a `{"$version":N,"data":...}` envelope. On load, values
+//! written at an older version are handed to [Versioned::upgrade] instead of failing to
+//! deserialize (or worse, deserializing into the wrong fields). Values saved before the type was versioned
+//! have no envelope and are reported as version 0. `$` can't start a Rust field name, so no
+//! derived struct is mistaken for an envelope.
+use std::any::type_name;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmwasm_std::{from_slice, StdError, StdResult, Storage};
+
+use crate::serialization::json_object_fields;
+use crate::{Item, Json, Map, PrimaryKey, Serde};
+
+/// Field holding the version of an envelope
+const VERSION_TAG: &str = "$version";
+
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// Version written into new envelopes, bump it whenever the stored fields change.
+    /// Must be at least 1, version 0 stands for values without an envelope.
+    const VERSION: u8;
+
+    /// Decodes `data` stored at an older `version`. For version 0 `data` is the whole stored
+    /// value, otherwise the JSON that was inside the envelope.
+    fn upgrade(version: u8, data: &[u8]) -> StdResult<Self> {
+        let _ = data;
+        Err(StdError::parse_err(
+            type_name::<Self>(),
+            format!("no upgrade from version {}", version),
+        ))
+    }
+}
+
+/// Serializes the value inside an envelope tagged with its current version
+pub fn to_envelope<T: Versioned>(value: &T) -> StdResult<Vec<u8>> {
+    let data = Json::serialize(value)?;
+    let mut out = format!("{{\"{}\":{},\"data\":", VERSION_TAG, T::VERSION).into_bytes();
+    out.extend_from_slice(&data);
+    out.push(b'}');
+    Ok(out)
+}
+
+/// Deserializes an enveloped value, upgrading it if it was stored at an older version
+pub fn from_envelope<T: Versioned>(bytes: &[u8]) -> StdResult<T> {
+    let (version, data) = match open_envelope(bytes) {
+        Some(opened) => opened?,
+        None => return T::upgrade(0, bytes),
+    };
+    if version == T::VERSION {
+        Json::deserialize(&data)
+    } else if version > T::VERSION {
+        Err(StdError::parse_err(
+            type_name::<T>(),
+            format!("stored version {} is newer than {}", version, T::VERSION),
+        ))
+    } else {
+        T::upgrade(version, &data)
+    }
+}
+
+/// Returns `None` if the bytes aren't an envelope
+fn open_envelope(bytes: &[u8]) -> Option<StdResult<(u8, Vec<u8>)>> {
+    let mut fields = json_object_fields(bytes).ok()?;
+    if fields.len() != 2 {
+        return None;
+    }
+    fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let (version, data) = match (fields.pop(), fields.pop()) {
+        (Some((d, data)), Some((v, version))) if v == VERSION_TAG && d == "data" => (version, data),
+        _ => return None,
+    };
+    Some(from_slice::<u8>(&version).map(|version| (version, data)))
+}
+
+impl<'a, T: Versioned> Item<'a, T, Json> {
+    pub fn save_versioned(&self, store: &mut dyn Storage, data: &T) -> StdResult<()> {
+        store.set(self.as_slice(), &to_envelope(data)?);
+        Ok(())
+    }
+
+    pub fn load_versioned(&self, store: &dyn Storage) -> StdResult<T> {
+        match store.get(self.as_slice()) {
+            Some(value) => from_envelope(&value),
+            None => Err(StdError::not_found(type_name::<T>())),
+        }
+    }
+
+    pub fn may_load_versioned(&self, store: &dyn Storage) -> StdResult<Option<T>> {
+        store
+            .get(self.as_slice())
+            .map(|value| from_envelope(&value))
+            .transpose()
+    }
+}
+
+impl<'a, K: PrimaryKey<'a>, T: Versioned> Map<'a, K, T, Json> {
+    pub fn save_versioned(&self, store: &mut dyn Storage, k: K, data: &T) -> StdResult<()> {
+        store.set(&self.key(k), &to_envelope(data)?);
+        Ok(())
+    }
+
+    pub fn load_versioned(&self, store: &dyn Storage, k: K) -> StdResult<T> {
+        match store.get(&self.key(k)) {
+            Some(value) => from_envelope(&value),
+            None => Err(StdError::not_found(type_name::<T>())),
+        }
+    }
+
+    pub fn may_load_versioned(&self, store: &dyn Storage, k: K) -> StdResult<Option<T>> {
+        store
+            .get(&self.key(k))
+            .map(|value| from_envelope(&value))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct ProfileV1 {
+        name: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Profile {
+        display_name: String,
+        bio: Option<String>,
+    }
+
+    impl Versioned for Profile {
+        const VERSION: u8 = 2;
+
+        fn upgrade(version: u8, data: &[u8]) -> StdResult<Self> {
+            match version {
+                0 | 1 => {
+                    let old: ProfileV1 = from_slice(data)?;
+                    Ok(Profile {
+                        display_name: old.name,
+                        bio: None,
+                    })
+                }
+                _ => Err(StdError::generic_err("unknown profile version")),
+            }
+        }
+    }
+
+    const PROFILE: Item<Profile> = Item::new("profile");
+    const PROFILES: Map<&str, Profile> = Map::new("profiles");
+
+    #[test]
+    fn round_trip() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let profile = Profile {
+            display_name: "alice".to_string(),
+            bio: Some("hi".to_string()),
+        };
+        PROFILE.save_versioned(&mut storage, &profile)?;
+        assert_eq!(
+            storage.get(b"profile").unwrap(),
+            br#"{"$version":2,"data":{"bio":"hi","display_name":"alice"}}"#.to_vec()
+        );
+        assert_eq!(PROFILE.load_versioned(&storage)?, profile);
+
+        assert_eq!(PROFILES.may_load_versioned(&storage, "bob")?, None);
+        PROFILES.save_versioned(&mut storage, "bob", &profile)?;
+        assert_eq!(PROFILES.load_versioned(&storage, "bob")?, profile);
+        Ok(())
+    }
+
+    #[test]
+    fn upgrades_older_values() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let upgraded = Profile {
+            display_name: "alice".to_string(),
+            bio: None,
+        };
+
+        // saved before Profile was versioned
+        storage.set(b"profile", br#"{"name":"alice"}"#);
+        assert_eq!(PROFILE.load_versioned(&storage)?, upgraded);
+
+        storage.set(b"profile", br#"{"$version":1,"data":{"name":"alice"}}"#);
+        assert_eq!(PROFILE.load_versioned(&storage)?, upgraded);
+
+        storage.set(b"profile", br#"{"$version":3,"data":{"name":"alice"}}"#);
+        assert!(PROFILE.load_versioned(&storage).is_err());
+        Ok(())
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Sample {
+        data: String,
+        v: u8,
+    }
+
+    impl Versioned for Sample {
+        const VERSION: u8 = 1;
+
+        fn upgrade(version: u8, data: &[u8]) -> StdResult<Self> {
+            match version {
+                0 => from_slice(data),
+                _ => Err(StdError::generic_err("unknown sample version")),
+            }
+        }
+    }
+
+    #[test]
+    fn unversioned_data_and_v_fields() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let sample = Sample {
+            data: "reading".to_string(),
+            v: 7,
+        };
+        // saved before Sample was versioned, its fields look like the old envelope
+        storage.set(b"sample", br#"{"data":"reading","v":7}"#);
+        let item: Item<Sample> = Item::new("sample");
+        assert_eq!(item.load_versioned(&storage)?, sample);
+
+        item.save_versioned(&mut storage, &sample)?;
+        assert_eq!(item.load_versioned(&storage)?, sample);
+        Ok(())
+    }
+}
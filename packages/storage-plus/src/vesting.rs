@@ -0,0 +1,344 @@
+//! Vesting schedules and a per beneficiary `Vesting` store that tracks what was claimed.
+//! Times are block times in seconds, vested amounts are always rounded down.
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, StdError, StdResult, Storage, Uint128};
+
+use crate::{Clock, Map};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VestingSchedule {
+    /// Everything vests at once at `cliff`
+    Cliff { total: Uint128, cliff: u64 },
+    /// Vests continuously from `start` to `end`, but nothing can be claimed before `cliff`
+    Linear {
+        total: Uint128,
+        start: u64,
+        end: u64,
+        cliff: Option<u64>,
+    },
+    /// `amount` vests at the end of each of the `periods` periods of `period` seconds
+    Periodic {
+        amount: Uint128,
+        start: u64,
+        period: u64,
+        periods: u32,
+    },
+}
+
+impl VestingSchedule {
+    /// Errors if a periodic schedule's total overflows
+    pub fn total(&self) -> StdResult<Uint128> {
+        match self {
+            VestingSchedule::Cliff { total, .. } | VestingSchedule::Linear { total, .. } => {
+                Ok(*total)
+            }
+            VestingSchedule::Periodic {
+                amount, periods, ..
+            } => Ok(amount.checked_mul(Uint128::from(*periods))?),
+        }
+    }
+
+    pub fn vested_amount(&self, at: u64) -> StdResult<Uint128> {
+        Ok(match *self {
+            VestingSchedule::Cliff { total, cliff } => {
+                if at >= cliff {
+                    total
+                } else {
+                    Uint128::zero()
+                }
+            }
+            VestingSchedule::Linear {
+                total,
+                start,
+                end,
+                cliff,
+            } => {
+                if at < cliff.unwrap_or(start) || at <= start {
+                    Uint128::zero()
+                } else if at >= end {
+                    total
+                } else {
+                    total.multiply_ratio(at - start, end - start)
+                }
+            }
+            VestingSchedule::Periodic {
+                amount,
+                start,
+                period,
+                periods,
+            } => {
+                if at < start {
+                    return Ok(Uint128::zero());
+                }
+                let elapsed = match (at - start).checked_div(period) {
+                    Some(elapsed) => elapsed.min(periods as u64),
+                    // periods of zero length have all ended
+                    None => periods as u64,
+                };
+                amount.checked_mul(Uint128::from(elapsed))?
+            }
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VestingAccount {
+    pub schedule: VestingSchedule,
+    pub claimed: Uint128,
+}
+
+impl VestingAccount {
+    pub fn claimable(&self, at: u64) -> StdResult<Uint128> {
+        Ok(self
+            .schedule
+            .vested_amount(at)?
+            .saturating_sub(self.claimed))
+    }
+}
+
+pub struct Vesting<'a> {
+    accounts: Map<'a, &'a Addr, VestingAccount>,
+}
+
+impl<'a> Vesting<'a> {
+    pub const fn new(namespace: &'a str) -> Self {
+        Vesting {
+            accounts: Map::new(namespace),
+        }
+    }
+
+    /// Starts vesting for the beneficiary, errors if it already has a schedule or the
+    /// schedule's total overflows
+    pub fn create(
+        &self,
+        store: &mut dyn Storage,
+        beneficiary: &'a Addr,
+        schedule: VestingSchedule,
+    ) -> StdResult<()> {
+        if self.accounts.has(store, beneficiary) {
+            return Err(StdError::generic_err(format!(
+                "{} already has a vesting schedule",
+                beneficiary
+            )));
+        }
+        schedule.total()?;
+        self.accounts.save(
+            store,
+            beneficiary,
+            &VestingAccount {
+                schedule,
+                claimed: Uint128::zero(),
+            },
+        )
+    }
+
+    pub fn load(&self, store: &dyn Storage, beneficiary: &'a Addr) -> StdResult<VestingAccount> {
+        self.accounts.load(store, beneficiary)
+    }
+
+    pub fn may_load(
+        &self,
+        store: &dyn Storage,
+        beneficiary: &'a Addr,
+    ) -> StdResult<Option<VestingAccount>> {
+        self.accounts.may_load(store, beneficiary)
+    }
+
+    pub fn claimable(
+        &self,
+        store: &dyn Storage,
+        beneficiary: &'a Addr,
+        clock: &impl Clock,
+    ) -> StdResult<Uint128> {
+        self.accounts
+            .may_load(store, beneficiary)?
+            .map(|account| account.claimable(clock.seconds()))
+            .unwrap_or_else(|| Ok(Uint128::zero()))
+    }
+
+    /// Marks everything vested so far as claimed and returns the amount to send out
+    pub fn claim(
+        &self,
+        store: &mut dyn Storage,
+        beneficiary: &'a Addr,
+        clock: &impl Clock,
+    ) -> StdResult<Uint128> {
+        let mut account = self.accounts.load(store, beneficiary)?;
+        let amount = account.claimable(clock.seconds())?;
+        if amount.is_zero() {
+            return Err(StdError::generic_err("nothing to claim"));
+        }
+        account.claimed += amount;
+        self.accounts.save(store, beneficiary, &account)?;
+        Ok(amount)
+    }
+
+    /// Stops vesting for the beneficiary, returns the amount that vested but wasn't claimed
+    /// yet and the amount that will never vest
+    pub fn revoke(
+        &self,
+        store: &mut dyn Storage,
+        beneficiary: &'a Addr,
+        clock: &impl Clock,
+    ) -> StdResult<(Uint128, Uint128)> {
+        let account = self.accounts.load(store, beneficiary)?;
+        let vested = account.schedule.vested_amount(clock.seconds())?;
+        let total = account.schedule.total()?;
+        self.accounts.remove(store, beneficiary);
+        Ok((
+            vested.saturating_sub(account.claimed),
+            total.saturating_sub(vested),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MockClock;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn cliff_schedule() -> StdResult<()> {
+        let schedule = VestingSchedule::Cliff {
+            total: Uint128::new(100),
+            cliff: 50,
+        };
+        assert_eq!(schedule.vested_amount(49)?, Uint128::zero());
+        assert_eq!(schedule.vested_amount(50)?, Uint128::new(100));
+        assert_eq!(schedule.total()?, Uint128::new(100));
+        Ok(())
+    }
+
+    #[test]
+    fn linear_schedule_rounds_down() -> StdResult<()> {
+        let schedule = VestingSchedule::Linear {
+            total: Uint128::new(10),
+            start: 0,
+            end: 3,
+            cliff: None,
+        };
+        assert_eq!(schedule.vested_amount(0)?, Uint128::zero());
+        // 10 * 1 / 3 = 3.33
+        assert_eq!(schedule.vested_amount(1)?, Uint128::new(3));
+        // 10 * 2 / 3 = 6.66
+        assert_eq!(schedule.vested_amount(2)?, Uint128::new(6));
+        assert_eq!(schedule.vested_amount(3)?, Uint128::new(10));
+        assert_eq!(schedule.vested_amount(u64::MAX)?, Uint128::new(10));
+
+        let with_cliff = VestingSchedule::Linear {
+            total: Uint128::new(1000),
+            start: 100,
+            end: 200,
+            cliff: Some(150),
+        };
+        assert_eq!(with_cliff.vested_amount(149)?, Uint128::zero());
+        assert_eq!(with_cliff.vested_amount(150)?, Uint128::new(500));
+
+        // no division by zero for instant schedules
+        let instant = VestingSchedule::Linear {
+            total: Uint128::new(7),
+            start: 10,
+            end: 10,
+            cliff: None,
+        };
+        assert_eq!(instant.vested_amount(10)?, Uint128::zero());
+        assert_eq!(instant.vested_amount(11)?, Uint128::new(7));
+
+        // large totals don't overflow
+        let large = VestingSchedule::Linear {
+            total: Uint128::MAX,
+            start: 0,
+            end: u64::MAX,
+            cliff: None,
+        };
+        assert_eq!(
+            large.vested_amount(u64::MAX / 2)?,
+            Uint128::MAX.multiply_ratio(u64::MAX / 2, u64::MAX)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn periodic_schedule() -> StdResult<()> {
+        let schedule = VestingSchedule::Periodic {
+            amount: Uint128::new(25),
+            start: 100,
+            period: 10,
+            periods: 4,
+        };
+        assert_eq!(schedule.total()?, Uint128::new(100));
+        assert_eq!(schedule.vested_amount(99)?, Uint128::zero());
+        assert_eq!(schedule.vested_amount(109)?, Uint128::zero());
+        assert_eq!(schedule.vested_amount(110)?, Uint128::new(25));
+        assert_eq!(schedule.vested_amount(135)?, Uint128::new(75));
+        assert_eq!(schedule.vested_amount(1000)?, Uint128::new(100));
+
+        let zero_period = VestingSchedule::Periodic {
+            amount: Uint128::new(25),
+            start: 100,
+            period: 0,
+            periods: 4,
+        };
+        assert_eq!(zero_period.vested_amount(100)?, Uint128::new(100));
+
+        // overflowing totals are errors rather than panics, and can't be created
+        let overflowing = VestingSchedule::Periodic {
+            amount: Uint128::MAX,
+            start: 0,
+            period: 1,
+            periods: 2,
+        };
+        assert_eq!(overflowing.vested_amount(1)?, Uint128::MAX);
+        assert!(overflowing.vested_amount(2).is_err());
+        assert!(overflowing.total().is_err());
+        let mut storage = MockStorage::new();
+        assert!(Vesting::new("vesting")
+            .create(&mut storage, &Addr::unchecked("alice"), overflowing)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn claims() -> StdResult<()> {
+        const VESTING: Vesting = Vesting::new("vesting");
+        let mut storage = MockStorage::new();
+        let alice = Addr::unchecked("alice");
+        let schedule = VestingSchedule::Linear {
+            total: Uint128::new(100),
+            start: 0,
+            end: 100,
+            cliff: Some(10),
+        };
+        VESTING.create(&mut storage, &alice, schedule.clone())?;
+        assert!(VESTING.create(&mut storage, &alice, schedule).is_err());
+
+        assert!(VESTING
+            .claim(&mut storage, &alice, &MockClock::at(5))
+            .is_err());
+        assert_eq!(
+            VESTING.claim(&mut storage, &alice, &MockClock::at(10))?,
+            Uint128::new(10)
+        );
+        assert!(VESTING
+            .claim(&mut storage, &alice, &MockClock::at(10))
+            .is_err());
+        assert_eq!(
+            VESTING.claimable(&storage, &alice, &MockClock::at(40))?,
+            Uint128::new(30)
+        );
+        assert_eq!(
+            VESTING.claim(&mut storage, &alice, &MockClock::at(40))?,
+            Uint128::new(30)
+        );
+
+        assert_eq!(
+            VESTING.revoke(&mut storage, &alice, &MockClock::at(50))?,
+            (Uint128::new(10), Uint128::new(50))
+        );
+        assert_eq!(VESTING.may_load(&storage, &alice)?, None);
+        Ok(())
+    }
+}
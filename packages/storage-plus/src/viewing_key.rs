@@ -0,0 +1,147 @@
+//! Viewing keys scoped to the queries they unlock, so a contract can hand an integrator a
+//! balance-only key instead of one that also reveals the user's history.
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use cosmwasm_std::{Addr, StdError, StdResult, Storage};
+
+use crate::Map;
+
+/// What a viewing key may be used for, combined with `|`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities(u8);
+
+impl Capabilities {
+    pub const NONE: Capabilities = Capabilities(0);
+    pub const BALANCE: Capabilities = Capabilities(1);
+    pub const HISTORY: Capabilities = Capabilities(1 << 1);
+    pub const ADMIN: Capabilities = Capabilities(1 << 2);
+    pub const ALL: Capabilities = Capabilities(0b111);
+
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Whether every capability in `other` is granted
+    pub const fn contains(&self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for Capabilities {
+    type Output = Capabilities;
+
+    fn bitor(self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 | other.0)
+    }
+}
+
+/// Viewing keys per user. Only a hash of each key is stored, together with its capabilities,
+/// and a user may hold any number of keys with different capabilities.
+pub struct ViewingKeys<'a> {
+    keys: Map<'a, (&'a Addr, &'a [u8]), Capabilities>,
+}
+
+impl<'a> ViewingKeys<'a> {
+    pub const fn new(storage_key: &'a str) -> Self {
+        ViewingKeys {
+            keys: Map::new(storage_key),
+        }
+    }
+
+    fn hash(key: &str) -> Vec<u8> {
+        Sha256::digest(key.as_bytes()).to_vec()
+    }
+
+    /// Stores `key` for `user`, replacing the capabilities it had if it was set before
+    pub fn set_key(
+        &self,
+        store: &mut dyn Storage,
+        user: &Addr,
+        key: &str,
+        capabilities: Capabilities,
+    ) -> StdResult<()> {
+        self.keys
+            .save(store, (user, &Self::hash(key)), &capabilities)
+    }
+
+    pub fn revoke(&self, store: &mut dyn Storage, user: &Addr, key: &str) {
+        self.keys.remove(store, (user, &Self::hash(key)))
+    }
+
+    /// Capabilities of the key, `None` if `user` has no such key
+    pub fn capabilities(
+        &self,
+        store: &dyn Storage,
+        user: &Addr,
+        key: &str,
+    ) -> StdResult<Option<Capabilities>> {
+        self.keys.may_load(store, (user, &Self::hash(key)))
+    }
+
+    /// Errors unless the key belongs to `user` and grants every capability in `required`.
+    /// Unknown keys and missing capabilities fail alike, so a query can't probe for keys.
+    pub fn assert_capability(
+        &self,
+        store: &dyn Storage,
+        user: &Addr,
+        key: &str,
+        required: Capabilities,
+    ) -> StdResult<()> {
+        match self.capabilities(store, user, key)? {
+            Some(capabilities) if capabilities.contains(required) => Ok(()),
+            _ => Err(StdError::generic_err("unauthorized")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    const KEYS: ViewingKeys = ViewingKeys::new("viewing_keys");
+
+    #[test]
+    fn scoped_keys() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+        KEYS.set_key(&mut storage, &alice, "wallet", Capabilities::ALL)?;
+        KEYS.set_key(&mut storage, &alice, "integrator", Capabilities::BALANCE)?;
+
+        KEYS.assert_capability(&storage, &alice, "wallet", Capabilities::ADMIN)?;
+        KEYS.assert_capability(&storage, &alice, "integrator", Capabilities::BALANCE)?;
+        assert!(KEYS
+            .assert_capability(&storage, &alice, "integrator", Capabilities::HISTORY)
+            .is_err());
+        assert!(KEYS
+            .assert_capability(
+                &storage,
+                &alice,
+                "integrator",
+                Capabilities::BALANCE | Capabilities::HISTORY
+            )
+            .is_err());
+        // keys only unlock their own user's data
+        assert!(KEYS
+            .assert_capability(&storage, &bob, "wallet", Capabilities::NONE)
+            .is_err());
+
+        KEYS.set_key(
+            &mut storage,
+            &alice,
+            "integrator",
+            Capabilities::BALANCE | Capabilities::HISTORY,
+        )?;
+        KEYS.assert_capability(&storage, &alice, "integrator", Capabilities::HISTORY)?;
+
+        KEYS.revoke(&mut storage, &alice, "integrator");
+        assert_eq!(KEYS.capabilities(&storage, &alice, "integrator")?, None);
+        assert_eq!(
+            KEYS.capabilities(&storage, &alice, "wallet")?,
+            Some(Capabilities::ALL)
+        );
+        Ok(())
+    }
+}
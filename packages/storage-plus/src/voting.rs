@@ -0,0 +1,456 @@
+//! `VotingPower` adds proposals, votes and tallies on top of a `SnapshotMap` of voting power,
+//! every proposal counts the power voters had at the start of the block it was created in.
+//! Expirations are block times in seconds.
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Decimal, Order, StdError, StdResult, Storage, Uint128};
+
+use crate::{Clock, Map, SnapshotItem, SnapshotMap};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Vote {
+    Yes,
+    No,
+    Abstain,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Open,
+    Passed,
+    Rejected,
+    Executed,
+}
+
+/// A proposal passes when the votes cast reach `quorum` of the total power and the yes votes
+/// reach `threshold` of the non abstaining ones
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VotingRules {
+    pub threshold: Decimal,
+    pub quorum: Decimal,
+}
+
+impl VotingRules {
+    pub fn validate(&self) -> StdResult<()> {
+        if self.threshold.is_zero() || self.threshold > Decimal::one() {
+            return Err(StdError::generic_err("threshold must be in (0, 1]"));
+        }
+        if self.quorum.is_zero() || self.quorum > Decimal::one() {
+            return Err(StdError::generic_err("quorum must be in (0, 1]"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Proposal {
+    pub proposer: Addr,
+    pub rules: VotingRules,
+    /// Height whose starting voting power counts
+    pub start_height: u64,
+    pub expires: u64,
+    pub total_power: Uint128,
+    pub yes: Uint128,
+    pub no: Uint128,
+    pub abstain: Uint128,
+    pub status: ProposalStatus,
+}
+
+impl Proposal {
+    pub fn is_expired(&self, clock: &impl Clock) -> bool {
+        clock.seconds() >= self.expires
+    }
+
+    pub fn quorum_reached(&self) -> bool {
+        let cast = self.yes + self.no + self.abstain;
+        !self.total_power.is_zero()
+            && Decimal::from_ratio(cast, self.total_power) >= self.rules.quorum
+    }
+
+    pub fn threshold_reached(&self) -> bool {
+        let counted = self.yes + self.no;
+        !counted.is_zero() && Decimal::from_ratio(self.yes, counted) >= self.rules.threshold
+    }
+
+    /// The status the proposal has at the given block, an open proposal is decided once it
+    /// expires
+    pub fn current_status(&self, clock: &impl Clock) -> ProposalStatus {
+        if self.status != ProposalStatus::Open || !self.is_expired(clock) {
+            self.status
+        } else if self.quorum_reached() && self.threshold_reached() {
+            ProposalStatus::Passed
+        } else {
+            ProposalStatus::Rejected
+        }
+    }
+}
+
+pub struct VotingPower<'a> {
+    power: SnapshotMap<'a, &'a Addr, Uint128>,
+    total: SnapshotItem<'a, Uint128>,
+    proposals: Map<'a, u64, Proposal>,
+    votes: Map<'a, (u64, &'a Addr), Vote>,
+}
+
+impl<'a> VotingPower<'a> {
+    /// Proposals add a checkpoint at their start height to `power` and `total`, so they work
+    /// with any `Strategy` other than `Never`
+    pub const fn new(
+        power: SnapshotMap<'a, &'a Addr, Uint128>,
+        total: SnapshotItem<'a, Uint128>,
+        proposals_namespace: &'a str,
+        votes_namespace: &'a str,
+    ) -> Self {
+        VotingPower {
+            power,
+            total,
+            proposals: Map::new(proposals_namespace),
+            votes: Map::new(votes_namespace),
+        }
+    }
+
+    pub fn power(&self, store: &dyn Storage, voter: &'a Addr) -> StdResult<Uint128> {
+        Ok(self.power.may_load(store, voter)?.unwrap_or_default())
+    }
+
+    pub fn power_at_height(
+        &self,
+        store: &dyn Storage,
+        voter: &'a Addr,
+        height: u64,
+    ) -> StdResult<Uint128> {
+        Ok(self
+            .power
+            .may_load_at_height(store, voter, height)?
+            .unwrap_or_default())
+    }
+
+    pub fn total(&self, store: &dyn Storage) -> StdResult<Uint128> {
+        Ok(self.total.may_load(store)?.unwrap_or_default())
+    }
+
+    /// Sets the voter's power and keeps the total in sync
+    pub fn set_power(
+        &self,
+        store: &mut dyn Storage,
+        voter: &'a Addr,
+        power: Uint128,
+        height: u64,
+    ) -> StdResult<()> {
+        let previous = self.power(store, voter)?;
+        let total = self
+            .total(store)?
+            .checked_sub(previous)?
+            .checked_add(power)?;
+        self.total.save(store, &total, height)?;
+        if power.is_zero() {
+            self.power.remove(store, voter, height)
+        } else {
+            self.power.save(store, voter, &power, height)
+        }
+    }
+
+    /// Opens a proposal that can be voted on until `expires`, returns its id
+    pub fn propose(
+        &self,
+        store: &mut dyn Storage,
+        clock: &impl Clock,
+        proposer: Addr,
+        rules: VotingRules,
+        expires: u64,
+    ) -> StdResult<u64> {
+        rules.validate()?;
+        if expires <= clock.seconds() {
+            return Err(StdError::generic_err("proposal would already be expired"));
+        }
+        self.power.add_checkpoint(store, clock.height())?;
+        self.total.add_checkpoint(store, clock.height())?;
+        let total_power = self
+            .total
+            .may_load_at_height(store, clock.height())?
+            .unwrap_or_default();
+        if total_power.is_zero() {
+            return Err(StdError::generic_err("there is no voting power"));
+        }
+
+        let id = self
+            .proposals
+            .keys(store, None, None, Order::Descending)
+            .next()
+            .transpose()?
+            .map_or(1, |last| last + 1);
+        self.proposals.save(
+            store,
+            id,
+            &Proposal {
+                proposer,
+                rules,
+                start_height: clock.height(),
+                expires,
+                total_power,
+                yes: Uint128::zero(),
+                no: Uint128::zero(),
+                abstain: Uint128::zero(),
+                status: ProposalStatus::Open,
+            },
+        )?;
+        Ok(id)
+    }
+
+    pub fn proposal(&self, store: &dyn Storage, id: u64) -> StdResult<Proposal> {
+        self.proposals.load(store, id)
+    }
+
+    pub fn vote_of(
+        &self,
+        store: &dyn Storage,
+        id: u64,
+        voter: &'a Addr,
+    ) -> StdResult<Option<Vote>> {
+        self.votes.may_load(store, (id, voter))
+    }
+
+    /// Casts the voter's power at the proposal's start, returns the weight of the vote
+    pub fn vote(
+        &self,
+        store: &mut dyn Storage,
+        clock: &impl Clock,
+        id: u64,
+        voter: &'a Addr,
+        vote: Vote,
+    ) -> StdResult<Uint128> {
+        let mut proposal = self.proposals.load(store, id)?;
+        if proposal.status != ProposalStatus::Open || proposal.is_expired(clock) {
+            return Err(StdError::generic_err(format!(
+                "proposal {} is not open for voting",
+                id
+            )));
+        }
+        if self.votes.has(store, (id, voter)) {
+            return Err(StdError::generic_err(format!(
+                "{} already voted on proposal {}",
+                voter, id
+            )));
+        }
+        let weight = self.power_at_height(store, voter, proposal.start_height)?;
+        if weight.is_zero() {
+            return Err(StdError::generic_err(format!(
+                "{} has no voting power on proposal {}",
+                voter, id
+            )));
+        }
+
+        let tally = match vote {
+            Vote::Yes => &mut proposal.yes,
+            Vote::No => &mut proposal.no,
+            Vote::Abstain => &mut proposal.abstain,
+        };
+        *tally = tally.checked_add(weight)?;
+        self.votes.save(store, (id, voter), &vote)?;
+        self.proposals.save(store, id, &proposal)?;
+        Ok(weight)
+    }
+
+    /// Records the outcome of an expired proposal and releases its checkpoint
+    pub fn close(
+        &self,
+        store: &mut dyn Storage,
+        clock: &impl Clock,
+        id: u64,
+    ) -> StdResult<ProposalStatus> {
+        let mut proposal = self.proposals.load(store, id)?;
+        if proposal.status != ProposalStatus::Open {
+            return Err(StdError::generic_err(format!("proposal {} is closed", id)));
+        }
+        if !proposal.is_expired(clock) {
+            return Err(StdError::generic_err(format!(
+                "proposal {} is still open for voting",
+                id
+            )));
+        }
+        proposal.status = proposal.current_status(clock);
+        self.power.remove_checkpoint(store, proposal.start_height)?;
+        self.total.remove_checkpoint(store, proposal.start_height)?;
+        self.proposals.save(store, id, &proposal)?;
+        Ok(proposal.status)
+    }
+
+    /// Marks a passed proposal as executed, so that it can't be executed twice
+    pub fn execute(&self, store: &mut dyn Storage, id: u64) -> StdResult<Proposal> {
+        let mut proposal = self.proposals.load(store, id)?;
+        if proposal.status != ProposalStatus::Passed {
+            return Err(StdError::generic_err(format!(
+                "proposal {} has not passed",
+                id
+            )));
+        }
+        proposal.status = ProposalStatus::Executed;
+        self.proposals.save(store, id, &proposal)?;
+        Ok(proposal)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{MockClock, Strategy};
+    use cosmwasm_std::testing::MockStorage;
+
+    const VOTING: VotingPower = VotingPower::new(
+        SnapshotMap::new("power", "power__check", "power__change", Strategy::Selected),
+        SnapshotItem::new("total", "total__check", "total__change", Strategy::Selected),
+        "proposals",
+        "votes",
+    );
+
+    fn rules() -> VotingRules {
+        VotingRules {
+            threshold: Decimal::percent(50),
+            quorum: Decimal::percent(40),
+        }
+    }
+
+    #[test]
+    fn power_is_snapshotted() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+        VOTING.set_power(&mut storage, &alice, Uint128::new(60), 1)?;
+        VOTING.set_power(&mut storage, &bob, Uint128::new(40), 1)?;
+        assert_eq!(VOTING.total(&storage)?, Uint128::new(100));
+
+        let id = VOTING.propose(
+            &mut storage,
+            &MockClock::new(5, 100),
+            alice.clone(),
+            rules(),
+            200,
+        )?;
+        assert_eq!(id, 1);
+        assert_eq!(
+            VOTING.proposal(&storage, id)?.total_power,
+            Uint128::new(100)
+        );
+
+        // power gained after the proposal started doesn't count
+        VOTING.set_power(&mut storage, &bob, Uint128::new(1000), 5)?;
+        assert_eq!(VOTING.total(&storage)?, Uint128::new(1060));
+        assert_eq!(
+            VOTING.vote(&mut storage, &MockClock::new(6, 110), id, &bob, Vote::No)?,
+            Uint128::new(40)
+        );
+        assert!(VOTING
+            .vote(&mut storage, &MockClock::new(6, 110), id, &bob, Vote::Yes)
+            .is_err());
+        assert!(VOTING
+            .vote(
+                &mut storage,
+                &MockClock::new(6, 110),
+                id,
+                &Addr::unchecked("carol"),
+                Vote::Yes
+            )
+            .is_err());
+
+        VOTING.set_power(&mut storage, &alice, Uint128::zero(), 7)?;
+        assert_eq!(
+            VOTING.vote(&mut storage, &MockClock::new(8, 120), id, &alice, Vote::Yes)?,
+            Uint128::new(60)
+        );
+        assert_eq!(VOTING.vote_of(&storage, id, &alice)?, Some(Vote::Yes));
+        Ok(())
+    }
+
+    #[test]
+    fn proposal_lifecycle() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+        let carol = Addr::unchecked("carol");
+        VOTING.set_power(&mut storage, &alice, Uint128::new(30), 1)?;
+        VOTING.set_power(&mut storage, &bob, Uint128::new(20), 1)?;
+        VOTING.set_power(&mut storage, &carol, Uint128::new(50), 1)?;
+
+        let open = MockClock::new(2, 100);
+        assert!(VOTING
+            .propose(&mut storage, &open, alice.clone(), rules(), 100)
+            .is_err());
+        let passing = VOTING.propose(&mut storage, &open, alice.clone(), rules(), 200)?;
+        let no_quorum = VOTING.propose(&mut storage, &open, alice.clone(), rules(), 200)?;
+        let rejected = VOTING.propose(&mut storage, &open, alice.clone(), rules(), 200)?;
+        assert_eq!((passing, no_quorum, rejected), (1, 2, 3));
+
+        // 30 yes against 20 no with 50 abstaining
+        VOTING.vote(&mut storage, &open, passing, &alice, Vote::Yes)?;
+        VOTING.vote(&mut storage, &open, passing, &bob, Vote::No)?;
+        VOTING.vote(&mut storage, &open, passing, &carol, Vote::Abstain)?;
+        // only 30 out of 100 voted
+        VOTING.vote(&mut storage, &open, no_quorum, &alice, Vote::Yes)?;
+        VOTING.vote(&mut storage, &open, rejected, &alice, Vote::Yes)?;
+        VOTING.vote(&mut storage, &open, rejected, &carol, Vote::No)?;
+
+        assert!(VOTING.close(&mut storage, &open, passing).is_err());
+        assert!(VOTING.execute(&mut storage, passing).is_err());
+
+        let expired = MockClock::new(10, 200);
+        assert!(VOTING
+            .vote(&mut storage, &expired, no_quorum, &bob, Vote::Yes)
+            .is_err());
+        assert_eq!(
+            VOTING.proposal(&storage, passing)?.current_status(&expired),
+            ProposalStatus::Passed
+        );
+        assert_eq!(
+            VOTING.close(&mut storage, &expired, passing)?,
+            ProposalStatus::Passed
+        );
+        assert_eq!(
+            VOTING.close(&mut storage, &expired, no_quorum)?,
+            ProposalStatus::Rejected
+        );
+        assert_eq!(
+            VOTING.close(&mut storage, &expired, rejected)?,
+            ProposalStatus::Rejected
+        );
+        assert!(VOTING.close(&mut storage, &expired, passing).is_err());
+
+        assert!(VOTING.execute(&mut storage, rejected).is_err());
+        assert_eq!(
+            VOTING.execute(&mut storage, passing)?.status,
+            ProposalStatus::Executed
+        );
+        assert!(VOTING.execute(&mut storage, passing).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let mut storage = MockStorage::new();
+        let alice = Addr::unchecked("alice");
+        VOTING
+            .set_power(&mut storage, &alice, Uint128::new(1), 1)
+            .unwrap();
+        let clock = MockClock::new(2, 100);
+
+        for rules in &[
+            VotingRules {
+                threshold: Decimal::zero(),
+                quorum: Decimal::percent(10),
+            },
+            VotingRules {
+                threshold: Decimal::percent(101),
+                quorum: Decimal::percent(10),
+            },
+            VotingRules {
+                threshold: Decimal::percent(50),
+                quorum: Decimal::zero(),
+            },
+        ] {
+            assert!(VOTING
+                .propose(&mut storage, &clock, alice.clone(), *rules, 200)
+                .is_err());
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! A `WeightedPicker` draws an index with probability proportional to its weight, e.g. a
+//! lottery ticket or a validator. Weights live in a Fenwick tree, so updating a weight and
+//! drawing both take O(log n) storage reads, see [PrefixSum](crate::PrefixSum) for the tree
+//! on its own.
+//!
+//! The picker doesn't produce randomness: pass a random number, or a seed such as
+//! `env.block.random` to [WeightedPicker::pick_seeded].
+use sha2::{Digest, Sha256};
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+
+use crate::prefix_sum::{lowest_bit, tree_search, tree_sum, tree_update};
+use crate::{Item, Map};
+
+pub struct WeightedPicker<'a> {
+    /// Fenwick tree nodes, 1-based
+    tree: Map<'a, u64, u128>,
+    weights: Map<'a, u64, u128>,
+    len: Item<'a, u64>,
+}
+
+impl<'a> WeightedPicker<'a> {
+    pub const fn new(
+        tree_namespace: &'a str,
+        weights_namespace: &'a str,
+        len_key: &'a str,
+    ) -> Self {
+        WeightedPicker {
+            tree: Map::new(tree_namespace),
+            weights: Map::new(weights_namespace),
+            len: Item::new(len_key),
+        }
+    }
+
+    pub fn len(&self, store: &dyn Storage) -> StdResult<u64> {
+        Ok(self.len.may_load(store)?.unwrap_or_default())
+    }
+
+    pub fn is_empty(&self, store: &dyn Storage) -> StdResult<bool> {
+        Ok(self.len(store)? == 0)
+    }
+
+    pub fn total(&self, store: &dyn Storage) -> StdResult<u128> {
+        let len = self.len(store)?;
+        tree_sum(&self.tree, store, len)
+    }
+
+    pub fn weight(&self, store: &dyn Storage, index: u64) -> StdResult<u128> {
+        self.weights
+            .may_load(store, index)?
+            .ok_or_else(|| StdError::not_found(format!("weighted entry {}", index)))
+    }
+
+    /// Adds an entry and returns its index, indexes count up from 0
+    pub fn push(&self, store: &mut dyn Storage, weight: u128) -> StdResult<u64> {
+        if self.total(store)?.checked_add(weight).is_none() {
+            return Err(StdError::generic_err("total weight overflow"));
+        }
+        let index = self.len(store)?;
+        let i = index + 1;
+        // The new node covers the entries (i - lowest_bit(i), i]
+        let covered =
+            tree_sum(&self.tree, store, index)? - tree_sum(&self.tree, store, i - lowest_bit(i))?;
+        self.tree.save(store, i, &(covered + weight))?;
+        self.weights.save(store, index, &weight)?;
+        self.len.save(store, &i)?;
+        Ok(index)
+    }
+
+    pub fn set_weight(&self, store: &mut dyn Storage, index: u64, weight: u128) -> StdResult<()> {
+        let old = self.weight(store, index)?;
+        if weight > old && self.total(store)?.checked_add(weight - old).is_none() {
+            return Err(StdError::generic_err("total weight overflow"));
+        }
+        let len = self.len(store)?;
+        if weight >= old {
+            tree_update(&self.tree, store, index + 1, len, weight - old, true)?;
+        } else {
+            tree_update(&self.tree, store, index + 1, len, old - weight, false)?;
+        }
+        self.weights.save(store, index, &weight)
+    }
+
+    /// The entry whose cumulative weight range contains `random % total`, entries with no
+    /// weight are never picked
+    pub fn pick(&self, store: &dyn Storage, random: u128) -> StdResult<u64> {
+        let total = self.total(store)?;
+        if total == 0 {
+            return Err(StdError::generic_err("nothing to pick from"));
+        }
+        let len = self.len(store)?;
+        tree_search(&self.tree, store, len, random % total)
+    }
+
+    /// Picks with a random number derived from the seed
+    pub fn pick_seeded(&self, store: &dyn Storage, seed: &[u8]) -> StdResult<u64> {
+        let mut random = [0u8; 16];
+        random.copy_from_slice(&Sha256::digest(seed)[..16]);
+        self.pick(store, u128::from_be_bytes(random))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    const TICKETS: WeightedPicker = WeightedPicker::new("tickets", "ticket_weights", "ticket_len");
+
+    #[test]
+    fn picks_by_cumulative_weight() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        assert!(TICKETS.pick(&storage, 0).is_err());
+
+        let weights = [3u128, 0, 5, 1, 7, 2];
+        for weight in weights.iter() {
+            TICKETS.push(&mut storage, *weight)?;
+        }
+        assert_eq!(TICKETS.len(&storage)?, 6);
+        assert_eq!(TICKETS.total(&storage)?, 18);
+
+        // ranges: 0 [0, 3), 2 [3, 8), 3 [8, 9), 4 [9, 16), 5 [16, 18)
+        let picks: StdResult<Vec<u64>> = (0..18u128).map(|r| TICKETS.pick(&storage, r)).collect();
+        assert_eq!(
+            picks?,
+            vec![0, 0, 0, 2, 2, 2, 2, 2, 3, 4, 4, 4, 4, 4, 4, 4, 5, 5]
+        );
+        assert_eq!(TICKETS.pick(&storage, 18)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn updates_weights() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        for weight in [3u128, 0, 5, 1, 7, 2].iter() {
+            TICKETS.push(&mut storage, *weight)?;
+        }
+        TICKETS.set_weight(&mut storage, 0, 0)?;
+        TICKETS.set_weight(&mut storage, 1, 4)?;
+        assert!(TICKETS.set_weight(&mut storage, 6, 1).is_err());
+        assert_eq!(TICKETS.weight(&storage, 1)?, 4);
+        assert_eq!(TICKETS.total(&storage)?, 19);
+        assert_eq!(TICKETS.pick(&storage, 0)?, 1);
+        assert_eq!(TICKETS.pick(&storage, 4)?, 2);
+        assert_eq!(TICKETS.pick(&storage, 18)?, 5);
+
+        // a single entry with all the weight is always picked
+        for index in 0..6 {
+            TICKETS.set_weight(&mut storage, index, 0)?;
+        }
+        TICKETS.set_weight(&mut storage, 3, 10)?;
+        assert_eq!(TICKETS.pick_seeded(&storage, b"block random")?, 3);
+        assert!(TICKETS.push(&mut storage, u128::MAX).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::{to_vec, Api, Binary, Coin, StdError, StdResult};
+use ripemd::Ripemd160;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// Fields are declared in alphabetical order, amino JSON is signed with sorted keys
+
+#[derive(Serialize)]
+struct SignDoc<'a> {
+    account_number: &'static str,
+    chain_id: &'static str,
+    fee: Fee,
+    memo: &'static str,
+    msgs: Vec<SignMsg<'a>>,
+    sequence: &'static str,
+}
+
+#[derive(Serialize)]
+struct Fee {
+    amount: Vec<Coin>,
+    gas: &'static str,
+}
+
+#[derive(Serialize)]
+struct SignMsg<'a> {
+    #[serde(rename = "type")]
+    msg_type: &'static str,
+    value: MsgSignData<'a>,
+}
+
+#[derive(Serialize)]
+struct MsgSignData<'a> {
+    data: Binary,
+    signer: &'a str,
+}
+
+/// The ADR-36 amino sign doc wallets sign for arbitrary `data`, with the empty chain id,
+/// zero fee, account number and sequence the spec requires
+pub fn adr36_sign_bytes(signer: &str, data: &[u8]) -> StdResult<Vec<u8>> {
+    to_vec(&SignDoc {
+        account_number: "0",
+        chain_id: "",
+        fee: Fee {
+            amount: vec![],
+            gas: "0",
+        },
+        memo: "",
+        msgs: vec![SignMsg {
+            msg_type: "sign/MsgSignData",
+            value: MsgSignData {
+                data: Binary::from(data),
+                signer,
+            },
+        }],
+        sequence: "0",
+    })
+}
+
+/// Bech32 address of a compressed secp256k1 public key
+pub fn pubkey_to_address(pubkey: &[u8], hrp: &str) -> StdResult<String> {
+    let hash = Ripemd160::digest(Sha256::digest(pubkey));
+    bech32::encode(hrp, hash.to_base32(), Variant::Bech32)
+        .map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+/// Checks that `signature` is `signer`'s ADR-36 signature of `data` and that `pubkey`
+/// belongs to `signer`
+pub fn verify_adr36(
+    api: &dyn Api,
+    signer: &str,
+    data: &[u8],
+    pubkey: &[u8],
+    signature: &[u8],
+) -> StdResult<()> {
+    let hrp = signer
+        .rfind('1')
+        .map(|separator| &signer[..separator])
+        .ok_or_else(|| StdError::generic_err(format!("invalid address {}", signer)))?;
+    if pubkey_to_address(pubkey, hrp)? != signer {
+        return Err(StdError::generic_err(format!(
+            "public key does not belong to {}",
+            signer
+        )));
+    }
+    let hash = Sha256::digest(&adr36_sign_bytes(signer, data)?);
+    if !api.secp256k1_verify(&hash, signature, pubkey)? {
+        return Err(StdError::generic_err("invalid ADR-36 signature"));
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PubKey {
+    /// `tendermint/PubKeySecp256k1`
+    #[serde(rename = "type")]
+    pub key_type: String,
+    pub value: Binary,
+}
+
+/// A signature as wallets and `secretd tx sign-doc` return it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Adr36Signature {
+    pub pub_key: PubKey,
+    pub signature: Binary,
+}
+
+impl Adr36Signature {
+    pub fn verify(&self, api: &dyn Api, signer: &str, data: &[u8]) -> StdResult<()> {
+        verify_adr36(
+            api,
+            signer,
+            data,
+            self.pub_key.value.as_slice(),
+            self.signature.as_slice(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::from_slice;
+    use cosmwasm_std::testing::MockApi;
+
+    const SIGNER: &str = "secret15wgtkntdf26hqan77g0kdsldcxjddypx7drdk5";
+    const DATA: &[u8] = b"login to example.com";
+    const SIGNATURE: &str = r#"{
+        "pub_key": {
+            "type": "tendermint/PubKeySecp256k1",
+            "value": "ArtQ4tiaTtcGY9CAZZ/grUubw+BsF6InQzlmy1nO7gIN"
+        },
+        "signature": "0os5aepBWJ9+yZ3fUZhfw8FwJSrCw/3n0otrxmATcH019rfUD8yaIuMDfSMjbMV4BhU2iA81gPuDoU1leCDCwg=="
+    }"#;
+
+    #[test]
+    fn sign_bytes() {
+        assert_eq!(
+            String::from_utf8(adr36_sign_bytes(SIGNER, DATA).unwrap()).unwrap(),
+            concat!(
+                r#"{"account_number":"0","chain_id":"","fee":{"amount":[],"gas":"0"},"memo":"","#,
+                r#""msgs":[{"type":"sign/MsgSignData","value":{"data":"bG9naW4gdG8gZXhhbXBsZS5jb20=","#,
+                r#""signer":"secret15wgtkntdf26hqan77g0kdsldcxjddypx7drdk5"}}],"sequence":"0"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn verify() {
+        let api = MockApi::default();
+        let signature: Adr36Signature = from_slice(SIGNATURE.as_bytes()).unwrap();
+        assert_eq!(
+            pubkey_to_address(signature.pub_key.value.as_slice(), "secret").unwrap(),
+            SIGNER
+        );
+        signature.verify(&api, SIGNER, DATA).unwrap();
+
+        // different data
+        assert!(signature
+            .verify(&api, SIGNER, b"login to evil.com")
+            .is_err());
+        // someone else's address
+        assert!(signature
+            .verify(&api, "secret1ap26qrlp8mcq2pg6r47w43l0y8zkqm8a450s03", DATA)
+            .is_err());
+        assert!(signature.verify(&api, "not an address", DATA).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use cosmwasm_std::{from_binary, to_binary, Binary, StdError, StdResult};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Most queries a single batch may carry
+pub const MAX_BATCH_QUERIES: usize = 32;
+
+/// Several queries answered in one round trip. Contracts add it to their query messages,
+/// e.g. `QueryMsg::Batch(BatchQuery<QueryMsg>)`, and [BatchQuery::run] it through their own
+/// query handler.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BatchQuery<Q> {
+    pub queries: Vec<Q>,
+}
+
+impl<Q> BatchQuery<Q> {
+    pub fn new(queries: Vec<Q>) -> Self {
+        BatchQuery { queries }
+    }
+
+    /// Answers every query in order, a failing query doesn't fail the others
+    pub fn run<F>(self, mut query: F) -> StdResult<Binary>
+    where
+        F: FnMut(Q) -> StdResult<Binary>,
+    {
+        if self.queries.len() > MAX_BATCH_QUERIES {
+            return Err(StdError::generic_err(format!(
+                "batch has {} queries, at most {} are allowed",
+                self.queries.len(),
+                MAX_BATCH_QUERIES
+            )));
+        }
+        let results: Vec<BatchQueryResult> = self
+            .queries
+            .into_iter()
+            .map(|msg| match query(msg) {
+                Ok(data) => BatchQueryResult::Ok(data),
+                Err(err) => BatchQueryResult::Err(err.to_string()),
+            })
+            .collect();
+        to_binary(&results)
+    }
+}
+
+/// The answer to one query of a batch, `{"ok":"<base64>"}` or `{"err":"<message>"}`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchQueryResult {
+    Ok(Binary),
+    Err(String),
+}
+
+impl BatchQueryResult {
+    pub fn decode<T: DeserializeOwned>(&self) -> StdResult<T> {
+        match self {
+            BatchQueryResult::Ok(data) => from_binary(data),
+            BatchQueryResult::Err(err) => Err(StdError::generic_err(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::from_slice;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    enum QueryMsg {
+        Balance { address: String },
+        Config {},
+    }
+
+    fn query(msg: QueryMsg) -> StdResult<Binary> {
+        match msg {
+            QueryMsg::Balance { address } if address == "alice" => to_binary(&100u64),
+            QueryMsg::Balance { address } => Err(StdError::not_found(address)),
+            QueryMsg::Config {} => to_binary("config"),
+        }
+    }
+
+    #[test]
+    fn results_keep_query_order() {
+        let batch = BatchQuery::new(vec![
+            QueryMsg::Balance {
+                address: "alice".to_string(),
+            },
+            QueryMsg::Balance {
+                address: "bob".to_string(),
+            },
+            QueryMsg::Config {},
+        ]);
+        let results: Vec<BatchQueryResult> = from_binary(&batch.run(query).unwrap()).unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].decode::<u64>().unwrap(), 100);
+        assert_eq!(
+            results[1],
+            BatchQueryResult::Err("bob not found".to_string())
+        );
+        assert!(results[1].decode::<u64>().is_err());
+        assert_eq!(results[2].decode::<String>().unwrap(), "config");
+    }
+
+    #[test]
+    fn serialization() {
+        let batch: BatchQuery<QueryMsg> = from_slice(br#"{"queries":[{"config":{}}]}"#).unwrap();
+        assert_eq!(batch, BatchQuery::new(vec![QueryMsg::Config {}]));
+        assert_eq!(
+            batch.run(query).unwrap().as_slice(),
+            br#"[{"ok":"ImNvbmZpZyI="}]"#
+        );
+    }
+
+    #[test]
+    fn batch_size_is_limited() {
+        let batch = BatchQuery::new(vec![QueryMsg::Config {}; MAX_BATCH_QUERIES + 1]);
+        assert!(batch.run(query).is_err());
+    }
+}
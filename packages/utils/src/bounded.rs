@@ -0,0 +1,232 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Deref;
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject, StringValidation};
+use schemars::JsonSchema;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use cosmwasm_std::{Binary, StdError};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum BoundedError {
+    #[error("Value is {actual} bytes long, the maximum is {max}")]
+    TooLong { max: usize, actual: usize },
+}
+
+impl From<BoundedError> for StdError {
+    fn from(err: BoundedError) -> Self {
+        StdError::generic_err(err.to_string())
+    }
+}
+
+fn check_len(max: usize, actual: usize) -> Result<(), BoundedError> {
+    if actual > max {
+        Err(BoundedError::TooLong { max, actual })
+    } else {
+        Ok(())
+    }
+}
+
+/// A string of at most `N` bytes. Deserializing a longer string fails, so oversized
+/// user input is rejected as soon as the message is parsed.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct BoundedString<const N: usize>(String);
+
+impl<const N: usize> BoundedString<N> {
+    pub fn new(value: impl Into<String>) -> Result<Self, BoundedError> {
+        let value = value.into();
+        check_len(N, value.len())?;
+        Ok(BoundedString(value))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl<const N: usize> Deref for BoundedString<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<const N: usize> fmt::Display for BoundedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<const N: usize> TryFrom<String> for BoundedString<N> {
+    type Error = BoundedError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for BoundedString<N> {
+    type Error = BoundedError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl<const N: usize> From<BoundedString<N>> for String {
+    fn from(value: BoundedString<N>) -> Self {
+        value.0
+    }
+}
+
+impl<const N: usize> Serialize for BoundedString<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for BoundedString<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::new(value).map_err(de::Error::custom)
+    }
+}
+
+impl<const N: usize> JsonSchema for BoundedString<N> {
+    fn schema_name() -> String {
+        format!("BoundedString_{}", N)
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                max_length: Some(N as u32),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// Binary data of at most `N` bytes, serialized as base64 like [Binary].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct BoundedBytes<const N: usize>(Binary);
+
+impl<const N: usize> BoundedBytes<N> {
+    pub fn new(value: impl Into<Binary>) -> Result<Self, BoundedError> {
+        let value = value.into();
+        check_len(N, value.len())?;
+        Ok(BoundedBytes(value))
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    pub fn into_inner(self) -> Binary {
+        self.0
+    }
+}
+
+impl<const N: usize> Deref for BoundedBytes<N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+impl<const N: usize> TryFrom<Binary> for BoundedBytes<N> {
+    type Error = BoundedError;
+
+    fn try_from(value: Binary) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl<const N: usize> TryFrom<Vec<u8>> for BoundedBytes<N> {
+    type Error = BoundedError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl<const N: usize> From<BoundedBytes<N>> for Binary {
+    fn from(value: BoundedBytes<N>) -> Self {
+        value.0
+    }
+}
+
+impl<const N: usize> Serialize for BoundedBytes<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for BoundedBytes<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Binary::deserialize(deserializer)?;
+        Self::new(value).map_err(de::Error::custom)
+    }
+}
+
+impl<const N: usize> JsonSchema for BoundedBytes<N> {
+    fn schema_name() -> String {
+        format!("BoundedBytes_{}", N)
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        // base64 can't be length checked by the schema, the limit applies to the decoded bytes
+        Binary::json_schema(gen)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::{from_slice, to_vec};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Profile {
+        name: BoundedString<8>,
+        avatar: BoundedBytes<4>,
+    }
+
+    #[test]
+    fn accepts_values_within_bounds() {
+        let profile: Profile = from_slice(br#"{"name":"satoshi","avatar":"AQIDBA=="}"#).unwrap();
+        assert_eq!(profile.name.as_str(), "satoshi");
+        assert_eq!(profile.avatar.as_slice(), &[1, 2, 3, 4]);
+
+        // round trips to the same json
+        let json = to_vec(&profile).unwrap();
+        assert_eq!(from_slice::<Profile>(&json).unwrap(), profile);
+    }
+
+    #[test]
+    fn rejects_oversized_values() {
+        let err = from_slice::<Profile>(br#"{"name":"satoshi nakamoto","avatar":""}"#).unwrap_err();
+        assert!(err.to_string().contains("the maximum is 8"), "{}", err);
+
+        let err = from_slice::<Profile>(br#"{"name":"","avatar":"AQIDBAU="}"#).unwrap_err();
+        assert!(err.to_string().contains("the maximum is 4"), "{}", err);
+
+        assert_eq!(
+            BoundedString::<3>::new("four"),
+            Err(BoundedError::TooLong { max: 3, actual: 4 })
+        );
+        // the limit is in bytes, not characters
+        assert!(BoundedString::<3>::new("éé").is_err());
+    }
+}
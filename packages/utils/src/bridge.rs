@@ -0,0 +1,153 @@
+use cosmwasm_std::Uint128;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Native denom wrapped by sSCRT
+pub const SCRT_DENOM: &str = "uscrt";
+
+/// Execute messages of a wrapped native token (sSCRT and other SNIP-20 deposit tokens).
+/// Deposit mints tokens for the native coins sent along, redeem burns them and sends the coins back.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WrappedTokenMsg {
+    Deposit {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        padding: Option<String>,
+    },
+    Redeem {
+        amount: Uint128,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        denom: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        padding: Option<String>,
+    },
+}
+
+impl WrappedTokenMsg {
+    pub fn deposit() -> Self {
+        WrappedTokenMsg::Deposit { padding: None }
+    }
+
+    pub fn redeem(amount: impl Into<Uint128>) -> Self {
+        WrappedTokenMsg::Redeem {
+            amount: amount.into(),
+            denom: None,
+            padding: None,
+        }
+    }
+}
+
+/// Message attached to a token send to an ICS-20 bridge contract, asking it to transfer the
+/// tokens over IBC
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Ics20TransferMsg {
+    /// The local channel to send the packets on
+    pub channel: String,
+    /// The address on the remote chain to receive these tokens
+    pub remote_address: String,
+    /// How long the packet lives in seconds, the bridge default is used if unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+/// The path an IBC voucher took to get here, e.g. `transfer/channel-0` for `uatom`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomTrace {
+    pub path: String,
+    pub base_denom: String,
+}
+
+impl DenomTrace {
+    pub fn new(port: &str, channel: &str, base_denom: &str) -> Self {
+        DenomTrace {
+            path: format!("{}/{}", port, channel),
+            base_denom: base_denom.to_string(),
+        }
+    }
+
+    /// Parses a full trace such as `transfer/channel-0/uatom`, a denom without any
+    /// port/channel pairs has an empty path
+    pub fn parse(full_denom: &str) -> Self {
+        let parts: Vec<&str> = full_denom.split('/').collect();
+        let mut hops = 0;
+        while hops * 2 + 2 < parts.len() && parts[hops * 2 + 1].starts_with("channel-") {
+            hops += 1;
+        }
+        DenomTrace {
+            path: parts[..hops * 2].join("/"),
+            base_denom: parts[hops * 2..].join("/"),
+        }
+    }
+
+    pub fn full_denom(&self) -> String {
+        if self.path.is_empty() {
+            self.base_denom.clone()
+        } else {
+            format!("{}/{}", self.path, self.base_denom)
+        }
+    }
+
+    /// The `ibc/<HASH>` denom the voucher has on this chain, native denoms are returned as is
+    pub fn ibc_denom(&self) -> String {
+        if self.path.is_empty() {
+            return self.base_denom.clone();
+        }
+        let hash = Sha256::digest(self.full_denom().as_bytes());
+        let hex: String = hash.iter().map(|b| format!("{:02X}", b)).collect();
+        format!("ibc/{}", hex)
+    }
+}
+
+/// True for IBC voucher denoms (`ibc/<HASH>`)
+pub fn is_ibc_denom(denom: &str) -> bool {
+    denom.starts_with("ibc/")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::{from_slice, to_vec};
+
+    #[test]
+    fn wrapped_token_msgs() {
+        assert_eq!(
+            to_vec(&WrappedTokenMsg::deposit()).unwrap(),
+            br#"{"deposit":{}}"#.to_vec()
+        );
+        assert_eq!(
+            to_vec(&WrappedTokenMsg::redeem(100u128)).unwrap(),
+            br#"{"redeem":{"amount":"100"}}"#.to_vec()
+        );
+        let msg: WrappedTokenMsg =
+            from_slice(br#"{"redeem":{"amount":"5","denom":"uscrt"}}"#).unwrap();
+        assert_eq!(
+            msg,
+            WrappedTokenMsg::Redeem {
+                amount: Uint128::new(5),
+                denom: Some(SCRT_DENOM.to_string()),
+                padding: None
+            }
+        );
+    }
+
+    #[test]
+    fn denom_traces() {
+        let trace = DenomTrace::parse("transfer/channel-0/uatom");
+        assert_eq!(trace, DenomTrace::new("transfer", "channel-0", "uatom"));
+        assert_eq!(
+            trace.ibc_denom(),
+            "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2"
+        );
+        assert!(is_ibc_denom(&trace.ibc_denom()));
+
+        let native = DenomTrace::parse("uscrt");
+        assert_eq!(native.path, "");
+        assert_eq!(native.ibc_denom(), "uscrt");
+
+        // base denoms may contain slashes themselves
+        let trace = DenomTrace::parse("transfer/channel-1/gamm/pool/1");
+        assert_eq!(trace.path, "transfer/channel-1");
+        assert_eq!(trace.base_denom, "gamm/pool/1");
+    }
+}
@@ -1,7 +1,8 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{BlockInfo, StdError, StdResult, Timestamp};
+use crate::clock::Clock;
+use cosmwasm_std::{StdError, StdResult, Timestamp};
 use std::cmp::Ordering;
 use std::fmt;
 use std::ops::{Add, Mul};
@@ -9,7 +10,7 @@ use std::ops::{Add, Mul};
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 /// Expiration represents a point in time when some event happens.
-/// It can compare with a BlockInfo and will return is_expired() == true
+/// It can compare with a Clock, e.g. BlockInfo, and will return is_expired() == true
 /// once the condition is hit (and for every block in the future)
 pub enum Expiration {
     /// AtHeight will expire when `env.block.height` >= height
@@ -38,10 +39,10 @@ impl Default for Expiration {
 }
 
 impl Expiration {
-    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+    pub fn is_expired(&self, clock: &impl Clock) -> bool {
         match self {
-            Expiration::AtHeight(height) => block.height >= *height,
-            Expiration::AtTime(time) => block.time >= *time,
+            Expiration::AtHeight(height) => clock.height() >= *height,
+            Expiration::AtTime(time) => clock.time() >= *time,
             Expiration::Never {} => false,
         }
     }
@@ -107,10 +108,10 @@ impl fmt::Display for Duration {
 
 impl Duration {
     /// Create an expiration for Duration after current block
-    pub fn after(&self, block: &BlockInfo) -> Expiration {
+    pub fn after(&self, clock: &impl Clock) -> Expiration {
         match self {
-            Duration::Height(h) => Expiration::AtHeight(block.height + h),
-            Duration::Time(t) => Expiration::AtTime(block.time.plus_seconds(*t)),
+            Duration::Height(h) => Expiration::AtHeight(clock.height() + h),
+            Duration::Time(t) => Expiration::AtTime(clock.time().plus_seconds(*t)),
         }
     }
 
@@ -149,6 +150,8 @@ impl Mul<u64> for Duration {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::clock::MockClock;
+    use cosmwasm_std::BlockInfo;
 
     #[test]
     fn compare_expiration() {
@@ -228,6 +231,21 @@ mod test {
         assert_eq!(Expiration::AtTime(Timestamp::from_seconds(8989)), end);
     }
 
+    #[test]
+    fn expires_on_mock_clock() {
+        let mut clock = MockClock::new(10, 100);
+        let at_height = Duration::Height(5).after(&clock);
+        let at_time = Duration::Time(60).after(&clock);
+        assert!(!at_height.is_expired(&clock));
+        assert!(!at_time.is_expired(&clock));
+
+        clock.advance_blocks(5);
+        assert!(at_height.is_expired(&clock));
+        assert!(!at_time.is_expired(clock.advance_seconds(59)));
+        assert!(at_time.is_expired(clock.advance_seconds(1)));
+        assert!(!Expiration::Never {}.is_expired(&clock));
+    }
+
     #[test]
     fn duration_math() {
         let long = (Duration::Height(444) + Duration::Height(555)).unwrap();
@@ -0,0 +1,107 @@
+use cosmwasm_std::{from_binary, to_binary, Binary, Response, StdError, StdResult};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    Success,
+    Failure,
+}
+
+/// Data envelope for execute responses, `{"status":"success","data":...}`.
+/// Lets callers tell a handled failure apart from an answer without parsing `data` first.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HandleAnswer<T> {
+    pub status: ResponseStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+}
+
+impl<T> HandleAnswer<T> {
+    pub fn success(data: T) -> Self {
+        HandleAnswer {
+            status: ResponseStatus::Success,
+            data: Some(data),
+        }
+    }
+
+    pub fn failure(data: Option<T>) -> Self {
+        HandleAnswer {
+            status: ResponseStatus::Failure,
+            data,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status == ResponseStatus::Success
+    }
+
+    /// The data of a successful answer, errors on failures and missing data
+    pub fn into_data(self) -> StdResult<T> {
+        match (self.status, self.data) {
+            (ResponseStatus::Success, Some(data)) => Ok(data),
+            (ResponseStatus::Success, None) => {
+                Err(StdError::generic_err("handle answer has no data"))
+            }
+            (ResponseStatus::Failure, _) => {
+                Err(StdError::generic_err("handle answer status is failure"))
+            }
+        }
+    }
+}
+
+impl<T: Serialize> HandleAnswer<T> {
+    /// A response carrying this answer as its data
+    pub fn into_response<C>(self) -> StdResult<Response<C>> {
+        Ok(Response::new().set_data(to_binary(&self)?))
+    }
+}
+
+impl<T: DeserializeOwned> HandleAnswer<T> {
+    pub fn from_binary(data: &Binary) -> StdResult<Self> {
+        from_binary(data)
+    }
+}
+
+/// Decodes the data of a successful [HandleAnswer], e.g. from a reply or another contract's
+/// execute response
+pub fn parse_handle_answer<T: DeserializeOwned>(data: &Binary) -> StdResult<T> {
+    HandleAnswer::from_binary(data)?.into_data()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    struct Minted {
+        token_id: String,
+    }
+
+    #[test]
+    fn round_trip() {
+        let minted = Minted {
+            token_id: "42".to_string(),
+        };
+        let response: Response = HandleAnswer::success(minted.clone())
+            .into_response()
+            .unwrap();
+        let data = response.data.unwrap();
+        assert_eq!(
+            data.as_slice(),
+            br#"{"status":"success","data":{"token_id":"42"}}"#
+        );
+        assert_eq!(parse_handle_answer::<Minted>(&data).unwrap(), minted);
+    }
+
+    #[test]
+    fn failures_are_errors() {
+        let data = to_binary(&HandleAnswer::<Minted>::failure(None)).unwrap();
+        assert_eq!(data.as_slice(), br#"{"status":"failure"}"#);
+        let answer = HandleAnswer::<Minted>::from_binary(&data).unwrap();
+        assert!(!answer.is_success());
+        assert!(parse_handle_answer::<Minted>(&data).is_err());
+    }
+}
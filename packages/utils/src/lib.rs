@@ -1,13 +1,23 @@
+mod adr36;
 mod balance;
+mod batch_query;
+mod bounded;
+mod bridge;
+mod clock;
 mod event;
 mod expiration;
+mod handle_answer;
+mod padding;
 #[cfg(feature = "iterator")]
 mod pagination;
 mod parse_reply;
 mod payment;
+mod permit;
 mod scheduled;
 mod threshold;
 
+pub use adr36::{adr36_sign_bytes, pubkey_to_address, verify_adr36, Adr36Signature, PubKey};
+pub use padding::{pad_query_result, pad_response, space_pad, BLOCK_SIZE};
 #[cfg(feature = "iterator")]
 pub use pagination::{
     calc_range_end, calc_range_start, calc_range_start_string, maybe_addr, maybe_canonical,
@@ -18,9 +28,15 @@ pub use parse_reply::{
     ParseReplyError,
 };
 pub use payment::{may_pay, must_pay, nonpayable, one_coin, PaymentError};
+pub use permit::{Permit, PermitParams, RelayerProof};
 pub use threshold::{Threshold, ThresholdError, ThresholdResponse};
 
 pub use crate::balance::NativeBalance;
+pub use crate::batch_query::{BatchQuery, BatchQueryResult, MAX_BATCH_QUERIES};
+pub use crate::bounded::{BoundedBytes, BoundedError, BoundedString};
+pub use crate::bridge::{is_ibc_denom, DenomTrace, Ics20TransferMsg, WrappedTokenMsg, SCRT_DENOM};
+pub use crate::clock::{Clock, MockClock};
 pub use crate::event::Event;
 pub use crate::expiration::{Duration, Expiration, DAY, HOUR, WEEK};
+pub use crate::handle_answer::{parse_handle_answer, HandleAnswer, ResponseStatus};
 pub use crate::scheduled::Scheduled;
@@ -0,0 +1,76 @@
+use cosmwasm_std::{Binary, Response, StdResult};
+
+/// Block size most Secret contracts pad their responses to
+pub const BLOCK_SIZE: usize = 256;
+
+/// Pads the message with spaces up to a multiple of `block_size`, so its length only leaks the
+/// number of blocks. A block size of 0 leaves the message as is.
+/// Trailing spaces are insignificant whitespace once the message is parsed as JSON.
+pub fn space_pad(message: &mut Vec<u8>, block_size: usize) -> &mut Vec<u8> {
+    if block_size == 0 {
+        return message;
+    }
+    let surplus = message.len() % block_size;
+    if surplus != 0 {
+        message.resize(message.len() + block_size - surplus, b' ');
+    }
+    message
+}
+
+/// Pads the data of a successful response, errors and responses without data are left alone
+pub fn pad_response<T>(
+    response: StdResult<Response<T>>,
+    block_size: usize,
+) -> StdResult<Response<T>> {
+    response.map(|mut response| {
+        response.data = response.data.map(|mut data| {
+            space_pad(&mut data.0, block_size);
+            data
+        });
+        response
+    })
+}
+
+/// Pads the result of a successful query
+pub fn pad_query_result(result: StdResult<Binary>, block_size: usize) -> StdResult<Binary> {
+    result.map(|mut data| {
+        space_pad(&mut data.0, block_size);
+        data
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::{from_binary, to_binary, StdError};
+
+    #[test]
+    fn pads_to_block_size() {
+        let mut message = b"{}".to_vec();
+        assert_eq!(space_pad(&mut message, 8), b"{}      ");
+        assert_eq!(space_pad(&mut message, 8).len(), 8);
+        assert_eq!(space_pad(&mut message, 0).len(), 8);
+
+        let mut message = vec![b'a'; 300];
+        assert_eq!(space_pad(&mut message, BLOCK_SIZE).len(), 512);
+    }
+
+    #[test]
+    fn padded_data_still_parses() {
+        let response: Response = Response::new().set_data(to_binary(&vec![1u8, 2]).unwrap());
+        let padded = pad_response(Ok(response), BLOCK_SIZE).unwrap();
+        let data = padded.data.unwrap();
+        assert_eq!(data.len(), BLOCK_SIZE);
+        assert_eq!(from_binary::<Vec<u8>>(&data).unwrap(), vec![1, 2]);
+
+        let padded: Response = pad_response(Ok(Response::new()), BLOCK_SIZE).unwrap();
+        assert_eq!(padded.data, None);
+
+        let query = pad_query_result(to_binary(&"balance"), 32).unwrap();
+        assert_eq!(query.len(), 32);
+        assert_eq!(from_binary::<String>(&query).unwrap(), "balance");
+
+        let err = StdError::generic_err("nope");
+        assert_eq!(pad_query_result(Err(err.clone()), 32), Err(err));
+    }
+}
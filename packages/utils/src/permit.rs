@@ -0,0 +1,340 @@
+use cosmwasm_std::{to_vec, Api, StdError, StdResult};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::adr36::{pubkey_to_address, Adr36Signature};
+use crate::clock::Clock;
+
+// Fields are declared in alphabetical order, amino JSON is signed with sorted keys
+
+#[derive(Serialize)]
+struct SignDoc<'a> {
+    account_number: &'static str,
+    chain_id: &'a str,
+    fee: Fee,
+    memo: &'static str,
+    msgs: [SignMsg<'a>; 1],
+    sequence: &'static str,
+}
+
+#[derive(Serialize)]
+struct Fee {
+    amount: [FeeCoin; 1],
+    gas: &'static str,
+}
+
+#[derive(Serialize)]
+struct FeeCoin {
+    amount: &'static str,
+    denom: &'static str,
+}
+
+#[derive(Serialize)]
+struct SignMsg<'a> {
+    #[serde(rename = "type")]
+    msg_type: &'static str,
+    value: SignedParams<'a>,
+}
+
+#[derive(Serialize)]
+struct SignedParams<'a> {
+    allowed_tokens: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delegate: Option<&'a str>,
+    permissions: &'a [String],
+    permit_name: &'a str,
+}
+
+/// The signed part of a SNIP-24 query permit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PermitParams {
+    pub allowed_tokens: Vec<String>,
+    pub chain_id: String,
+    /// Address the signer delegates read access to, e.g. a dapp backend. Only that address
+    /// may query with the permit, see [Permit::verify_delegated].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delegate: Option<String>,
+    pub permissions: Vec<String>,
+    pub permit_name: String,
+}
+
+/// A signed query permit as wallets and `secretd tx sign-doc` produce it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Permit {
+    pub params: PermitParams,
+    pub signature: Adr36Signature,
+}
+
+/// A delegate's proof that it presents a delegated permit itself, see
+/// [Permit::verify_delegated]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RelayerProof {
+    /// Block time in seconds from which the proof is rejected
+    pub expires: u64,
+    /// The delegate's ADR-36 signature of [Permit::relayer_proof_data]
+    pub signature: Adr36Signature,
+}
+
+impl Permit {
+    /// The amino sign doc of the permit, with the fixed zero fee, account number, sequence
+    /// and empty memo wallets sign permits with
+    pub fn sign_bytes(&self) -> StdResult<Vec<u8>> {
+        to_vec(&SignDoc {
+            account_number: "0",
+            chain_id: &self.params.chain_id,
+            fee: Fee {
+                amount: [FeeCoin {
+                    amount: "0",
+                    denom: "uscrt",
+                }],
+                gas: "1",
+            },
+            memo: "",
+            msgs: [SignMsg {
+                msg_type: "query_permit",
+                value: SignedParams {
+                    allowed_tokens: &self.params.allowed_tokens,
+                    delegate: self.params.delegate.as_deref(),
+                    permissions: &self.params.permissions,
+                    permit_name: &self.params.permit_name,
+                },
+            }],
+            sequence: "0",
+        })
+    }
+
+    /// What the delegate signs to prove it holds the permit: the permit's signature followed
+    /// by the proof's expiry in big endian seconds
+    pub fn relayer_proof_data(&self, expires: u64) -> Vec<u8> {
+        let mut data = self.signature.signature.to_vec();
+        data.extend_from_slice(&expires.to_be_bytes());
+        data
+    }
+
+    /// Checks the permit was signed for `contract` and returns the signer's address.
+    /// Delegated permits are rejected, only [Permit::verify_delegated] accepts them.
+    pub fn verify(&self, api: &dyn Api, contract: &str, hrp: &str) -> StdResult<String> {
+        if let Some(delegate) = &self.params.delegate {
+            return Err(StdError::generic_err(format!(
+                "permit is delegated to {}, it needs a relayer proof",
+                delegate
+            )));
+        }
+        self.verify_signature(api, contract, hrp)
+    }
+
+    /// Checks a delegated permit presented by `relayer` and returns the signer's address,
+    /// the account whose data may be read.
+    ///
+    /// Queries have no sender and can't write state, so the relayer proves it is the delegate
+    /// with a signature that can't be bound to a nonce. Until it expires the proof is a bearer
+    /// credential: whoever sees it together with the permit can query as the delegate, keep
+    /// expiries short and the proof as private as the permit.
+    pub fn verify_delegated(
+        &self,
+        api: &dyn Api,
+        clock: &impl Clock,
+        contract: &str,
+        hrp: &str,
+        relayer: &str,
+        proof: &RelayerProof,
+    ) -> StdResult<String> {
+        match &self.params.delegate {
+            Some(delegate) if delegate == relayer => {}
+            Some(delegate) => {
+                return Err(StdError::generic_err(format!(
+                    "permit is delegated to {}, not {}",
+                    delegate, relayer
+                )))
+            }
+            None => return Err(StdError::generic_err("permit is not delegated")),
+        }
+        if clock.seconds() >= proof.expires {
+            return Err(StdError::generic_err("relayer proof expired"));
+        }
+        proof
+            .signature
+            .verify(api, relayer, &self.relayer_proof_data(proof.expires))?;
+        self.verify_signature(api, contract, hrp)
+    }
+
+    fn verify_signature(&self, api: &dyn Api, contract: &str, hrp: &str) -> StdResult<String> {
+        if !self
+            .params
+            .allowed_tokens
+            .iter()
+            .any(|token| token == contract)
+        {
+            return Err(StdError::generic_err(format!(
+                "permit {} is not valid for {}",
+                self.params.permit_name, contract
+            )));
+        }
+        let pubkey = self.signature.pub_key.value.as_slice();
+        let hash = Sha256::digest(&self.sign_bytes()?);
+        if !api.secp256k1_verify(&hash, self.signature.signature.as_slice(), pubkey)? {
+            return Err(StdError::generic_err("invalid permit signature"));
+        }
+        pubkey_to_address(pubkey, hrp)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::adr36::PubKey;
+    use crate::clock::MockClock;
+    use cosmwasm_std::testing::MockApi;
+    use cosmwasm_std::{from_slice, Binary};
+
+    const OWNER: &str = "secret1jfw5q2ygp0gvn450h3lu0hlwjanfsc5ufe5wu3";
+    const RELAYER: &str = "secret18kvyc4nelyueadktgx574st0dxkl9xexk9435e";
+    const EXPIRES: u64 = 1_700_000_000;
+    // Signed by OWNER, delegated to RELAYER
+    const DELEGATED_PERMIT: &str = r#"{
+        "params": {
+            "allowed_tokens": ["secret1token"],
+            "chain_id": "secret-4",
+            "delegate": "secret18kvyc4nelyueadktgx574st0dxkl9xexk9435e",
+            "permissions": ["balance"],
+            "permit_name": "dapp"
+        },
+        "signature": {
+            "pub_key": {
+                "type": "tendermint/PubKeySecp256k1",
+                "value": "AnWSqrXUNhjdoT+6ceOZPNdRenEtPaSWZMBu4b09H3Cv"
+            },
+            "signature": "gIB0DnCQQdG6NnCKDG6ZXkjELk89xhIBc+KFjD7cxEkzEV/qZDmW8dt/Z003nZ4aZBZzY1ml4dIFILcfoUwOUA=="
+        }
+    }"#;
+    // Signed by RELAYER, expires at EXPIRES
+    const RELAYER_PROOF: &str = r#"{
+        "expires": 1700000000,
+        "signature": {
+            "pub_key": {
+                "type": "tendermint/PubKeySecp256k1",
+                "value": "AuV0DmO60oCB7Xz2VN1sGQKcoDOC/AWrX13agfLFW4Rb"
+            },
+            "signature": "G2AwjMgQ2UNkVyDcDIZ3ivf46CiFTOTH/f+hFKrTn+0kys0m4bljtRPmhVnP3os5047MSeQhSfaq6DaVYVSuqw=="
+        }
+    }"#;
+
+    fn permit(delegate: Option<&str>) -> Permit {
+        Permit {
+            params: PermitParams {
+                allowed_tokens: vec!["secret1token".to_string()],
+                chain_id: "secret-4".to_string(),
+                delegate: delegate.map(str::to_string),
+                permissions: vec!["balance".to_string()],
+                permit_name: "test".to_string(),
+            },
+            signature: Adr36Signature {
+                pub_key: PubKey {
+                    key_type: "tendermint/PubKeySecp256k1".to_string(),
+                    value: Binary::from(vec![2; 33]),
+                },
+                signature: Binary::from(vec![1; 64]),
+            },
+        }
+    }
+
+    #[test]
+    fn sign_bytes() {
+        assert_eq!(
+            String::from_utf8(permit(None).sign_bytes().unwrap()).unwrap(),
+            concat!(
+                r#"{"account_number":"0","chain_id":"secret-4","#,
+                r#""fee":{"amount":[{"amount":"0","denom":"uscrt"}],"gas":"1"},"memo":"","#,
+                r#""msgs":[{"type":"query_permit","value":{"allowed_tokens":["secret1token"],"#,
+                r#""permissions":["balance"],"permit_name":"test"}}],"sequence":"0"}"#
+            )
+        );
+        assert!(
+            String::from_utf8(permit(Some("secret1dapp")).sign_bytes().unwrap())
+                .unwrap()
+                .contains(r#""delegate":"secret1dapp","permissions""#)
+        );
+    }
+
+    #[test]
+    fn delegated_permit() -> StdResult<()> {
+        let api = MockApi::default();
+        let permit: Permit = from_slice(DELEGATED_PERMIT.as_bytes())?;
+        let proof: RelayerProof = from_slice(RELAYER_PROOF.as_bytes())?;
+        let clock = MockClock::at(EXPIRES - 60);
+        assert_eq!(
+            permit.verify_delegated(&api, &clock, "secret1token", "secret", RELAYER, &proof)?,
+            OWNER
+        );
+
+        // the delegated permit is useless without the relayer's proof
+        assert!(permit
+            .verify(&api, "secret1token", "secret")
+            .unwrap_err()
+            .to_string()
+            .contains("needs a relayer proof"));
+        // expired proofs, proofs of other addresses and tampered expiries are rejected
+        assert!(permit
+            .verify_delegated(
+                &api,
+                &MockClock::at(EXPIRES),
+                "secret1token",
+                "secret",
+                RELAYER,
+                &proof
+            )
+            .is_err());
+        assert!(permit
+            .verify_delegated(&api, &clock, "secret1token", "secret", OWNER, &proof)
+            .is_err());
+        let extended = RelayerProof {
+            expires: EXPIRES + 60,
+            ..proof.clone()
+        };
+        assert!(permit
+            .verify_delegated(&api, &clock, "secret1token", "secret", RELAYER, &extended)
+            .is_err());
+        // and the permit is still only valid for its tokens
+        assert!(permit
+            .verify_delegated(&api, &clock, "secret1other", "secret", RELAYER, &proof)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn delegate_must_match() {
+        let api = MockApi::default();
+        let clock = MockClock::at(0);
+        let proof = RelayerProof {
+            expires: 1,
+            signature: permit(None).signature,
+        };
+        let err = permit(Some("secret1dapp"))
+            .verify_delegated(
+                &api,
+                &clock,
+                "secret1token",
+                "secret",
+                "secret1other",
+                &proof,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("delegated to secret1dapp"));
+        assert!(permit(None)
+            .verify_delegated(
+                &api,
+                &clock,
+                "secret1token",
+                "secret",
+                "secret1dapp",
+                &proof
+            )
+            .is_err());
+        assert!(permit(None)
+            .verify(&api, "secret1other", "secret")
+            .unwrap_err()
+            .to_string()
+            .contains("not valid for secret1other"));
+    }
+}
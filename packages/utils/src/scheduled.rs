@@ -1,8 +1,9 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::clock::Clock;
 use crate::Duration;
-use cosmwasm_std::{BlockInfo, StdError, StdResult, Timestamp};
+use cosmwasm_std::{StdError, StdResult, Timestamp};
 use std::cmp::Ordering;
 use std::fmt;
 use std::ops::Add;
@@ -10,7 +11,7 @@ use std::ops::Add;
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 /// Scheduled represents a point in time when an event happens.
-/// It can compare with a BlockInfo and will return is_triggered() == true
+/// It can compare with a Clock, e.g. BlockInfo, and will return is_triggered() == true
 /// once the condition is hit (and for every block in the future)
 pub enum Scheduled {
     /// AtHeight will schedule when `env.block.height` >= height
@@ -30,10 +31,10 @@ impl fmt::Display for Scheduled {
 
 impl Scheduled {
     #[allow(dead_code)]
-    pub fn is_triggered(&self, block: &BlockInfo) -> bool {
+    pub fn is_triggered(&self, clock: &impl Clock) -> bool {
         match self {
-            Scheduled::AtHeight(height) => block.height >= *height,
-            Scheduled::AtTime(time) => block.time >= *time,
+            Scheduled::AtHeight(height) => clock.height() >= *height,
+            Scheduled::AtTime(time) => clock.time() >= *time,
         }
     }
 }
@@ -66,6 +67,7 @@ impl PartialOrd for Scheduled {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::clock::MockClock;
 
     #[test]
     fn compare_schedules() {
@@ -114,4 +116,15 @@ mod test {
         let end = Scheduled::AtHeight(12345) + Duration::Time(1500);
         end.unwrap_err();
     }
+
+    #[test]
+    fn triggers_on_mock_clock() {
+        let mut clock = MockClock::new(10, 100);
+        assert!(!Scheduled::AtHeight(12).is_triggered(&clock));
+        assert!(Scheduled::AtHeight(12).is_triggered(clock.advance_blocks(2)));
+        assert!(!Scheduled::AtTime(Timestamp::from_seconds(160)).is_triggered(&clock));
+        assert!(
+            Scheduled::AtTime(Timestamp::from_seconds(160)).is_triggered(clock.advance_seconds(60))
+        );
+    }
 }